@@ -0,0 +1,35 @@
+-- Add up migration script here
+
+CREATE TYPE moderation_status AS ENUM ('PENDING', 'ACCEPTED', 'REJECTED');
+
+-- already existing images got validated on upload
+ALTER TABLE image ADD COLUMN moderation_status moderation_status NOT NULL DEFAULT 'ACCEPTED';
+ALTER TABLE image ALTER COLUMN moderation_status SET DEFAULT 'PENDING';
+ALTER TABLE image ADD COLUMN review_attempts integer NOT NULL DEFAULT 0;
+ALTER TABLE image ADD COLUMN review_message text;
+
+CREATE INDEX image_pending_idx ON image (link_date) WHERE moderation_status = 'PENDING';
+
+-- recreate view to include new columns
+DROP VIEW image_detail;
+CREATE VIEW image_detail AS (
+    SELECT image.*, COALESCE(upvotes::integer, 0) as upvotes, COALESCE(downvotes::integer, 0) as downvotes,
+    COALESCE(report_count::integer, 0) as report_count,
+    image_rank(link_date, COALESCE(upvotes, 0), COALESCE(downvotes, 0), COALESCE(report_count, 0), approved) as rank
+    FROM (
+        -- image
+        SELECT * FROM image
+    ) image LEFT JOIN (
+        -- ratings
+        SELECT image_id,
+        COUNT(*) FILTER (WHERE rating = 1) as upvotes,
+        COUNT(*) FILTER (WHERE rating = -1) as downvotes
+        FROM image_rating
+        GROUP BY image_id
+    ) rating USING(image_id) LEFT JOIN (
+        -- report
+        SELECT image_id, COUNT(*) as report_count
+        FROM image_report
+        GROUP BY image_id
+    ) reports USING(image_id)
+);
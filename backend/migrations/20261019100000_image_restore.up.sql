@@ -0,0 +1,36 @@
+-- Add up migration script here
+
+-- deleted images are kept for a grace period before they get removed permanently
+ALTER TABLE image ADD COLUMN deletion_date date;
+ALTER TABLE image ADD COLUMN restored_by text;
+ALTER TABLE image ADD COLUMN restore_date date;
+
+CREATE INDEX image_deleted_idx ON image (deletion_date) WHERE deletion_date IS NOT NULL;
+
+-- dismissed reports are kept so the reporting user still does not see the image
+ALTER TABLE image_report ADD COLUMN dismissed boolean NOT NULL DEFAULT false;
+
+-- recreate view to include new columns and ignore dismissed reports
+DROP VIEW image_detail;
+CREATE VIEW image_detail AS (
+    SELECT image.*, COALESCE(upvotes::integer, 0) as upvotes, COALESCE(downvotes::integer, 0) as downvotes,
+    COALESCE(report_count::integer, 0) as report_count,
+    image_rank(link_date, COALESCE(upvotes, 0), COALESCE(downvotes, 0), COALESCE(report_count, 0), approved) as rank
+    FROM (
+        -- image
+        SELECT * FROM image
+    ) image LEFT JOIN (
+        -- ratings
+        SELECT image_id,
+        COUNT(*) FILTER (WHERE rating = 1) as upvotes,
+        COUNT(*) FILTER (WHERE rating = -1) as downvotes
+        FROM image_rating
+        GROUP BY image_id
+    ) rating USING(image_id) LEFT JOIN (
+        -- report
+        SELECT image_id, COUNT(*) as report_count
+        FROM image_report
+        WHERE NOT dismissed
+        GROUP BY image_id
+    ) reports USING(image_id)
+);
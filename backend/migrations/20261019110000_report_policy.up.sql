@@ -0,0 +1,85 @@
+-- Add up migration script here
+
+-- weights of report reasons and ranking settings, written by the backend from its configuration
+CREATE TABLE report_reason_weight (
+    reason report_reason PRIMARY KEY,
+    weight double precision NOT NULL CHECK (weight >= 0)
+);
+
+CREATE TABLE image_rank_policy (
+    id boolean PRIMARY KEY DEFAULT true CHECK (id),
+    report_penalty double precision NOT NULL DEFAULT 10 CHECK (report_penalty >= 0),
+    new_image_days integer NOT NULL DEFAULT 30 CHECK (new_image_days > 0)
+);
+INSERT INTO image_rank_policy DEFAULT VALUES;
+
+CREATE FUNCTION image_rank(link_date date, upvote bigint, downvote bigint,
+report_score double precision, approved boolean, report_penalty double precision, new_image_days integer) RETURNS real AS $$
+DECLARE
+    s real := upvote::real / (upvote + downvote + report_penalty * report_score * (NOT approved)::integer + 1);
+    a real := 1./2. - 1./(2. * new_image_days) * (CURRENT_DATE - link_date);
+BEGIN
+    IF a <= 0 THEN
+        a := 0;
+    END IF;
+
+    RETURN (1 - a) * s + a;
+END;
+$$
+LANGUAGE plpgsql
+IMMUTABLE
+RETURNS NULL ON NULL INPUT;
+
+-- recreate view to weight reports by their reason
+DROP VIEW image_detail;
+CREATE VIEW image_detail AS (
+    SELECT image.*, COALESCE(upvotes::integer, 0) as upvotes, COALESCE(downvotes::integer, 0) as downvotes,
+    COALESCE(report_count::integer, 0) as report_count,
+    image_rank(link_date, COALESCE(upvotes, 0), COALESCE(downvotes, 0), COALESCE(report_score, 0), approved,
+        policy.report_penalty, policy.new_image_days) as rank
+    FROM (
+        -- image
+        SELECT * FROM image
+    ) image LEFT JOIN (
+        -- ratings
+        SELECT image_id,
+        COUNT(*) FILTER (WHERE rating = 1) as upvotes,
+        COUNT(*) FILTER (WHERE rating = -1) as downvotes
+        FROM image_rating
+        GROUP BY image_id
+    ) rating USING(image_id) LEFT JOIN (
+        -- report
+        SELECT image_id, COUNT(*) as report_count, SUM(COALESCE(weight, 1)) as report_score
+        FROM image_report LEFT JOIN report_reason_weight USING (reason)
+        WHERE NOT dismissed
+        GROUP BY image_id
+    ) reports USING(image_id) CROSS JOIN image_rank_policy policy
+);
+
+-- tests: same results as the previous ranking with default settings
+DO LANGUAGE plpgsql $$
+DECLARE
+    res real;
+BEGIN
+    res := image_rank(CURRENT_DATE, 0, 0, 0, false, 10, 30);
+    ASSERT res = 0.5, '1: returned ' || res;
+
+    res := image_rank(CURRENT_DATE - 30, 0, 0, 0, false, 10, 30);
+    ASSERT res = 0, '2: returned ' || res;
+
+    res := image_rank(CURRENT_DATE - 29, 0, 0, 0, false, 10, 30);
+    ASSERT res > 0, '3: returned ' || res;
+
+    res := image_rank(CURRENT_DATE - 30, 50, 0, 10, false, 10, 30);
+    ASSERT res - 1./3. < 0.1, '4: returned ' || res;
+
+    res := image_rank(CURRENT_DATE - 30, 50, 0, 5, false, 20, 30);
+    ASSERT res - 1./3. < 0.1, '5: returned ' || res;
+
+    res := image_rank(CURRENT_DATE - 30, 100, 0, 10, true, 10, 30);
+    ASSERT res > 0.99, '6: returned ' || res;
+
+    res := image_rank(CURRENT_DATE - 30, 0, 0, 0, false, 10, 60);
+    ASSERT res = 0.25, '7: returned ' || res;
+END;
+$$;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+DROP INDEX image_report_user_idx;
+DROP TABLE client_ban;
+DROP TABLE client_activity;
+DROP TYPE client_action;
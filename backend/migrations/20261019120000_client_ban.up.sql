@@ -0,0 +1,22 @@
+-- Add up migration script here
+
+CREATE TYPE client_action AS ENUM ('REPORT', 'UPLOAD', 'RATING');
+
+-- number of rate limited actions per client and day
+CREATE TABLE client_activity (
+    client_id uuid NOT NULL,
+    action client_action NOT NULL,
+    day date NOT NULL DEFAULT CURRENT_DATE,
+    count integer NOT NULL DEFAULT 1,
+    PRIMARY KEY (client_id, action, day)
+);
+
+-- clients whose actions get ignored without them noticing
+CREATE TABLE client_ban (
+    client_id uuid PRIMARY KEY,
+    banned_by text NOT NULL,
+    reason text,
+    ban_date date NOT NULL DEFAULT CURRENT_DATE
+);
+
+CREATE INDEX image_report_user_idx ON image_report (user_id, report_date);
@@ -0,0 +1,23 @@
+-- Add down migration script here
+
+DROP TRIGGER api_key_ident ON api_key;
+DROP FUNCTION api_key_set_ident;
+
+-- hashed keys cannot be restored
+DELETE FROM api_key WHERE api_key IS NULL;
+
+DROP INDEX api_key_ident_idx;
+ALTER TABLE api_key DROP CONSTRAINT api_key_secret_check;
+ALTER TABLE api_key DROP COLUMN last_used;
+ALTER TABLE api_key DROP COLUMN request_count;
+ALTER TABLE api_key DROP COLUMN revoked_date;
+ALTER TABLE api_key DROP COLUMN expiry_date;
+ALTER TABLE api_key DROP COLUMN created_date;
+ALTER TABLE api_key DROP COLUMN scope;
+ALTER TABLE api_key DROP COLUMN key_hash;
+ALTER TABLE api_key DROP COLUMN ident;
+ALTER TABLE api_key DROP COLUMN key_id;
+ALTER TABLE api_key ALTER COLUMN api_key SET NOT NULL;
+ALTER TABLE api_key ADD PRIMARY KEY (api_key);
+
+DROP TYPE api_key_scope;
@@ -0,0 +1,32 @@
+-- Add up migration script here
+
+CREATE TYPE api_key_scope AS ENUM ('READ_ONLY', 'UPLOAD', 'MODERATE');
+
+-- keys created from now on are only stored hashed, existing keys are kept in plain text until rotated
+ALTER TABLE api_key DROP CONSTRAINT api_key_pkey;
+ALTER TABLE api_key ALTER COLUMN api_key DROP NOT NULL;
+ALTER TABLE api_key ADD COLUMN key_id uuid PRIMARY KEY DEFAULT gen_random_uuid();
+ALTER TABLE api_key ADD COLUMN ident text;
+ALTER TABLE api_key ADD COLUMN key_hash text;
+ALTER TABLE api_key ADD COLUMN scope api_key_scope NOT NULL DEFAULT 'MODERATE';
+ALTER TABLE api_key ADD COLUMN created_date date NOT NULL DEFAULT CURRENT_DATE;
+ALTER TABLE api_key ADD COLUMN expiry_date date;
+ALTER TABLE api_key ADD COLUMN revoked_date date;
+ALTER TABLE api_key ADD COLUMN request_count bigint NOT NULL DEFAULT 0;
+ALTER TABLE api_key ADD COLUMN last_used timestamptz;
+
+UPDATE api_key SET ident = left(api_key, 10);
+ALTER TABLE api_key ALTER COLUMN ident SET NOT NULL;
+ALTER TABLE api_key ADD CONSTRAINT api_key_secret_check CHECK ((api_key IS NULL) <> (key_hash IS NULL));
+CREATE UNIQUE INDEX api_key_ident_idx ON api_key (ident) WHERE revoked_date IS NULL;
+
+-- plain keys inserted manually get their identifier automatically
+CREATE FUNCTION api_key_set_ident() RETURNS trigger AS $$
+BEGIN
+    NEW.ident := COALESCE(NEW.ident, left(NEW.api_key, 10));
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER api_key_ident BEFORE INSERT ON api_key
+    FOR EACH ROW EXECUTE FUNCTION api_key_set_ident();
@@ -0,0 +1,3 @@
+-- Add down migration script here
+
+ALTER TABLE api_key DROP COLUMN compatibility_mode;
@@ -0,0 +1,4 @@
+-- Add up migration script here
+
+-- keys in compatibility mode also accept requests without timestamp and nonce
+ALTER TABLE api_key ADD COLUMN compatibility_mode boolean NOT NULL DEFAULT true;
@@ -0,0 +1,9 @@
+-- Add down migration script here
+
+ALTER TABLE image DROP COLUMN deleted_by;
+ALTER TABLE image DROP COLUMN verified_by;
+
+DROP TABLE admin_session;
+DROP TABLE admin_account;
+
+DROP TYPE admin_role;
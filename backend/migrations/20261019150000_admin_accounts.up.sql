@@ -0,0 +1,25 @@
+-- Add up migration script here
+
+CREATE TYPE admin_role AS ENUM ('MODERATOR', 'OPERATOR');
+
+-- accounts logging in via openid connect have no password
+CREATE TABLE admin_account (
+    username text PRIMARY KEY,
+    password_hash text,
+    role admin_role NOT NULL DEFAULT 'MODERATOR',
+    created_date date NOT NULL DEFAULT CURRENT_DATE
+);
+
+-- only a hash of the session token is stored, like for api keys
+CREATE TABLE admin_session (
+    token_hash text PRIMARY KEY,
+    username text NOT NULL REFERENCES admin_account(username) ON DELETE CASCADE,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    expires_at timestamptz NOT NULL
+);
+
+CREATE INDEX admin_session_expiry_idx ON admin_session (expires_at);
+
+-- moderation actions record the responsible administrator
+ALTER TABLE image ADD COLUMN verified_by text;
+ALTER TABLE image ADD COLUMN deleted_by text;
@@ -0,0 +1,7 @@
+-- Add down migration script here
+
+DROP TRIGGER audit_log_append_only ON audit_log;
+DROP FUNCTION reject_audit_log_change;
+DROP TABLE audit_log;
+
+DROP TYPE audit_action;
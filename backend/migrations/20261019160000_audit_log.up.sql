@@ -0,0 +1,32 @@
+-- Add up migration script here
+
+CREATE TYPE audit_action AS ENUM (
+    'REPORT_IMAGE', 'HIDE_IMAGE', 'UPVOTE_IMAGE', 'DOWNVOTE_IMAGE', 'REMOVE_UPVOTE', 'REMOVE_DOWNVOTE',
+    'UPLOAD_IMAGE', 'RATE_MEAL', 'VERIFY_IMAGE', 'DELETE_IMAGE', 'DELETE_OWN_IMAGE', 'UNHIDE_IMAGE',
+    'RESTORE_IMAGE', 'BAN_CLIENT', 'UNBAN_CLIENT', 'ERASE_CLIENT_DATA'
+);
+
+-- every state-changing command, never to be changed or deleted
+CREATE TABLE audit_log (
+    audit_id bigint GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    -- client id, administrator or 'system'
+    actor text NOT NULL,
+    action audit_action NOT NULL,
+    -- id of the image, meal or client the action affected
+    target_id uuid NOT NULL,
+    reason text
+);
+
+CREATE INDEX audit_log_target_idx ON audit_log (target_id, created_at);
+CREATE INDEX audit_log_actor_idx ON audit_log (actor, created_at);
+
+CREATE FUNCTION reject_audit_log_change() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'the audit log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_append_only
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION reject_audit_log_change();
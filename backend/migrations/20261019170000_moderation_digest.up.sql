@@ -0,0 +1,9 @@
+-- Add up migration script here
+
+-- progress of the moderation digest, holding at most one row
+CREATE TABLE moderation_digest (
+    singleton boolean PRIMARY KEY DEFAULT true CHECK (singleton),
+    -- last audit log entry included in a sent digest
+    last_audit_id bigint NOT NULL,
+    sent_at timestamptz NOT NULL DEFAULT now()
+);
@@ -0,0 +1,8 @@
+-- Add up migration script here
+
+-- outcome of meal plan parsing, holding at most one row
+CREATE TABLE parse_status (
+    singleton boolean PRIMARY KEY DEFAULT true CHECK (singleton),
+    -- end of the last parse run without any errors
+    last_success timestamptz NOT NULL
+);
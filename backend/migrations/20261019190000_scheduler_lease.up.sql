@@ -0,0 +1,8 @@
+-- Add up migration script here
+
+-- replica currently allowed to run the scheduled jobs, see `Scheduler`
+CREATE TABLE scheduler_lease (
+    name text PRIMARY KEY,
+    holder uuid NOT NULL,
+    expires_at timestamptz NOT NULL
+);
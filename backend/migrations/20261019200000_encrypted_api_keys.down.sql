@@ -0,0 +1,9 @@
+-- Add down migration script here
+
+-- encrypted keys cannot be decrypted here
+DELETE FROM api_key WHERE encrypted_key IS NOT NULL;
+
+ALTER TABLE api_key DROP CONSTRAINT api_key_secret_check;
+ALTER TABLE api_key ADD CONSTRAINT api_key_secret_check CHECK ((api_key IS NULL) <> (key_hash IS NULL));
+ALTER TABLE api_key DROP COLUMN legacy;
+ALTER TABLE api_key DROP COLUMN encrypted_key;
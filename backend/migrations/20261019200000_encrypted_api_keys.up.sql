@@ -0,0 +1,10 @@
+-- Add up migration script here
+
+-- secrets of api keys get stored encrypted with a key only known to the server,
+-- existing secrets get encrypted on the next start of the server
+ALTER TABLE api_key ADD COLUMN encrypted_key bytea;
+ALTER TABLE api_key ADD COLUMN legacy boolean NOT NULL DEFAULT false;
+UPDATE api_key SET legacy = true;
+
+ALTER TABLE api_key DROP CONSTRAINT api_key_secret_check;
+ALTER TABLE api_key ADD CONSTRAINT api_key_secret_check CHECK (num_nonnulls(api_key, key_hash, encrypted_key) = 1);
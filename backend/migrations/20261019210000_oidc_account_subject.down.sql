@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+ALTER TABLE admin_account DROP CONSTRAINT admin_account_oidc_check;
+ALTER TABLE admin_account DROP CONSTRAINT admin_account_oidc_subject_key;
+ALTER TABLE admin_account DROP COLUMN oidc_subject;
+ALTER TABLE admin_account DROP COLUMN oidc_issuer;
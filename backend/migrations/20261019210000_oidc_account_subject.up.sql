@@ -0,0 +1,7 @@
+-- Add up migration script here
+
+-- accounts of openid connect logins are identified by issuer and subject instead of the username chosen at the provider
+ALTER TABLE admin_account ADD COLUMN oidc_issuer text;
+ALTER TABLE admin_account ADD COLUMN oidc_subject text;
+ALTER TABLE admin_account ADD CONSTRAINT admin_account_oidc_subject_key UNIQUE (oidc_issuer, oidc_subject);
+ALTER TABLE admin_account ADD CONSTRAINT admin_account_oidc_check CHECK ((oidc_issuer IS NULL) = (oidc_subject IS NULL));
@@ -0,0 +1,13 @@
+-- Add up migration script here
+
+-- erasing the data of a client replaces its id by a pseudonym, any other change is still rejected
+CREATE OR REPLACE FUNCTION reject_audit_log_change() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' AND current_setting('mensa.pseudonymise_audit_log', true) = 'on'
+        AND (NEW.audit_id, NEW.created_at, NEW.action, NEW.reason)
+            IS NOT DISTINCT FROM (OLD.audit_id, OLD.created_at, OLD.action, OLD.reason) THEN
+        RETURN NEW;
+    END IF;
+    RAISE EXCEPTION 'the audit log is append-only';
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+ALTER TABLE api_key ALTER COLUMN compatibility_mode SET DEFAULT true;
+DROP TABLE api_key_nonce;
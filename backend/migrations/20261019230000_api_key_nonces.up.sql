@@ -0,0 +1,13 @@
+-- Add up migration script here
+
+-- nonces of requests authenticated with an api key, kept as long as the requests would be accepted, see `ReplayGuard`
+CREATE TABLE api_key_nonce (
+    key_id uuid NOT NULL REFERENCES api_key(key_id) ON DELETE CASCADE,
+    nonce text NOT NULL,
+    expires_at timestamptz NOT NULL,
+    PRIMARY KEY (key_id, nonce)
+);
+CREATE INDEX api_key_nonce_expires_at ON api_key_nonce (expires_at);
+
+-- new keys require timestamp and nonce, existing keys keep their mode
+ALTER TABLE api_key ALTER COLUMN compatibility_mode SET DEFAULT false;
@@ -0,0 +1,7 @@
+-- Add up migration script here
+
+-- jobs whose scheduled runs get skipped on all instances, see `ScheduledJob`
+CREATE TABLE scheduler_paused_job (
+    name text PRIMARY KEY,
+    paused_at timestamptz NOT NULL DEFAULT now()
+);
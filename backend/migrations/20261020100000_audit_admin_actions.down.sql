@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+-- values of enums can not be removed, entries using them are kept as the audit log is append-only
+ALTER TABLE admin_account DROP COLUMN account_id;
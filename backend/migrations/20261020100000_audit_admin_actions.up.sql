@@ -0,0 +1,13 @@
+-- Add up migration script here
+
+ALTER TYPE audit_action ADD VALUE 'REJECT_IMAGE';
+ALTER TYPE audit_action ADD VALUE 'ADD_API_KEY';
+ALTER TYPE audit_action ADD VALUE 'ROTATE_API_KEY';
+ALTER TYPE audit_action ADD VALUE 'REVOKE_API_KEY';
+ALTER TYPE audit_action ADD VALUE 'SET_COMPATIBILITY_MODE';
+ALTER TYPE audit_action ADD VALUE 'SAVE_ADMIN_ACCOUNT';
+ALTER TYPE audit_action ADD VALUE 'DELETE_ADMIN_ACCOUNT';
+ALTER TYPE audit_action ADD VALUE 'SET_RANK_POLICY';
+
+-- audit entries refer to their target by id, also for accounts which are identified by their name
+ALTER TABLE admin_account ADD COLUMN account_id uuid NOT NULL UNIQUE DEFAULT gen_random_uuid();
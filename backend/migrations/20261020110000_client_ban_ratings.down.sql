@@ -0,0 +1,51 @@
+-- Add down migration script here
+
+DROP VIEW image_detail;
+CREATE VIEW image_detail AS (
+    SELECT image.*, COALESCE(upvotes::integer, 0) as upvotes, COALESCE(downvotes::integer, 0) as downvotes,
+    COALESCE(report_count::integer, 0) as report_count,
+    image_rank(link_date, COALESCE(upvotes, 0), COALESCE(downvotes, 0), COALESCE(report_score, 0), approved,
+        policy.report_penalty, policy.new_image_days) as rank
+    FROM (
+        -- image
+        SELECT * FROM image
+    ) image LEFT JOIN (
+        -- ratings
+        SELECT image_id,
+        COUNT(*) FILTER (WHERE rating = 1) as upvotes,
+        COUNT(*) FILTER (WHERE rating = -1) as downvotes
+        FROM image_rating
+        GROUP BY image_id
+    ) rating USING(image_id) LEFT JOIN (
+        -- report
+        SELECT image_id, COUNT(*) as report_count, SUM(COALESCE(weight, 1)) as report_score
+        FROM image_report LEFT JOIN report_reason_weight USING (reason)
+        WHERE NOT dismissed
+        GROUP BY image_id
+    ) reports USING(image_id) CROSS JOIN image_rank_policy policy
+);
+
+DROP VIEW meal_detail;
+CREATE VIEW meal_detail AS (
+    SELECT meal.*, COALESCE(stat.new, true) as new,
+		COALESCE(stat.frequency, 0) as frequency, stat.last_served, stat.next_served,
+		COALESCE(rating.average_rating, 0) as average_rating, COALESCE(rating.rating_count, 0) as rating_count
+	FROM (
+        -- meal
+        SELECT *
+        FROM meal JOIN food USING (food_id)
+    ) meal LEFT JOIN (
+        -- statistics
+        SELECT food_id, COUNT(*) FILTER (WHERE serve_date < CURRENT_DATE) = 0 as new, 
+        COUNT(*) FILTER (WHERE serve_date >= CURRENT_DATE - 30 * 3 AND serve_date < CURRENT_DATE) as frequency,
+        MAX(serve_date) FILTER (WHERE serve_date < CURRENT_DATE) as last_served,
+        MIN(serve_date) FILTER (WHERE serve_date > CURRENT_DATE) as next_served 
+        FROM food_plan
+        GROUP BY food_id
+    ) stat USING (food_id) LEFT JOIN (
+        -- ratings
+        SELECT food_id, AVG(rating::real)::real as average_rating, COUNT(*) as rating_count 
+        FROM meal_rating
+        GROUP BY food_id
+    ) rating USING (food_id)
+);
@@ -5,10 +5,12 @@ use lettre::address::AddressError;
 use serde::Serialize;
 use thiserror::Error;
 
+use chrono::{DateTime, Utc};
+
 use crate::util::{Date, ReportReason, Uuid};
 
-/// Result returned when sending emails, potentially containing a [`MailError`].
-pub type Result<T> = std::result::Result<T, MailError>;
+/// Result returned when sending notifications, potentially containing a [`NotificationError`].
+pub type Result<T> = std::result::Result<T, NotificationError>;
 
 /// Interface for notification of administrators.
 #[async_trait]
@@ -19,11 +21,25 @@ pub trait AdminNotification: Sync + Send {
     async fn notify_admin_image_verified(&self, image_id: Uuid) -> Result<()>;
     /// Notifies an administrator about an image gotten deleted.
     async fn notify_admin_image_deleted(&self, image_id: Uuid) -> Result<()>;
+    /// Notifies an administrator about an image gotten deleted by the client who uploaded it.
+    async fn notify_admin_image_withdrawn(&self, image_id: Uuid, client_id: Uuid) -> Result<()>;
+    /// Notifies an administrator about an image which could not be reviewed automatically, failing with `error` the last time.
+    /// The image got hidden until an administrator reviews it.
+    async fn notify_admin_image_review_failed(&self, image_id: Uuid, error: &str) -> Result<()>;
+    /// Notifies an administrator about a client which reported `report_count` images today and might be abusing reports.
+    async fn notify_admin_mass_reports(&self, client_id: Uuid, report_count: u32) -> Result<()>;
 }
 
-/// Enum describing the possible ways, the mail notification can fail.
+/// Interface for sending summaries of the moderation activity to administrators.
+#[async_trait]
+pub trait DigestNotification: Sync + Send {
+    /// Sends a summary of the moderation activity since the last digest to an administrator.
+    async fn notify_admin_digest(&self, digest: &ModerationDigest) -> Result<()>;
+}
+
+/// Enum describing the possible ways, the notification by mail or webhook can fail.
 #[derive(Debug, Error)]
-pub enum MailError {
+pub enum NotificationError {
     /// Error occurring when an email address could not be parsed.
     #[error("an error occurred while parsing the addresses: {0}")]
     AddressError(#[from] AddressError),
@@ -33,9 +49,12 @@ pub enum MailError {
     /// Error occurring when mail sender instance could bot be build.
     #[error("an error occurred while sending the mail: {0}")]
     MailSendError(#[from] lettre::transport::smtp::Error),
+    /// Error occurring when a webhook could not be called.
+    #[error("an error occurred while calling the webhook: {0}")]
+    WebhookError(#[from] reqwest::Error),
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 /// Structure containing all information about the reporting of an image.
 pub struct ImageReportInfo {
     /// Reason for the report.
@@ -69,3 +88,44 @@ pub struct ImageReportInfo {
     /// list of urls of other images of the same meal.
     pub other_image_urls: Vec<String>,
 }
+
+/// Structure summarizing the moderation activity over a period of time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ModerationDigest {
+    /// Time of the first action included.
+    pub since: Option<DateTime<Utc>>,
+    /// Reported images, most reported first.
+    pub reported_images: Vec<DigestReport>,
+    /// Images hidden automatically because of their reports.
+    pub hidden_images: Vec<DigestAction>,
+    /// Images verified by administrators.
+    pub verified_images: Vec<DigestAction>,
+    /// Images deleted by administrators.
+    pub deleted_images: Vec<DigestAction>,
+}
+
+/// Structure summarizing the reports of an image in a [`ModerationDigest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DigestReport {
+    /// Identifier of the reported image.
+    pub image_id: Uuid,
+    /// URL to the image at the image hoster.
+    pub image_url: String,
+    /// Number of reports in the summarized period.
+    pub report_count: u32,
+    /// Distinct reasons given for the reports.
+    pub reasons: Vec<String>,
+}
+
+/// Structure describing an action taken on an image in a [`ModerationDigest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DigestAction {
+    /// Identifier of the affected image.
+    pub image_id: Uuid,
+    /// URL to the image at the image hoster.
+    pub image_url: String,
+    /// Administrator who took the action, or [`SYSTEM_ACTOR`](crate::util::SYSTEM_ACTOR).
+    pub actor: String,
+    /// Time the action was taken at.
+    pub time: DateTime<Utc>,
+}
@@ -7,11 +7,12 @@ use thiserror::Error;
 
 use crate::{
     layer::logic::api_command::image_preprocessing::ImagePreprocessingError,
-    util::{ReportReason, Uuid},
+    util::{ClientAction, ReportReason, Uuid},
 };
 
 use super::{
-    admin_notification::MailError, image_storage, image_validation, persistent_data::DataError,
+    admin_notification::NotificationError, image_storage, image_validation,
+    persistent_data::DataError,
 };
 
 /// Result returned from commands, potentially containing a [`CommandError`].
@@ -53,10 +54,41 @@ pub trait Command: Send + Sync {
     async fn set_meal_rating(&self, meal_id: Uuid, rating: u32, client_id: Uuid) -> Result<()>;
 
     /// Marks an image as verified.
-    async fn verify_image(&self, image_id: Uuid) -> Result<()>;
+    /// `verified_by` names the administrator responsible.
+    async fn verify_image(&self, image_id: Uuid, verified_by: &str) -> Result<()>;
 
-    /// Deletes an image.
-    async fn delete_image(&self, image_id: Uuid) -> Result<()>;
+    /// Deletes an image. It can be restored until the retention period is over.
+    /// `deleted_by` names the administrator responsible.
+    async fn delete_image(&self, image_id: Uuid, deleted_by: &str) -> Result<()>;
+
+    /// Deletes an image on behalf of the client who uploaded it. The image and its file get removed permanently.
+    /// Fails with [`CommandError::NotUploader`] if the image was uploaded by someone else.
+    async fn delete_my_image(&self, image_id: Uuid, client_id: Uuid) -> Result<()>;
+
+    /// Erases all data of a client, including its uploaded images. Its reports are kept anonymously.
+    /// `erased_by` names the administrator responsible.
+    async fn erase_client_data(&self, client_id: Uuid, erased_by: &str) -> Result<()>;
+
+    /// Makes a hidden image visible again.
+    /// `unhidden_by` names the administrator responsible.
+    async fn unhide_image(&self, image_id: Uuid, unhidden_by: &str) -> Result<()>;
+
+    /// Makes a hidden or deleted image visible again and dismisses its reports.
+    /// `restored_by` names the administrator responsible.
+    async fn restore_image(&self, image_id: Uuid, restored_by: &str) -> Result<()>;
+
+    /// Shadow-bans a client: its reports, votes, ratings and uploads get ignored without it noticing.
+    /// `banned_by` names the administrator responsible.
+    async fn ban_client(
+        &self,
+        client_id: Uuid,
+        banned_by: &str,
+        reason: Option<String>,
+    ) -> Result<()>;
+
+    /// Lifts the ban of a client.
+    /// `unbanned_by` names the administrator responsible.
+    async fn unban_client(&self, client_id: Uuid, unbanned_by: &str) -> Result<()>;
 }
 
 #[async_trait]
@@ -114,12 +146,51 @@ impl<C: Command> Command for Arc<C> {
             .await
     }
 
-    async fn verify_image(&self, image_id: Uuid) -> Result<()> {
-        Self::as_ref(self).verify_image(image_id).await
+    async fn verify_image(&self, image_id: Uuid, verified_by: &str) -> Result<()> {
+        Self::as_ref(self).verify_image(image_id, verified_by).await
+    }
+
+    async fn delete_image(&self, image_id: Uuid, deleted_by: &str) -> Result<()> {
+        Self::as_ref(self).delete_image(image_id, deleted_by).await
+    }
+
+    async fn delete_my_image(&self, image_id: Uuid, client_id: Uuid) -> Result<()> {
+        Self::as_ref(self)
+            .delete_my_image(image_id, client_id)
+            .await
+    }
+
+    async fn erase_client_data(&self, client_id: Uuid, erased_by: &str) -> Result<()> {
+        Self::as_ref(self)
+            .erase_client_data(client_id, erased_by)
+            .await
     }
 
-    async fn delete_image(&self, image_id: Uuid) -> Result<()> {
-        Self::as_ref(self).delete_image(image_id).await
+    async fn unhide_image(&self, image_id: Uuid, unhidden_by: &str) -> Result<()> {
+        Self::as_ref(self).unhide_image(image_id, unhidden_by).await
+    }
+
+    async fn restore_image(&self, image_id: Uuid, restored_by: &str) -> Result<()> {
+        Self::as_ref(self)
+            .restore_image(image_id, restored_by)
+            .await
+    }
+
+    async fn ban_client(
+        &self,
+        client_id: Uuid,
+        banned_by: &str,
+        reason: Option<String>,
+    ) -> Result<()> {
+        Self::as_ref(self)
+            .ban_client(client_id, banned_by, reason)
+            .await
+    }
+
+    async fn unban_client(&self, client_id: Uuid, unbanned_by: &str) -> Result<()> {
+        Self::as_ref(self)
+            .unban_client(client_id, unbanned_by)
+            .await
     }
 }
 
@@ -146,5 +217,11 @@ pub enum CommandError {
     ImageValidationError(#[from] image_validation::ImageValidationError),
     /// Error while trying to send aan admin notification.
     #[error("Administrator could not be notified: {0}")]
-    AdminNotificationError(#[from] MailError),
+    AdminNotificationError(#[from] NotificationError),
+    /// Error when a client did an action too often.
+    #[error("Daily limit of {0} exceeded, please try again tomorrow")]
+    RateLimited(ClientAction),
+    /// Error when a client tries to modify an image it did not upload.
+    #[error("Image {0} was not uploaded by this client")]
+    NotUploader(Uuid),
 }
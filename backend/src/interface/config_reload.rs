@@ -0,0 +1,27 @@
+//! This interface allows applying changed settings while the application keeps running.
+use async_trait::async_trait;
+use thiserror::Error;
+
+/// Result of reloading the settings, containing a [`ReloadError`] if they could not be applied.
+pub type Result<T> = std::result::Result<T, ReloadError>;
+
+/// Interface for reloading all settings which can change without restarting the application.
+#[async_trait]
+pub trait ConfigReload: Send + Sync {
+    /// Reads the settings again and applies those which can change while running.
+    /// Requests and jobs in progress finish with the settings they started with.
+    /// # Errors
+    /// if the new settings are invalid, in which case none of them get applied, or if some could not be applied.
+    async fn reload(&self) -> Result<()>;
+}
+
+/// Enum describing why settings could not be reloaded.
+#[derive(Debug, Error)]
+pub enum ReloadError {
+    /// The new settings are invalid, so the previous ones stay in use.
+    #[error("invalid settings: {0}")]
+    InvalidConfig(String),
+    /// Some of the new settings could not be applied.
+    #[error("could not apply {0}")]
+    NotApplied(String),
+}
@@ -0,0 +1,9 @@
+//! This interface allows sending summaries of the moderation activity to administrators.
+use async_trait::async_trait;
+
+/// Interface allowing to send a digest of the moderation activity.
+#[async_trait]
+pub trait DigestScheduling: Send + Sync {
+    /// Sends a summary of all reports, automatic hides, verifications and deletions since the last digest, if there were any.
+    async fn send_digest(&self);
+}
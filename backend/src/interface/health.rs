@@ -0,0 +1,42 @@
+//! This interface allows checking whether the dependencies of the application are usable.
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+/// Result of a health check, containing a [`HealthError`] if the checked dependency is not usable.
+pub type Result<T> = std::result::Result<T, HealthError>;
+
+/// Interface for a dependency which needs to be usable for the application to serve requests.
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    /// Name identifying the checked dependency in readiness reports.
+    fn name(&self) -> &'static str;
+
+    /// Whether requests can not be served without the dependency.
+    /// Other dependencies only get reported, without making the application not ready.
+    fn is_required(&self) -> bool {
+        true
+    }
+
+    /// Checks whether the dependency is currently usable.
+    /// # Errors
+    /// describing why the dependency is not usable.
+    async fn check_health(&self) -> Result<()>;
+}
+
+/// Enum describing why a dependency is not usable.
+#[derive(Debug, Error)]
+pub enum HealthError {
+    /// The dependency could not be reached.
+    #[error("not reachable: {0}")]
+    Unreachable(String),
+    /// Files could not be written.
+    #[error("not writable: {0}")]
+    NotWritable(String),
+    /// The last successful meal plan parsing is too long ago.
+    #[error("last successful parse at {0} is too long ago")]
+    ParseOutdated(DateTime<Utc>),
+    /// There was no successful meal plan parsing yet.
+    #[error("no successful parse yet")]
+    NeverParsed,
+}
@@ -0,0 +1,16 @@
+//! This interface allows starting the review of uploaded images.
+use async_trait::async_trait;
+
+/// Interface allowing to start the review of uploaded images.
+#[async_trait]
+pub trait ImageReviewScheduling: Send + Sync {
+    /// Validates all pending images.
+    /// Images passing the validation become visible, others get rejected.
+    /// Reviews which failed because of an error get retried on a later call.
+    async fn start_image_review(&self);
+
+    /// Validates visible images again which were not verified for some time, so improved validations also apply to them.
+    /// Images no longer passing the validation get hidden.
+    /// Images whose validation failed because of an error get retried on a later call.
+    async fn start_image_reverification(&self);
+}
@@ -15,6 +15,10 @@ pub trait ImageStorage: Send + Sync {
     async fn save_image(&self, id: Uuid, image: ImageResource) -> Result<()>;
     /// Deletes an image resource.
     async fn delete_image(&self, id: Uuid) -> Result<()>;
+    /// Loads a previously saved image.
+    async fn load_image(&self, id: Uuid) -> Result<ImageResource>;
+    /// Returns the ids of all saved images.
+    async fn list_images(&self) -> Result<Vec<Uuid>>;
 }
 
 /// Enum describing possible ways an file operation can go wrong.
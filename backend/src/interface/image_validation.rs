@@ -18,7 +18,8 @@ pub type Result<T> = std::result::Result<T, ImageValidationError>;
 #[async_trait]
 pub trait ImageValidation: Send + Sync {
     /// Validates if an image does not contain any inappropriate (explicit, etc.) content.
-    async fn validate_image(&self, image: &ImageResource) -> Result<()>;
+    /// `meal_name` is the name of the meal the image got uploaded for, validators may check whether the image fits it.
+    async fn validate_image(&self, image: &ImageResource, meal_name: &str) -> Result<()>;
 }
 
 /// Enum describing possible ways an image validation can go wrong
@@ -54,6 +55,27 @@ pub enum ImageValidationError {
     /// The json file could not be read.
     #[error("The json file could not be read: {0}")]
     FileReaderError(#[from] io::Error),
+    /// The `OpenAI` compatible api decided that the image is not acceptable.
+    #[error("The provided image got rejected: {0}")]
+    OpenAiRejectionError(String),
+    /// The image does not show the meal it got uploaded for.
+    #[error("The provided image does not show the meal '{0}': {1}")]
+    MealMismatchError(String, String),
+}
+
+impl ImageValidationError {
+    /// Whether this error means the image got rejected because of its content.
+    /// Other errors are caused by the validation itself failing and might succeed when retried.
+    #[must_use]
+    pub const fn is_rejection(&self) -> bool {
+        matches!(
+            self,
+            Self::SafeSearchRejectionError(..)
+                | Self::GeminiRejectionError(_)
+                | Self::OpenAiRejectionError(_)
+                | Self::MealMismatchError(..)
+        )
+    }
 }
 
 /// Structure that contains all information necessary for the image validation component.
@@ -63,6 +85,8 @@ pub struct ImageValidationInfo {
     pub safe_search_info: Option<SafeSearchInfo>,
     /// See [`GeminiInfo`]
     pub gemini_info: Option<GeminiInfo>,
+    /// See [`OpenAiInfo`]
+    pub openai_info: Option<OpenAiInfo>,
 }
 
 /// This struct contains all safe search api related info.
@@ -85,6 +109,21 @@ pub struct GeminiInfo {
     pub gemini_text_request: String,
 }
 
+/// This struct contains all information related to an `OpenAI` compatible api.
+/// See each entry for more information.
+pub struct OpenAiInfo {
+    /// Base url of the api, for example `https://api.openai.com/v1` or the url of a locally hosted model.
+    pub base_url: String,
+    /// Name of the model to use.
+    pub model: String,
+    /// This api key is sent as bearer token, if set.
+    pub api_key: Option<String>,
+    /// This string contains the question, that the model answers for each image validation.
+    pub text_request: String,
+    /// Whether images not showing the meal they got uploaded for should be rejected.
+    pub reject_meal_mismatch: bool,
+}
+
 /// This struct is needed for decompiling json error responses.
 /// ```json
 /// {
@@ -1,5 +1,22 @@
 //! This interface allows starting the operations for updating the menu from the the canteen's website.
 use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::{interface::mensa_parser::ParseError, util::Date};
+
+/// Result returned from parse runs started on demand, potentially containing a [`ParseRunError`].
+pub type Result<T> = std::result::Result<T, ParseRunError>;
+
+/// Error indicating that a parse run did not complete without errors.
+#[derive(Debug, Error)]
+pub enum ParseRunError {
+    /// The meal plan could not be parsed.
+    #[error("the meal plan could not be parsed: {0}")]
+    ParseError(#[from] ParseError),
+    /// The parsed meal plan could not be saved completely.
+    #[error("the parsed meal plan could not be saved completely, see logs for details")]
+    ResolveError,
+}
 
 /// Interface allowing to start the operations for updating the menu from the the canteen's website.
 #[async_trait]
@@ -11,4 +28,13 @@ pub trait MensaParseScheduling: Send + Sync {
     /// Initiate the parsing procedure of the canteen-website.
     /// Only parse meals for the next four weeks.
     async fn start_full_parsing(&self);
+
+    /// Initiate the parsing procedure of the canteen-website for the given date.
+    /// If a canteen is given by its identifier on the website, only its meals get parsed.
+    /// # Errors
+    /// if not everything could be parsed and saved.
+    async fn start_date_parsing(&self, date: Date, canteen: Option<&str>) -> Result<()>;
+
+    /// Returns whether the canteen, given by its identifier on the website, is among the parsed canteens.
+    async fn is_known_canteen(&self, canteen: &str) -> bool;
 }
@@ -24,6 +24,9 @@ pub enum ParseError {
     /// Could not build client for making web requests.
     #[error("the html reqwest client creation failed: {0}")]
     ClientBuilderFailed(String),
+    /// The requested canteen is not among the canteens to parse.
+    #[error("the canteen is unknown: {0}")]
+    UnknownCanteen(String),
 }
 
 #[async_trait]
@@ -31,6 +34,10 @@ pub enum ParseError {
 pub trait MealplanParser: Send + Sync {
     /// Initiate a parse procedure. Returns a canteen struct containing meal plan data of the given date.
     async fn parse(&self, day: Date) -> Result<Vec<ParseCanteen>>;
+    /// Initiate a parse procedure for a single canteen, given by its identifier on the meal plan website. Returns a canteen struct containing meal plan data of the given date.
+    async fn parse_canteen(&self, day: Date, canteen: &str) -> Result<Vec<ParseCanteen>>;
     /// Initiate a parse procedure. Returns a tuple containing meal plan data of the next four weeks. The tuple contains a canteen struct with the related date.
     async fn parse_all(&self) -> Result<Vec<(Date, Vec<ParseCanteen>)>>;
+    /// Returns whether the canteen, given by its identifier on the meal plan website, is among the parsed canteens.
+    async fn is_known_canteen(&self, canteen: &str) -> bool;
 }
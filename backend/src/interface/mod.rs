@@ -1,8 +1,13 @@
 //! Module bundle for interface models
 pub mod admin_notification;
 pub mod api_command;
+pub mod config_reload;
+pub mod digest;
+pub mod health;
+pub mod image_review;
 pub mod image_storage;
 pub mod image_validation;
 pub mod mealplan_management;
 pub mod mensa_parser;
 pub mod persistent_data;
+pub mod retention;
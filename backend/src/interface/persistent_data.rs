@@ -1,13 +1,22 @@
 //! The interfaces specified here allow access to data stored in a persistent datastore like a database.
 pub mod model;
 
-use crate::interface::persistent_data::model::{ApiKey, Canteen, Image, Line, Meal, Side};
-use crate::util::{Additive, Allergen, Date, FoodType, NutritionData, Price, ReportReason, Uuid};
+use crate::interface::persistent_data::model::{
+    AdminAccount, ApiKey, ApiKeyInfo, AuditEntry, AuditFilter, Canteen, ClientBan, ClientData,
+    Image, Line, Meal, ModerationFilter, ModerationImage, PendingImage, RankPolicy,
+    ReportingClient, Side, StoredImage, UploaderStats,
+};
+use crate::util::{
+    Additive, AdminRole, Allergen, ApiKeyScope, ClientAction, Date, FoodType, NutritionData, Price,
+    ReportReason, Uuid,
+};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use model::ExtendedImage;
 use sqlx::migrate::MigrateError;
 use std::num::TryFromIntError;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 use self::model::EnvironmentInfo;
@@ -35,6 +44,9 @@ pub enum DataError {
     /// Database migration could not be run.
     #[error("error while running database migration: {0}")]
     MigrateError(Arc<MigrateError>),
+    /// A stored secret could not be decrypted, e.g. because the key used for encryption changed.
+    #[error("could not decrypt a stored secret")]
+    DecryptionError,
 }
 
 impl From<sqlx::Error> for DataError {
@@ -181,18 +193,53 @@ pub trait MealplanManagementDataAccess: Send + Sync {
         date: Date,
         price: Price,
     ) -> Result<()>;
+
+    /// Remembers that a parse run just finished without errors, see [`crate::interface::health`].
+    async fn mark_parse_succeeded(&self) -> Result<()>;
 }
 
 #[async_trait]
 /// An interface for api actions. The Command component uses this interface for database access.
+///
+/// All methods changing data record the change in the audit log in the same transaction.
 pub trait CommandDataAccess: Sync + Send {
     /// Returns the [`ExtendedImage`] struct of image.
     async fn get_image_info(&self, image_id: Uuid) -> Result<ExtendedImage>;
     /// Marks an image as hidden. Hidden images cant be seen by users.
-    async fn hide_image(&self, image_id: Uuid) -> Result<()>;
-    /// Saves an image report
-    async fn add_report(&self, image_id: Uuid, client_id: Uuid, reason: ReportReason)
-        -> Result<()>;
+    /// The hide gets recorded as done automatically for the given `reason`.
+    async fn hide_image(&self, image_id: Uuid, reason: &str) -> Result<()>;
+    /// Saves an image report. Reports of banned clients get dismissed right away.
+    /// Returns the number of reports of the image which were not dismissed, including this one.
+    async fn add_report(
+        &self,
+        image_id: Uuid,
+        client_id: Uuid,
+        reason: ReportReason,
+    ) -> Result<u32>;
+    /// Returns the reasons of all reports of an image which were not dismissed.
+    async fn get_report_reasons(&self, image_id: Uuid) -> Result<Vec<ReportReason>>;
+    /// Returns statistics about all images uploaded by the given user.
+    async fn get_uploader_stats(&self, user_id: Uuid) -> Result<UploaderStats>;
+    /// Replaces the settings used for calculating the rank of images.
+    /// A change of the settings gets recorded as done by [`crate::util::SYSTEM_ACTOR`].
+    async fn set_rank_policy(&self, policy: &RankPolicy) -> Result<()>;
+    /// Returns how often the client did this action today.
+    async fn count_client_actions(&self, client_id: Uuid, action: ClientAction) -> Result<u32>;
+    /// Records an action of a client and returns how often the client did this action today, including this one.
+    async fn record_client_action(&self, client_id: Uuid, action: ClientAction) -> Result<u32>;
+    /// Checks whether the given client is banned.
+    async fn is_client_banned(&self, client_id: Uuid) -> Result<bool>;
+    /// Bans a client, which makes its actions be ignored, and dismisses all its reports.
+    /// While banned, its votes and ratings do not count and its uploads are not shown.
+    /// Banning a client again updates the reason.
+    async fn ban_client(
+        &self,
+        client_id: Uuid,
+        banned_by: &str,
+        reason: Option<&str>,
+    ) -> Result<()>;
+    /// Lifts the ban of a client. Returns [`DataError::NoSuchItem`] if the client is not banned.
+    async fn unban_client(&self, client_id: Uuid, unbanned_by: &str) -> Result<()>;
     /// Adds an upvote to the given image. An user can only down- or upvote an image.
     async fn add_upvote(&self, image_id: Uuid, user_id: Uuid) -> Result<()>;
     /// Adds a downvote to the given image. An user can only down- or upvote an image.
@@ -202,27 +249,243 @@ pub trait CommandDataAccess: Sync + Send {
     /// Removes a downvote from the given image.
     async fn remove_downvote(&self, image_id: Uuid, user_id: Uuid) -> Result<()>;
     /// Adds an image link to the database. The image will be related to the given meal.
+    /// The image is pending until reviewed, see [`ImageReviewDataAccess`].
     async fn link_image(&self, meal_id: Uuid, user_id: Uuid) -> Result<Uuid>;
 
     /// Reverts the linking of the given image by deleting the link.
     /// Useful if an error ocurred with the image itself.
     async fn revert_link_image(&self, image_id: Uuid) -> Result<()>;
 
+    /// Deletes an image for good on request of its uploader.
+    /// Returns [`DataError::NoSuchItem`] if the image does not exist or was uploaded by another client.
+    async fn withdraw_image(&self, image_id: Uuid, client_id: Uuid) -> Result<()>;
+
     /// Adds or updates a rating to the database. The rating will be related to the given meal and the given user.
     async fn add_rating(&self, meal_id: Uuid, user_id: Uuid, rating: u32) -> Result<()>;
 
     /// Marks an image as verified. This leads to future reports being ignored.
-    async fn verify_image(&self, image_id: Uuid) -> Result<()>;
+    /// `verified_by` gets recorded as the one responsible.
+    /// Returns [`DataError::NoSuchItem`] if the image does not exist.
+    async fn verify_image(&self, image_id: Uuid, verified_by: &str) -> Result<()>;
 
-    /// Deletes all entries related to an image.
-    async fn delete_image(&self, image_id: Uuid) -> Result<()>;
+    /// Makes a hidden image visible to users again.
+    /// `unhidden_by` gets recorded as the one responsible.
+    /// Returns [`DataError::NoSuchItem`] if the image does not exist or got deleted, as deleted images can only be restored.
+    async fn unhide_image(&self, image_id: Uuid, unhidden_by: &str) -> Result<()>;
+
+    /// Makes a hidden or deleted image visible again and dismisses all its reports.
+    /// `restored_by` gets recorded as the one responsible.
+    /// Returns [`DataError::NoSuchItem`] if the image does not exist (anymore).
+    async fn restore_image(&self, image_id: Uuid, restored_by: &str) -> Result<()>;
+
+    /// Marks an image as deleted, hiding it from all users.
+    /// It can be restored until it gets purged after the retention period, see [`RetentionDataAccess`].
+    /// `deleted_by` gets recorded as the one responsible.
+    /// Images deleted before keep their deletion date.
+    /// Returns [`DataError::NoSuchItem`] if the image does not exist (anymore).
+    async fn delete_image(&self, image_id: Uuid, deleted_by: &str) -> Result<()>;
+
+    /// Erases all data of the given client: its ratings, votes, activity and uploaded images get deleted and its reports get anonymized.
+    /// A ban of the client is kept. In the audit log, the id of the client gets replaced by a pseudonym.
+    /// `erased_by` gets recorded as the one responsible.
+    /// Returns the ids of the deleted images, whose files still have to be removed.
+    async fn erase_client_data(&self, client_id: Uuid, erased_by: &str) -> Result<Vec<Uuid>>;
+}
+
+#[async_trait]
+/// An interface for reviewing uploaded images. The image review component uses this interface for database access.
+pub trait ImageReviewDataAccess: Send + Sync {
+    /// Returns all images still waiting for their review which failed less than `max_attempts` times, oldest first.
+    async fn get_pending_images(&self, max_attempts: u32) -> Result<Vec<PendingImage>>;
+    /// Marks a pending image as accepted, making it visible to all users.
+    async fn accept_image(&self, image_id: Uuid) -> Result<()>;
+    /// Marks a pending image as rejected, the reason is kept for the uploader and administrators.
+    async fn reject_image(&self, image_id: Uuid, reason: &str) -> Result<()>;
+    /// Records that reviewing or verifying the image again failed, e.g. because the validation api was not reachable.
+    async fn add_failed_review_attempt(&self, image_id: Uuid, error: &str) -> Result<()>;
+    /// Ends the review of a pending image which failed too often, the last error is kept for administrators.
+    /// The image gets accepted but hidden, so it is only shown after an administrator made it visible again.
+    async fn hide_unreviewable_image(&self, image_id: Uuid, error: &str) -> Result<()>;
+    /// Returns at most `limit` visible accepted images last verified at least `days` days ago, least recently verified first.
+    /// Images verified by an administrator, images of external hosters and images failing to be verified `max_attempts` times are not returned.
+    async fn get_images_to_reverify(
+        &self,
+        days: u32,
+        limit: u32,
+        max_attempts: u32,
+    ) -> Result<Vec<PendingImage>>;
+    /// Records that the image passed the validation again, resetting the failed attempts.
+    async fn renew_verification(&self, image_id: Uuid) -> Result<()>;
+    /// Hides an image which no longer passes the validation, the reason is kept for administrators.
+    async fn hide_failing_image(&self, image_id: Uuid, reason: &str) -> Result<()>;
+}
+
+#[async_trait]
+/// An interface for removing data which is no longer needed. The retention component uses this interface for database access.
+pub trait RetentionDataAccess: Send + Sync {
+    /// Returns all images deleted at least `grace_days` days ago.
+    async fn get_expired_deleted_images(&self, grace_days: u32) -> Result<Vec<Uuid>>;
+    /// Permanently deletes all entries related to a deleted image.
+    async fn purge_image(&self, image_id: Uuid) -> Result<()>;
+    /// Deletes the recorded client actions of previous days, returning the number of deleted entries.
+    /// Only counts them if `dry_run`.
+    async fn purge_old_client_activity(&self, dry_run: bool) -> Result<u64>;
+    /// Deletes all image reports made at least `days` days ago, returning the number of deleted reports.
+    /// Only counts them if `dry_run`.
+    async fn purge_old_reports(&self, days: u32, dry_run: bool) -> Result<u64>;
+    /// Deletes all meals and sides last served at least `days` days ago, returning the number of deleted foods.
+    /// Meals with images are kept. Only counts them if `dry_run`.
+    async fn purge_unserved_foods(&self, days: u32, dry_run: bool) -> Result<u64>;
+    /// Returns all images which are not deleted but hidden or rejected since at least `days` days.
+    async fn get_long_hidden_images(&self, days: u32) -> Result<Vec<Uuid>>;
+    /// Returns all images whose file is stored locally, including deleted ones.
+    async fn get_stored_images(&self) -> Result<Vec<StoredImage>>;
+    /// Deletes an image like an administrator would, so it can still be restored during the grace period.
+    async fn delete_image(&self, image_id: Uuid, reason: &str) -> Result<()>;
+}
+
+/// An interface for collecting moderation activity to summarize in a digest. The moderation digest component uses this interface for database access.
+#[async_trait]
+pub trait DigestDataAccess: Send + Sync {
+    /// Returns the audit log entries of image reports, automatic hides, verifications and deletions not included in a digest yet, oldest first.
+    /// If no digest got sent so far, only the entries of the last day are returned.
+    async fn get_undigested_entries(&self) -> Result<Vec<AuditEntry>>;
+    /// Marks all audit log entries up to and including `last_entry_id` as included in a sent digest.
+    async fn mark_digested(&self, last_entry_id: i64) -> Result<()>;
+}
+
+/// An interface for electing a single backend instance running the scheduled jobs and sharing their state between all instances.
+/// The scheduler uses this interface for database access.
+#[async_trait]
+pub trait SchedulerLeaseDataAccess: Send + Sync {
+    /// Acquires or renews the lease `name` for `holder` for the given `duration`, unless it is held by another holder and not expired yet.
+    /// Returns whether `holder` now holds the lease.
+    async fn try_acquire_lease(&self, name: &str, holder: Uuid, duration: Duration)
+        -> Result<bool>;
+    /// Releases the lease `name` if it is held by `holder`, so other holders can acquire it right away.
+    async fn release_lease(&self, name: &str, holder: Uuid) -> Result<()>;
+    /// Returns the names of all jobs whose scheduled runs get skipped.
+    async fn get_paused_jobs(&self) -> Result<Vec<String>>;
+    /// Sets whether the scheduled runs of the job `name` get skipped.
+    async fn set_job_paused(&self, name: &str, paused: bool) -> Result<()>;
+}
+
+/// An interface for database requests of administrators. The admin api uses this interface for database access.
+#[async_trait]
+pub trait AdminDataAccess: Send + Sync {
+    /// Returns all images matching the given filter together with their reports, most recently uploaded first.
+    async fn get_moderation_images(
+        &self,
+        filter: &ModerationFilter,
+    ) -> Result<Vec<ModerationImage>>;
+    /// Returns all clients which reported at least `min_reports` images in the last `days` days, most reports first.
+    async fn get_mass_reporters(&self, min_reports: u32, days: u32)
+        -> Result<Vec<ReportingClient>>;
+    /// Returns all entries of the audit log matching the given filter, most recent first.
+    async fn get_audit_log(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>>;
+    /// Returns all banned clients, most recently banned first.
+    async fn get_banned_clients(&self) -> Result<Vec<ClientBan>>;
+    /// Returns all data stored about the given client.
+    async fn get_client_data(&self, client_id: Uuid) -> Result<ClientData>;
+    /// Returns all administrator accounts, ordered by username.
+    async fn get_admin_accounts(&self) -> Result<Vec<AdminAccount>>;
+    /// Returns the administrator account with the given username.
+    /// Returns [`DataError::NoSuchItem`] if there is no such account.
+    async fn get_admin_account(&self, username: &str) -> Result<AdminAccount>;
+    /// Creates an administrator account or updates the existing one with this username.
+    /// If `password_hash` is `None`, the password of an existing account is kept.
+    /// `saved_by` gets recorded as the one responsible.
+    async fn save_admin_account(
+        &self,
+        username: &str,
+        password_hash: Option<&str>,
+        role: AdminRole,
+        saved_by: &str,
+    ) -> Result<()>;
+    /// Returns the account of the given subject of an openid connect provider after updating its role.
+    /// On the first login of the subject, an account named `username` gets created,
+    /// or an existing account of this name without password and subject gets linked to the subject.
+    /// Returns `None` if the name is already taken by another account.
+    /// Creating, linking or changing the role of an account gets recorded as done by [`crate::util::SYSTEM_ACTOR`].
+    async fn save_oidc_account(
+        &self,
+        issuer: &str,
+        subject: &str,
+        username: &str,
+        role: AdminRole,
+    ) -> Result<Option<AdminAccount>>;
+    /// Deletes an administrator account together with its sessions.
+    /// `deleted_by` gets recorded as the one responsible.
+    /// Returns [`DataError::NoSuchItem`] if there is no such account.
+    async fn delete_admin_account(&self, username: &str, deleted_by: &str) -> Result<()>;
+    /// Adds a session of an administrator, of which only the hash of the token gets stored.
+    /// Expired sessions get removed.
+    async fn add_admin_session(
+        &self,
+        token_hash: &str,
+        username: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()>;
+    /// Returns the account the session with the given token hash belongs to.
+    /// Returns [`DataError::NoSuchItem`] if there is no such session or it expired.
+    async fn get_admin_session(&self, token_hash: &str) -> Result<AdminAccount>;
+    /// Ends the session with the given token hash.
+    async fn delete_admin_session(&self, token_hash: &str) -> Result<()>;
 }
 
 /// An interface for database access necessary for the authentication process.
 #[async_trait]
 pub trait AuthDataAccess: Sync + Send {
-    /// Loads all `api_keys` from the database.
-    async fn get_api_keys(&self) -> Result<Vec<ApiKey>>;
+    /// Returns the api key with exactly the given identifier, if it exists and did not get revoked.
+    async fn get_api_key(&self, ident: &str) -> Result<Option<ApiKey>>;
+    /// Returns information and usage statistics of all `api_keys`, including revoked ones, most recently created first.
+    async fn get_api_key_infos(&self) -> Result<Vec<ApiKeyInfo>>;
+    /// Adds a new api key, which only gets stored encrypted. Returns the id of the new key.
+    /// `added_by` gets recorded as the one responsible.
+    async fn add_api_key(
+        &self,
+        ident: &str,
+        key: &str,
+        description: &str,
+        scope: ApiKeyScope,
+        expiry_date: Option<Date>,
+        added_by: &str,
+    ) -> Result<Uuid>;
+    /// Replaces the secret of an api key by the given key, keeping all other settings.
+    /// `rotated_by` gets recorded as the one responsible.
+    /// Returns [`DataError::NoSuchItem`] if there is no such key which did not get revoked.
+    async fn rotate_api_key(
+        &self,
+        id: Uuid,
+        ident: &str,
+        key: &str,
+        rotated_by: &str,
+    ) -> Result<()>;
+    /// Revokes an api key, so it can no longer be used.
+    /// `revoked_by` gets recorded as the one responsible.
+    /// Returns [`DataError::NoSuchItem`] if there is no such key which did not get revoked already.
+    async fn revoke_api_key(&self, id: Uuid, revoked_by: &str) -> Result<()>;
+    /// Sets whether requests without replay protection are accepted with an api key.
+    /// `changed_by` gets recorded as the one responsible.
+    /// Returns [`DataError::NoSuchItem`] if there is no such key which did not get revoked.
+    async fn set_api_key_compatibility_mode(
+        &self,
+        id: Uuid,
+        enabled: bool,
+        changed_by: &str,
+    ) -> Result<()>;
+    /// Adds the given number of requests to the usage statistics of each api key.
+    async fn add_api_key_usage(&self, usage: &[(Uuid, u64)]) -> Result<()>;
+    /// Remembers that a nonce got used in a request with an api key until `expires_at`.
+    /// Returns `false` if it was already used with this key and did not expire yet, so the request is a replay.
+    async fn use_api_key_nonce(
+        &self,
+        key_id: Uuid,
+        nonce: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool>;
+    /// Removes all expired nonces, see [`Self::use_api_key_nonce`].
+    async fn remove_expired_api_key_nonces(&self) -> Result<()>;
 }
 
 #[async_trait]
@@ -248,6 +511,10 @@ pub trait RequestDataAccess: Send + Sync {
         meal_id: Uuid,
         client_id: Option<Uuid>,
     ) -> Result<Vec<Image>>;
+    /// Returns all images uploaded by the given user which did not get deleted, including pending and rejected ones, most recently uploaded first.
+    async fn get_my_images(&self, client_id: Uuid) -> Result<Vec<Image>>;
+    /// Returns all data stored about the given client.
+    async fn get_client_data(&self, client_id: Uuid) -> Result<ClientData>;
     /// Returns the rating done by the given user for the given meal.
     async fn get_personal_rating(&self, meal_id: Uuid, client_id: Uuid) -> Result<Option<u32>>;
     /// Checks if the given image got an upvote by the given user
@@ -1,16 +1,70 @@
 //! These structs are used for database operations.
-use crate::util::Price;
 use crate::util::{self, Date};
+use crate::util::{
+    AdminRole, ApiKeyScope, AuditAction, ClientAction, ModerationStatus, Price, ReportReason,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 
 use util::{FoodType, Uuid};
 
-/// Struct to storage related data. Contains all api-key related information.
+/// Struct to storage related data. Contains all api-key related information necessary for authentication.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ApiKey {
-    /// The api-key
+    /// Database-identification of the api-key.
+    pub id: Uuid,
+    /// Leading characters of the api-key, identifying it in requests.
+    pub ident: String,
+    /// Secret for signing requests, see <https://github.com/kronos-et-al/MensaApp/blob/main/doc/ApiAuth.md>.
     pub key: String,
     /// An short description for the api-key.
     pub description: String,
+    /// Requests allowed with this api-key.
+    pub scope: ApiKeyScope,
+    /// Last day the api-key is valid, if it expires.
+    pub expiry_date: Option<Date>,
+    /// Whether requests without timestamp and nonce, which can be replayed, are accepted.
+    pub compatibility_mode: bool,
+}
+
+/// This structure contains information and usage statistics about an api-key, without its secret.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct ApiKeyInfo {
+    /// Database-identification of the api-key.
+    pub id: Uuid,
+    /// Leading characters of the api-key, identifying it in requests.
+    pub ident: String,
+    /// An short description for the api-key.
+    pub description: String,
+    /// Requests allowed with this api-key.
+    pub scope: ApiKeyScope,
+    /// Whether the api-key was created before keys got stored encrypted, so it should be rotated.
+    pub legacy: bool,
+    /// Whether requests without timestamp and nonce, which can be replayed, are accepted.
+    pub compatibility_mode: bool,
+    /// Day the api-key got created.
+    pub created_date: Date,
+    /// Last day the api-key is valid, if it expires.
+    pub expiry_date: Option<Date>,
+    /// Day the api-key got revoked, if so.
+    pub revoked_date: Option<Date>,
+    /// Number of authenticated requests made with this api-key.
+    pub request_count: u64,
+    /// Time of the last authenticated request made with this api-key.
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// This structure contains an account of an administrator using the admin api.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AdminAccount {
+    /// Name identifying the administrator.
+    pub username: String,
+    /// Argon2 hash of the password, `None` if the administrator logs in via openid connect.
+    pub password_hash: Option<String>,
+    /// Permissions of the administrator.
+    pub role: AdminRole,
+    /// Day the account got created.
+    pub created_date: Date,
 }
 
 /// Struct for database-operations. Related to the database entity 'canteen'.
@@ -96,6 +150,32 @@ pub struct Image {
     pub meal_id: Uuid,
     /// User ids of users that reported this image. May not be filled with data.
     pub reporting_users: Option<Vec<Uuid>>,
+    /// Client id of the user who uploaded this image.
+    pub uploader_id: Uuid,
+    /// State of the automatic moderation of this image.
+    pub moderation_status: ModerationStatus,
+}
+
+/// This structure contains all information of an uploaded image necessary to review it.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct PendingImage {
+    /// Database-identification of the image.
+    pub id: Uuid,
+    /// Name of the meal this image got uploaded for.
+    pub meal_name: String,
+    /// Number of reviews of this image that failed so far.
+    pub review_attempts: u32,
+}
+
+/// This structure contains all information of an image necessary to compare it to the stored image files.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StoredImage {
+    /// Database-identification of the image.
+    pub id: Uuid,
+    /// Date the image got uploaded.
+    pub upload_date: Date,
+    /// Whether the image is deleted, but can still be restored.
+    pub deleted: bool,
 }
 
 /// This structure contains all information of an image necessary to file a report.
@@ -129,3 +209,216 @@ pub struct EnvironmentInfo {
     /// The maximum amount of stars for each category
     pub max_rating: u32,
 }
+
+/// This structure contains a single report of an image.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ImageReport {
+    /// Client id of the user who reported the image.
+    pub client_id: Uuid,
+    /// Reason the image got reported for.
+    pub reason: ReportReason,
+    /// Date the image got reported at.
+    pub date: Date,
+}
+
+/// This structure contains all information of an image administrators need to moderate it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ModerationImage {
+    /// Information about the image and its meal, see [`ExtendedImage`].
+    pub info: ExtendedImage,
+    /// Whether the image is currently hidden from users.
+    pub hidden: bool,
+    /// Date the image got deleted at, if it got deleted but not yet purged.
+    pub deletion_date: Option<Date>,
+    /// All reports of this image which were not dismissed, oldest first.
+    pub reports: Vec<ImageReport>,
+}
+
+/// The lists of images requiring the attention of administrators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModerationQueue {
+    /// Images which got reported but not yet verified.
+    #[default]
+    Reported,
+    /// Images which are hidden from users, e.g. because of too many reports.
+    Hidden,
+    /// Images which were not yet verified by an administrator.
+    Unverified,
+    /// Images which got deleted but can still be restored.
+    Deleted,
+}
+
+/// Criteria for selecting images to moderate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModerationFilter {
+    /// The list of images to select from.
+    pub queue: ModerationQueue,
+    /// Only select images of this meal.
+    pub meal_id: Option<Uuid>,
+    /// Only select images reported at least once for this reason.
+    pub reason: Option<ReportReason>,
+    /// Only select images uploaded at or after this date.
+    pub from: Option<Date>,
+    /// Only select images uploaded at or before this date.
+    pub to: Option<Date>,
+    /// Maximum number of images to select.
+    pub limit: u32,
+    /// Number of images to skip, for pagination.
+    pub offset: u32,
+}
+
+/// This structure contains a single entry of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// Id of the entry, increasing over time.
+    pub id: i64,
+    /// Time the action happened at.
+    pub time: DateTime<Utc>,
+    /// Client id or administrator who took the action, [`util::SYSTEM_ACTOR`] for automatic actions.
+    pub actor: String,
+    /// The action taken.
+    pub action: AuditAction,
+    /// Id of the image, meal, client, api key or administrator account the action affected, nil for changes of the rank policy.
+    pub target_id: Uuid,
+    /// Reason given for the action, if any.
+    pub reason: Option<String>,
+}
+
+/// Criteria for selecting entries of the audit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditFilter {
+    /// Only select entries of this actor.
+    pub actor: Option<String>,
+    /// Only select entries of this action.
+    pub action: Option<AuditAction>,
+    /// Only select entries affecting this image, meal or client.
+    pub target_id: Option<Uuid>,
+    /// Only select entries at or after this date.
+    pub from: Option<Date>,
+    /// Only select entries at or before this date.
+    pub to: Option<Date>,
+    /// Maximum number of entries to select.
+    pub limit: u32,
+    /// Number of entries to skip, for pagination.
+    pub offset: u32,
+}
+
+/// Information about the previous uploads of a user, used to judge how trustworthy new uploads are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UploaderStats {
+    /// Number of images of this user verified by an administrator.
+    pub approved_images: u32,
+    /// Number of images of this user which got deleted by an administrator or rejected by the automatic validation.
+    pub removed_images: u32,
+}
+
+/// Settings for calculating the rank of images.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankPolicy {
+    /// How much a (weighted) report of an unverified image counts compared to a downvote.
+    pub report_penalty: f64,
+    /// Number of days new images get ranked higher.
+    pub new_image_days: u32,
+    /// Weights of reports with the given reason, reasons not listed have a weight of 1.
+    pub reason_weights: Vec<(ReportReason, f64)>,
+}
+
+/// This structure contains the shadow-ban of a client. Actions of banned clients get ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClientBan {
+    /// Id of the banned client.
+    pub client_id: Uuid,
+    /// Administrator who banned the client.
+    pub banned_by: String,
+    /// Reason for the ban, if given.
+    pub reason: Option<String>,
+    /// Date the client got banned at.
+    pub date: Date,
+}
+
+/// This structure contains the number of reports a client made recently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportingClient {
+    /// Id of the reporting client.
+    pub client_id: Uuid,
+    /// Number of images the client reported recently.
+    pub report_count: u32,
+    /// Whether the client is banned already.
+    pub banned: bool,
+}
+
+/// This structure contains all data stored about a single client, e.g. for answering a data access request.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct ClientData {
+    /// Id of the client.
+    pub client_id: Uuid,
+    /// Ratings of meals by the client.
+    pub meal_ratings: Vec<ClientMealRating>,
+    /// Votes for images by the client.
+    pub image_votes: Vec<ClientImageVote>,
+    /// Reports of images by the client.
+    pub image_reports: Vec<ClientImageReport>,
+    /// Images uploaded by the client.
+    pub images: Vec<ClientImage>,
+    /// Number of rate limited actions by the client per day.
+    pub activity: Vec<ClientActivity>,
+    /// Ban of the client, if any.
+    pub ban: Option<ClientBan>,
+}
+
+/// This structure contains the rating of a meal by a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ClientMealRating {
+    /// Id of the rated meal.
+    pub meal_id: Uuid,
+    /// Rating given.
+    pub rating: u32,
+}
+
+/// This structure contains the vote for an image by a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ClientImageVote {
+    /// Id of the voted image.
+    pub image_id: Uuid,
+    /// Whether the vote is an upvote, otherwise it is a downvote.
+    pub upvote: bool,
+}
+
+/// This structure contains the report of an image by a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ClientImageReport {
+    /// Id of the reported image.
+    pub image_id: Uuid,
+    /// Reason of the report.
+    pub reason: ReportReason,
+    /// Date of the report.
+    pub date: Date,
+    /// Whether an administrator dismissed the report.
+    pub dismissed: bool,
+}
+
+/// This structure contains an image uploaded by a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ClientImage {
+    /// Id of the image.
+    pub image_id: Uuid,
+    /// Id of the meal the image belongs to.
+    pub meal_id: Uuid,
+    /// Upload date of the image.
+    pub upload_date: Date,
+    /// State of the automatic moderation of this image.
+    pub moderation_status: ModerationStatus,
+    /// Date the image got deleted at, if it got deleted.
+    pub deletion_date: Option<Date>,
+}
+
+/// This structure contains how often a client did a rate limited action on a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ClientActivity {
+    /// Kind of action.
+    pub action: ClientAction,
+    /// Day the actions were done.
+    pub day: Date,
+    /// Number of actions that day.
+    pub count: u32,
+}
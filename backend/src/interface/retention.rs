@@ -0,0 +1,83 @@
+//! This interface allows starting the cleanup of data which is no longer needed.
+use std::fmt::Display;
+
+use async_trait::async_trait;
+
+use crate::util::Uuid;
+
+/// Interface allowing to start the cleanup of data which is no longer needed.
+#[async_trait]
+pub trait RetentionScheduling: Send + Sync {
+    /// Permanently removes all data whose retention period is over,
+    /// like deleted images which can no longer be restored.
+    /// In a dry run, nothing gets removed and the report contains what would have been removed.
+    async fn start_retention(&self) -> RetentionReport;
+
+    /// Compares the stored image files with the images in the database,
+    /// removing files without image and deleting images without file.
+    /// In a dry run, nothing gets removed and the report contains what would have been removed.
+    async fn start_image_reconciliation(&self) -> ReconciliationReport;
+}
+
+/// Summary of what a retention run removed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionReport {
+    /// Whether nothing got removed actually.
+    pub dry_run: bool,
+    /// Number of deleted images removed permanently after their grace period.
+    pub purged_images: usize,
+    /// Number of images hidden for too long which got deleted.
+    pub deleted_hidden_images: usize,
+    /// Number of removed outdated image reports.
+    pub removed_reports: u64,
+    /// Number of removed client activity entries of previous days.
+    pub removed_client_activity: u64,
+    /// Number of removed meals and sides which were not served for too long.
+    pub removed_foods: u64,
+}
+
+impl Display for RetentionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verb = if self.dry_run {
+            "would remove"
+        } else {
+            "removed"
+        };
+        write!(
+            f,
+            "Retention {verb} {} deleted images, {} images hidden for too long, {} outdated reports, {} client activity entries and {} foods no longer served.",
+            self.purged_images,
+            self.deleted_hidden_images,
+            self.removed_reports,
+            self.removed_client_activity,
+            self.removed_foods
+        )
+    }
+}
+
+/// Summary of the differences between stored image files and images in the database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReconciliationReport {
+    /// Whether nothing got removed actually.
+    pub dry_run: bool,
+    /// Stored image files without image in the database, which got removed.
+    pub orphaned_files: Vec<Uuid>,
+    /// Images in the database without stored file, which got deleted.
+    pub missing_files: Vec<Uuid>,
+}
+
+impl Display for ReconciliationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verb = if self.dry_run {
+            "would remove"
+        } else {
+            "removed"
+        };
+        write!(
+            f,
+            "Image reconciliation {verb} {} files without image and {} images without file.",
+            self.orphaned_files.len(),
+            self.missing_files.len()
+        )
+    }
+}
@@ -0,0 +1,1017 @@
+//! Module responsible for handling database requests of administrators.
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    interface::persistent_data::{
+        model::{
+            AdminAccount, AuditEntry, AuditFilter, ClientActivity, ClientBan, ClientData,
+            ClientImage, ClientImageReport, ClientImageVote, ClientMealRating, ExtendedImage,
+            Image, ImageReport, ModerationFilter, ModerationImage, ModerationQueue,
+            ReportingClient,
+        },
+        AdminDataAccess, DataError, Result,
+    },
+    null_error,
+    util::{
+        image_id_to_url, AdminRole, AuditAction, ClientAction, ModerationStatus, ReportReason,
+        Uuid, SYSTEM_ACTOR,
+    },
+};
+
+use super::audit::add_audit_entry;
+
+/// Class implementing all database requests arising from the admin api.
+#[derive(Debug)]
+pub struct PersistentAdminData {
+    pub(super) pool: Pool<Postgres>,
+}
+
+#[async_trait]
+#[allow(clippy::missing_panics_doc)] // necessary because sqlx macro sometimes create unreachable panics?
+impl AdminDataAccess for PersistentAdminData {
+    async fn get_moderation_images(
+        &self,
+        filter: &ModerationFilter,
+    ) -> Result<Vec<ModerationImage>> {
+        let queue = match filter.queue {
+            ModerationQueue::Reported => "REPORTED",
+            ModerationQueue::Hidden => "HIDDEN",
+            ModerationQueue::Unverified => "UNVERIFIED",
+            ModerationQueue::Deleted => "DELETED",
+        };
+
+        let records = sqlx::query!(
+            r#"
+            SELECT approved, link_date as upload_date, report_count, currently_visible, deletion_date,
+            upvotes, downvotes, image_id, rank, food_id, f.name as meal_name,
+            user_id, moderation_status as "moderation_status: ModerationStatus"
+            FROM image_detail i JOIN food f USING (food_id)
+            WHERE moderation_status <> 'REJECTED'
+            AND CASE $1
+                WHEN 'REPORTED' THEN report_count > 0 AND NOT approved AND deletion_date IS NULL
+                WHEN 'HIDDEN' THEN NOT currently_visible AND deletion_date IS NULL
+                WHEN 'DELETED' THEN deletion_date IS NOT NULL
+                ELSE NOT approved AND deletion_date IS NULL
+            END
+            AND ($2::uuid IS NULL OR food_id = $2)
+            AND ($3::report_reason IS NULL OR EXISTS (
+                SELECT FROM image_report r WHERE r.image_id = i.image_id AND r.reason = $3 AND NOT dismissed
+            ))
+            AND ($4::date IS NULL OR link_date >= $4)
+            AND ($5::date IS NULL OR link_date <= $5)
+            ORDER BY link_date DESC, image_id
+            LIMIT $6 OFFSET $7
+            "#,
+            queue,
+            filter.meal_id,
+            filter.reason as _,
+            filter.from,
+            filter.to,
+            i64::from(filter.limit),
+            i64::from(filter.offset),
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let image_ids = records
+            .iter()
+            .map(|r| Ok(null_error!(r.image_id)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut reports = self.get_reports(&image_ids).await?;
+        let meal_ids = records
+            .iter()
+            .map(|r| Ok(null_error!(r.food_id)))
+            .collect::<Result<Vec<_>>>()?;
+        let meal_images = self.get_visible_images(&meal_ids).await?;
+
+        let mut images = Vec::with_capacity(records.len());
+        for record in records {
+            let id = null_error!(record.image_id);
+            let meal_id = null_error!(record.food_id);
+            images.push(ModerationImage {
+                info: ExtendedImage {
+                    image: Image {
+                        approved: null_error!(record.approved),
+                        rank: null_error!(record.rank),
+                        report_count: u32::try_from(null_error!(record.report_count))?,
+                        upload_date: null_error!(record.upload_date),
+                        downvotes: u32::try_from(null_error!(record.downvotes))?,
+                        upvotes: u32::try_from(null_error!(record.upvotes))?,
+                        id,
+                        meal_id,
+                        reporting_users: Option::default(),
+                        uploader_id: null_error!(record.user_id),
+                        moderation_status: null_error!(record.moderation_status),
+                    },
+                    meal_name: record.meal_name,
+                    other_image_urls: meal_images
+                        .get(&meal_id)
+                        .into_iter()
+                        .flatten()
+                        .filter(|&&image_id| image_id != id)
+                        .map(|&image_id| image_id_to_url(image_id))
+                        .collect(),
+                },
+                hidden: !null_error!(record.currently_visible),
+                deletion_date: record.deletion_date,
+                reports: reports.remove(&id).unwrap_or_default(),
+            });
+        }
+        Ok(images)
+    }
+
+    async fn get_mass_reporters(
+        &self,
+        min_reports: u32,
+        days: u32,
+    ) -> Result<Vec<ReportingClient>> {
+        let records = sqlx::query!(
+            r#"
+            SELECT user_id, COUNT(*) as "report_count!",
+            EXISTS (SELECT FROM client_ban WHERE client_id = user_id) as "banned!"
+            FROM image_report
+            WHERE report_date >= CURRENT_DATE - $2::integer
+            GROUP BY user_id
+            HAVING COUNT(*) >= $1
+            ORDER BY COUNT(*) DESC, user_id
+            "#,
+            i64::from(min_reports),
+            i32::try_from(days)?
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        records
+            .into_iter()
+            .map(|r| {
+                Ok(ReportingClient {
+                    client_id: r.user_id,
+                    report_count: u32::try_from(r.report_count)?,
+                    banned: r.banned,
+                })
+            })
+            .collect()
+    }
+
+    async fn get_audit_log(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        let entries = sqlx::query_as!(
+            AuditEntry,
+            r#"
+            SELECT audit_id as id, created_at as time, actor, action as "action: AuditAction", target_id, reason
+            FROM audit_log
+            WHERE ($1::text IS NULL OR actor = $1)
+            AND ($2::audit_action IS NULL OR action = $2)
+            AND ($3::uuid IS NULL OR target_id = $3)
+            AND ($4::date IS NULL OR created_at >= $4::date)
+            AND ($5::date IS NULL OR created_at < $5::date + 1)
+            ORDER BY audit_id DESC
+            LIMIT $6 OFFSET $7
+            "#,
+            filter.actor,
+            filter.action as _,
+            filter.target_id,
+            filter.from,
+            filter.to,
+            i64::from(filter.limit),
+            i64::from(filter.offset),
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    async fn get_banned_clients(&self) -> Result<Vec<ClientBan>> {
+        let bans = sqlx::query!(
+            "SELECT client_id, banned_by, reason, ban_date FROM client_ban ORDER BY ban_date DESC, client_id"
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|r| ClientBan {
+            client_id: r.client_id,
+            banned_by: r.banned_by,
+            reason: r.reason,
+            date: r.ban_date,
+        })
+        .collect();
+        Ok(bans)
+    }
+
+    async fn get_client_data(&self, client_id: Uuid) -> Result<ClientData> {
+        get_client_data(&self.pool, client_id).await
+    }
+
+    async fn get_admin_accounts(&self) -> Result<Vec<AdminAccount>> {
+        let accounts = sqlx::query_as!(
+            AdminAccount,
+            r#"
+            SELECT username, password_hash, role as "role: AdminRole", created_date
+            FROM admin_account ORDER BY username
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(accounts)
+    }
+
+    async fn get_admin_account(&self, username: &str) -> Result<AdminAccount> {
+        sqlx::query_as!(
+            AdminAccount,
+            r#"
+            SELECT username, password_hash, role as "role: AdminRole", created_date
+            FROM admin_account WHERE username = $1
+            "#,
+            username
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(DataError::NoSuchItem)
+    }
+
+    async fn save_admin_account(
+        &self,
+        username: &str,
+        password_hash: Option<&str>,
+        role: AdminRole,
+        saved_by: &str,
+    ) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let account_id = sqlx::query_scalar!(
+            "
+            INSERT INTO admin_account (username, password_hash, role) VALUES ($1, $2, $3)
+            ON CONFLICT (username) DO UPDATE
+            SET password_hash = COALESCE($2, admin_account.password_hash), role = $3
+            RETURNING account_id
+            ",
+            username,
+            password_hash,
+            role as _
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+        let password = if password_hash.is_some() {
+            ", new password"
+        } else {
+            ""
+        };
+        add_audit_entry(
+            &mut transaction,
+            saved_by,
+            AuditAction::SaveAdminAccount,
+            account_id,
+            Some(&format!("{username} ({role}{password})")),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn save_oidc_account(
+        &self,
+        issuer: &str,
+        subject: &str,
+        username: &str,
+        role: AdminRole,
+    ) -> Result<Option<AdminAccount>> {
+        let mut transaction = self.pool.begin().await?;
+        // the joined row still contains the role before the update
+        let existing = sqlx::query!(
+            r#"
+            UPDATE admin_account a SET role = $3 FROM admin_account previous
+            WHERE a.username = previous.username AND a.oidc_issuer = $1 AND a.oidc_subject = $2
+            RETURNING a.account_id, a.username, a.password_hash, a.role as "role: AdminRole", a.created_date,
+            previous.role as "previous_role: AdminRole"
+            "#,
+            issuer,
+            subject,
+            role as _
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if let Some(existing) = existing {
+            if existing.previous_role != role {
+                add_audit_entry(
+                    &mut transaction,
+                    SYSTEM_ACTOR,
+                    AuditAction::SaveAdminAccount,
+                    existing.account_id,
+                    Some(&format!("{} ({role}) via {issuer}", existing.username)),
+                )
+                .await?;
+            }
+            transaction.commit().await?;
+            return Ok(Some(AdminAccount {
+                username: existing.username,
+                password_hash: existing.password_hash,
+                role: existing.role,
+                created_date: existing.created_date,
+            }));
+        }
+
+        // accounts with password or of another subject must not be taken over
+        let account = sqlx::query!(
+            r#"
+            INSERT INTO admin_account (username, role, oidc_issuer, oidc_subject) VALUES ($3, $4, $1, $2)
+            ON CONFLICT (username) DO UPDATE
+            SET role = $4, oidc_issuer = $1, oidc_subject = $2
+            WHERE admin_account.password_hash IS NULL AND admin_account.oidc_subject IS NULL
+            RETURNING account_id, username, password_hash, role as "role: AdminRole", created_date
+            "#,
+            issuer,
+            subject,
+            username,
+            role as _
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        let Some(account) = account else {
+            return Ok(None);
+        };
+        add_audit_entry(
+            &mut transaction,
+            SYSTEM_ACTOR,
+            AuditAction::SaveAdminAccount,
+            account.account_id,
+            Some(&format!("{username} ({role}) via {issuer}")),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(Some(AdminAccount {
+            username: account.username,
+            password_hash: account.password_hash,
+            role: account.role,
+            created_date: account.created_date,
+        }))
+    }
+
+    async fn delete_admin_account(&self, username: &str, deleted_by: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let account_id = sqlx::query_scalar!(
+            "DELETE FROM admin_account WHERE username = $1 RETURNING account_id",
+            username
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(DataError::NoSuchItem)?;
+        add_audit_entry(
+            &mut transaction,
+            deleted_by,
+            AuditAction::DeleteAdminAccount,
+            account_id,
+            Some(username),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn add_admin_session(
+        &self,
+        token_hash: &str,
+        username: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query!("DELETE FROM admin_session WHERE expires_at < now()")
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO admin_session (token_hash, username, expires_at) VALUES ($1, $2, $3)",
+            token_hash,
+            username,
+            expires_at
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_admin_session(&self, token_hash: &str) -> Result<AdminAccount> {
+        sqlx::query_as!(
+            AdminAccount,
+            r#"
+            SELECT username, password_hash, role as "role: AdminRole", created_date
+            FROM admin_session JOIN admin_account USING (username)
+            WHERE token_hash = $1 AND expires_at > now()
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(DataError::NoSuchItem)
+    }
+
+    async fn delete_admin_session(&self, token_hash: &str) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM admin_session WHERE token_hash = $1",
+            token_hash
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl PersistentAdminData {
+    /// Returns the visible images of the given meals, best ranked first.
+    async fn get_visible_images(&self, meal_ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<Uuid>>> {
+        let records = sqlx::query!(
+            r#"
+            SELECT food_id as "food_id!", image_id as "image_id!" FROM image_detail
+            WHERE currently_visible AND moderation_status = 'ACCEPTED' AND food_id = ANY($1)
+            AND user_id NOT IN (SELECT client_id FROM client_ban)
+            ORDER BY rank DESC, image_id
+            "#,
+            meal_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut images: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        for r in records {
+            images.entry(r.food_id).or_default().push(r.image_id);
+        }
+        Ok(images)
+    }
+
+    async fn get_reports(&self, image_ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<ImageReport>>> {
+        let records = sqlx::query!(
+            r#"
+            SELECT image_id, user_id, reason as "reason: ReportReason", report_date
+            FROM image_report WHERE image_id = ANY($1) AND NOT dismissed
+            ORDER BY report_date, user_id
+            "#,
+            image_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut reports: HashMap<Uuid, Vec<ImageReport>> = HashMap::new();
+        for r in records {
+            reports.entry(r.image_id).or_default().push(ImageReport {
+                client_id: r.user_id,
+                reason: r.reason,
+                date: r.report_date,
+            });
+        }
+        Ok(reports)
+    }
+}
+
+/// Returns all data stored about the given client.
+pub(super) async fn get_client_data(pool: &Pool<Postgres>, client_id: Uuid) -> Result<ClientData> {
+    let meal_ratings = sqlx::query!(
+        "SELECT food_id, rating FROM meal_rating WHERE user_id = $1 ORDER BY food_id",
+        client_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|r| {
+        Ok(ClientMealRating {
+            meal_id: r.food_id,
+            rating: u32::try_from(r.rating)?,
+        })
+    })
+    .collect::<Result<_>>()?;
+
+    let image_votes = sqlx::query!(
+        "SELECT image_id, rating FROM image_rating WHERE user_id = $1 ORDER BY image_id",
+        client_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|r| ClientImageVote {
+        image_id: r.image_id,
+        upvote: r.rating > 0,
+    })
+    .collect();
+
+    let image_reports = sqlx::query!(
+        r#"
+        SELECT image_id, reason as "reason: ReportReason", report_date, dismissed
+        FROM image_report WHERE user_id = $1 ORDER BY report_date, image_id
+        "#,
+        client_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|r| ClientImageReport {
+        image_id: r.image_id,
+        reason: r.reason,
+        date: r.report_date,
+        dismissed: r.dismissed,
+    })
+    .collect();
+
+    let images = sqlx::query!(
+        r#"
+        SELECT image_id, food_id, link_date, moderation_status as "moderation_status: ModerationStatus", deletion_date
+        FROM image WHERE user_id = $1 ORDER BY link_date, image_id
+        "#,
+        client_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|r| ClientImage {
+        image_id: r.image_id,
+        meal_id: r.food_id,
+        upload_date: r.link_date,
+        moderation_status: r.moderation_status,
+        deletion_date: r.deletion_date,
+    })
+    .collect();
+
+    let activity = sqlx::query!(
+        r#"
+        SELECT action as "action: ClientAction", day, count
+        FROM client_activity WHERE client_id = $1 ORDER BY day, action
+        "#,
+        client_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|r| {
+        Ok(ClientActivity {
+            action: r.action,
+            day: r.day,
+            count: u32::try_from(r.count)?,
+        })
+    })
+    .collect::<Result<_>>()?;
+
+    let ban = get_client_ban(pool, client_id).await?;
+
+    Ok(ClientData {
+        client_id,
+        meal_ratings,
+        image_votes,
+        image_reports,
+        images,
+        activity,
+        ban,
+    })
+}
+
+async fn get_client_ban(pool: &Pool<Postgres>, client_id: Uuid) -> Result<Option<ClientBan>> {
+    let ban = sqlx::query!(
+        "SELECT client_id, banned_by, reason, ban_date FROM client_ban WHERE client_id = $1",
+        client_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|r| ClientBan {
+        client_id: r.client_id,
+        banned_by: r.banned_by,
+        reason: r.reason,
+        date: r.ban_date,
+    });
+    Ok(ban)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use chrono::Local;
+    use sqlx::PgPool;
+
+    use super::*;
+
+    const MEAL_ID: Uuid = Uuid::from_u128(0xf733_7122_b018_48ad_b420_6202_dc3c_b4ff);
+    const REPORTED_ID: Uuid = Uuid::from_u128(0xea8c_ce48_a3c7_4f8e_a222_5f38_91c1_3804);
+    const HIDDEN_ID: Uuid = Uuid::from_u128(0x6815_3ab6_ebbf_48f4_b8dd_a9b2_a19a_5221);
+
+    fn filter(queue: ModerationQueue) -> ModerationFilter {
+        ModerationFilter {
+            queue,
+            meal_id: None,
+            reason: None,
+            from: None,
+            to: None,
+            limit: 100,
+            offset: 0,
+        }
+    }
+
+    fn ids(images: &[ModerationImage]) -> Vec<Uuid> {
+        images.iter().map(|i| i.info.image.id).collect()
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_get_reported_images(pool: PgPool) {
+        let admin = PersistentAdminData { pool };
+
+        let images = admin
+            .get_moderation_images(&filter(ModerationQueue::Reported))
+            .await
+            .unwrap();
+        assert_eq!(ids(&images), vec![REPORTED_ID]);
+
+        let image = &images[0];
+        assert!(!image.hidden);
+        assert_eq!(
+            image.info.meal_name,
+            "Geflügel - Cevapcici, Ajvar, Djuvec Reis"
+        );
+        assert_eq!(image.info.image.report_count, 1);
+        assert_eq!(image.info.other_image_urls.len(), 2);
+        assert_eq!(
+            image.reports,
+            vec![ImageReport {
+                client_id: Uuid::parse_str("c51d2d81-3547-4f07-af58-ed613c6ece67").unwrap(),
+                reason: ReportReason::Advert,
+                date: Local::now().date_naive().succ_opt().unwrap(),
+            }]
+        );
+    }
+
+    #[sqlx::test(fixtures("meal", "image", "pending_image"))]
+    async fn test_get_hidden_and_unverified_images(pool: PgPool) {
+        let admin = PersistentAdminData { pool };
+
+        let hidden = admin
+            .get_moderation_images(&filter(ModerationQueue::Hidden))
+            .await
+            .unwrap();
+        assert_eq!(ids(&hidden), vec![HIDDEN_ID]);
+        assert!(hidden[0].hidden);
+        assert!(hidden[0].reports.is_empty());
+
+        // approved and rejected images are not listed
+        let unverified = admin
+            .get_moderation_images(&filter(ModerationQueue::Unverified))
+            .await
+            .unwrap();
+        assert_eq!(unverified.len(), 6);
+        assert!(unverified.iter().all(|i| !i.info.image.approved
+            && i.info.image.moderation_status != ModerationStatus::Rejected));
+    }
+
+    #[sqlx::test(fixtures("meal", "image", "pending_image"))]
+    async fn test_filter_images(pool: PgPool) {
+        let admin = PersistentAdminData { pool };
+        let today = Local::now().date_naive();
+
+        let by_reason = |reason| ModerationFilter {
+            reason: Some(reason),
+            ..filter(ModerationQueue::Unverified)
+        };
+        let images = admin
+            .get_moderation_images(&by_reason(ReportReason::Advert))
+            .await
+            .unwrap();
+        assert_eq!(ids(&images), vec![REPORTED_ID]);
+        let images = admin
+            .get_moderation_images(&by_reason(ReportReason::Offensive))
+            .await
+            .unwrap();
+        assert!(images.is_empty());
+
+        let by_meal = |meal_id| ModerationFilter {
+            meal_id: Some(meal_id),
+            ..filter(ModerationQueue::Unverified)
+        };
+        let images = admin
+            .get_moderation_images(&by_meal(MEAL_ID))
+            .await
+            .unwrap();
+        assert_eq!(images.len(), 6);
+        let images = admin
+            .get_moderation_images(&by_meal(Uuid::default()))
+            .await
+            .unwrap();
+        assert!(images.is_empty());
+
+        // pending image uploaded yesterday
+        let images = admin
+            .get_moderation_images(&ModerationFilter {
+                to: today.pred_opt(),
+                ..filter(ModerationQueue::Unverified)
+            })
+            .await
+            .unwrap();
+        assert_eq!(images.len(), 1);
+        let images = admin
+            .get_moderation_images(&ModerationFilter {
+                from: Some(today),
+                ..filter(ModerationQueue::Unverified)
+            })
+            .await
+            .unwrap();
+        assert_eq!(images.len(), 5);
+
+        let images = admin
+            .get_moderation_images(&ModerationFilter {
+                limit: 2,
+                offset: 5,
+                ..filter(ModerationQueue::Unverified)
+            })
+            .await
+            .unwrap();
+        assert_eq!(images.len(), 1);
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_get_mass_reporters(pool: PgPool) {
+        let admin = PersistentAdminData { pool: pool.clone() };
+        let client_id = Uuid::parse_str("c51d2d81-3547-4f07-af58-ed613c6ece67").unwrap();
+
+        let reporters = admin.get_mass_reporters(1, 1).await.unwrap();
+        assert_eq!(
+            reporters,
+            vec![ReportingClient {
+                client_id,
+                report_count: 1,
+                banned: false
+            }]
+        );
+        assert!(admin.get_mass_reporters(2, 1).await.unwrap().is_empty());
+
+        sqlx::query!(
+            "INSERT INTO client_ban (client_id, banned_by) VALUES ($1, 'admin')",
+            client_id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert!(admin.get_mass_reporters(1, 1).await.unwrap()[0].banned);
+
+        let bans = admin.get_banned_clients().await.unwrap();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].client_id, client_id);
+        assert_eq!(bans[0].banned_by, "admin");
+        assert_eq!(bans[0].reason, None);
+    }
+
+    #[sqlx::test(fixtures("meal", "image", "rating"))]
+    async fn test_get_client_data(pool: PgPool) {
+        let admin = PersistentAdminData { pool };
+        let client_id = Uuid::parse_str("c51d2d81-3547-4f07-af58-ed613c6ece67").unwrap();
+
+        let data = admin.get_client_data(client_id).await.unwrap();
+        assert_eq!(data.client_id, client_id);
+        assert_eq!(data.images.len(), 3);
+        assert_eq!(data.image_reports.len(), 1);
+        assert_eq!(data.image_reports[0].image_id, REPORTED_ID);
+        assert_eq!(data.meal_ratings.len(), 2);
+        assert_eq!(data.image_votes.len(), 2);
+        assert!(data.image_votes.iter().all(|v| v.upvote));
+        assert_eq!(data.ban, None);
+
+        let nobody = admin.get_client_data(Uuid::default()).await.unwrap();
+        assert_eq!(
+            nobody,
+            ClientData {
+                client_id: Uuid::default(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_get_audit_log(pool: PgPool) {
+        let admin = PersistentAdminData { pool: pool.clone() };
+        let image_id = Uuid::from_u128(1);
+        let client_id = Uuid::from_u128(2);
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_log (actor, action, target_id, reason) VALUES
+            ($2, 'REPORT_IMAGE', $1, 'Advert'), ('system', 'HIDE_IMAGE', $1, NULL),
+            ('admin', 'DELETE_IMAGE', $1, NULL), ('admin', 'BAN_CLIENT', $2::uuid, 'spam')
+            "#,
+            image_id,
+            client_id.to_string(),
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let filter = AuditFilter {
+            actor: None,
+            action: None,
+            target_id: None,
+            from: None,
+            to: None,
+            limit: 10,
+            offset: 0,
+        };
+        let entries = admin.get_audit_log(&filter).await.unwrap();
+        assert_eq!(entries.len(), 4);
+        // most recent first
+        assert_eq!(entries[0].action, AuditAction::BanClient);
+        assert_eq!(entries[0].reason.as_deref(), Some("spam"));
+        assert_eq!(entries[3].actor, client_id.to_string());
+
+        let history = AuditFilter {
+            target_id: Some(image_id),
+            ..filter.clone()
+        };
+        assert_eq!(admin.get_audit_log(&history).await.unwrap().len(), 3);
+        let by_admin = AuditFilter {
+            actor: Some("admin".into()),
+            action: Some(AuditAction::DeleteImage),
+            ..filter.clone()
+        };
+        let entries = admin.get_audit_log(&by_admin).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].target_id, image_id);
+
+        let today = Local::now().date_naive();
+        let page = AuditFilter {
+            from: Some(today),
+            to: Some(today),
+            limit: 2,
+            offset: 3,
+            ..filter.clone()
+        };
+        assert_eq!(admin.get_audit_log(&page).await.unwrap().len(), 1);
+        let tomorrow = AuditFilter {
+            from: today.succ_opt(),
+            ..filter
+        };
+        assert!(admin.get_audit_log(&tomorrow).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_admin_accounts(pool: PgPool) {
+        let admin = PersistentAdminData { pool: pool.clone() };
+
+        admin
+            .save_admin_account("alice", Some("hash"), AdminRole::Operator, "admin")
+            .await
+            .unwrap();
+        admin
+            .save_admin_account("bob", None, AdminRole::Moderator, "admin")
+            .await
+            .unwrap();
+        // updating keeps the password if none is given
+        admin
+            .save_admin_account("alice", None, AdminRole::Moderator, "admin")
+            .await
+            .unwrap();
+
+        let accounts = admin.get_admin_accounts().await.unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].username, "alice");
+        assert_eq!(accounts[0].password_hash.as_deref(), Some("hash"));
+        assert_eq!(accounts[0].role, AdminRole::Moderator);
+        assert_eq!(accounts[1].password_hash, None);
+
+        assert_eq!(
+            admin.get_admin_account("bob").await.unwrap().role,
+            AdminRole::Moderator
+        );
+        assert!(matches!(
+            admin.get_admin_account("carol").await,
+            Err(DataError::NoSuchItem)
+        ));
+
+        admin.delete_admin_account("bob", "admin").await.unwrap();
+        assert!(matches!(
+            admin.delete_admin_account("bob", "admin").await,
+            Err(DataError::NoSuchItem)
+        ));
+
+        let entries = sqlx::query!(
+            r#"SELECT action as "action: AuditAction", reason FROM audit_log WHERE actor = 'admin' ORDER BY audit_id"#
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            entries
+                .into_iter()
+                .map(|e| (e.action, e.reason.unwrap_or_default()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    AuditAction::SaveAdminAccount,
+                    "alice (OPERATOR, new password)".into()
+                ),
+                (AuditAction::SaveAdminAccount, "bob (MODERATOR)".into()),
+                (AuditAction::SaveAdminAccount, "alice (MODERATOR)".into()),
+                (AuditAction::DeleteAdminAccount, "bob".into()),
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_oidc_accounts(pool: PgPool) {
+        let admin = PersistentAdminData { pool: pool.clone() };
+        let issuer = "https://example.org";
+        admin
+            .save_admin_account("alice", Some("hash"), AdminRole::Operator, "admin")
+            .await
+            .unwrap();
+        admin
+            .save_admin_account("bob", None, AdminRole::Moderator, "admin")
+            .await
+            .unwrap();
+
+        // accounts with password can not be taken over
+        let taken = admin
+            .save_oidc_account(issuer, "sub-1", "alice", AdminRole::Operator)
+            .await
+            .unwrap();
+        assert_eq!(taken, None);
+
+        // accounts without password get linked on the first login
+        let linked = admin
+            .save_oidc_account(issuer, "sub-2", "bob", AdminRole::Operator)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(linked.username, "bob");
+        assert_eq!(linked.role, AdminRole::Operator);
+        let taken = admin
+            .save_oidc_account(issuer, "sub-3", "bob", AdminRole::Operator)
+            .await
+            .unwrap();
+        assert_eq!(taken, None);
+
+        // the subject keeps its account when its name changes at the provider
+        let renamed = admin
+            .save_oidc_account(issuer, "sub-2", "robert", AdminRole::Moderator)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(renamed.username, "bob");
+        assert_eq!(renamed.role, AdminRole::Moderator);
+
+        let created = admin
+            .save_oidc_account(issuer, "sub-3", "carol", AdminRole::Moderator)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(created.password_hash, None);
+        assert_eq!(admin.get_admin_accounts().await.unwrap().len(), 3);
+
+        // logins only get recorded if the account changed
+        admin
+            .save_oidc_account(issuer, "sub-3", "carol", AdminRole::Moderator)
+            .await
+            .unwrap();
+        let reasons = sqlx::query_scalar!(
+            "SELECT reason FROM audit_log WHERE actor = 'system' AND action = 'SAVE_ADMIN_ACCOUNT' ORDER BY audit_id"
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            reasons,
+            [
+                "bob (OPERATOR) via https://example.org",
+                "bob (MODERATOR) via https://example.org",
+                "carol (MODERATOR) via https://example.org"
+            ]
+            .map(|r| Some(r.to_owned()))
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_admin_sessions(pool: PgPool) {
+        let admin = PersistentAdminData { pool };
+        admin
+            .save_admin_account("alice", None, AdminRole::Operator, "admin")
+            .await
+            .unwrap();
+
+        let expiry = Utc::now() + chrono::Duration::hours(1);
+        admin
+            .add_admin_session("token", "alice", expiry)
+            .await
+            .unwrap();
+        admin
+            .add_admin_session("expired", "alice", Utc::now() - chrono::Duration::hours(1))
+            .await
+            .unwrap();
+
+        let account = admin.get_admin_session("token").await.unwrap();
+        assert_eq!(account.username, "alice");
+        assert_eq!(account.role, AdminRole::Operator);
+        assert!(matches!(
+            admin.get_admin_session("expired").await,
+            Err(DataError::NoSuchItem)
+        ));
+
+        admin.delete_admin_session("token").await.unwrap();
+        assert!(admin.get_admin_session("token").await.is_err());
+
+        // sessions end with their account
+        admin
+            .add_admin_session("other", "alice", expiry)
+            .await
+            .unwrap();
+        admin.delete_admin_account("alice", "admin").await.unwrap();
+        assert!(admin.get_admin_session("other").await.is_err());
+    }
+}
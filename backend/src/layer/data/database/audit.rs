@@ -0,0 +1,57 @@
+//! Recording of actions in the append-only audit log, shared by all data access components.
+//! Entries get written in the transaction of the recorded action, so they exist exactly if the action took effect.
+
+use sqlx::PgConnection;
+
+use crate::{
+    interface::persistent_data::Result,
+    util::{AuditAction, Uuid},
+};
+
+/// Appends an entry to the audit log as part of the transaction `conn` belongs to.
+pub(super) async fn add_audit_entry(
+    conn: &mut PgConnection,
+    actor: &str,
+    action: AuditAction,
+    target_id: Uuid,
+    reason: Option<&str>,
+) -> Result<()> {
+    sqlx::query!(
+        "INSERT INTO audit_log (actor, action, target_id, reason) VALUES ($1, $2, $3, $4)",
+        actor,
+        action as _,
+        target_id,
+        reason
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Replaces the id of a client by a new random pseudonym in all entries of the audit log, which is returned.
+/// The entries stay linked to each other, but can no longer be linked to the client.
+/// This is the only change of the audit log allowed, and only as part of the transaction `conn` belongs to.
+pub(super) async fn pseudonymise_client(conn: &mut PgConnection, client_id: Uuid) -> Result<Uuid> {
+    let pseudonym = Uuid::new_v4();
+    sqlx::query!("SELECT set_config('mensa.pseudonymise_audit_log', 'on', true)")
+        .fetch_one(&mut *conn)
+        .await?;
+    sqlx::query!(
+        "UPDATE audit_log SET actor = $2 WHERE actor = $1",
+        client_id.to_string(),
+        pseudonym.to_string()
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "UPDATE audit_log SET target_id = $2 WHERE target_id = $1",
+        client_id,
+        pseudonym
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!("SELECT set_config('mensa.pseudonymise_audit_log', 'off', true)")
+        .fetch_one(&mut *conn)
+        .await?;
+    Ok(pseudonym)
+}
@@ -1,56 +1,484 @@
 //! Module responsible for handling database requests for the authentication process.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::{Pool, Postgres};
 
-use crate::interface::persistent_data::{model::ApiKey, AuthDataAccess, Result};
+use crate::{
+    interface::persistent_data::{
+        model::{ApiKey, ApiKeyInfo},
+        AuthDataAccess, DataError, Result,
+    },
+    null_error,
+    util::{ApiKeyScope, AuditAction, Date, Uuid},
+};
+
+use super::{audit::add_audit_entry, cipher::ApiKeyCipher};
 
 /// Class implementing all database requests arising from graphql manipulations.
 #[derive(Debug)]
 pub struct PersistentAuthData {
     pub(super) pool: Pool<Postgres>,
+    pub(super) cipher: ApiKeyCipher,
+}
+
+impl PersistentAuthData {
+    /// Encrypts the secrets of api keys which are still stored in plain text,
+    /// i.e. those created before secrets got encrypted or inserted into the database manually.
+    /// Returns the number of encrypted keys.
+    /// # Errors
+    /// if the keys could not be read or written
+    pub async fn encrypt_plain_keys(&self) -> Result<usize> {
+        let plain = sqlx::query!(
+            r#"
+            SELECT key_id, COALESCE(api_key, key_hash) as "key!" FROM api_key
+            WHERE encrypted_key IS NULL
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        for key in &plain {
+            sqlx::query!(
+                "
+                UPDATE api_key SET encrypted_key = $2, api_key = NULL, key_hash = NULL, legacy = true
+                WHERE key_id = $1
+                ",
+                key.key_id,
+                self.cipher.encrypt(&key.key)
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(plain.len())
+    }
 }
 
 #[async_trait]
 impl AuthDataAccess for PersistentAuthData {
-    async fn get_api_keys(&self) -> Result<Vec<ApiKey>> {
-        let keys = sqlx::query_as!(
-            ApiKey,
-            "SELECT api_key as key, description FROM api_key ORDER BY api_key"
+    async fn get_api_key(&self, ident: &str) -> Result<Option<ApiKey>> {
+        let Some(record) = sqlx::query!(
+            r#"
+            SELECT key_id, ident, encrypted_key, COALESCE(api_key, key_hash) as plain_key, description,
+                scope as "scope: ApiKeyScope", expiry_date, compatibility_mode
+            FROM api_key WHERE ident = $1 AND revoked_date IS NULL
+            "#,
+            ident
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let key = match record.encrypted_key {
+            Some(encrypted) => self.cipher.decrypt(&encrypted)?,
+            // not encrypted yet, see `encrypt_plain_keys`
+            None => null_error!(record.plain_key),
+        };
+        Ok(Some(ApiKey {
+            id: record.key_id,
+            ident: record.ident,
+            key,
+            description: record.description,
+            scope: record.scope,
+            expiry_date: record.expiry_date,
+            compatibility_mode: record.compatibility_mode,
+        }))
+    }
+
+    async fn get_api_key_infos(&self) -> Result<Vec<ApiKeyInfo>> {
+        sqlx::query!(
+            r#"
+            SELECT key_id, ident, description, scope as "scope: ApiKeyScope", legacy OR encrypted_key IS NULL as "legacy!",
+                compatibility_mode, created_date, expiry_date, revoked_date, request_count, last_used
+            FROM api_key ORDER BY created_date DESC, ident
+            "#
         )
         .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|r| {
+            Ok(ApiKeyInfo {
+                id: r.key_id,
+                ident: r.ident,
+                description: r.description,
+                scope: r.scope,
+                legacy: r.legacy,
+                compatibility_mode: r.compatibility_mode,
+                created_date: r.created_date,
+                expiry_date: r.expiry_date,
+                revoked_date: r.revoked_date,
+                request_count: u64::try_from(r.request_count)?,
+                last_used: r.last_used,
+            })
+        })
+        .collect()
+    }
+
+    async fn add_api_key(
+        &self,
+        ident: &str,
+        key: &str,
+        description: &str,
+        scope: ApiKeyScope,
+        expiry_date: Option<Date>,
+        added_by: &str,
+    ) -> Result<Uuid> {
+        let mut transaction = self.pool.begin().await?;
+        let id = sqlx::query_scalar!(
+            "
+            INSERT INTO api_key (ident, encrypted_key, description, scope, expiry_date)
+            VALUES ($1, $2, $3, $4, $5) RETURNING key_id
+            ",
+            ident,
+            self.cipher.encrypt(key),
+            description,
+            scope as _,
+            expiry_date
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            added_by,
+            AuditAction::AddApiKey,
+            id,
+            Some(&format!("scope {scope}")),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(id)
+    }
+
+    async fn rotate_api_key(
+        &self,
+        id: Uuid,
+        ident: &str,
+        key: &str,
+        rotated_by: &str,
+    ) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let result = sqlx::query!(
+            "
+            UPDATE api_key SET ident = $2, encrypted_key = $3, api_key = NULL, key_hash = NULL, legacy = false
+            WHERE key_id = $1 AND revoked_date IS NULL
+            ",
+            id,
+            ident,
+            self.cipher.encrypt(key)
+        )
+        .execute(&mut *transaction)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(DataError::NoSuchItem);
+        }
+        add_audit_entry(
+            &mut transaction,
+            rotated_by,
+            AuditAction::RotateApiKey,
+            id,
+            None,
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn revoke_api_key(&self, id: Uuid, revoked_by: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let result = sqlx::query!(
+            "UPDATE api_key SET revoked_date = CURRENT_DATE WHERE key_id = $1 AND revoked_date IS NULL",
+            id
+        )
+        .execute(&mut *transaction)
         .await?;
+        if result.rows_affected() == 0 {
+            return Err(DataError::NoSuchItem);
+        }
+        add_audit_entry(
+            &mut transaction,
+            revoked_by,
+            AuditAction::RevokeApiKey,
+            id,
+            None,
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
 
-        Ok(keys)
+    async fn set_api_key_compatibility_mode(
+        &self,
+        id: Uuid,
+        enabled: bool,
+        changed_by: &str,
+    ) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let result = sqlx::query!(
+            "UPDATE api_key SET compatibility_mode = $2 WHERE key_id = $1 AND revoked_date IS NULL",
+            id,
+            enabled
+        )
+        .execute(&mut *transaction)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(DataError::NoSuchItem);
+        }
+        add_audit_entry(
+            &mut transaction,
+            changed_by,
+            AuditAction::SetCompatibilityMode,
+            id,
+            Some(if enabled { "enabled" } else { "disabled" }),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn add_api_key_usage(&self, usage: &[(Uuid, u64)]) -> Result<()> {
+        let ids: Vec<Uuid> = usage.iter().map(|(id, _)| *id).collect();
+        let counts = usage
+            .iter()
+            .map(|(_, count)| i64::try_from(*count))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        sqlx::query!(
+            "
+            UPDATE api_key SET request_count = request_count + u.count, last_used = now()
+            FROM UNNEST($1::uuid[], $2::bigint[]) AS u(key_id, count)
+            WHERE api_key.key_id = u.key_id
+            ",
+            &ids,
+            &counts
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn use_api_key_nonce(
+        &self,
+        key_id: Uuid,
+        nonce: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool> {
+        // an expired nonce may be used again, as the request it was used in would not be accepted anymore
+        let result = sqlx::query!(
+            "
+            INSERT INTO api_key_nonce (key_id, nonce, expires_at) VALUES ($1, $2, $3)
+            ON CONFLICT (key_id, nonce) DO UPDATE SET expires_at = EXCLUDED.expires_at
+            WHERE api_key_nonce.expires_at < now()
+            ",
+            key_id,
+            nonce,
+            expires_at
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn remove_expired_api_key_nonces(&self) -> Result<()> {
+        sqlx::query!("DELETE FROM api_key_nonce WHERE expires_at < now()")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unwrap_used)]
+    use chrono::{TimeDelta, Utc};
     use sqlx::PgPool;
 
     use super::PersistentAuthData;
-    use crate::interface::persistent_data::{model::ApiKey, AuthDataAccess};
+    use crate::{
+        interface::persistent_data::{AuthDataAccess, DataError},
+        layer::data::database::cipher::ApiKeyCipher,
+        util::{ApiKeyScope, Uuid},
+    };
+
+    fn auth_data(pool: &PgPool) -> PersistentAuthData {
+        PersistentAuthData {
+            pool: pool.clone(),
+            cipher: ApiKeyCipher::new(&[1; 32]),
+        }
+    }
 
     #[sqlx::test(fixtures("api_key"))]
-    async fn test_get_api_keys(pool: PgPool) {
-        let auth = PersistentAuthData { pool: pool.clone() };
-
-        assert!(auth.get_api_keys().await.is_ok());
-        assert_eq!(auth.get_api_keys().await.unwrap(), provide_dummy_api_keys());
-    }
-
-    fn provide_dummy_api_keys() -> Vec<ApiKey> {
-        vec![
-            ApiKey {
-                key: "abc".into(),
-                description: String::new(),
-            },
-            ApiKey {
-                key: "YWpzZGg4MnozNzhkMnppZGFzYXNkMiBzYWZzYSBzPGE5MDk4".into(),
-                description: String::new(),
-            },
-        ]
+    async fn test_get_api_key(pool: PgPool) {
+        let auth = auth_data(&pool);
+
+        let key = auth.get_api_key("YWpzZGg4Mn").await.unwrap().unwrap();
+        assert_eq!(key.key, "YWpzZGg4MnozNzhkMnppZGFzYXNkMiBzYWZzYSBzPGE5MDk4");
+        assert_eq!(key.scope, ApiKeyScope::Moderate);
+        assert!(key.expiry_date.is_none());
+        assert_eq!(auth.get_api_key("abc").await.unwrap().unwrap().key, "abc");
+
+        // identifiers have to match exactly
+        assert!(auth.get_api_key("YWpzZGg4").await.unwrap().is_none());
+        assert!(auth.get_api_key("a").await.unwrap().is_none());
+        assert!(auth.get_api_key("abcd").await.unwrap().is_none());
+    }
+
+    #[sqlx::test(fixtures("api_key"))]
+    async fn test_encrypt_plain_keys(pool: PgPool) {
+        let auth = auth_data(&pool);
+
+        assert_eq!(auth.encrypt_plain_keys().await.unwrap(), 2);
+        assert_eq!(auth.encrypt_plain_keys().await.unwrap(), 0);
+        let plain = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM api_key WHERE api_key IS NOT NULL OR key_hash IS NOT NULL"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(plain, Some(0));
+
+        assert_eq!(auth.get_api_key("abc").await.unwrap().unwrap().key, "abc");
+        assert!(auth
+            .get_api_key_infos()
+            .await
+            .unwrap()
+            .iter()
+            .all(|k| k.legacy));
+
+        let other_cipher = PersistentAuthData {
+            pool,
+            cipher: ApiKeyCipher::new(&[2; 32]),
+        };
+        assert!(matches!(
+            other_cipher.get_api_key("abc").await,
+            Err(DataError::DecryptionError)
+        ));
+    }
+
+    #[sqlx::test(fixtures("api_key"))]
+    async fn test_manage_api_keys(pool: PgPool) {
+        let auth = auth_data(&pool);
+
+        let id = auth
+            .add_api_key(
+                "0123456789",
+                "0123456789abc",
+                "test",
+                ApiKeyScope::ReadOnly,
+                None,
+                "admin",
+            )
+            .await
+            .unwrap();
+        let key = auth.get_api_key("0123456789").await.unwrap().unwrap();
+        assert_eq!(key.id, id);
+        assert_eq!(key.key, "0123456789abc");
+        assert_eq!(key.scope, ApiKeyScope::ReadOnly);
+        // new keys require replay protection
+        assert!(!key.compatibility_mode);
+
+        // only the encrypted key gets stored
+        let stored = sqlx::query_scalar!("SELECT encrypted_key FROM api_key WHERE key_id = $1", id)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&stored).contains("0123456789abc"));
+
+        auth.set_api_key_compatibility_mode(id, true, "admin")
+            .await
+            .unwrap();
+        assert!(
+            auth.get_api_key("0123456789")
+                .await
+                .unwrap()
+                .unwrap()
+                .compatibility_mode
+        );
+        auth.set_api_key_compatibility_mode(id, false, "admin")
+            .await
+            .unwrap();
+
+        auth.rotate_api_key(id, "9876543210", "9876543210abc", "admin")
+            .await
+            .unwrap();
+        assert!(auth.get_api_key("0123456789").await.unwrap().is_none());
+        assert_eq!(
+            auth.get_api_key("9876543210").await.unwrap().unwrap().key,
+            "9876543210abc"
+        );
+        auth.add_api_key_usage(&[(id, 3)]).await.unwrap();
+        auth.add_api_key_usage(&[(id, 2)]).await.unwrap();
+
+        let info = auth
+            .get_api_key_infos()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|k| k.id == id)
+            .unwrap();
+        assert_eq!(info.ident, "9876543210");
+        assert_eq!(info.request_count, 5);
+        assert!(info.last_used.is_some());
+        assert!(!info.legacy);
+        assert!(!info.compatibility_mode);
+
+        auth.revoke_api_key(id, "admin").await.unwrap();
+        assert!(auth.get_api_key("9876543210").await.unwrap().is_none());
+        assert!(matches!(
+            auth.revoke_api_key(id, "admin").await,
+            Err(DataError::NoSuchItem)
+        ));
+        assert!(matches!(
+            auth.rotate_api_key(Uuid::default(), "", "", "admin").await,
+            Err(DataError::NoSuchItem)
+        ));
+
+        // all changes but the failed ones got recorded
+        let actions = sqlx::query_scalar!(
+            "SELECT action::text FROM audit_log WHERE target_id = $1 AND actor = 'admin' ORDER BY audit_id",
+            id
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            actions,
+            [
+                "ADD_API_KEY",
+                "SET_COMPATIBILITY_MODE",
+                "SET_COMPATIBILITY_MODE",
+                "ROTATE_API_KEY",
+                "REVOKE_API_KEY"
+            ]
+            .map(|a| Some(a.to_owned()))
+        );
+    }
+
+    #[sqlx::test(fixtures("api_key"))]
+    async fn test_api_key_nonces(pool: PgPool) {
+        let auth = auth_data(&pool);
+        let key = auth.get_api_key("abc").await.unwrap().unwrap().id;
+        let other_key = auth.get_api_key("YWpzZGg4Mn").await.unwrap().unwrap().id;
+        let valid = Utc::now() + TimeDelta::minutes(5);
+        let expired = Utc::now() - TimeDelta::minutes(5);
+
+        assert!(auth.use_api_key_nonce(key, "nonce", valid).await.unwrap());
+        assert!(!auth.use_api_key_nonce(key, "nonce", valid).await.unwrap());
+        // nonces are remembered per api key
+        assert!(auth
+            .use_api_key_nonce(other_key, "nonce", valid)
+            .await
+            .unwrap());
+
+        // expired nonces may be used again and get removed
+        assert!(auth.use_api_key_nonce(key, "old", expired).await.unwrap());
+        assert!(auth.use_api_key_nonce(key, "old", expired).await.unwrap());
+        auth.remove_expired_api_key_nonces().await.unwrap();
+        let remaining = sqlx::query_scalar!("SELECT COUNT(*) FROM api_key_nonce")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, Some(2));
     }
 }
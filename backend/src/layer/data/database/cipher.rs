@@ -0,0 +1,91 @@
+//! See [`ApiKeyCipher`].
+use std::fmt::Debug;
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, OsRng},
+    Aes256Gcm, Key, KeyInit, Nonce,
+};
+
+use crate::interface::persistent_data::{DataError, Result};
+
+/// Length of the key used for encrypting api keys in bytes.
+pub const API_KEY_CIPHER_KEY_LENGTH: usize = 32;
+/// Length of the random nonce stored in front of each encrypted api key in bytes.
+const NONCE_LENGTH: usize = 12;
+
+/// Encrypts the secrets of api keys before they get stored.
+///
+/// The key used for this is only known to the server and never gets stored in the database,
+/// so a copy of the database is not enough to sign requests.
+#[derive(Clone)]
+pub struct ApiKeyCipher {
+    cipher: Aes256Gcm,
+}
+
+impl ApiKeyCipher {
+    /// Creates a cipher using AES-256-GCM with the given key.
+    #[must_use]
+    pub fn new(key: &[u8; API_KEY_CIPHER_KEY_LENGTH]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+
+    /// Encrypts `secret` using a new random nonce, which is put in front of the returned cipher text.
+    pub(super) fn encrypt(&self, secret: &str) -> Vec<u8> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let cipher_text = self
+            .cipher
+            .encrypt(&nonce, secret.as_bytes())
+            .expect("encrypting a short text in memory does not fail");
+        [nonce.as_slice(), &cipher_text].concat()
+    }
+
+    /// Decrypts a secret encrypted by [`Self::encrypt`].
+    /// # Errors
+    /// [`DataError::DecryptionError`] if the secret was encrypted with another key or got changed.
+    pub(super) fn decrypt(&self, encrypted: &[u8]) -> Result<String> {
+        if encrypted.len() < NONCE_LENGTH {
+            return Err(DataError::DecryptionError);
+        }
+        let (nonce, cipher_text) = encrypted.split_at(NONCE_LENGTH);
+        let secret = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), cipher_text)
+            .map_err(|_| DataError::DecryptionError)?;
+        String::from_utf8(secret).map_err(|_| DataError::DecryptionError)
+    }
+}
+
+impl Debug for ApiKeyCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeyCipher").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiKeyCipher;
+    use crate::interface::persistent_data::DataError;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let cipher = ApiKeyCipher::new(&[7; 32]);
+        let encrypted = cipher.encrypt("secret key");
+        assert_ne!(encrypted, cipher.encrypt("secret key"));
+        assert_eq!(
+            cipher.decrypt(&encrypted).ok().as_deref(),
+            Some("secret key")
+        );
+
+        let other = ApiKeyCipher::new(&[8; 32]);
+        assert!(matches!(
+            other.decrypt(&encrypted),
+            Err(DataError::DecryptionError)
+        ));
+        assert!(matches!(
+            cipher.decrypt(&encrypted[..5]),
+            Err(DataError::DecryptionError)
+        ));
+    }
+}
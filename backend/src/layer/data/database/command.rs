@@ -2,13 +2,18 @@
 use async_trait::async_trait;
 use sqlx::{Pool, Postgres};
 
+use super::audit::{add_audit_entry, pseudonymise_client};
+
 use crate::{
     interface::persistent_data::{
-        model::{ExtendedImage, Image},
-        CommandDataAccess, Result,
+        model::{ExtendedImage, Image, RankPolicy, UploaderStats},
+        CommandDataAccess, DataError, Result,
     },
     null_error,
-    util::{image_id_to_url, ReportReason, Uuid},
+    util::{
+        image_id_to_url, AuditAction, ClientAction, ModerationStatus, ReportReason, Uuid,
+        SYSTEM_ACTOR,
+    },
 };
 
 /// Class implementing all database requests arising from graphql manipulations.
@@ -24,7 +29,8 @@ impl CommandDataAccess for PersistentCommandData {
         let record = sqlx::query!(
             r#"
             SELECT approved, link_date as upload_date, report_count,
-            upvotes, downvotes, image_id, rank, food_id, f.name as meal_name
+            upvotes, downvotes, image_id, rank, food_id, f.name as meal_name,
+            user_id, moderation_status as "moderation_status: ModerationStatus"
             FROM image_detail JOIN food f USING (food_id)
             WHERE image_id = $1
             ORDER BY image_id
@@ -34,20 +40,8 @@ impl CommandDataAccess for PersistentCommandData {
         .fetch_one(&self.pool)
         .await?;
 
-        let other_image_urls = sqlx::query_scalar!(
-            "
-            SELECT image_id FROM image_detail 
-            WHERE currently_visible AND food_id = $1 AND image_id <> $2
-            ORDER BY rank DESC
-            ",
-            record.food_id,
-            image_id
-        )
-        .fetch_all(&self.pool)
-        .await?
-        .iter()
-        .map(|i| Ok(image_id_to_url(null_error!(i))))
-        .collect::<Result<Vec<_>>>()?;
+        let other_image_urls =
+            get_other_image_urls(&self.pool, null_error!(record.food_id), image_id).await?;
 
         Ok(ExtendedImage {
             image: Image {
@@ -60,19 +54,31 @@ impl CommandDataAccess for PersistentCommandData {
                 id: null_error!(record.image_id),
                 meal_id: null_error!(record.food_id),
                 reporting_users: Option::default(),
+                uploader_id: null_error!(record.user_id),
+                moderation_status: null_error!(record.moderation_status),
             },
             meal_name: record.meal_name,
             other_image_urls,
         })
     }
 
-    async fn hide_image(&self, image_id: Uuid) -> Result<()> {
+    async fn hide_image(&self, image_id: Uuid, reason: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
         sqlx::query!(
             "UPDATE image SET currently_visible = false WHERE image_id = $1",
             image_id
         )
-        .execute(&self.pool)
+        .execute(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            SYSTEM_ACTOR,
+            AuditAction::HideImage,
+            image_id,
+            Some(reason),
+        )
         .await?;
+        transaction.commit().await?;
         Ok(())
     }
 
@@ -81,19 +87,216 @@ impl CommandDataAccess for PersistentCommandData {
         image_id: Uuid,
         client_id: Uuid,
         reason: ReportReason,
-    ) -> Result<()> {
+    ) -> Result<u32> {
+        let mut transaction = self.pool.begin().await?;
         sqlx::query!(
-            "INSERT INTO image_report (image_id, user_id, reason) VALUES ($1, $2, $3)",
+            "
+            INSERT INTO image_report (image_id, user_id, reason, dismissed)
+            VALUES ($1, $2, $3, EXISTS (SELECT FROM client_ban WHERE client_id = $2))
+            ",
             image_id,
             client_id,
             reason as _
         )
-        .execute(&self.pool)
+        .execute(&mut *transaction)
+        .await?;
+        let report_count = sqlx::query_scalar!(
+            r#"SELECT count(*) as "count!" FROM image_report WHERE image_id = $1 AND NOT dismissed"#,
+            image_id
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            &client_id.to_string(),
+            AuditAction::ReportImage,
+            image_id,
+            Some(&reason.to_string()),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(u32::try_from(report_count)?)
+    }
+
+    async fn get_report_reasons(&self, image_id: Uuid) -> Result<Vec<ReportReason>> {
+        let reasons = sqlx::query_scalar!(
+            r#"
+            SELECT reason as "reason: ReportReason" FROM image_report
+            WHERE image_id = $1 AND NOT dismissed
+            "#,
+            image_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(reasons)
+    }
+
+    async fn get_uploader_stats(&self, user_id: Uuid) -> Result<UploaderStats> {
+        let record = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE approved AND deletion_date IS NULL) as "approved!",
+                COUNT(*) FILTER (WHERE deletion_date IS NOT NULL OR moderation_status = 'REJECTED') as "removed!"
+            FROM image WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(UploaderStats {
+            approved_images: u32::try_from(record.approved)?,
+            removed_images: u32::try_from(record.removed)?,
+        })
+    }
+
+    async fn set_rank_policy(&self, policy: &RankPolicy) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let previous =
+            sqlx::query!("SELECT report_penalty, new_image_days FROM image_rank_policy FOR UPDATE")
+                .fetch_one(&mut *transaction)
+                .await?;
+        let previous_weights = sqlx::query!(
+            r#"SELECT reason as "reason: ReportReason", weight FROM report_reason_weight"#
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+        // the policy gets set on every start, but only changes are recorded
+        let changed = (previous.report_penalty - policy.report_penalty).abs() > f64::EPSILON
+            || i64::from(previous.new_image_days) != i64::from(policy.new_image_days)
+            || previous_weights.len() != policy.reason_weights.len()
+            || policy.reason_weights.iter().any(|(reason, weight)| {
+                !previous_weights
+                    .iter()
+                    .any(|w| w.reason == *reason && (w.weight - weight).abs() <= f64::EPSILON)
+            });
+
+        sqlx::query!(
+            "UPDATE image_rank_policy SET report_penalty = $1, new_image_days = $2",
+            policy.report_penalty,
+            i32::try_from(policy.new_image_days)?
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!("DELETE FROM report_reason_weight")
+            .execute(&mut *transaction)
+            .await?;
+        for (reason, weight) in &policy.reason_weights {
+            sqlx::query!(
+                "INSERT INTO report_reason_weight (reason, weight) VALUES ($1, $2)",
+                *reason as _,
+                weight
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+        if changed {
+            add_audit_entry(
+                &mut transaction,
+                SYSTEM_ACTOR,
+                AuditAction::SetRankPolicy,
+                Uuid::nil(),
+                Some(&describe_rank_policy(policy)),
+            )
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn count_client_actions(&self, client_id: Uuid, action: ClientAction) -> Result<u32> {
+        let count = sqlx::query_scalar!(
+            "SELECT count FROM client_activity WHERE client_id = $1 AND action = $2 AND day = CURRENT_DATE",
+            client_id,
+            action as _
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(u32::try_from(count.unwrap_or_default())?)
+    }
+
+    async fn record_client_action(&self, client_id: Uuid, action: ClientAction) -> Result<u32> {
+        let count = sqlx::query_scalar!(
+            "
+            INSERT INTO client_activity (client_id, action) VALUES ($1, $2)
+            ON CONFLICT (client_id, action, day) DO UPDATE SET count = client_activity.count + 1
+            RETURNING count
+            ",
+            client_id,
+            action as _
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(u32::try_from(count)?)
+    }
+
+    async fn is_client_banned(&self, client_id: Uuid) -> Result<bool> {
+        let banned = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT FROM client_ban WHERE client_id = $1) as "banned!""#,
+            client_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(banned)
+    }
+
+    async fn ban_client(
+        &self,
+        client_id: Uuid,
+        banned_by: &str,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query!(
+            "
+            INSERT INTO client_ban (client_id, banned_by, reason) VALUES ($1, $2, $3)
+            ON CONFLICT (client_id) DO UPDATE SET banned_by = $2, reason = $3
+            ",
+            client_id,
+            banned_by,
+            reason
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            "UPDATE image_report SET dismissed = true WHERE user_id = $1",
+            client_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            banned_by,
+            AuditAction::BanClient,
+            client_id,
+            reason,
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn unban_client(&self, client_id: Uuid, unbanned_by: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let result = sqlx::query!("DELETE FROM client_ban WHERE client_id = $1", client_id)
+            .execute(&mut *transaction)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(DataError::NoSuchItem);
+        }
+        add_audit_entry(
+            &mut transaction,
+            unbanned_by,
+            AuditAction::UnbanClient,
+            client_id,
+            None,
+        )
         .await?;
+        transaction.commit().await?;
         Ok(())
     }
 
     async fn add_upvote(&self, image_id: Uuid, user_id: Uuid) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
         sqlx::query!(
             "
             INSERT INTO image_rating (user_id, image_id, rating) 
@@ -104,12 +307,22 @@ impl CommandDataAccess for PersistentCommandData {
             user_id,
             image_id
         )
-        .execute(&self.pool)
+        .execute(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            &user_id.to_string(),
+            AuditAction::UpvoteImage,
+            image_id,
+            None,
+        )
         .await?;
+        transaction.commit().await?;
         Ok(())
     }
 
     async fn add_downvote(&self, image_id: Uuid, user_id: Uuid) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
         sqlx::query!(
             "
             INSERT INTO image_rating (user_id, image_id, rating) 
@@ -120,43 +333,82 @@ impl CommandDataAccess for PersistentCommandData {
             user_id,
             image_id
         )
-        .execute(&self.pool)
+        .execute(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            &user_id.to_string(),
+            AuditAction::DownvoteImage,
+            image_id,
+            None,
+        )
         .await?;
+        transaction.commit().await?;
         Ok(())
     }
 
     async fn remove_upvote(&self, image_id: Uuid, user_id: Uuid) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
         sqlx::query!(
             "DELETE FROM image_rating WHERE user_id = $1 AND image_id = $2 AND rating = 1",
             user_id,
             image_id
         )
-        .execute(&self.pool)
+        .execute(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            &user_id.to_string(),
+            AuditAction::RemoveUpvote,
+            image_id,
+            None,
+        )
         .await?;
+        transaction.commit().await?;
         Ok(())
     }
 
     async fn remove_downvote(&self, image_id: Uuid, user_id: Uuid) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
         sqlx::query!(
             "DELETE FROM image_rating WHERE user_id = $1 AND image_id = $2 AND rating = -1",
             user_id,
             image_id
         )
-        .execute(&self.pool)
+        .execute(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            &user_id.to_string(),
+            AuditAction::RemoveDownvote,
+            image_id,
+            None,
+        )
         .await?;
+        transaction.commit().await?;
         Ok(())
     }
 
     async fn link_image(&self, meal_id: Uuid, user_id: Uuid) -> Result<Uuid> {
-        sqlx::query_scalar!(
+        let mut transaction = self.pool.begin().await?;
+        let image_id = sqlx::query_scalar!(
             "INSERT INTO image (user_id, food_id) VALUES ($1, $2)
             RETURNING (image_id)",
             user_id,
             meal_id,
         )
-        .fetch_one(&self.pool)
-        .await
-        .map_err(Into::into)
+        .fetch_one(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            &user_id.to_string(),
+            AuditAction::UploadImage,
+            image_id,
+            None,
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(image_id)
     }
 
     async fn revert_link_image(&self, image_id: Uuid) -> Result<()> {
@@ -166,7 +418,32 @@ impl CommandDataAccess for PersistentCommandData {
         Ok(())
     }
 
+    async fn withdraw_image(&self, image_id: Uuid, client_id: Uuid) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let result = sqlx::query!(
+            "DELETE FROM image WHERE image_id = $1 AND user_id = $2",
+            image_id,
+            client_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(DataError::NoSuchItem);
+        }
+        add_audit_entry(
+            &mut transaction,
+            &client_id.to_string(),
+            AuditAction::DeleteOwnImage,
+            image_id,
+            None,
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
     async fn add_rating(&self, meal_id: Uuid, user_id: Uuid, rating: u32) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
         sqlx::query!(
             "
             INSERT INTO meal_rating (user_id, food_id, rating) 
@@ -178,29 +455,217 @@ impl CommandDataAccess for PersistentCommandData {
             meal_id,
             i16::try_from(rating)?
         )
-        .execute(&self.pool)
+        .execute(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            &user_id.to_string(),
+            AuditAction::RateMeal,
+            meal_id,
+            None,
+        )
         .await?;
+        transaction.commit().await?;
         Ok(())
     }
 
-    async fn delete_image(&self, image_id: Uuid) -> Result<()> {
-        sqlx::query!("DELETE FROM image WHERE image_id = $1", image_id)
-            .execute(&self.pool)
+    async fn delete_image(&self, image_id: Uuid, deleted_by: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        // images deleted before keep their deletion date, so their grace period does not get extended
+        let deleted = sqlx::query!(
+            "
+            UPDATE image SET deletion_date = COALESCE(deletion_date, CURRENT_DATE),
+            currently_visible = false, deleted_by = $2
+            WHERE image_id = $1
+            ",
+            image_id,
+            deleted_by
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+        if deleted == 0 {
+            return Err(DataError::NoSuchItem);
+        }
+        add_audit_entry(
+            &mut transaction,
+            deleted_by,
+            AuditAction::DeleteImage,
+            image_id,
+            None,
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn erase_client_data(&self, client_id: Uuid, erased_by: &str) -> Result<Vec<Uuid>> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query!("DELETE FROM meal_rating WHERE user_id = $1", client_id)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!("DELETE FROM image_rating WHERE user_id = $1", client_id)
+            .execute(&mut *transaction)
             .await?;
+        sqlx::query!(
+            "DELETE FROM client_activity WHERE client_id = $1",
+            client_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        // reports are kept for moderation, but can no longer be linked to the client
+        sqlx::query!(
+            "UPDATE image_report SET user_id = gen_random_uuid() WHERE user_id = $1",
+            client_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        let image_ids = sqlx::query_scalar!(
+            "DELETE FROM image WHERE user_id = $1 RETURNING image_id",
+            client_id
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+        // the audit log is kept, but can no longer be linked to the client
+        let pseudonym = pseudonymise_client(&mut transaction, client_id).await?;
+        add_audit_entry(
+            &mut transaction,
+            erased_by,
+            AuditAction::EraseClientData,
+            pseudonym,
+            None,
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(image_ids)
+    }
+
+    async fn verify_image(&self, image_id: Uuid, verified_by: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        let verified = sqlx::query!(
+            "UPDATE image SET approved = true, verified_by = $2 WHERE image_id = $1",
+            image_id,
+            verified_by
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+        if verified == 0 {
+            return Err(DataError::NoSuchItem);
+        }
+        add_audit_entry(
+            &mut transaction,
+            verified_by,
+            AuditAction::VerifyImage,
+            image_id,
+            None,
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn unhide_image(&self, image_id: Uuid, unhidden_by: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        // deleted images can only be restored
+        let unhidden = sqlx::query!(
+            "UPDATE image SET currently_visible = true WHERE image_id = $1 AND deletion_date IS NULL",
+            image_id
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+        if unhidden == 0 {
+            return Err(DataError::NoSuchItem);
+        }
+        add_audit_entry(
+            &mut transaction,
+            unhidden_by,
+            AuditAction::UnhideImage,
+            image_id,
+            None,
+        )
+        .await?;
+        transaction.commit().await?;
         Ok(())
     }
 
-    async fn verify_image(&self, image_id: Uuid) -> Result<()> {
+    async fn restore_image(&self, image_id: Uuid, restored_by: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+
+        let restored = sqlx::query!(
+            "
+            UPDATE image SET currently_visible = true, deletion_date = NULL,
+            restored_by = $2, restore_date = CURRENT_DATE
+            WHERE image_id = $1
+            ",
+            image_id,
+            restored_by
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+        if restored == 0 {
+            return Err(DataError::NoSuchItem);
+        }
+
         sqlx::query!(
-            "UPDATE image SET approved = true WHERE image_id = $1",
+            "UPDATE image_report SET dismissed = true WHERE image_id = $1",
             image_id
         )
-        .execute(&self.pool)
+        .execute(&mut *transaction)
         .await?;
+        add_audit_entry(
+            &mut transaction,
+            restored_by,
+            AuditAction::RestoreImage,
+            image_id,
+            None,
+        )
+        .await?;
+
+        transaction.commit().await?;
         Ok(())
     }
 }
 
+/// Returns a description of the settings of `policy` for the audit log.
+fn describe_rank_policy(policy: &RankPolicy) -> String {
+    let weights = policy
+        .reason_weights
+        .iter()
+        .map(|(reason, weight)| format!("{reason:?} {weight}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "report penalty {}, new image days {}, reason weights: {weights}",
+        policy.report_penalty, policy.new_image_days
+    )
+}
+
+/// Returns the urls of all other visible images of the given meal, best ranked first.
+async fn get_other_image_urls(
+    pool: &Pool<Postgres>,
+    meal_id: Uuid,
+    image_id: Uuid,
+) -> Result<Vec<String>> {
+    sqlx::query_scalar!(
+        "
+        SELECT image_id FROM image_detail
+        WHERE currently_visible AND moderation_status = 'ACCEPTED' AND food_id = $1 AND image_id <> $2
+        AND user_id NOT IN (SELECT client_id FROM client_ban)
+        ORDER BY rank DESC, image_id
+        ",
+        meal_id,
+        image_id
+    )
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|i| Ok(image_id_to_url(null_error!(i))))
+    .collect()
+}
+
 #[cfg(test)]
 mod test {
     #![allow(clippy::unwrap_used)]
@@ -234,11 +699,13 @@ mod test {
                 report_count: 0,
                 meal_id: Uuid::parse_str("f7337122-b018-48ad-b420-6202dc3cb4ff").unwrap(),
                 reporting_users: Option::default(),
+                uploader_id: Uuid::parse_str("c51d2d81-3547-4f07-af58-ed613c6ece67").unwrap(),
+                moderation_status: ModerationStatus::Accepted,
             },
             meal_name: "Geflügel - Cevapcici, Ajvar, Djuvec Reis".into(),
             other_image_urls: vec![
-                image_id_to_url(Uuid::parse_str("ea8cce48-a3c7-4f8e-a222-5f3891c13804").unwrap()),
                 image_id_to_url(Uuid::parse_str("1aa73d5d-1701-4975-aa3c-1422a8bc10e8").unwrap()),
+                image_id_to_url(Uuid::parse_str("ea8cce48-a3c7-4f8e-a222-5f3891c13804").unwrap()),
             ],
         }
     }
@@ -249,14 +716,39 @@ mod test {
         let image_id = Uuid::parse_str("76b904fe-d0f1-4122-8832-d0e21acab86d").unwrap();
 
         let hidden_images = number_of_hidden_images(&pool).await;
-        assert!(command.hide_image(image_id).await.is_ok());
+        assert!(command.hide_image(image_id, "reported").await.is_ok());
         assert_eq!(number_of_hidden_images(&pool).await, hidden_images + 1);
-        assert!(command.hide_image(image_id).await.is_ok());
+        assert!(command.hide_image(image_id, "reported").await.is_ok());
+        assert_eq!(number_of_hidden_images(&pool).await, hidden_images + 1);
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_unhide_image(pool: PgPool) {
+        let command = PersistentCommandData { pool: pool.clone() };
+        let image_id = Uuid::parse_str("76b904fe-d0f1-4122-8832-d0e21acab86d").unwrap();
+
+        let hidden_images = number_of_hidden_images(&pool).await;
+        command.hide_image(image_id, "reported").await.unwrap();
+        assert!(command.unhide_image(image_id, "admin").await.is_ok());
+        assert_eq!(number_of_hidden_images(&pool).await, hidden_images);
+        assert!(command.unhide_image(image_id, "admin").await.is_ok());
+        assert_eq!(number_of_hidden_images(&pool).await, hidden_images);
+
+        // deleted images stay hidden
+        command.delete_image(image_id, "admin").await.unwrap();
+        assert!(matches!(
+            command.unhide_image(image_id, "admin").await,
+            Err(DataError::NoSuchItem)
+        ));
         assert_eq!(number_of_hidden_images(&pool).await, hidden_images + 1);
+        assert!(matches!(
+            command.unhide_image(WRONG_UUID, "admin").await,
+            Err(DataError::NoSuchItem)
+        ));
     }
 
     async fn number_of_hidden_images(pool: &PgPool) -> usize {
-        sqlx::query!("SELECT * FROM image WHERE currently_visible = false")
+        sqlx::query!("SELECT image_id FROM image WHERE currently_visible = false")
             .fetch_all(pool)
             .await
             .unwrap()
@@ -271,10 +763,13 @@ mod test {
         let reason = ReportReason::Advert;
 
         let reports = number_of_reports(&pool).await;
-        assert!(command
-            .add_report(image_id, client_id, reason)
-            .await
-            .is_ok());
+        assert_eq!(
+            command
+                .add_report(image_id, client_id, reason)
+                .await
+                .unwrap(),
+            1
+        );
         assert_eq!(number_of_reports(&pool).await, reports + 1);
         assert!(command
             .add_report(image_id, client_id, reason)
@@ -285,6 +780,325 @@ mod test {
             .await
             .is_err());
         assert_eq!(number_of_reports(&pool).await, reports + 1);
+
+        // failed reports are not recorded in the audit log
+        let entries = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM audit_log WHERE action = 'REPORT_IMAGE'"#
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(entries, 1);
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_get_report_reasons(pool: PgPool) {
+        let command = PersistentCommandData { pool };
+        let image_id = Uuid::parse_str("ea8cce48-a3c7-4f8e-a222-5f3891c13804").unwrap();
+
+        let reasons = command.get_report_reasons(image_id).await.unwrap();
+        assert_eq!(reasons, vec![ReportReason::Advert]);
+
+        // dismissed reports are ignored
+        command.restore_image(image_id, "admin").await.unwrap();
+        assert!(command
+            .get_report_reasons(image_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_get_uploader_stats(pool: PgPool) {
+        let command = PersistentCommandData { pool };
+        let user_id = Uuid::parse_str("c51d2d81-3547-4f07-af58-ed613c6ece67").unwrap();
+
+        let stats = command.get_uploader_stats(user_id).await.unwrap();
+        assert_eq!(
+            stats,
+            UploaderStats {
+                approved_images: 1,
+                removed_images: 0
+            }
+        );
+
+        let image_id = Uuid::parse_str("76b904fe-d0f1-4122-8832-d0e21acab86d").unwrap();
+        command.delete_image(image_id, "admin").await.unwrap();
+        let stats = command.get_uploader_stats(user_id).await.unwrap();
+        assert_eq!(stats.removed_images, 1);
+
+        let stats = command.get_uploader_stats(WRONG_UUID).await.unwrap();
+        assert_eq!(stats, UploaderStats::default());
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_set_rank_policy(pool: PgPool) {
+        let command = PersistentCommandData { pool: pool.clone() };
+        let image_id = Uuid::parse_str("ea8cce48-a3c7-4f8e-a222-5f3891c13804").unwrap();
+        command.add_upvote(image_id, WRONG_UUID).await.unwrap();
+
+        let default_policy = RankPolicy {
+            report_penalty: 10.0,
+            new_image_days: 30,
+            reason_weights: vec![],
+        };
+        command.set_rank_policy(&default_policy).await.unwrap();
+        let default_rank = image_rank(&pool, image_id).await;
+
+        command
+            .set_rank_policy(&RankPolicy {
+                reason_weights: vec![(ReportReason::Advert, 3.0)],
+                ..default_policy.clone()
+            })
+            .await
+            .unwrap();
+        assert!(image_rank(&pool, image_id).await < default_rank);
+
+        let offensive_policy = RankPolicy {
+            reason_weights: vec![(ReportReason::Offensive, 3.0)],
+            ..default_policy
+        };
+        command.set_rank_policy(&offensive_policy).await.unwrap();
+        assert!((image_rank(&pool, image_id).await - default_rank).abs() < f32::EPSILON);
+
+        // only changes get recorded
+        command.set_rank_policy(&offensive_policy).await.unwrap();
+        let reasons = sqlx::query_scalar!(
+            "SELECT reason FROM audit_log WHERE action = 'SET_RANK_POLICY' ORDER BY audit_id"
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            reasons,
+            [
+                "report penalty 10, new image days 30, reason weights: Advert 3",
+                "report penalty 10, new image days 30, reason weights: Offensive 3"
+            ]
+            .map(|r| Some(r.to_owned()))
+        );
+    }
+
+    async fn image_rank(pool: &PgPool, image_id: Uuid) -> f32 {
+        sqlx::query_scalar!(
+            "SELECT rank FROM image_detail WHERE image_id = $1",
+            image_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn test_record_client_action(pool: PgPool) {
+        let command = PersistentCommandData { pool };
+        let client_id = Uuid::default();
+
+        for expected in 1..=3 {
+            let count = command
+                .record_client_action(client_id, ClientAction::Report)
+                .await
+                .unwrap();
+            assert_eq!(count, expected);
+        }
+        // counted separately
+        let count = command
+            .record_client_action(client_id, ClientAction::Upload)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        let count = command
+            .record_client_action(WRONG_UUID, ClientAction::Report)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let count = command
+            .count_client_actions(client_id, ClientAction::Report)
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+        let count = command
+            .count_client_actions(client_id, ClientAction::Rating)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_ban_client(pool: PgPool) {
+        let command = PersistentCommandData { pool };
+        let client_id = Uuid::parse_str("c51d2d81-3547-4f07-af58-ed613c6ece67").unwrap();
+        let reported_id = Uuid::parse_str("ea8cce48-a3c7-4f8e-a222-5f3891c13804").unwrap();
+        let image_id = Uuid::parse_str("76b904fe-d0f1-4122-8832-d0e21acab86d").unwrap();
+
+        assert!(!command.is_client_banned(client_id).await.unwrap());
+        assert!(command.unban_client(client_id, "admin").await.is_err());
+
+        command
+            .ban_client(client_id, "admin", Some("spam"))
+            .await
+            .unwrap();
+        assert!(command.is_client_banned(client_id).await.unwrap());
+        // banning again is fine
+        command.ban_client(client_id, "admin", None).await.unwrap();
+
+        // existing and new reports are dismissed
+        assert!(command
+            .get_report_reasons(reported_id)
+            .await
+            .unwrap()
+            .is_empty());
+        command
+            .add_report(image_id, client_id, ReportReason::Advert)
+            .await
+            .unwrap();
+        assert!(command
+            .get_report_reasons(image_id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        command.unban_client(client_id, "admin").await.unwrap();
+        assert!(!command.is_client_banned(client_id).await.unwrap());
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_banned_client_does_not_count(pool: PgPool) {
+        let command = PersistentCommandData { pool: pool.clone() };
+        let client_id = Uuid::parse_str("c51d2d81-3547-4f07-af58-ed613c6ece67").unwrap();
+        let image_id = Uuid::parse_str("ea8cce48-a3c7-4f8e-a222-5f3891c13804").unwrap();
+        let meal_id = Uuid::parse_str("f7337122-b018-48ad-b420-6202dc3cb4ff").unwrap();
+
+        command.add_upvote(image_id, client_id).await.unwrap();
+        command.add_rating(meal_id, client_id, 5).await.unwrap();
+        let info = command.get_image_info(image_id).await.unwrap();
+        assert_eq!(info.image.upvotes, 1);
+        assert_eq!(info.other_image_urls.len(), 2);
+        assert_eq!(meal_rating_count(&pool, meal_id).await, 1);
+
+        // votes, ratings and uploads of banned clients are ignored
+        command.ban_client(client_id, "admin", None).await.unwrap();
+        let info = command.get_image_info(image_id).await.unwrap();
+        assert_eq!(info.image.upvotes, 0);
+        assert!(info.other_image_urls.is_empty());
+        assert_eq!(meal_rating_count(&pool, meal_id).await, 0);
+
+        // but count again once the ban is lifted
+        command.unban_client(client_id, "admin").await.unwrap();
+        let info = command.get_image_info(image_id).await.unwrap();
+        assert_eq!(info.image.upvotes, 1);
+        assert_eq!(meal_rating_count(&pool, meal_id).await, 1);
+    }
+
+    async fn meal_rating_count(pool: &PgPool, meal_id: Uuid) -> i64 {
+        sqlx::query_scalar!(
+            "SELECT rating_count FROM meal_detail WHERE food_id = $1",
+            meal_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .unwrap()
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_erase_client_data(pool: PgPool) {
+        let command = PersistentCommandData { pool: pool.clone() };
+        let client_id = Uuid::parse_str("c51d2d81-3547-4f07-af58-ed613c6ece67").unwrap();
+        let meal_id = Uuid::parse_str("f7337122-b018-48ad-b420-6202dc3cb4ff").unwrap();
+        let other_image = Uuid::parse_str("ea8cce48-a3c7-4f8e-a222-5f3891c13804").unwrap();
+
+        command.add_rating(meal_id, client_id, 4).await.unwrap();
+        command.add_upvote(other_image, client_id).await.unwrap();
+        command
+            .record_client_action(client_id, ClientAction::Rating)
+            .await
+            .unwrap();
+
+        let mut deleted = command.erase_client_data(client_id, "admin").await.unwrap();
+        deleted.sort();
+        assert_eq!(
+            deleted,
+            vec![
+                Uuid::parse_str("1aa73d5d-1701-4975-aa3c-1422a8bc10e8").unwrap(),
+                Uuid::parse_str("68153ab6-ebbf-48f4-b8dd-a9b2a19a5221").unwrap(),
+                Uuid::parse_str("76b904fe-d0f1-4122-8832-d0e21acab86d").unwrap(),
+            ]
+        );
+
+        let remaining = sqlx::query_scalar!(
+            r#"
+            SELECT (SELECT COUNT(*) FROM meal_rating WHERE user_id = $1)
+                + (SELECT COUNT(*) FROM image_rating WHERE user_id = $1)
+                + (SELECT COUNT(*) FROM image_report WHERE user_id = $1)
+                + (SELECT COUNT(*) FROM client_activity WHERE client_id = $1)
+                + (SELECT COUNT(*) FROM image WHERE user_id = $1) as "count!"
+            "#,
+            client_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(remaining, 0);
+
+        // the report of the other image is kept anonymously
+        assert_eq!(
+            command.get_report_reasons(other_image).await.unwrap(),
+            vec![ReportReason::Advert]
+        );
+
+        // the audit log is kept, but only contains a pseudonym of the client
+        let entries =
+            sqlx::query!("SELECT actor, action::text, target_id FROM audit_log ORDER BY audit_id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries
+            .iter()
+            .all(|e| e.actor != client_id.to_string() && e.target_id != client_id));
+        let pseudonym = entries[2].target_id;
+        assert_eq!(entries[2].action.as_deref(), Some("ERASE_CLIENT_DATA"));
+        assert_eq!(entries[0].actor, pseudonym.to_string());
+        assert_eq!(entries[1].actor, pseudonym.to_string());
+    }
+
+    #[sqlx::test]
+    async fn test_audit_log_append_only(pool: PgPool) {
+        let command = PersistentCommandData { pool: pool.clone() };
+        let image_id = Uuid::from_u128(1);
+
+        command.hide_image(image_id, "spam").await.unwrap();
+        let reason = sqlx::query_scalar!(
+            "SELECT reason FROM audit_log WHERE target_id = $1",
+            image_id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(reason.as_deref(), Some("spam"));
+
+        // entries can neither be changed nor deleted
+        assert!(sqlx::query!("UPDATE audit_log SET reason = NULL")
+            .execute(&pool)
+            .await
+            .is_err());
+        let mut transaction = pool.begin().await.unwrap();
+        sqlx::query!("SELECT set_config('mensa.pseudonymise_audit_log', 'on', true)")
+            .fetch_one(&mut *transaction)
+            .await
+            .unwrap();
+        assert!(sqlx::query!("UPDATE audit_log SET reason = NULL")
+            .execute(&mut *transaction)
+            .await
+            .is_err());
+        drop(transaction);
+        assert!(sqlx::query!("DELETE FROM audit_log")
+            .execute(&pool)
+            .await
+            .is_err());
     }
 
     async fn number_of_reports(pool: &PgPool) -> usize {
@@ -419,7 +1233,7 @@ mod test {
     }
 
     async fn number_of_images(pool: &PgPool) -> usize {
-        sqlx::query!("SELECT * FROM image")
+        sqlx::query!("SELECT image_id FROM image")
             .fetch_all(pool)
             .await
             .unwrap()
@@ -486,29 +1300,98 @@ mod test {
     async fn test_delete_image(pool: PgPool) {
         let command = PersistentCommandData { pool: pool.clone() };
         let id = "ea8cce48-a3c7-4f8e-a222-5f3891c13804".try_into().unwrap();
-        command.delete_image(id).await.unwrap();
+        command.delete_image(id, "admin").await.unwrap();
 
-        assert_eq!(
-            0,
-            sqlx::query_scalar!("SELECT COUNT(*) FROM image WHERE image_id = $1", id)
+        // the image is kept until purged
+        let image = sqlx::query!(
+            "SELECT currently_visible, deletion_date, deleted_by FROM image WHERE image_id = $1",
+            id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(!image.currently_visible);
+        assert_eq!(image.deletion_date, Some(Local::now().date_naive()));
+        assert_eq!(image.deleted_by.as_deref(), Some("admin"));
+
+        // deleting again does not extend the grace period
+        sqlx::query!(
+            "UPDATE image SET deletion_date = CURRENT_DATE - 3 WHERE image_id = $1",
+            id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        command.delete_image(id, "admin").await.unwrap();
+        let deletion_date =
+            sqlx::query_scalar!("SELECT deletion_date FROM image WHERE image_id = $1", id)
                 .fetch_one(&pool)
                 .await
-                .unwrap()
-                .unwrap()
+                .unwrap();
+        assert_eq!(
+            deletion_date,
+            Some(Local::now().date_naive() - chrono::Days::new(3))
+        );
+
+        assert!(matches!(
+            command.delete_image(WRONG_UUID, "admin").await,
+            Err(DataError::NoSuchItem)
+        ));
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_restore_image(pool: PgPool) {
+        let command = PersistentCommandData { pool: pool.clone() };
+        let id = "ea8cce48-a3c7-4f8e-a222-5f3891c13804".try_into().unwrap();
+        command.hide_image(id, "reported").await.unwrap();
+        command.delete_image(id, "admin").await.unwrap();
+
+        command.restore_image(id, "admin").await.unwrap();
+
+        let image = sqlx::query!(
+            "SELECT currently_visible, deletion_date, restored_by, restore_date FROM image WHERE image_id = $1",
+            id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(image.currently_visible);
+        assert_eq!(image.deletion_date, None);
+        assert_eq!(image.restored_by.as_deref(), Some("admin"));
+        assert_eq!(image.restore_date, Some(Local::now().date_naive()));
+
+        // reports got dismissed but are kept
+        assert_eq!(
+            command.get_image_info(id).await.unwrap().image.report_count,
+            0
         );
+        assert_eq!(number_of_reports(&pool).await, 1);
+
+        assert!(matches!(
+            command.restore_image(WRONG_UUID, "admin").await,
+            Err(DataError::NoSuchItem)
+        ));
     }
 
     #[sqlx::test(fixtures("meal", "image"))]
     async fn test_verify_image(pool: PgPool) {
         let command = PersistentCommandData { pool: pool.clone() };
         let id = "ea8cce48-a3c7-4f8e-a222-5f3891c13804".try_into().unwrap();
-        command.verify_image(id).await.unwrap();
+        command.verify_image(id, "admin").await.unwrap();
 
-        assert!(
-            sqlx::query_scalar!("SELECT approved FROM image WHERE image_id = $1", id)
-                .fetch_one(&pool)
-                .await
-                .unwrap()
-        );
+        let image = sqlx::query!(
+            "SELECT approved, verified_by FROM image WHERE image_id = $1",
+            id
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(image.approved);
+        assert_eq!(image.verified_by.as_deref(), Some("admin"));
+
+        assert!(matches!(
+            command.verify_image(WRONG_UUID, "admin").await,
+            Err(DataError::NoSuchItem)
+        ));
     }
 }
@@ -0,0 +1,97 @@
+//! Module responsible for handling database requests for the moderation digest.
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    interface::persistent_data::{model::AuditEntry, DigestDataAccess, Result},
+    util::AuditAction,
+};
+
+/// Class implementing all database requests arising from sending moderation digests.
+#[derive(Debug)]
+pub struct PersistentDigestData {
+    pub(super) pool: Pool<Postgres>,
+}
+
+#[async_trait]
+impl DigestDataAccess for PersistentDigestData {
+    async fn get_undigested_entries(&self) -> Result<Vec<AuditEntry>> {
+        let entries = sqlx::query_as!(
+            AuditEntry,
+            r#"
+            SELECT audit_id as id, created_at as time, actor, action as "action: AuditAction", target_id, reason
+            FROM audit_log
+            WHERE action IN ('REPORT_IMAGE', 'HIDE_IMAGE', 'VERIFY_IMAGE', 'DELETE_IMAGE')
+            AND CASE WHEN EXISTS (SELECT FROM moderation_digest)
+                THEN audit_id > (SELECT last_audit_id FROM moderation_digest)
+                ELSE created_at >= now() - interval '1 day'
+            END
+            ORDER BY audit_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    async fn mark_digested(&self, last_entry_id: i64) -> Result<()> {
+        sqlx::query!(
+            "
+            INSERT INTO moderation_digest (last_audit_id) VALUES ($1)
+            ON CONFLICT (singleton) DO UPDATE SET last_audit_id = $1, sent_at = now()
+            ",
+            last_entry_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::util::Uuid;
+
+    #[sqlx::test]
+    async fn test_undigested_entries(pool: PgPool) {
+        let digest = PersistentDigestData { pool: pool.clone() };
+        let image_id = Uuid::from_u128(1);
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_log (actor, action, target_id, reason, created_at) VALUES
+            ('client', 'REPORT_IMAGE', $1, 'Advert', now() - interval '2 days'),
+            ('client', 'REPORT_IMAGE', $1, 'Offensive', now()), ('system', 'HIDE_IMAGE', $1, NULL, now()),
+            ('client', 'UPVOTE_IMAGE', $1, NULL, now()), ('admin', 'DELETE_IMAGE', $1, NULL, now())
+            "#,
+            image_id,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // without previous digest, only the last day
+        let entries = digest.get_undigested_entries().await.unwrap();
+        let actions: Vec<_> = entries.iter().map(|e| e.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                AuditAction::ReportImage,
+                AuditAction::HideImage,
+                AuditAction::DeleteImage
+            ]
+        );
+        assert_eq!(entries[0].reason.as_deref(), Some("Offensive"));
+
+        digest.mark_digested(entries[1].id).await.unwrap();
+        let entries = digest.get_undigested_entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, AuditAction::DeleteImage);
+
+        digest.mark_digested(entries[0].id).await.unwrap();
+        assert!(digest.get_undigested_entries().await.unwrap().is_empty());
+    }
+}
@@ -1,12 +1,28 @@
 //! Module containing a factory pattern to construct instances to access the database for all components needing it.
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use std::time::Duration;
+
+use metrics::gauge;
+use sqlx::{
+    migrate::{Migrate, Migrator},
+    postgres::PgPoolOptions,
+    Pool, Postgres,
+};
 use tracing::info;
 
 use crate::interface::persistent_data::Result;
 
 use super::{
-    auth::PersistentAuthData, command::PersistentCommandData,
-    mealplan_management::PersistentMealplanManagementData, request::PersistentRequestData,
+    admin::PersistentAdminData,
+    auth::PersistentAuthData,
+    cipher::ApiKeyCipher,
+    command::PersistentCommandData,
+    digest::PersistentDigestData,
+    health::{DatabaseHealth, ParseHealth},
+    image_review::PersistentImageReviewData,
+    mealplan_management::PersistentMealplanManagementData,
+    request::PersistentRequestData,
+    retention::PersistentRetentionData,
+    scheduling::PersistentSchedulerLeaseData,
 };
 
 /// Structure containing all information necessary to connect to a database.
@@ -18,6 +34,17 @@ pub struct DatabaseInfo {
     pub max_weeks_data: u32,
 }
 
+/// A database migration and whether it is applied, see [`DataAccessFactory::migration_status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// Version of the migration, which is the time it got created.
+    pub version: i64,
+    /// Description of the migration.
+    pub description: String,
+    /// Whether the migration is applied to the database.
+    pub applied: bool,
+}
+
 /// This class is responsible for instantiating the database access implementations classes.
 #[derive(Debug)]
 pub struct DataAccessFactory {
@@ -28,6 +55,8 @@ pub struct DataAccessFactory {
 
 const MAX_DB_CONNECTIONS: u32 = 20;
 
+static MIGRATOR: Migrator = sqlx::migrate!();
+
 impl DataAccessFactory {
     /// Creates a new factory object for the database access instances.
     /// On creation, a connection to the database is established.
@@ -41,15 +70,82 @@ impl DataAccessFactory {
             .connect(&info.connection)
             .await?;
 
+        let factory = Self {
+            pool,
+            max_weeks_data: info.max_weeks_data,
+        };
         if should_migrate {
-            sqlx::migrate!().run(&pool).await?;
-            info!("Successfully run database migrations");
+            factory.migrate().await?;
         }
+        Ok(factory)
+    }
 
-        Ok(Self {
-            pool,
-            max_weeks_data: info.max_weeks_data,
-        })
+    /// Applies all database migrations which are not applied yet.
+    /// # Errors
+    /// if a migration could not be applied
+    pub async fn migrate(&self) -> Result<()> {
+        MIGRATOR.run(&self.pool).await?;
+        info!("Successfully run database migrations");
+        Ok(())
+    }
+
+    /// Reverts the last applied database migration.
+    /// Returns the reverted migration, `None` if there was none applied.
+    /// # Errors
+    /// if the migration could not be reverted
+    pub async fn revert_last_migration(&self) -> Result<Option<MigrationStatus>> {
+        let mut applied: Vec<_> = self
+            .migration_status()
+            .await?
+            .into_iter()
+            .filter(|migration| migration.applied)
+            .collect();
+        let Some(mut last) = applied.pop() else {
+            return Ok(None);
+        };
+        let target = applied.last().map_or(0, |migration| migration.version);
+
+        MIGRATOR.undo(&self.pool, target).await?;
+        info!(
+            "Successfully reverted database migration {} {}",
+            last.version, last.description
+        );
+        last.applied = false;
+        Ok(Some(last))
+    }
+
+    /// Returns all database migrations ordered by their version and whether they are applied.
+    /// # Errors
+    /// if the applied migrations could not be read from the database
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        let mut connection = self.pool.acquire().await?;
+        connection.ensure_migrations_table().await?;
+        let applied = connection.list_applied_migrations().await?;
+
+        Ok(MIGRATOR
+            .iter()
+            .filter(|migration| migration.migration_type.is_up_migration())
+            .map(|migration| MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: applied.iter().any(|a| a.version == migration.version),
+            })
+            .collect())
+    }
+
+    /// Records the number of idle and used database connections as metrics every `interval` in the background.
+    pub fn record_pool_metrics(&self, interval: Duration) {
+        let pool = self.pool.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                let idle = u32::try_from(pool.num_idle()).unwrap_or(u32::MAX);
+                let used = pool.size().saturating_sub(idle);
+                gauge!("db_pool_connections", "state" => "idle").set(idle);
+                gauge!("db_pool_connections", "state" => "used").set(used);
+            }
+        });
     }
 
     /// Returns a object for accessing database requests for api commands.
@@ -74,11 +170,70 @@ impl DataAccessFactory {
         PersistentRequestData::new(self.pool.clone(), self.max_weeks_data)
     }
 
+    /// Returns a object for accessing database requests for reviewing uploaded images.
+    #[must_use]
+    pub fn get_image_review_data_access(&self) -> PersistentImageReviewData {
+        PersistentImageReviewData {
+            pool: self.pool.clone(),
+        }
+    }
+
+    /// Returns a object for accessing database requests for removing data which is no longer needed.
+    #[must_use]
+    pub fn get_retention_data_access(&self) -> PersistentRetentionData {
+        PersistentRetentionData {
+            pool: self.pool.clone(),
+        }
+    }
+
+    /// Returns a object for accessing database requests for the moderation digest.
+    #[must_use]
+    pub fn get_digest_data_access(&self) -> PersistentDigestData {
+        PersistentDigestData {
+            pool: self.pool.clone(),
+        }
+    }
+
+    /// Returns a object for accessing database requests for electing the instance running the scheduled jobs.
+    #[must_use]
+    pub fn get_scheduler_lease_data_access(&self) -> PersistentSchedulerLeaseData {
+        PersistentSchedulerLeaseData {
+            pool: self.pool.clone(),
+        }
+    }
+
+    /// Returns a object checking whether the database can be queried.
+    #[must_use]
+    pub fn get_database_health(&self) -> DatabaseHealth {
+        DatabaseHealth {
+            pool: self.pool.clone(),
+        }
+    }
+
+    /// Returns a object checking whether the last successful meal plan parsing is at most `max_age` ago.
+    #[must_use]
+    pub fn get_parse_health(&self, max_age: Duration) -> ParseHealth {
+        ParseHealth {
+            pool: self.pool.clone(),
+            max_age,
+        }
+    }
+
+    /// Returns a object for accessing database requests of administrators.
+    #[must_use]
+    pub fn get_admin_data_access(&self) -> PersistentAdminData {
+        PersistentAdminData {
+            pool: self.pool.clone(),
+        }
+    }
+
     /// Returns a object for accessing database requests for authentication.
+    /// Secrets of api keys get encrypted using `cipher`.
     #[must_use]
-    pub fn get_auth_data_access(&self) -> PersistentAuthData {
+    pub fn get_auth_data_access(&self, cipher: ApiKeyCipher) -> PersistentAuthData {
         PersistentAuthData {
             pool: self.pool.clone(),
+            cipher,
         }
     }
 }
@@ -89,7 +244,10 @@ mod tests {
     use dotenvy::dotenv;
     use sqlx::migrate::MigrateDatabase;
 
-    use crate::layer::data::database::factory::{DataAccessFactory, DatabaseInfo};
+    use crate::layer::data::database::{
+        cipher::ApiKeyCipher,
+        factory::{DataAccessFactory, DatabaseInfo},
+    };
 
     #[tokio::test]
     async fn test_factory() {
@@ -111,7 +269,59 @@ mod tests {
         let _ = factory.get_command_data_access();
         let _ = factory.get_mealplan_management_data_access();
         let _ = factory.get_request_data_access();
-        let _ = factory.get_auth_data_access();
+        let _ = factory.get_auth_data_access(ApiKeyCipher::new(&[0; 32]));
+        let _ = factory.get_image_review_data_access();
+        let _ = factory.get_admin_data_access();
+        let _ = factory.get_retention_data_access();
+        let _ = factory.get_database_health();
+
+        std::mem::drop(factory); // drop database connection
+
+        sqlx::Postgres::drop_database(&connection)
+            .await
+            .expect("failed to delete test database");
+    }
+
+    #[tokio::test]
+    async fn test_migrations() {
+        dotenv().ok();
+        let mut connection = std::env::var("DATABASE_URL").expect("test needs DATABASE_URL set");
+        connection.push_str("_migration_test");
+
+        sqlx::Postgres::create_database(&connection)
+            .await
+            .expect("failed to create test database");
+
+        let info = DatabaseInfo {
+            connection: connection.clone(),
+            max_weeks_data: 4,
+        };
+        let factory = DataAccessFactory::new(info, false)
+            .await
+            .expect("failed to access test database");
+        let status = factory.migration_status().await.expect("status readable");
+        assert!(!status.is_empty());
+        assert!(status.iter().all(|migration| !migration.applied));
+        assert_eq!(factory.revert_last_migration().await.ok(), Some(None));
+
+        factory.migrate().await.expect("migrations run");
+        assert!(factory
+            .migration_status()
+            .await
+            .expect("status readable")
+            .iter()
+            .all(|migration| migration.applied));
+
+        let reverted = factory
+            .revert_last_migration()
+            .await
+            .expect("migration reverted")
+            .expect("migration was applied");
+        let status = factory.migration_status().await.expect("status readable");
+        assert_eq!(status.last(), Some(&reverted));
+        assert!(status[..status.len() - 1]
+            .iter()
+            .all(|migration| migration.applied));
 
         std::mem::drop(factory); // drop database connection
 
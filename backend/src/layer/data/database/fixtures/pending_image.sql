@@ -0,0 +1,5 @@
+INSERT INTO image(image_id, user_id, food_id, link_date, review_attempts, moderation_status)
+VALUES  ('0b1f5a3e-1b4d-4c51-9d44-3d9a9c8e1a01', 'c51d2d81-3547-4f07-af58-ed613c6ece67', 'f7337122-b018-48ad-b420-6202dc3cb4ff', CURRENT_DATE - 1, 0, 'PENDING'),
+        ('5e2c7b0d-7a4f-4e8e-8f0b-6c0c1f6d2a02', '00adb927-8cb9-4d80-ae01-d8f2e8f2d4cf', 'f7337122-b018-48ad-b420-6202dc3cb4ff', CURRENT_DATE, 2, 'PENDING'),
+        ('9a3d6e1f-2c8b-4f7a-b5d9-8e4f0a7c3b03', '00adb927-8cb9-4d80-ae01-d8f2e8f2d4cf', 'f7337122-b018-48ad-b420-6202dc3cb4ff', CURRENT_DATE, 5, 'PENDING'),
+        ('c4e8a2b6-3d1f-4a9c-8e7b-1f5d9c2a6e04', '00adb927-8cb9-4d80-ae01-d8f2e8f2d4cf', 'f7337122-b018-48ad-b420-6202dc3cb4ff', CURRENT_DATE, 0, 'REJECTED');
@@ -0,0 +1,106 @@
+//! Module responsible for checking whether the database and the data it gets filled with are usable.
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{Pool, Postgres};
+
+use crate::interface::health::{HealthCheck, HealthError, Result};
+
+/// Class checking whether the database can be queried.
+#[derive(Debug)]
+pub struct DatabaseHealth {
+    pub(super) pool: Pool<Postgres>,
+}
+
+#[async_trait]
+impl HealthCheck for DatabaseHealth {
+    fn name(&self) -> &'static str {
+        "database"
+    }
+
+    async fn check_health(&self) -> Result<()> {
+        sqlx::query!("SELECT 1 as one")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| HealthError::Unreachable(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Class checking whether the meal plan recently got parsed without errors.
+#[derive(Debug)]
+pub struct ParseHealth {
+    pub(super) pool: Pool<Postgres>,
+    pub(super) max_age: Duration,
+}
+
+#[async_trait]
+impl HealthCheck for ParseHealth {
+    fn name(&self) -> &'static str {
+        "parse"
+    }
+
+    async fn check_health(&self) -> Result<()> {
+        let last_success = sqlx::query_scalar!("SELECT last_success FROM parse_status")
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| HealthError::Unreachable(e.to_string()))?
+            .ok_or(HealthError::NeverParsed)?;
+
+        let age = (Utc::now() - last_success).to_std().unwrap_or_default();
+        if age > self.max_age {
+            return Err(HealthError::ParseOutdated(last_success));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        interface::persistent_data::MealplanManagementDataAccess,
+        layer::data::database::mealplan_management::PersistentMealplanManagementData,
+    };
+
+    #[sqlx::test]
+    async fn test_database_health(pool: PgPool) {
+        let health = DatabaseHealth { pool: pool.clone() };
+        assert!(health.check_health().await.is_ok());
+
+        pool.close().await;
+        assert!(matches!(
+            health.check_health().await,
+            Err(HealthError::Unreachable(_))
+        ));
+    }
+
+    #[sqlx::test]
+    async fn test_parse_health(pool: PgPool) {
+        let health = ParseHealth {
+            pool: pool.clone(),
+            max_age: Duration::from_hours(1),
+        };
+        assert!(matches!(
+            health.check_health().await,
+            Err(HealthError::NeverParsed)
+        ));
+
+        let mealplan = PersistentMealplanManagementData { pool: pool.clone() };
+        mealplan.mark_parse_succeeded().await.unwrap();
+        assert!(health.check_health().await.is_ok());
+
+        sqlx::query!("UPDATE parse_status SET last_success = now() - interval '2 hours'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(matches!(
+            health.check_health().await,
+            Err(HealthError::ParseOutdated(_))
+        ));
+    }
+}
@@ -0,0 +1,409 @@
+//! Module responsible for handling database requests for reviewing uploaded images.
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres};
+
+use super::audit::add_audit_entry;
+
+use crate::{
+    interface::persistent_data::{model::PendingImage, ImageReviewDataAccess, Result},
+    util::{AuditAction, Uuid, SYSTEM_ACTOR},
+};
+
+/// Class implementing all database requests arising from reviewing uploaded images.
+#[derive(Debug)]
+pub struct PersistentImageReviewData {
+    pub(super) pool: Pool<Postgres>,
+}
+
+#[async_trait]
+impl ImageReviewDataAccess for PersistentImageReviewData {
+    async fn get_pending_images(&self, max_attempts: u32) -> Result<Vec<PendingImage>> {
+        sqlx::query!(
+            "
+            SELECT image_id, review_attempts, f.name as meal_name
+            FROM image JOIN food f USING (food_id)
+            WHERE moderation_status = 'PENDING' AND deletion_date IS NULL AND review_attempts < $1
+            ORDER BY link_date, image_id
+            ",
+            i32::try_from(max_attempts)?
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|r| {
+            Ok(PendingImage {
+                id: r.image_id,
+                meal_name: r.meal_name,
+                review_attempts: u32::try_from(r.review_attempts)?,
+            })
+        })
+        .collect()
+    }
+
+    async fn accept_image(&self, image_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            "
+            UPDATE image SET moderation_status = 'ACCEPTED', review_message = NULL, review_attempts = 0,
+            last_verified_date = CURRENT_DATE
+            WHERE image_id = $1
+            ",
+            image_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn reject_image(&self, image_id: Uuid, reason: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query!(
+            "UPDATE image SET moderation_status = 'REJECTED', review_message = $2 WHERE image_id = $1",
+            image_id,
+            reason
+        )
+        .execute(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            SYSTEM_ACTOR,
+            AuditAction::RejectImage,
+            image_id,
+            Some(reason),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn add_failed_review_attempt(&self, image_id: Uuid, error: &str) -> Result<()> {
+        sqlx::query!(
+            "
+            UPDATE image SET review_attempts = review_attempts + 1, review_message = $2
+            WHERE image_id = $1
+            ",
+            image_id,
+            error
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn hide_unreviewable_image(&self, image_id: Uuid, error: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query!(
+            "
+            UPDATE image SET moderation_status = 'ACCEPTED', currently_visible = false, review_message = $2,
+            review_attempts = 0, last_verified_date = CURRENT_DATE
+            WHERE image_id = $1
+            ",
+            image_id,
+            error
+        )
+        .execute(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            SYSTEM_ACTOR,
+            AuditAction::HideImage,
+            image_id,
+            Some(error),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn get_images_to_reverify(
+        &self,
+        days: u32,
+        limit: u32,
+        max_attempts: u32,
+    ) -> Result<Vec<PendingImage>> {
+        // images of external hosters (with url) can not be loaded for validation
+        sqlx::query!(
+            "
+            SELECT image_id, review_attempts, f.name as meal_name
+            FROM image JOIN food f USING (food_id)
+            WHERE moderation_status = 'ACCEPTED' AND currently_visible AND NOT approved AND url IS NULL
+            AND deletion_date IS NULL AND last_verified_date <= CURRENT_DATE - $1::integer
+            AND review_attempts < $3
+            ORDER BY last_verified_date, image_id
+            LIMIT $2
+            ",
+            i32::try_from(days)?,
+            i64::from(limit),
+            i32::try_from(max_attempts)?
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|r| {
+            Ok(PendingImage {
+                id: r.image_id,
+                meal_name: r.meal_name,
+                review_attempts: u32::try_from(r.review_attempts)?,
+            })
+        })
+        .collect()
+    }
+
+    async fn renew_verification(&self, image_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            "
+            UPDATE image SET last_verified_date = CURRENT_DATE, review_attempts = 0, review_message = NULL
+            WHERE image_id = $1
+            ",
+            image_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn hide_failing_image(&self, image_id: Uuid, reason: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query!(
+            "
+            UPDATE image SET currently_visible = false, review_message = $2, last_verified_date = CURRENT_DATE
+            WHERE image_id = $1
+            ",
+            image_id,
+            reason
+        )
+        .execute(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            SYSTEM_ACTOR,
+            AuditAction::HideImage,
+            image_id,
+            Some(reason),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::util::ModerationStatus;
+
+    const PENDING_ID: Uuid = Uuid::from_u128(0x0b1f_5a3e_1b4d_4c51_9d44_3d9a_9c8e_1a01);
+    const REVERIFY_ID: Uuid = Uuid::from_u128(0x76b9_04fe_d0f1_4122_8832_d0e2_1aca_b86d);
+    const OTHER_REVERIFY_ID: Uuid = Uuid::from_u128(0xea8c_ce48_a3c7_4f8e_a222_5f38_91c1_3804);
+
+    #[sqlx::test(fixtures("meal", "image", "pending_image"))]
+    async fn test_get_pending_images(pool: PgPool) {
+        let review = PersistentImageReviewData { pool };
+
+        let pending = review.get_pending_images(3).await.unwrap();
+        assert_eq!(
+            pending
+                .iter()
+                .map(|i| i.review_attempts)
+                .collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+        assert_eq!(pending[0].id, PENDING_ID);
+        assert_eq!(
+            pending[0].meal_name,
+            "Geflügel - Cevapcici, Ajvar, Djuvec Reis"
+        );
+
+        assert_eq!(review.get_pending_images(10).await.unwrap().len(), 3);
+        assert!(review.get_pending_images(0).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test(fixtures("meal", "image", "pending_image"))]
+    async fn test_accept_image(pool: PgPool) {
+        let review = PersistentImageReviewData { pool: pool.clone() };
+
+        review.accept_image(PENDING_ID).await.unwrap();
+        assert_eq!(
+            get_status(&pool, PENDING_ID).await,
+            (ModerationStatus::Accepted, 0, None)
+        );
+        assert_eq!(review.get_pending_images(10).await.unwrap().len(), 2);
+    }
+
+    #[sqlx::test(fixtures("meal", "image", "pending_image"))]
+    async fn test_reject_image(pool: PgPool) {
+        let review = PersistentImageReviewData { pool: pool.clone() };
+
+        review.reject_image(PENDING_ID, "no food").await.unwrap();
+        assert_eq!(
+            get_status(&pool, PENDING_ID).await,
+            (ModerationStatus::Rejected, 0, Some("no food".into()))
+        );
+
+        let reason = sqlx::query_scalar!(
+            "SELECT reason FROM audit_log WHERE target_id = $1 AND action = 'REJECT_IMAGE'",
+            PENDING_ID
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(reason.as_deref(), Some("no food"));
+    }
+
+    #[sqlx::test(fixtures("meal", "image", "pending_image"))]
+    async fn test_add_failed_review_attempt(pool: PgPool) {
+        let review = PersistentImageReviewData { pool: pool.clone() };
+
+        review
+            .add_failed_review_attempt(PENDING_ID, "timeout")
+            .await
+            .unwrap();
+        review
+            .add_failed_review_attempt(PENDING_ID, "api error")
+            .await
+            .unwrap();
+        assert_eq!(
+            get_status(&pool, PENDING_ID).await,
+            (ModerationStatus::Pending, 2, Some("api error".into()))
+        );
+    }
+
+    #[sqlx::test(fixtures("meal", "image", "pending_image"))]
+    async fn test_hide_unreviewable_image(pool: PgPool) {
+        let review = PersistentImageReviewData { pool: pool.clone() };
+
+        review
+            .hide_unreviewable_image(PENDING_ID, "timeout")
+            .await
+            .unwrap();
+        assert_eq!(
+            get_status(&pool, PENDING_ID).await,
+            (ModerationStatus::Accepted, 0, Some("timeout".into()))
+        );
+        assert!(review
+            .get_pending_images(10)
+            .await
+            .unwrap()
+            .iter()
+            .all(|i| i.id != PENDING_ID));
+
+        let r = sqlx::query!(
+            "
+            SELECT currently_visible, (SELECT reason FROM audit_log WHERE target_id = $1 AND action = 'HIDE_IMAGE')
+            FROM image WHERE image_id = $1
+            ",
+            PENDING_ID
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(!r.currently_visible);
+        assert_eq!(r.reason.as_deref(), Some("timeout"));
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_get_images_to_reverify(pool: PgPool) {
+        let review = PersistentImageReviewData { pool: pool.clone() };
+
+        // hidden images and images verified by administrators are skipped
+        let images = review.get_images_to_reverify(0, 10, 3).await.unwrap();
+        assert_eq!(
+            images.iter().map(|i| i.id).collect::<Vec<_>>(),
+            vec![REVERIFY_ID, OTHER_REVERIFY_ID]
+        );
+        assert_eq!(
+            review.get_images_to_reverify(0, 1, 3).await.unwrap().len(),
+            1
+        );
+        assert!(review
+            .get_images_to_reverify(1, 10, 3)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // images failing too often are skipped until they get verified
+        review
+            .add_failed_review_attempt(REVERIFY_ID, "timeout")
+            .await
+            .unwrap();
+        assert_eq!(
+            review.get_images_to_reverify(0, 10, 1).await.unwrap()[0].id,
+            OTHER_REVERIFY_ID
+        );
+        review.renew_verification(REVERIFY_ID).await.unwrap();
+        assert_eq!(get_status(&pool, REVERIFY_ID).await.1, 0);
+
+        // images of external hosters are skipped
+        sqlx::query!(
+            "UPDATE image SET url = 'https://example.org/image.jpg' WHERE image_id = $1",
+            OTHER_REVERIFY_ID
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let images = review.get_images_to_reverify(0, 10, 3).await.unwrap();
+        assert_eq!(
+            images.iter().map(|i| i.id).collect::<Vec<_>>(),
+            vec![REVERIFY_ID]
+        );
+
+        sqlx::query!("UPDATE image SET last_verified_date = CURRENT_DATE - 30")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query!(
+            "UPDATE image SET url = NULL WHERE image_id = $1",
+            OTHER_REVERIFY_ID
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        review.renew_verification(REVERIFY_ID).await.unwrap();
+        let images = review.get_images_to_reverify(30, 10, 3).await.unwrap();
+        assert_eq!(
+            images.iter().map(|i| i.id).collect::<Vec<_>>(),
+            vec![OTHER_REVERIFY_ID]
+        );
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_hide_failing_image(pool: PgPool) {
+        let review = PersistentImageReviewData { pool: pool.clone() };
+
+        review
+            .hide_failing_image(REVERIFY_ID, "no food")
+            .await
+            .unwrap();
+        let r = sqlx::query!(
+            "SELECT currently_visible, review_message FROM image WHERE image_id = $1",
+            REVERIFY_ID
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(!r.currently_visible);
+        assert_eq!(r.review_message.as_deref(), Some("no food"));
+
+        let reason = sqlx::query_scalar!(
+            "SELECT reason FROM audit_log WHERE target_id = $1 AND action = 'HIDE_IMAGE'",
+            REVERIFY_ID
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(reason.as_deref(), Some("no food"));
+    }
+
+    async fn get_status(pool: &PgPool, image_id: Uuid) -> (ModerationStatus, i32, Option<String>) {
+        let r = sqlx::query!(
+            r#"SELECT moderation_status as "moderation_status: ModerationStatus", review_attempts, review_message FROM image WHERE image_id = $1"#,
+            image_id
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        (r.moderation_status, r.review_attempts, r.review_message)
+    }
+}
@@ -278,6 +278,18 @@ impl MealplanManagementDataAccess for PersistentMealplanManagementData {
     ) -> Result<()> {
         self.add_to_plan(side_id, line_id, date, price).await
     }
+
+    async fn mark_parse_succeeded(&self) -> Result<()> {
+        sqlx::query!(
+            "
+            INSERT INTO parse_status (last_success) VALUES (now())
+            ON CONFLICT (singleton) DO UPDATE SET last_success = now()
+            "
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
 }
 
 impl PersistentMealplanManagementData {
@@ -1,7 +1,15 @@
 //! This component is responsible for accesses to the database.
+pub mod admin;
+mod audit;
 pub mod auth;
+pub mod cipher;
 pub mod command;
+pub mod digest;
 pub mod factory;
+pub mod health;
+pub mod image_review;
 pub mod mealplan_management;
 pub mod request;
+pub mod retention;
+pub mod scheduling;
 mod types;
@@ -14,12 +14,14 @@ use dataloader::{
 };
 use sqlx::{Pool, Postgres};
 
+use super::admin::get_client_data;
+
 use crate::{
     interface::persistent_data::{
-        model::{Canteen, EnvironmentInfo, Image, Line, Meal, Side},
+        model::{Canteen, ClientData, EnvironmentInfo, Image, Line, Meal, Side},
         DataError, RequestDataAccess, Result,
     },
-    util::{Additive, Allergen, Date, NutritionData, Uuid},
+    util::{Additive, Allergen, Date, ModerationStatus, NutritionData, Uuid},
 };
 
 /// Class implementing all database requests arising from graphql manipulations.
@@ -159,6 +161,10 @@ impl RequestDataAccess for PersistentRequestData {
             .await?
             .unwrap_or_default()
             .into_iter()
+            .filter(|im| {
+                im.moderation_status == ModerationStatus::Accepted
+                    || client_id.is_some_and(|client_id| client_id == im.uploader_id)
+            })
             .filter(|im| {
                 im.reporting_users.as_ref().is_none_or(|users| {
                     let Some(client_id) = client_id else {
@@ -170,6 +176,43 @@ impl RequestDataAccess for PersistentRequestData {
             .collect())
     }
 
+    async fn get_my_images(&self, client_id: Uuid) -> Result<Vec<Image>> {
+        sqlx::query!(
+            r#"
+            SELECT image_id as "image_id!", rank as "rank!", upvotes as "upvotes!", downvotes as "downvotes!",
+                approved as "approved!", report_count as "report_count!", link_date as "upload_date!",
+                food_id as "meal_id!", user_id as "uploader_id!", moderation_status as "moderation_status!: ModerationStatus"
+            FROM image_detail
+            WHERE user_id = $1 AND deletion_date IS NULL
+            ORDER BY link_date DESC, image_id
+            "#,
+            client_id
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|m| {
+            Ok(Image {
+                id: m.image_id,
+                rank: m.rank,
+                upvotes: u32::try_from(m.upvotes)?,
+                downvotes: u32::try_from(m.downvotes)?,
+                approved: m.approved,
+                upload_date: m.upload_date,
+                report_count: u32::try_from(m.report_count)?,
+                meal_id: m.meal_id,
+                reporting_users: None,
+                uploader_id: m.uploader_id,
+                moderation_status: m.moderation_status,
+            })
+        })
+        .collect()
+    }
+
+    async fn get_client_data(&self, client_id: Uuid) -> Result<ClientData> {
+        get_client_data(&self.pool, client_id).await
+    }
+
     async fn get_personal_rating(&self, food_id: Uuid, client_id: Uuid) -> Result<Option<u32>> {
         self.rating_loader
             .load_one(RatingKey {
@@ -432,6 +475,44 @@ mod tests {
         );
     }
 
+    #[sqlx::test(fixtures("meal", "image", "pending_image"))]
+    async fn test_get_visible_pending_images(pool: PgPool) {
+        let request = PersistentRequestData::new(pool, MAX_WEEKS_DATA);
+        let meal_id = Uuid::parse_str("f7337122-b018-48ad-b420-6202dc3cb4ff").unwrap();
+        let uploader_id = Uuid::parse_str("00adb927-8cb9-4d80-ae01-d8f2e8f2d4cf").unwrap();
+        let other_id = Uuid::parse_str("c51d2d81-3547-4f07-af58-ed613c6ece67").unwrap();
+
+        // pending images are only visible to their uploader, rejected ones to nobody
+        let images = request
+            .get_visible_images(meal_id, Some(uploader_id))
+            .await
+            .unwrap();
+        assert_eq!(images.len(), 5);
+        assert_eq!(
+            images
+                .iter()
+                .filter(|i| i.moderation_status == ModerationStatus::Pending)
+                .count(),
+            2
+        );
+        assert!(images
+            .iter()
+            .filter(|i| i.moderation_status == ModerationStatus::Pending)
+            .all(|i| i.uploader_id == uploader_id));
+
+        let images = request
+            .get_visible_images(meal_id, Some(other_id))
+            .await
+            .unwrap();
+        assert_eq!(images.len(), 3);
+
+        let images = request.get_visible_images(meal_id, None).await.unwrap();
+        assert_eq!(images.len(), 3);
+        assert!(images
+            .iter()
+            .all(|i| i.moderation_status == ModerationStatus::Accepted));
+    }
+
     fn provide_dummy_images() -> Vec<Image> {
         let image1 = Image {
             id: Uuid::parse_str("1aa73d5d-1701-4975-aa3c-1422a8bc10e8").unwrap(),
@@ -443,6 +524,8 @@ mod tests {
             report_count: 0,
             meal_id: Uuid::parse_str("f7337122-b018-48ad-b420-6202dc3cb4ff").unwrap(),
             reporting_users: Some(Vec::new()),
+            uploader_id: Uuid::parse_str("c51d2d81-3547-4f07-af58-ed613c6ece67").unwrap(),
+            moderation_status: ModerationStatus::Accepted,
         };
         let image2 = Image {
             id: Uuid::parse_str("76b904fe-d0f1-4122-8832-d0e21acab86d").unwrap(),
@@ -452,6 +535,28 @@ mod tests {
         vec![image1, image2]
     }
 
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_get_my_images(pool: PgPool) {
+        let request = PersistentRequestData::new(pool.clone(), MAX_WEEKS_DATA);
+        let client_id = Uuid::parse_str("c51d2d81-3547-4f07-af58-ed613c6ece67").unwrap();
+
+        // hidden and unverified images are included as well
+        let images = request.get_my_images(client_id).await.unwrap();
+        assert_eq!(images.len(), 3);
+        assert!(images.iter().all(|i| i.uploader_id == client_id));
+
+        sqlx::query!(
+            "UPDATE image SET deletion_date = CURRENT_DATE WHERE image_id = $1",
+            images[0].id
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(request.get_my_images(client_id).await.unwrap().len(), 2);
+
+        assert!(request.get_my_images(WRONG_UUID).await.unwrap().is_empty());
+    }
+
     #[sqlx::test(fixtures("meal", "image", "rating"))]
     async fn test_get_personal_rating(pool: PgPool) {
         let request = PersistentRequestData::new(pool, MAX_WEEKS_DATA);
@@ -6,7 +6,7 @@ use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 
 use crate::interface::persistent_data::model::{EnvironmentInfo, Image, Side};
-use crate::util::{Additive, Allergen, FoodType, NutritionData, Price};
+use crate::util::{Additive, Allergen, FoodType, ModerationStatus, NutritionData, Price};
 
 use crate::{
     interface::persistent_data::{
@@ -258,10 +258,12 @@ impl Loader<Uuid> for ImageLoader {
             r#"
                 SELECT image_id as "image_id!", rank as "rank!", upvotes as "upvotes!", downvotes as "downvotes!", approved as "approved!", 
                     report_count as "report_count!", link_date as "upload_date!", food_id as "meal_id!", 
-                    COALESCE(array_agg(r.user_id) FILTER (WHERE r.user_id IS NOT NULL), ARRAY[]::uuid[]) as "reporting_users!"
+                    COALESCE(array_agg(r.user_id) FILTER (WHERE r.user_id IS NOT NULL), ARRAY[]::uuid[]) as "reporting_users!",
+                    image_detail.user_id as "uploader_id!", moderation_status as "moderation_status!: ModerationStatus"
                 FROM image_detail LEFT JOIN image_report r USING (image_id)
-                WHERE currently_visible AND food_id = ANY ($1)
-                GROUP BY image_id, rank, upvotes, downvotes, approved, report_count, link_date, food_id
+                WHERE currently_visible AND moderation_status <> 'REJECTED' AND food_id = ANY ($1)
+                AND image_detail.user_id NOT IN (SELECT client_id FROM client_ban)
+                GROUP BY image_id, rank, upvotes, downvotes, approved, report_count, link_date, food_id, image_detail.user_id, moderation_status
                 ORDER BY rank DESC, image_id
             "#,
             &keys
@@ -279,7 +281,9 @@ impl Loader<Uuid> for ImageLoader {
                     upload_date: m.upload_date,
                     report_count: u32::try_from(m.report_count)?,
                     meal_id: m.meal_id,
-                    reporting_users: Some(m.reporting_users) // todo maybe put into outer tuple instead modifying image struct and carrying these additional arrays everywhere. Overhead?
+                    reporting_users: Some(m.reporting_users), // todo maybe put into outer tuple instead modifying image struct and carrying these additional arrays everywhere. Overhead?
+                    uploader_id: m.uploader_id,
+                    moderation_status: m.moderation_status,
             });
             Ok(h)
         }).await
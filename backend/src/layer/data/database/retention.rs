@@ -0,0 +1,363 @@
+//! Module responsible for handling database requests for removing data which is no longer needed.
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres};
+
+use super::audit::add_audit_entry;
+
+use crate::{
+    interface::persistent_data::{model::StoredImage, Result, RetentionDataAccess},
+    util::{AuditAction, Uuid, SYSTEM_ACTOR},
+};
+
+/// Class implementing all database requests arising from removing data which is no longer needed.
+#[derive(Debug)]
+pub struct PersistentRetentionData {
+    pub(super) pool: Pool<Postgres>,
+}
+
+#[async_trait]
+impl RetentionDataAccess for PersistentRetentionData {
+    async fn get_expired_deleted_images(&self, grace_days: u32) -> Result<Vec<Uuid>> {
+        let images = sqlx::query_scalar!(
+            "
+            SELECT image_id FROM image
+            WHERE deletion_date <= CURRENT_DATE - $1::integer
+            ORDER BY deletion_date, image_id
+            ",
+            i32::try_from(grace_days)?
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(images)
+    }
+
+    async fn purge_image(&self, image_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM image WHERE image_id = $1 AND deletion_date IS NOT NULL",
+            image_id
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn purge_old_client_activity(&self, dry_run: bool) -> Result<u64> {
+        let mut transaction = self.pool.begin().await?;
+        let result = sqlx::query!("DELETE FROM client_activity WHERE day < CURRENT_DATE")
+            .execute(&mut *transaction)
+            .await?;
+        if !dry_run {
+            transaction.commit().await?;
+        }
+        Ok(result.rows_affected())
+    }
+
+    async fn purge_old_reports(&self, days: u32, dry_run: bool) -> Result<u64> {
+        let mut transaction = self.pool.begin().await?;
+        let result = sqlx::query!(
+            "DELETE FROM image_report WHERE report_date <= CURRENT_DATE - $1::integer",
+            i32::try_from(days)?
+        )
+        .execute(&mut *transaction)
+        .await?;
+        if !dry_run {
+            transaction.commit().await?;
+        }
+        Ok(result.rows_affected())
+    }
+
+    async fn purge_unserved_foods(&self, days: u32, dry_run: bool) -> Result<u64> {
+        let mut transaction = self.pool.begin().await?;
+        let foods = sqlx::query_scalar!(
+            "
+            SELECT food_id FROM food
+            WHERE NOT EXISTS (
+                SELECT FROM food_plan
+                WHERE food_plan.food_id = food.food_id AND serve_date > CURRENT_DATE - $1::integer
+            )
+            AND NOT EXISTS (SELECT FROM image WHERE image.food_id = food.food_id)
+            FOR UPDATE
+            ",
+            i32::try_from(days)?
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        // referencing rows have to be removed first
+        sqlx::query!("DELETE FROM food_plan WHERE food_id = ANY($1)", &foods)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!("DELETE FROM food_additive WHERE food_id = ANY($1)", &foods)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!("DELETE FROM food_allergen WHERE food_id = ANY($1)", &foods)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!(
+            "DELETE FROM food_nutrition_data WHERE food_id = ANY($1)",
+            &foods
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!("DELETE FROM food_env_score WHERE food_id = ANY($1)", &foods)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!("DELETE FROM meal_rating WHERE food_id = ANY($1)", &foods)
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query!("DELETE FROM meal WHERE food_id = ANY($1)", &foods)
+            .execute(&mut *transaction)
+            .await?;
+        let result = sqlx::query!("DELETE FROM food WHERE food_id = ANY($1)", &foods)
+            .execute(&mut *transaction)
+            .await?;
+
+        if !dry_run {
+            transaction.commit().await?;
+        }
+        Ok(result.rows_affected())
+    }
+
+    async fn get_long_hidden_images(&self, days: u32) -> Result<Vec<Uuid>> {
+        // the audit log knows when an image got hidden because of reports, rejected images are hidden since their review
+        let images = sqlx::query_scalar!(
+            "
+            SELECT image_id FROM image
+            WHERE deletion_date IS NULL
+            AND (NOT currently_visible OR moderation_status = 'REJECTED')
+            AND COALESCE(
+                (SELECT max(created_at)::date FROM audit_log WHERE target_id = image_id AND action = 'HIDE_IMAGE'),
+                link_date
+            ) <= CURRENT_DATE - $1::integer
+            ORDER BY link_date, image_id
+            ",
+            i32::try_from(days)?
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(images)
+    }
+
+    async fn get_stored_images(&self) -> Result<Vec<StoredImage>> {
+        // images with url got uploaded to flickr before images got stored locally
+        let images = sqlx::query_as!(
+            StoredImage,
+            r#"
+            SELECT image_id as id, link_date as upload_date, deletion_date IS NOT NULL as "deleted!"
+            FROM image WHERE url IS NULL
+            ORDER BY image_id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(images)
+    }
+
+    async fn delete_image(&self, image_id: Uuid, reason: &str) -> Result<()> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query!(
+            "
+            UPDATE image SET deletion_date = CURRENT_DATE, currently_visible = false, deleted_by = $2
+            WHERE image_id = $1 AND deletion_date IS NULL
+            ",
+            image_id,
+            SYSTEM_ACTOR
+        )
+        .execute(&mut *transaction)
+        .await?;
+        add_audit_entry(
+            &mut transaction,
+            SYSTEM_ACTOR,
+            AuditAction::DeleteImage,
+            image_id,
+            Some(reason),
+        )
+        .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use sqlx::PgPool;
+
+    use super::*;
+
+    const DELETED_ID: Uuid = Uuid::from_u128(0x76b9_04fe_d0f1_4122_8832_d0e2_1aca_b86d);
+    const VISIBLE_ID: Uuid = Uuid::from_u128(0x1aa7_3d5d_1701_4975_aa3c_1422_a8bc_10e8);
+    const HIDDEN_ID: Uuid = Uuid::from_u128(0x6815_3ab6_ebbf_48f4_b8dd_a9b2_a19a_5221);
+    const MEAL_WITH_IMAGES: Uuid = Uuid::from_u128(0xf733_7122_b018_48ad_b420_6202_dc3c_b4ff);
+
+    async fn delete_days_ago(pool: &PgPool, image_id: Uuid, days: i32) {
+        sqlx::query!(
+            "UPDATE image SET deletion_date = CURRENT_DATE - $2::integer WHERE image_id = $1",
+            image_id,
+            days
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_get_expired_deleted_images(pool: PgPool) {
+        let retention = PersistentRetentionData { pool: pool.clone() };
+        assert!(retention
+            .get_expired_deleted_images(0)
+            .await
+            .unwrap()
+            .is_empty());
+
+        delete_days_ago(&pool, DELETED_ID, 10).await;
+        assert_eq!(
+            retention.get_expired_deleted_images(10).await.unwrap(),
+            vec![DELETED_ID]
+        );
+        assert!(retention
+            .get_expired_deleted_images(11)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_purge_image(pool: PgPool) {
+        let retention = PersistentRetentionData { pool: pool.clone() };
+        delete_days_ago(&pool, DELETED_ID, 10).await;
+
+        retention.purge_image(DELETED_ID).await.unwrap();
+        // images not deleted before are kept
+        retention.purge_image(VISIBLE_ID).await.unwrap();
+
+        let images = sqlx::query_scalar!(
+            "SELECT image_id FROM image WHERE image_id IN ($1, $2)",
+            DELETED_ID,
+            VISIBLE_ID
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(images, vec![VISIBLE_ID]);
+    }
+
+    #[sqlx::test]
+    async fn test_purge_old_client_activity(pool: PgPool) {
+        let retention = PersistentRetentionData { pool: pool.clone() };
+        sqlx::query!(
+            "
+            INSERT INTO client_activity (client_id, action, day)
+            VALUES ($1, 'REPORT', CURRENT_DATE - 1), ($1, 'UPLOAD', CURRENT_DATE)
+            ",
+            DELETED_ID
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(retention.purge_old_client_activity(true).await.unwrap(), 1);
+        assert_eq!(retention.purge_old_client_activity(false).await.unwrap(), 1);
+        assert_eq!(retention.purge_old_client_activity(false).await.unwrap(), 0);
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_purge_old_reports(pool: PgPool) {
+        let retention = PersistentRetentionData { pool: pool.clone() };
+        assert_eq!(retention.purge_old_reports(0, false).await.unwrap(), 0);
+
+        sqlx::query!("UPDATE image_report SET report_date = CURRENT_DATE - 30")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(retention.purge_old_reports(31, false).await.unwrap(), 0);
+        assert_eq!(retention.purge_old_reports(30, true).await.unwrap(), 1);
+        assert_eq!(retention.purge_old_reports(30, false).await.unwrap(), 1);
+        assert_eq!(retention.purge_old_reports(30, false).await.unwrap(), 0);
+    }
+
+    #[sqlx::test(fixtures(
+        "canteen",
+        "line",
+        "meal",
+        "food_plan",
+        "image",
+        "additive",
+        "meal_rating"
+    ))]
+    async fn test_purge_unserved_foods(pool: PgPool) {
+        let retention = PersistentRetentionData { pool: pool.clone() };
+        // only the meal never served
+        assert_eq!(retention.purge_unserved_foods(1, true).await.unwrap(), 1);
+        assert_eq!(retention.purge_unserved_foods(1, false).await.unwrap(), 1);
+
+        sqlx::query!("UPDATE food_plan SET serve_date = CURRENT_DATE - 10")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(retention.purge_unserved_foods(11, false).await.unwrap(), 0);
+        // meals with images are kept
+        assert_eq!(retention.purge_unserved_foods(10, false).await.unwrap(), 3);
+
+        let foods = sqlx::query_scalar!("SELECT food_id FROM food")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(foods, vec![MEAL_WITH_IMAGES]);
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_get_long_hidden_images(pool: PgPool) {
+        let retention = PersistentRetentionData { pool: pool.clone() };
+        assert_eq!(
+            retention.get_long_hidden_images(0).await.unwrap(),
+            vec![HIDDEN_ID]
+        );
+        assert!(retention
+            .get_long_hidden_images(1)
+            .await
+            .unwrap()
+            .is_empty());
+
+        sqlx::query!(
+            "UPDATE image SET link_date = CURRENT_DATE - 10, moderation_status = 'REJECTED' WHERE image_id = $1",
+            VISIBLE_ID
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            retention.get_long_hidden_images(10).await.unwrap(),
+            vec![VISIBLE_ID]
+        );
+
+        // images get deleted only once
+        retention.delete_image(VISIBLE_ID, "test").await.unwrap();
+        assert!(retention
+            .get_long_hidden_images(10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[sqlx::test(fixtures("meal", "image"))]
+    async fn test_get_stored_images(pool: PgPool) {
+        let retention = PersistentRetentionData { pool: pool.clone() };
+        retention.delete_image(DELETED_ID, "test").await.unwrap();
+
+        let images = retention.get_stored_images().await.unwrap();
+        assert_eq!(images.len(), 4);
+        assert!(images
+            .iter()
+            .all(|image| image.deleted == (image.id == DELETED_ID)));
+
+        let reason = sqlx::query_scalar!(
+            "SELECT reason FROM audit_log WHERE target_id = $1 AND action = 'DELETE_IMAGE'",
+            DELETED_ID
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(reason.as_deref(), Some("test"));
+    }
+}
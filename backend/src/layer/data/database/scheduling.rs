@@ -0,0 +1,156 @@
+//! Module responsible for handling database requests for electing the instance running the scheduled jobs and for their shared state.
+use std::time::Duration;
+
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    interface::persistent_data::{Result, SchedulerLeaseDataAccess},
+    util::Uuid,
+};
+
+/// Class implementing all database requests arising from electing the instance running the scheduled jobs and from pausing them.
+#[derive(Debug)]
+pub struct PersistentSchedulerLeaseData {
+    pub(super) pool: Pool<Postgres>,
+}
+
+#[async_trait]
+impl SchedulerLeaseDataAccess for PersistentSchedulerLeaseData {
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: Uuid,
+        duration: Duration,
+    ) -> Result<bool> {
+        // expiry is compared using the clock of the database only, so clocks of instances may differ
+        let acquired = sqlx::query_scalar!(
+            "
+            INSERT INTO scheduler_lease (name, holder, expires_at)
+            VALUES ($1, $2, now() + make_interval(secs => $3))
+            ON CONFLICT (name) DO UPDATE SET holder = $2, expires_at = excluded.expires_at
+            WHERE scheduler_lease.holder = $2 OR scheduler_lease.expires_at < now()
+            RETURNING holder
+            ",
+            name,
+            holder,
+            duration.as_secs_f64()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(acquired.is_some())
+    }
+
+    async fn release_lease(&self, name: &str, holder: Uuid) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM scheduler_lease WHERE name = $1 AND holder = $2",
+            name,
+            holder
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_paused_jobs(&self) -> Result<Vec<String>> {
+        let paused = sqlx::query_scalar!("SELECT name FROM scheduler_paused_job ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(paused)
+    }
+
+    async fn set_job_paused(&self, name: &str, paused: bool) -> Result<()> {
+        if paused {
+            sqlx::query!(
+                "INSERT INTO scheduler_paused_job (name) VALUES ($1) ON CONFLICT (name) DO NOTHING",
+                name
+            )
+            .execute(&self.pool)
+            .await?;
+        } else {
+            sqlx::query!("DELETE FROM scheduler_paused_job WHERE name = $1", name)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use sqlx::PgPool;
+
+    use super::*;
+
+    const LEASE: &str = "scheduler";
+
+    #[sqlx::test]
+    async fn test_lease(pool: PgPool) {
+        let lease_data = PersistentSchedulerLeaseData { pool };
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        let duration = Duration::from_millis(500);
+
+        assert!(lease_data
+            .try_acquire_lease(LEASE, first, duration)
+            .await
+            .unwrap());
+        assert!(lease_data
+            .try_acquire_lease(LEASE, first, duration)
+            .await
+            .unwrap());
+        assert!(!lease_data
+            .try_acquire_lease(LEASE, second, duration)
+            .await
+            .unwrap());
+        assert!(lease_data
+            .try_acquire_lease("other", second, duration)
+            .await
+            .unwrap());
+
+        // fail over after expiry
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(lease_data
+            .try_acquire_lease(LEASE, second, duration)
+            .await
+            .unwrap());
+        assert!(!lease_data
+            .try_acquire_lease(LEASE, first, duration)
+            .await
+            .unwrap());
+
+        // releasing only works for the holder
+        lease_data.release_lease(LEASE, first).await.unwrap();
+        assert!(!lease_data
+            .try_acquire_lease(LEASE, first, duration)
+            .await
+            .unwrap());
+        lease_data.release_lease(LEASE, second).await.unwrap();
+        assert!(lease_data
+            .try_acquire_lease(LEASE, first, duration)
+            .await
+            .unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_paused_jobs(pool: PgPool) {
+        let lease_data = PersistentSchedulerLeaseData { pool };
+
+        assert!(lease_data.get_paused_jobs().await.unwrap().is_empty());
+        lease_data.set_job_paused("retention", true).await.unwrap();
+        lease_data.set_job_paused("digest", true).await.unwrap();
+        lease_data.set_job_paused("digest", true).await.unwrap();
+        assert_eq!(
+            lease_data.get_paused_jobs().await.unwrap(),
+            vec!["digest", "retention"]
+        );
+
+        lease_data.set_job_paused("digest", false).await.unwrap();
+        lease_data.set_job_paused("unknown", false).await.unwrap();
+        assert_eq!(
+            lease_data.get_paused_jobs().await.unwrap(),
+            vec!["retention"]
+        );
+    }
+}
@@ -9,11 +9,18 @@ use tokio::fs;
 use tracing::trace;
 
 use crate::{
-    interface::image_storage::{ImageStorage, Result},
+    interface::{
+        health::{HealthCheck, HealthError, Result as HealthResult},
+        image_storage::{ImageStorage, Result},
+    },
     util::{ImageResource, Uuid},
 };
 
+/// File written and removed again for checking whether images can be saved.
+const HEALTH_CHECK_FILE: &str = ".health_check";
+
 /// Struct containing all information necessary to construct a [`FileHandler`].
+#[derive(Debug, Clone)]
 pub struct FileHandlerInfo {
     /// Path where images should be stored
     pub image_dir: PathBuf,
@@ -61,6 +68,56 @@ impl ImageStorage for FileHandler {
 
         Ok(())
     }
+
+    async fn load_image(&self, id: Uuid) -> Result<ImageResource> {
+        let mut path = self.image_path.clone();
+        path.push(id.to_string());
+        path.set_extension(IMAGE_EXTENSION);
+
+        let image = tokio::task::spawn_blocking(move || image::open(path))
+            .await
+            .expect("image loading should not panic nor get aborted")?;
+
+        Ok(image)
+    }
+
+    async fn list_images(&self) -> Result<Vec<Uuid>> {
+        let mut entries = fs::read_dir(&self.image_path).await?;
+        let mut ids = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            // other files like the one written for health checks are no images
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == IMAGE_EXTENSION) {
+                if let Some(id) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| Uuid::try_parse(stem).ok())
+                {
+                    ids.push(id);
+                }
+            }
+        }
+        Ok(ids)
+    }
+}
+
+#[async_trait]
+impl HealthCheck for FileHandler {
+    fn name(&self) -> &'static str {
+        "image_dir"
+    }
+
+    async fn check_health(&self) -> HealthResult<()> {
+        let mut path = self.image_path.clone();
+        path.push(HEALTH_CHECK_FILE);
+
+        fs::write(&path, [])
+            .await
+            .map_err(|e| HealthError::NotWritable(format!("{}: {e}", self.image_path.display())))?;
+        fs::remove_file(&path)
+            .await
+            .map_err(|e| HealthError::NotWritable(format!("{}: {e}", self.image_path.display())))
+    }
 }
 
 #[cfg(test)]
@@ -131,4 +188,66 @@ mod tests {
         file_handler.delete_image(uuid).await.unwrap();
         assert!(!fs::try_exists(&image_path).await.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_load_image() {
+        let image =
+            ImageResource::ImageRgb8(ImageBuffer::from_fn(10, 10, |_, _| image::Rgb([10; 3])));
+
+        let uuid = Uuid::new_v4();
+
+        let temp_dir = TempDir::new().unwrap();
+        let info = FileHandlerInfo {
+            image_dir: temp_dir.path().to_path_buf(),
+        };
+
+        let file_handler = FileHandler::new(info);
+
+        assert!(file_handler.load_image(uuid).await.is_err());
+
+        file_handler.save_image(uuid, image.clone()).await.unwrap();
+        assert_eq!(image, file_handler.load_image(uuid).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_list_images() {
+        let image =
+            ImageResource::ImageRgb8(ImageBuffer::from_fn(10, 10, |_, _| image::Rgb([10; 3])));
+
+        let uuid = Uuid::new_v4();
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_handler = FileHandler::new(FileHandlerInfo {
+            image_dir: temp_dir.path().to_path_buf(),
+        });
+
+        assert!(file_handler.list_images().await.unwrap().is_empty());
+
+        file_handler.save_image(uuid, image).await.unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "no image")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("no_uuid.jpg"), [])
+            .await
+            .unwrap();
+        assert_eq!(file_handler.list_images().await.unwrap(), vec![uuid]);
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_handler = FileHandler::new(FileHandlerInfo {
+            image_dir: temp_dir.path().to_path_buf(),
+        });
+        assert!(file_handler.check_health().await.is_ok());
+        assert!(!temp_dir.path().join(HEALTH_CHECK_FILE).exists());
+
+        let file_handler = FileHandler::new(FileHandlerInfo {
+            image_dir: temp_dir.path().join("missing"),
+        });
+        assert!(matches!(
+            file_handler.check_health().await,
+            Err(HealthError::NotWritable(_))
+        ));
+    }
 }
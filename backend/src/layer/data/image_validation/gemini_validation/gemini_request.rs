@@ -1,6 +1,8 @@
 use crate::interface::image_validation::ImageValidationError::JsonDecodeFailed;
 use crate::interface::image_validation::{parse_request, Result};
 use crate::layer::data::image_validation::gemini_validation::json_request::GeminiResponseJson;
+use crate::util::trace_context_headers;
+use tracing::instrument;
 
 // Consider: This is the beta version. Change if depreciated!
 const API_REST_URL: &str =
@@ -57,9 +59,11 @@ impl GeminiRequest {
         }
     }
 
+    #[instrument(skip_all)]
     async fn request_api(&self, b64_image: &str) -> Result<GeminiResponseJson> {
         let resp = reqwest::Client::new()
             .post(API_REST_URL)
+            .headers(trace_context_headers())
             .query(&[("key", &self.api_key)])
             .json(&build_request_body(&self.text_request, b64_image))
             .send()
@@ -3,6 +3,8 @@ use crate::interface::image_validation::Result;
 use crate::interface::image_validation::{ImageValidation, ImageValidationInfo};
 use crate::layer::data::image_validation::gemini_validation::gemini_evaluation::GeminiEvaluation;
 use crate::layer::data::image_validation::gemini_validation::gemini_request::GeminiRequest;
+use crate::layer::data::image_validation::openai_validation::openai_evaluation::OpenAiEvaluation;
+use crate::layer::data::image_validation::openai_validation::openai_request::OpenAiRequest;
 use crate::layer::data::image_validation::safe_search_validation::safe_search_evaluation::SafeSearchEvaluation;
 use crate::layer::data::image_validation::safe_search_validation::safe_search_request::SafeSearchRequest;
 use crate::util::ImageResource;
@@ -21,9 +23,11 @@ use std::io::Cursor;
 ///     - determine which images are allowed<br>
 ///     - and returning helpful error messages if an image got not accepted
 #[derive(Default)]
+#[allow(clippy::struct_field_names)]
 pub struct GoogleApiHandler {
     safe_search_handler: Option<SafeSearchHandler>,
     gemini_handler: Option<GeminiHandler>,
+    openai_handler: Option<OpenAiHandler>,
 }
 
 struct SafeSearchHandler {
@@ -36,6 +40,11 @@ struct GeminiHandler {
     request: GeminiRequest,
 }
 
+struct OpenAiHandler {
+    evaluation: OpenAiEvaluation,
+    request: OpenAiRequest,
+}
+
 impl GoogleApiHandler {
     /// This method creates a new instance of the [`GoogleApiHandler`].
     /// # Params
@@ -66,34 +75,43 @@ impl GoogleApiHandler {
             } else {
                 None
             },
+            openai_handler: info.openai_info.map(|info| OpenAiHandler {
+                evaluation: OpenAiEvaluation::new(info.reject_meal_mismatch),
+                request: OpenAiRequest::new(
+                    &info.base_url,
+                    info.model,
+                    info.api_key,
+                    info.text_request,
+                ),
+            }),
         })
     }
 }
 
 #[async_trait]
 impl ImageValidation for GoogleApiHandler {
-    async fn validate_image(&self, image: &ImageResource) -> Result<()> {
+    async fn validate_image(&self, image: &ImageResource, meal_name: &str) -> Result<()> {
         let b64_image = image_to_base64(image)?;
 
-        let safe_search_result = match self.safe_search_handler.as_ref() {
-            Some(handler) => {
-                let results = handler.request.encoded_image_validation(&b64_image).await?;
-                handler.evaluation.verify(&results)
-            }
-            None => Ok(()),
-        };
+        if let Some(handler) = self.safe_search_handler.as_ref() {
+            let results = handler.request.encoded_image_validation(&b64_image).await?;
+            handler.evaluation.verify(&results)?;
+        }
 
-        if safe_search_result.is_ok() {
-            match self.gemini_handler.as_ref() {
-                Some(handler) => {
-                    let results = handler.request.encoded_image_validation(&b64_image).await?;
-                    handler.evaluation.evaluate(&results)
-                }
-                None => Ok(()),
-            }
-        } else {
-            safe_search_result
+        if let Some(handler) = self.gemini_handler.as_ref() {
+            let results = handler.request.encoded_image_validation(&b64_image).await?;
+            handler.evaluation.evaluate(&results)?;
+        }
+
+        if let Some(handler) = self.openai_handler.as_ref() {
+            let results = handler
+                .request
+                .encoded_image_validation(&b64_image, meal_name)
+                .await?;
+            handler.evaluation.evaluate(&results, meal_name)?;
         }
+
+        Ok(())
     }
 }
 
@@ -125,6 +143,7 @@ mod tests {
     const P_B64_IMG: &str = "src/layer/data/image_validation/test/b64_test.png";
     const VALID_IMG: &str = "src/layer/data/image_validation/test/valid_food.jpg";
     const INVALID_IMG: &str = "src/layer/data/image_validation/test/invalid_food.jpg";
+    const MEAL_NAME: &str = "Pasta";
 
     #[derive(Debug, serde::Deserialize)]
     struct SampleSet {
@@ -225,23 +244,23 @@ mod tests {
     async fn test_validate_image() {
         let acceptance = [2, 2, 2, 2, 2];
         assert!(get_handler(true, true, acceptance, &String::default())
-            .validate_image(&image::open(VALID_IMG).unwrap())
+            .validate_image(&image::open(VALID_IMG).unwrap(), MEAL_NAME)
             .await
             .is_ok());
         assert!(get_handler(true, true, acceptance, &String::default())
-            .validate_image(&image::open(INVALID_IMG).unwrap())
+            .validate_image(&image::open(INVALID_IMG).unwrap(), MEAL_NAME)
             .await
             .is_err());
         assert!(get_handler(false, false, acceptance, &String::default())
-            .validate_image(&image::open(INVALID_IMG).unwrap())
+            .validate_image(&image::open(INVALID_IMG).unwrap(), MEAL_NAME)
             .await
             .is_ok());
         assert!(get_handler(false, true, acceptance, &String::default())
-            .validate_image(&image::open(INVALID_IMG).unwrap())
+            .validate_image(&image::open(INVALID_IMG).unwrap(), MEAL_NAME)
             .await
             .is_err());
         assert!(get_handler(false, true, acceptance, &String::default())
-            .validate_image(&image::open(VALID_IMG).unwrap())
+            .validate_image(&image::open(VALID_IMG).unwrap(), MEAL_NAME)
             .await
             .is_ok());
     }
@@ -290,6 +309,7 @@ mod tests {
         GoogleApiHandler::new(ImageValidationInfo {
             safe_search_info,
             gemini_info,
+            openai_info: None,
         })
         .unwrap()
     }
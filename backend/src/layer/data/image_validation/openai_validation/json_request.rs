@@ -0,0 +1,69 @@
+use serde::Deserialize;
+
+/// Example for a valid response structure of the chat completions endpoint:
+/// ```json
+/// {
+///   "choices": [
+///     {
+///       "message": {
+///         "role": "assistant",
+///         "content": "{\"acceptable\":true,\"shows_meal\":true,\"reason\":\"Pasta with tomato sauce.\"}"
+///       }
+///     }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct OpenAiResponseJson {
+    pub(crate) choices: Vec<ChoiceJson>,
+}
+/// See [`OpenAiResponseJson`]
+#[derive(Debug, Deserialize)]
+pub struct ChoiceJson {
+    pub(crate) message: MessageJson,
+}
+/// See [`OpenAiResponseJson`]
+#[derive(Debug, Deserialize)]
+pub struct MessageJson {
+    pub(crate) content: Option<String>,
+    pub(crate) refusal: Option<String>,
+}
+
+/// Structured assessment the model is asked to answer with.
+/// ```json
+/// {
+///   "acceptable": false,
+///   "shows_meal": false,
+///   "reason": "The picture shows a person."
+/// }
+/// ```
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+pub struct OpenAiAssessment {
+    /// Whether the image shows food and does not contain inappropriate content.
+    pub acceptable: bool,
+    /// Whether the image plausibly shows the meal with the given name.
+    pub shows_meal: bool,
+    /// Short explanation for the decision.
+    pub reason: String,
+}
+
+/// Error structure returned by `OpenAI` compatible apis.
+/// ```json
+/// {
+///   "error": {
+///     "message": "Incorrect API key provided.",
+///     "type": "invalid_request_error"
+///   }
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct OpenAiErrorJson {
+    pub(crate) error: OpenAiErrorInfo,
+}
+/// See [`OpenAiErrorJson`]
+#[derive(Debug, Deserialize)]
+pub struct OpenAiErrorInfo {
+    pub(crate) message: String,
+    #[serde(rename = "type")]
+    pub(crate) error_type: Option<String>,
+}
@@ -0,0 +1,7 @@
+//! This module contains all components of the validation using an `OpenAI` compatible api.
+/// See [`json_request`]
+pub mod json_request;
+/// See [`openai_evaluation::OpenAiEvaluation`]
+pub mod openai_evaluation;
+/// See [`openai_request::OpenAiRequest`]
+pub mod openai_request;
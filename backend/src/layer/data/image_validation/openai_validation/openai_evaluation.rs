@@ -0,0 +1,95 @@
+use tracing::info;
+
+use crate::interface::image_validation::ImageValidationError::{
+    MealMismatchError, OpenAiRejectionError,
+};
+use crate::interface::image_validation::Result;
+use crate::layer::data::image_validation::openai_validation::json_request::OpenAiAssessment;
+
+/// The [`OpenAiEvaluation`] struct is used to evaluate the structured response of an `OpenAI` compatible api.
+#[derive(Default)]
+pub struct OpenAiEvaluation {
+    reject_meal_mismatch: bool,
+}
+
+impl OpenAiEvaluation {
+    /// This method creates a new instance of the [`OpenAiEvaluation`].
+    /// # Params
+    /// `reject_meal_mismatch`<br>
+    /// Whether images should be rejected if they do not show the meal they got uploaded for.
+    /// Otherwise a mismatch only gets logged.
+    #[must_use]
+    pub const fn new(reject_meal_mismatch: bool) -> Self {
+        Self {
+            reject_meal_mismatch,
+        }
+    }
+
+    /// This method evaluates the assessment provided by the api.
+    /// # Params
+    /// `assessment`<br>
+    /// The structured answer of the model.<br>
+    /// `meal_name`<br>
+    /// Name of the meal the image got uploaded for.
+    /// # Errors
+    /// This method returns an error if the evaluation decides to deny the image.
+    /// # Return
+    /// Nothing, what means the evaluation decided to accept the image.
+    pub fn evaluate(&self, assessment: &OpenAiAssessment, meal_name: &str) -> Result<()> {
+        if !assessment.acceptable {
+            return Err(OpenAiRejectionError(assessment.reason.clone()));
+        }
+
+        if !assessment.shows_meal {
+            if self.reject_meal_mismatch {
+                return Err(MealMismatchError(
+                    meal_name.to_string(),
+                    assessment.reason.clone(),
+                ));
+            }
+            info!(
+                "Image does not seem to show meal '{meal_name}' but got accepted: {}",
+                assessment.reason
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interface::image_validation::ImageValidationError;
+    use crate::layer::data::image_validation::openai_validation::{
+        json_request::OpenAiAssessment, openai_evaluation::OpenAiEvaluation,
+    };
+
+    fn assessment(acceptable: bool, shows_meal: bool) -> OpenAiAssessment {
+        OpenAiAssessment {
+            acceptable,
+            shows_meal,
+            reason: "reason".into(),
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let lenient = OpenAiEvaluation::new(false);
+        assert!(lenient.evaluate(&assessment(true, true), "Pasta").is_ok());
+        assert!(lenient.evaluate(&assessment(true, false), "Pasta").is_ok());
+        assert!(matches!(
+            lenient.evaluate(&assessment(false, true), "Pasta"),
+            Err(ImageValidationError::OpenAiRejectionError(_))
+        ));
+
+        let strict = OpenAiEvaluation::new(true);
+        assert!(strict.evaluate(&assessment(true, true), "Pasta").is_ok());
+        assert!(matches!(
+            strict.evaluate(&assessment(true, false), "Pasta"),
+            Err(ImageValidationError::MealMismatchError(meal, _)) if meal == "Pasta"
+        ));
+        assert!(matches!(
+            strict.evaluate(&assessment(false, false), "Pasta"),
+            Err(ImageValidationError::OpenAiRejectionError(_))
+        ));
+    }
+}
@@ -0,0 +1,328 @@
+use crate::interface::image_validation::ImageValidationError::{
+    ApiResponseError, JsonDecodeFailed, OpenAiRejectionError,
+};
+use crate::interface::image_validation::Result;
+use crate::layer::data::image_validation::openai_validation::json_request::{
+    OpenAiAssessment, OpenAiErrorJson, OpenAiResponseJson,
+};
+use crate::util::trace_context_headers;
+use tracing::instrument;
+
+const CHAT_COMPLETIONS_PATH: &str = "chat/completions";
+const REQUEST_TYPE: &str = "image/png";
+const SYSTEM_PROMPT: &str = "You moderate images uploaded to a canteen app. \
+    An image is acceptable if it shows food and does not contain people, text, advertisement or other inappropriate content. \
+    Additionally decide whether the image plausibly shows the meal with the given name. \
+    Give a short explanation in English.";
+
+/// The [`OpenAiRequest`] struct is used to send images with a question to an `OpenAI` compatible
+/// chat completions api and requests a structured assessment via rest.
+pub struct OpenAiRequest {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    text_request: String,
+}
+
+impl OpenAiRequest {
+    /// This method is used to create a new instance of the [`OpenAiRequest`] struct.
+    /// # Params
+    /// `base_url`<br>
+    /// Base url of the api, e.g. `https://api.openai.com/v1` or the one of a local server.<br>
+    /// `model`<br>
+    /// Name of the model that should be used.<br>
+    /// `api_key`<br>
+    /// This param contains the api key used as bearer token, if any.<br>
+    /// `text_request`<br>
+    /// This string contains the question that will be answered by the model.
+    /// # Return
+    /// The mentioned [`OpenAiRequest`] struct.
+    #[must_use]
+    pub fn new(
+        base_url: &str,
+        model: String,
+        api_key: Option<String>,
+        text_request: String,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!("{}/{CHAT_COMPLETIONS_PATH}", base_url.trim_end_matches('/')),
+            model,
+            api_key,
+            text_request,
+        }
+    }
+
+    /// This method calls the chat completions api with the provided image and meal name.
+    /// The api responds with an [`OpenAiResponseJson`] whose message contains an [`OpenAiAssessment`].
+    /// # Params
+    /// `b64_image`<br>
+    /// This param contains the png image as base64 string.<br>
+    /// `meal_name`<br>
+    /// Name of the meal the image got uploaded for.
+    /// # Errors
+    /// If the api responded with an error, refused to answer or any connection fault happened, an error will be returned.
+    /// See [`crate::interface::image_validation::ImageValidationError`] for more info about the errors.
+    /// # Return
+    /// The assessment of the model.
+    pub async fn encoded_image_validation(
+        &self,
+        b64_image: &str,
+        meal_name: &str,
+    ) -> Result<OpenAiAssessment> {
+        let message = self
+            .request_api(b64_image, meal_name)
+            .await?
+            .choices
+            .pop()
+            .ok_or(JsonDecodeFailed)?
+            .message;
+
+        if let Some(refusal) = message.refusal {
+            return Err(OpenAiRejectionError(refusal));
+        }
+
+        serde_json::from_str(&message.content.ok_or(JsonDecodeFailed)?)
+            .map_err(|_| JsonDecodeFailed)
+    }
+
+    #[instrument(skip_all)]
+    async fn request_api(&self, b64_image: &str, meal_name: &str) -> Result<OpenAiResponseJson> {
+        let mut request = self.client.post(&self.url).json(&build_request_body(
+            &self.model,
+            &self.text_request,
+            meal_name,
+            b64_image,
+        ));
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let resp = request.headers(trace_context_headers()).send().await?;
+        let status = resp.status();
+        let text = resp.text().await?;
+
+        if status.is_success() {
+            serde_json::from_str(&text).map_err(|_| JsonDecodeFailed)
+        } else {
+            let error = serde_json::from_str::<OpenAiErrorJson>(&text)
+                .map_err(|_| ApiResponseError(status.as_u16().into(), status.to_string(), text))?
+                .error;
+            Err(ApiResponseError(
+                status.as_u16().into(),
+                error.error_type.unwrap_or_else(|| status.to_string()),
+                error.message,
+            ))
+        }
+    }
+}
+
+/// ```json
+///     {
+///         "model": "gpt-4o-mini",
+///         "messages": [
+///             { "role": "system", "content": "..." },
+///             { "role": "user", "content": [
+///                 { "type": "text", "text": "This is a Question? The meal is called 'Pasta'." },
+///                 { "type": "image_url", "image_url": { "url": "data:image/png;base64,..." } }
+///             ]}
+///         ],
+///         "response_format": { "type": "json_schema", "json_schema": { ... } }
+///     }
+/// ```
+fn build_request_body(
+    model: &str,
+    text_request: &str,
+    meal_name: &str,
+    b64_image: &str,
+) -> serde_json::Value {
+    serde_json::json!({
+        "model": model,
+        "messages": [{
+            "role": "system",
+            "content": SYSTEM_PROMPT,
+        },
+        {
+            "role": "user",
+            "content": [{
+                "type": "text",
+                "text": format!("{text_request} The meal is called '{meal_name}'."),
+            },
+            {
+                "type": "image_url",
+                "image_url": {
+                    "url": format!("data:{REQUEST_TYPE};base64,{b64_image}"),
+                }
+            }]
+        }],
+        "response_format": {
+            "type": "json_schema",
+            "json_schema": {
+                "name": "image_assessment",
+                "strict": true,
+                "schema": {
+                    "type": "object",
+                    "properties": {
+                        "acceptable": { "type": "boolean" },
+                        "shows_meal": { "type": "boolean" },
+                        "reason": { "type": "string" },
+                    },
+                    "required": ["acceptable", "shows_meal", "reason"],
+                    "additionalProperties": false,
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    use super::{build_request_body, OpenAiRequest, REQUEST_TYPE};
+    use crate::interface::image_validation::ImageValidationError;
+    use crate::layer::data::image_validation::openai_validation::json_request::OpenAiAssessment;
+
+    const B64_IMAGE: &str = "iVBORw0KGgoAAAANSUhEUgAAAAgAAAAIAQMAAAD+wSzIAAAABlBMVEX///+/v7+jQ3Y5AAAADklEQVQI12P4AIX8EAgALgAD/aNpbtEAAAAASUVORK5CYII";
+    const API_KEY: &str = "secret";
+    const MODEL: &str = "test-model";
+
+    type Received = Arc<Mutex<Vec<(Option<String>, Value)>>>;
+
+    /// Starts a local stub of the chat completions api answering with `status` and `response`.
+    async fn start_stub(status: StatusCode, response: Value) -> (String, Received) {
+        let received = Received::default();
+        let app = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(
+                    move |State(received): State<Received>,
+                          headers: HeaderMap,
+                          Json(body): Json<Value>| async move {
+                        let auth = headers
+                            .get("authorization")
+                            .map(|h| h.to_str().unwrap().to_string());
+                        received.lock().unwrap().push((auth, body));
+                        (status, Json(response))
+                    },
+                ),
+            )
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}/v1/"), received)
+    }
+
+    fn completion(content: &str) -> Value {
+        json!({
+            "choices": [{
+                "message": { "role": "assistant", "content": content }
+            }]
+        })
+    }
+
+    #[test]
+    fn test_build_request_body() {
+        let json = build_request_body(MODEL, "Is this food?", "Pasta", B64_IMAGE);
+        assert_eq!(json["model"], MODEL);
+        assert_eq!(
+            json["messages"][1]["content"][0]["text"],
+            "Is this food? The meal is called 'Pasta'."
+        );
+        assert_eq!(
+            json["messages"][1]["content"][1]["image_url"]["url"],
+            format!("data:{REQUEST_TYPE};base64,{B64_IMAGE}")
+        );
+        assert_eq!(json["response_format"]["type"], "json_schema");
+    }
+
+    #[tokio::test]
+    async fn test_encoded_image_validation() {
+        let (url, received) = start_stub(
+            StatusCode::OK,
+            completion(r#"{"acceptable":true,"shows_meal":false,"reason":"Some soup."}"#),
+        )
+        .await;
+        let request = OpenAiRequest::new(&url, MODEL.into(), Some(API_KEY.into()), "Food?".into());
+
+        let assessment = request
+            .encoded_image_validation(B64_IMAGE, "Pasta")
+            .await
+            .unwrap();
+        assert_eq!(
+            assessment,
+            OpenAiAssessment {
+                acceptable: true,
+                shows_meal: false,
+                reason: "Some soup.".into()
+            }
+        );
+
+        let (auth, body) = received.lock().unwrap().pop().unwrap();
+        assert_eq!(auth.as_deref(), Some("Bearer secret"));
+        assert_eq!(body["model"], MODEL);
+    }
+
+    #[tokio::test]
+    async fn test_without_api_key() {
+        let (url, received) = start_stub(
+            StatusCode::OK,
+            completion(r#"{"acceptable":false,"shows_meal":false,"reason":"A person."}"#),
+        )
+        .await;
+        let request = OpenAiRequest::new(&url, MODEL.into(), None, "Food?".into());
+
+        assert!(
+            !request
+                .encoded_image_validation(B64_IMAGE, "Pasta")
+                .await
+                .unwrap()
+                .acceptable
+        );
+        assert_eq!(received.lock().unwrap()[0].0, None);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_responses() {
+        let (url, _) = start_stub(
+            StatusCode::UNAUTHORIZED,
+            json!({"error": {"message": "Incorrect API key provided.", "type": "invalid_request_error"}}),
+        )
+        .await;
+        let result = OpenAiRequest::new(&url, MODEL.into(), None, "Food?".into())
+            .encoded_image_validation(B64_IMAGE, "Pasta")
+            .await;
+        assert!(
+            matches!(result, Err(ImageValidationError::ApiResponseError(401, ref t, _)) if t == "invalid_request_error"),
+            "{result:?}"
+        );
+
+        let (url, _) = start_stub(StatusCode::OK, completion("Yes, this is food.")).await;
+        let result = OpenAiRequest::new(&url, MODEL.into(), None, "Food?".into())
+            .encoded_image_validation(B64_IMAGE, "Pasta")
+            .await;
+        assert!(matches!(
+            result,
+            Err(ImageValidationError::JsonDecodeFailed)
+        ));
+
+        let (url, _) = start_stub(
+            StatusCode::OK,
+            json!({"choices": [{"message": {"content": null, "refusal": "I can not help."}}]}),
+        )
+        .await;
+        let result = OpenAiRequest::new(&url, MODEL.into(), None, "Food?".into())
+            .encoded_image_validation(B64_IMAGE, "Pasta")
+            .await;
+        assert!(matches!(
+            result,
+            Err(ImageValidationError::OpenAiRejectionError(_))
+        ));
+    }
+}
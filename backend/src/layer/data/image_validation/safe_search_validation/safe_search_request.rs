@@ -3,8 +3,10 @@ use crate::interface::image_validation::{parse_request, Result};
 use crate::layer::data::image_validation::safe_search_validation::json_request::{
     SafeSearchJson, SafeSearchResponseJson,
 };
+use crate::util::trace_context_headers;
 use google_jwt_auth::usage::Usage::CloudVision;
 use google_jwt_auth::AuthConfig;
+use tracing::instrument;
 
 const API_REST_URL: &str = "https://vision.googleapis.com/v1/images:annotate";
 const PROJECT_ID_HEADER: &str = "x-goog-user-project";
@@ -60,6 +62,7 @@ impl SafeSearchRequest {
         }
     }
 
+    #[instrument(skip_all)]
     async fn request_api(
         &self,
         b64_image: &str,
@@ -74,6 +77,7 @@ impl SafeSearchRequest {
             .header(PROJECT_ID_HEADER, &self.google_project_id)
             .header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE)
             .header(reqwest::header::ACCEPT_CHARSET, CHARSET)
+            .headers(trace_context_headers())
             .json(&build_request_body(b64_image))
             .send()
             .await?
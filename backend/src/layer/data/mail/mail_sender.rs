@@ -1,73 +1,214 @@
 //! Module responsible for sending email notifications to administrators.
 
+use std::{sync::Arc, time::Duration};
+
 use async_trait::async_trait;
+use metrics::counter;
 use minijinja::{context, Environment, Value};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 use crate::{
-    interface::admin_notification::{AdminNotification, ImageReportInfo, Result},
+    interface::{
+        admin_notification::{
+            AdminNotification, DigestNotification, ImageReportInfo, ModerationDigest, Result,
+        },
+        health::{HealthCheck, HealthError, Result as HealthResult},
+    },
     layer::data::mail::mail_info::MailInfo,
     util::{self, Uuid},
 };
 use lettre::{
     message::{Mailbox, MaybeString, SinglePart},
     transport::smtp::authentication::Credentials,
-    Address, Message, SmtpTransport, Transport,
+    Address, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
 };
 
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 const REPORT_TEMPLATE: &str = include_str!("./template/template.html");
 const NOTIFY_TEMPLATE: &str = include_str!("./template/notification.html");
+const DIGEST_TEMPLATE: &str = include_str!("./template/digest.html");
 const REPORT_CSS: &str = include_str!("./template/output.css");
 const SENDER_NAME: &str = "MensaKa";
 const RECEIVER_NAME: &str = "Administrator";
+/// Delays before each further attempt of sending a mail which could not be sent.
+const RETRY_DELAYS: [Duration; 4] = [
+    Duration::from_mins(1),
+    Duration::from_mins(5),
+    Duration::from_mins(30),
+    Duration::from_hours(2),
+];
+/// Maximum number of mails retried at the same time, further mails which could not be sent get dropped.
+const MAX_RETRIED_MAILS: usize = 100;
+
+type Mailer = AsyncSmtpTransport<Tokio1Executor>;
 
 /// Class for sending emails.
+///
+/// Mails which could not be sent get retried in the background, each on its own, see [`RETRY_DELAYS`],
+/// unless created with [`MailSender::without_retries`]. At most [`MAX_RETRIED_MAILS`] are retried at the same time.
+/// Clones share their connection and their limit of retried mails.
+#[derive(Clone)]
 pub struct MailSender {
     config: MailInfo,
-    mailer: SmtpTransport, // todo async transport?
+    mailer: Mailer,
+    /// Permits for mails being retried, `None` if mails do not get retried.
+    retries: Option<Arc<Semaphore>>,
 }
 
 #[async_trait]
 impl AdminNotification for MailSender {
     async fn notify_admin_image_report(&self, info: ImageReportInfo) {
-        if let Err(error) = self.try_notify_admin_image_report(&info) {
+        if let Err(error) = self.try_notify_admin_image_report(&info).await {
             error!(%info.image_id, %info.reason, self.config.admin_email_address, "Error notifying administrator: {error}");
         }
     }
     async fn notify_admin_image_deleted(&self, image_id: Uuid) -> Result<()> {
         let subject = format!("❌ Image {}… deleted", &image_id.to_string()[..6]);
 
-        let body = Self::get_notification_body("deleted", image_id);
+        let restore_url = Self::get_restore_url(image_id);
+        let body = Self::get_notification_body("deleted", image_id, Some(&restore_url));
 
-        self.send_message(subject, image_id, body)
+        self.send_message(subject, image_id, body).await
+    }
+
+    async fn notify_admin_image_withdrawn(&self, image_id: Uuid, client_id: Uuid) -> Result<()> {
+        let subject = format!("🗑️ Image {}… withdrawn", &image_id.to_string()[..6]);
+
+        // the image is already gone for good, so there is nothing to restore
+        let body = Self::get_notification_body(
+            &format!("deleted by its uploader {client_id}"),
+            image_id,
+            None,
+        );
+
+        self.send_message(subject, image_id, body).await
     }
 
     async fn notify_admin_image_verified(&self, image_id: Uuid) -> Result<()> {
         let subject = format!("✅ Image {}… verified", &image_id.to_string()[..6]);
 
-        let body = Self::get_notification_body("verified", image_id);
+        let body = Self::get_notification_body("verified", image_id, None);
+
+        self.send_message(subject, image_id, body).await
+    }
+
+    async fn notify_admin_image_review_failed(&self, image_id: Uuid, error: &str) -> Result<()> {
+        let subject = format!(
+            "⚠️ Image {}… needs a manual review",
+            &image_id.to_string()[..6]
+        );
+
+        let body = Self::get_notification_body(
+            &format!("hidden until reviewed manually, as the automatic review failed: {error}"),
+            image_id,
+            None,
+        );
+
+        self.send_message(subject, image_id, body).await
+    }
+
+    async fn notify_admin_mass_reports(&self, client_id: Uuid, report_count: u32) -> Result<()> {
+        let subject = format!(
+            "🚩 Client {}… reported {report_count} images today",
+            &client_id.to_string()[..6]
+        );
+
+        let env = Environment::new();
+        let template = env
+            .template_from_str(NOTIFY_TEMPLATE)
+            .expect("template always preset");
+        let body = template
+            .render(context!(
+                css => REPORT_CSS,
+                client_id => client_id,
+                action => format!("reported {report_count} images today and might be abusing reports"),
+            ))
+            .expect("all arguments provided at compile time");
 
-        self.send_message(subject, image_id, body)
+        // mails about the same client get grouped like the ones about an image
+        self.send_message(subject, client_id, body).await
+    }
+}
+
+#[async_trait]
+impl DigestNotification for MailSender {
+    async fn notify_admin_digest(&self, digest: &ModerationDigest) -> Result<()> {
+        let subject = format!(
+            "📋 Moderation digest: {} reported, {} hidden, {} verified, {} deleted",
+            digest.reported_images.len(),
+            digest.hidden_images.len(),
+            digest.verified_images.len(),
+            digest.deleted_images.len()
+        );
+        let message = Message::builder()
+            .from(self.get_sender()?)
+            .to(self.get_receiver()?)
+            .subject(subject)
+            .singlepart(SinglePart::html(MaybeString::String(Self::get_digest(
+                digest,
+            ))))?;
+        self.send(message).await;
+        info!("Sent moderation digest to administrators");
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl HealthCheck for MailSender {
+    fn name(&self) -> &'static str {
+        "mail"
+    }
+
+    /// Mails only get sent to administrators, so requests can be served without them.
+    /// This also avoids connecting to the smtp server on each readiness probe.
+    fn is_required(&self) -> bool {
+        false
+    }
+
+    async fn check_health(&self) -> HealthResult<()> {
+        match self.mailer.test_connection().await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(HealthError::Unreachable(format!(
+                "no connection to {}",
+                self.config.smtp_server
+            ))),
+            Err(e) => Err(HealthError::Unreachable(e.to_string())),
+        }
     }
 }
 
 impl MailSender {
-    /// Creates a new [`MailSender`] with the attributes defined in config. Also creates an SMTP connection to the smtp server defined in config
+    /// Creates a new [`MailSender`] with the attributes defined in config. Also creates an SMTP connection to the smtp server defined in config.
     ///
     /// # Errors
     /// Returns an error, if the connection could not be established to the smtp server
     pub fn new(config: MailInfo) -> Result<Self> {
+        let mut sender = Self::without_retries(config)?;
+        sender.retries = Some(Arc::new(Semaphore::new(MAX_RETRIED_MAILS)));
+        Ok(sender)
+    }
+
+    /// Creates a new [`MailSender`] like [`MailSender::new`], which gives up on mails which could not be sent right away.
+    /// For short-lived processes like commands from the command line, which would exit before retrying.
+    ///
+    /// # Errors
+    /// Returns an error, if the connection could not be established to the smtp server
+    pub fn without_retries(config: MailInfo) -> Result<Self> {
         let creds = Credentials::new(config.username.clone(), config.password.clone());
-        let transport_builder = SmtpTransport::relay(&config.smtp_server)?;
+        let transport_builder = Mailer::relay(&config.smtp_server)?;
         let mailer = transport_builder
             .port(config.smtp_port)
             .credentials(creds)
             .build();
-        Ok(Self { config, mailer })
+        Ok(Self {
+            config,
+            mailer,
+            retries: None,
+        })
     }
 
-    fn try_notify_admin_image_report(&self, info: &ImageReportInfo) -> Result<()> {
+    async fn try_notify_admin_image_report(&self, info: &ImageReportInfo) -> Result<()> {
         let report = Self::get_report(info);
 
         let subject = format!(
@@ -87,7 +228,7 @@ impl MailSender {
             }
         );
 
-        self.send_message(subject, info.image_id, report)?;
+        self.send_message(subject, info.image_id, report).await?;
         info!(
             ?info,
             "Notified administrators about image report for image with id {}", info.image_id,
@@ -116,12 +257,17 @@ impl MailSender {
                 css => REPORT_CSS,
                 delete_url => util::local_to_global_url(&format!("/admin/report/delete_image/{}", info.image_id)),
                 verify_url => util::local_to_global_url(&format!("/admin/report/verify_image/{}", info.image_id)),
+                restore_url => Self::get_restore_url(info.image_id),
                 ..Value::from_serialize(info),
             ))
             .expect("all arguments provided at compile time")
     }
 
-    fn get_notification_body(action: &str, image_id: Uuid) -> String {
+    fn get_restore_url(image_id: Uuid) -> String {
+        util::local_to_global_url(&format!("/admin/report/restore_image/{image_id}"))
+    }
+
+    fn get_notification_body(action: &str, image_id: Uuid, restore_url: Option<&str>) -> String {
         let env = Environment::new();
         let template = env
             .template_from_str(NOTIFY_TEMPLATE)
@@ -132,6 +278,23 @@ impl MailSender {
                 css => REPORT_CSS,
                 action => action,
                 image_id => image_id,
+                restore_url => restore_url,
+            ))
+            .expect("all arguments provided at compile time")
+    }
+
+    fn get_digest(digest: &ModerationDigest) -> String {
+        let env = Environment::new();
+        let template = env
+            .template_from_str(DIGEST_TEMPLATE)
+            .expect("template always preset");
+
+        template
+            .render(context!(
+                css => REPORT_CSS,
+                since => digest.since.map(|since| since.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string()),
+                report_url => util::local_to_global_url("/admin/report"),
+                ..Value::from_serialize(digest),
             ))
             .expect("all arguments provided at compile time")
     }
@@ -140,31 +303,104 @@ impl MailSender {
         format!("<{image_id}@image-reports.mensa-ka.de>")
     }
 
-    fn send_message(&self, subject: impl Into<String>, image_id: Uuid, body: String) -> Result<()> {
+    /// Sends a mail, queueing it for retrying if the smtp server could not be reached.
+    async fn send_message(
+        &self,
+        subject: impl Into<String>,
+        image_id: Uuid,
+        body: String,
+    ) -> Result<()> {
         let message = Message::builder()
             .from(self.get_sender()?)
             .to(self.get_receiver()?)
             .subject(subject)
             .references(Self::get_references_tag(image_id))
             .singlepart(SinglePart::html(MaybeString::String(body)))?;
-        self.mailer.send(&message)?;
+        self.send(message).await;
         Ok(())
     }
+
+    /// Sends a built mail, retrying it in the background if the smtp server could not be reached.
+    async fn send(&self, message: Message) {
+        send_or_retry(&self.mailer, message, self.retries.as_ref()).await;
+    }
+}
+
+/// Sends a mail, retrying it in the background if it could not be sent and one of the `retries` permits is available.
+async fn send_or_retry<T>(mailer: &T, message: Message, retries: Option<&Arc<Semaphore>>)
+where
+    T: AsyncTransport + Clone + Send + Sync + 'static,
+    T::Error: std::fmt::Display,
+{
+    let Err(error) = mailer.send(message.clone()).await else {
+        return;
+    };
+    counter!("admin_notification_failures_total", "channel" => "mail").increment(1);
+    let Some(permit) = retries.and_then(|retries| retries.clone().try_acquire_owned().ok()) else {
+        counter!("admin_notification_failures_total", "channel" => "mail_given_up").increment(1);
+        error!("Could not send mail: {error}");
+        return;
+    };
+    warn!("Could not send mail, retrying later: {error}");
+    tokio::spawn(retry_mail(mailer.clone(), message, permit));
+}
+
+/// Retries sending a mail after each of the [`RETRY_DELAYS`], holding `permit` until it got sent or given up.
+async fn retry_mail<T>(mailer: T, message: Message, permit: OwnedSemaphorePermit)
+where
+    T: AsyncTransport + Sync,
+    T::Error: std::fmt::Display,
+{
+    if !resend(&mailer, &message, &RETRY_DELAYS).await {
+        counter!("admin_notification_failures_total", "channel" => "mail_given_up").increment(1);
+        error!(
+            "Giving up sending mail after {} further attempts.",
+            RETRY_DELAYS.len()
+        );
+    }
+    drop(permit);
+}
+
+/// Tries sending the mail again after each of the given delays, returning whether it got sent.
+async fn resend<T>(mailer: &T, message: &Message, delays: &[Duration]) -> bool
+where
+    T: AsyncTransport + Sync,
+    T::Error: std::fmt::Display,
+{
+    for (attempt, delay) in delays.iter().enumerate() {
+        tokio::time::sleep(*delay).await;
+        match mailer.send(message.clone()).await {
+            Ok(_) => return true,
+            Err(error) => warn!("Attempt {} of resending mail failed: {error}", attempt + 1),
+        }
+    }
+    false
 }
 
 #[cfg(test)]
 mod test {
     #![allow(clippy::unwrap_used)]
-    use super::REPORT_CSS;
+    use super::{resend, send_or_retry, MAX_RETRIED_MAILS, REPORT_CSS, RETRY_DELAYS};
     use crate::{
-        interface::admin_notification::{AdminNotification, ImageReportInfo},
+        interface::admin_notification::{
+            AdminNotification, DigestAction, DigestReport, ImageReportInfo, ModerationDigest,
+        },
         layer::data::mail::mail_info::MailInfo,
         layer::data::mail::mail_sender::MailSender,
-        util::Uuid,
+        util::{self, Uuid},
     };
-    use chrono::Local;
+    use chrono::{Local, Utc};
     use dotenvy;
-    use std::env::{self, VarError};
+    use lettre::{transport::stub::AsyncStubTransport, Message};
+    use std::{
+        env::{self, VarError},
+        sync::Arc,
+        time::Duration,
+    };
+    use tokio::{
+        sync::Semaphore,
+        time::{sleep, sleep_until, Instant},
+    };
     use tracing_test::traced_test;
 
     const SMTP_SERVER_ENV_NAME: &str = "SMTP_SERVER";
@@ -254,15 +490,60 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_get_notification_body() {
+        let id = Uuid::default();
+        let restore_url = MailSender::get_restore_url(id);
+
+        let deleted = MailSender::get_notification_body("deleted", id, Some(&restore_url));
+        assert!(deleted.contains(&id.to_string()));
+        assert!(deleted.contains(&restore_url));
+
+        let verified = MailSender::get_notification_body("verified", id, None);
+        assert!(!verified.contains(&restore_url));
+        assert!(!verified.contains("{{ "));
+    }
+
+    #[test]
+    fn test_get_digest() {
+        let image_id = Uuid::from_u128(1);
+        let digest = ModerationDigest {
+            since: Some(Utc::now()),
+            reported_images: vec![DigestReport {
+                image_id,
+                image_url: util::image_id_to_url(image_id),
+                report_count: 3,
+                reasons: vec!["Advert".into(), "NoMeal".into()],
+            }],
+            deleted_images: vec![DigestAction {
+                image_id: Uuid::from_u128(2),
+                image_url: util::image_id_to_url(Uuid::from_u128(2)),
+                actor: "moderator".into(),
+                time: Utc::now(),
+            }],
+            ..Default::default()
+        };
+
+        let body = MailSender::get_digest(&digest);
+        assert!(!body.contains("{{ "));
+        assert!(body.contains(REPORT_CSS));
+        assert!(body.contains("3x: Advert, NoMeal"));
+        assert!(body.contains(&format!("/admin/report/verify_image/{image_id}")));
+        assert!(body.contains("Deleted (1)"));
+        assert!(body.contains("moderator"));
+        assert!(!body.contains("Verified ("));
+    }
+
     #[tokio::test]
     async fn test_try_notify_admin_image_report() {
         let mail_info = get_mail_info().unwrap();
         let mail_sender = MailSender::new(mail_info).unwrap();
-        assert!(mail_sender.mailer.test_connection().unwrap());
+        assert!(mail_sender.mailer.test_connection().await.unwrap());
         let report_info = get_report_info();
 
         assert!(mail_sender
             .try_notify_admin_image_report(&report_info)
+            .await
             .is_ok());
     }
 
@@ -271,7 +552,7 @@ mod test {
     async fn test_notify_admin_iamge_report() {
         let mail_info = get_mail_info().unwrap();
         let mail_sender = MailSender::new(mail_info).unwrap();
-        assert!(mail_sender.mailer.test_connection().unwrap());
+        assert!(mail_sender.mailer.test_connection().await.unwrap());
         let report_info = get_report_info();
         mail_sender.notify_admin_image_report(report_info).await;
 
@@ -282,11 +563,86 @@ mod test {
         });
     }
 
+    #[tokio::test]
+    async fn test_resend() {
+        let message = get_message();
+        let delays = [Duration::ZERO; 3];
+
+        // gives up after all attempts failed
+        let failing = AsyncStubTransport::new_error();
+        assert!(!resend(&failing, &message, &delays).await);
+        assert_eq!(failing.messages().await.len(), 3);
+
+        // stops after the first success
+        let working = AsyncStubTransport::new_ok();
+        assert!(resend(&working, &message, &delays).await);
+        assert_eq!(working.messages().await.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_send_or_retry() {
+        let message = get_message();
+        let retries = Arc::new(Semaphore::new(MAX_RETRIED_MAILS));
+
+        // sent mails are not retried
+        let working = AsyncStubTransport::new_ok();
+        send_or_retry(&working, message.clone(), Some(&retries)).await;
+        assert_eq!(working.messages().await.len(), 1);
+        assert_eq!(retries.available_permits(), MAX_RETRIED_MAILS);
+
+        // failed mails are retried after each delay, holding a permit until given up
+        let failing = AsyncStubTransport::new_error();
+        send_or_retry(&failing, message.clone(), Some(&retries)).await;
+        assert_eq!(failing.messages().await.len(), 1);
+        assert_eq!(retries.available_permits(), MAX_RETRIED_MAILS - 1);
+        let mut due = Instant::now();
+        for (attempt, delay) in RETRY_DELAYS.iter().enumerate() {
+            due += *delay;
+            sleep_until(due - Duration::from_secs(1)).await;
+            assert_eq!(failing.messages().await.len(), attempt + 1);
+            sleep_until(due + Duration::from_secs(1)).await;
+            assert_eq!(failing.messages().await.len(), attempt + 2);
+        }
+        assert_eq!(retries.available_permits(), MAX_RETRIED_MAILS);
+
+        // without retries, failed mails are given up right away
+        let failing = AsyncStubTransport::new_error();
+        send_or_retry(&failing, message.clone(), None).await;
+        sleep(RETRY_DELAYS.iter().sum()).await;
+        assert_eq!(failing.messages().await.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_send_or_retry_limit() {
+        let message = get_message();
+        let retries = Arc::new(Semaphore::new(MAX_RETRIED_MAILS));
+        let failing = AsyncStubTransport::new_error();
+
+        // only the first mails are retried, the others are given up
+        for _ in 0..MAX_RETRIED_MAILS + 5 {
+            send_or_retry(&failing, message.clone(), Some(&retries)).await;
+        }
+        assert_eq!(retries.available_permits(), 0);
+        assert_eq!(failing.messages().await.len(), MAX_RETRIED_MAILS + 5);
+        sleep(RETRY_DELAYS[0] + Duration::from_secs(1)).await;
+        assert_eq!(failing.messages().await.len(), 2 * MAX_RETRIED_MAILS + 5);
+
+        // permits are given back once the retried mails are given up
+        sleep(RETRY_DELAYS.iter().sum()).await;
+        assert_eq!(retries.available_permits(), MAX_RETRIED_MAILS);
+        assert_eq!(
+            failing.messages().await.len(),
+            (RETRY_DELAYS.len() + 1) * MAX_RETRIED_MAILS + 5
+        );
+        send_or_retry(&failing, message, Some(&retries)).await;
+        assert_eq!(retries.available_permits(), MAX_RETRIED_MAILS - 1);
+    }
+
     #[tokio::test]
     async fn test_notify_admin_image_deleted() {
         let mail_info = get_mail_info().unwrap();
         let sender = MailSender::new(mail_info).unwrap();
-        assert!(sender.mailer.test_connection().unwrap());
+        assert!(sender.mailer.test_connection().await.unwrap());
 
         let id = Uuid::default();
 
@@ -297,13 +653,22 @@ mod test {
     async fn test_notify_admin_image_verified() {
         let mail_info = get_mail_info().unwrap();
         let sender = MailSender::new(mail_info).unwrap();
-        assert!(sender.mailer.test_connection().unwrap());
+        assert!(sender.mailer.test_connection().await.unwrap());
 
         let id = Uuid::default();
 
         assert!(sender.notify_admin_image_verified(id).await.is_ok());
     }
 
+    fn get_message() -> Message {
+        Message::builder()
+            .from("sender@example.com".parse().unwrap())
+            .to("admin@example.com".parse().unwrap())
+            .subject("test")
+            .body(String::from("test"))
+            .unwrap()
+    }
+
     fn get_report_info() -> ImageReportInfo {
         ImageReportInfo {
             reason: crate::util::ReportReason::Advert,
@@ -4,4 +4,5 @@ pub mod database;
 pub mod file_handler;
 pub mod image_validation;
 pub mod mail;
+pub mod notification;
 pub mod swka_parser;
@@ -0,0 +1,258 @@
+//! See [`NotificationFanOut`].
+
+use async_trait::async_trait;
+use futures::future::join_all;
+
+use crate::{
+    interface::admin_notification::{AdminNotification, ImageReportInfo, Result},
+    util::Uuid,
+};
+
+use super::{
+    notification_info::{ChannelFilter, NotificationInfo, NotificationKind},
+    webhook_sender::WebhookSender,
+};
+
+type Channel = Box<dyn AdminNotification>;
+
+/// Class sending each notification to all channels whose filter allows it, e.g. mail and webhooks.
+#[derive(Default)]
+pub struct NotificationFanOut {
+    channels: Vec<(ChannelFilter, Channel)>,
+}
+
+impl NotificationFanOut {
+    /// Creates a new [`NotificationFanOut`] notifying over `mail` and all webhooks defined in info.
+    ///
+    /// # Errors
+    /// Returns an error, if the http client for a webhook could not be created
+    pub fn new(info: NotificationInfo, mail: impl AdminNotification + 'static) -> Result<Self> {
+        let mut notification = Self::default().with_channel(info.mail_filter, mail);
+        for webhook in info.webhooks {
+            notification =
+                notification.with_channel(webhook.filter.clone(), WebhookSender::new(webhook)?);
+        }
+        Ok(notification)
+    }
+
+    /// Adds a channel which gets all notifications allowed by `filter`.
+    #[must_use]
+    pub fn with_channel(
+        mut self,
+        filter: ChannelFilter,
+        channel: impl AdminNotification + 'static,
+    ) -> Self {
+        self.channels.push((filter, Box::new(channel)));
+        self
+    }
+
+    fn channels(&self, kind: NotificationKind) -> impl Iterator<Item = &Channel> {
+        self.channels
+            .iter()
+            .filter(move |(filter, _)| filter.allows(kind))
+            .map(|(_, channel)| channel)
+    }
+
+    /// Returns the first error after all channels got notified.
+    async fn notify_all<'a, F, Fut>(&'a self, kind: NotificationKind, notify: F) -> Result<()>
+    where
+        F: Fn(&'a Channel) -> Fut,
+        Fut: std::future::Future<Output = Result<()>>,
+    {
+        join_all(self.channels(kind).map(notify))
+            .await
+            .into_iter()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AdminNotification for NotificationFanOut {
+    async fn notify_admin_image_report(&self, info: ImageReportInfo) {
+        let channels = self
+            .channels
+            .iter()
+            .filter(|(filter, _)| filter.allows_report(&info))
+            .map(|(_, channel)| channel.notify_admin_image_report(info.clone()));
+        join_all(channels).await;
+    }
+
+    async fn notify_admin_image_verified(&self, image_id: Uuid) -> Result<()> {
+        self.notify_all(NotificationKind::Verified, |channel| {
+            channel.notify_admin_image_verified(image_id)
+        })
+        .await
+    }
+
+    async fn notify_admin_image_deleted(&self, image_id: Uuid) -> Result<()> {
+        self.notify_all(NotificationKind::Deleted, |channel| {
+            channel.notify_admin_image_deleted(image_id)
+        })
+        .await
+    }
+
+    async fn notify_admin_image_withdrawn(&self, image_id: Uuid, client_id: Uuid) -> Result<()> {
+        self.notify_all(NotificationKind::Withdrawn, |channel| {
+            channel.notify_admin_image_withdrawn(image_id, client_id)
+        })
+        .await
+    }
+
+    async fn notify_admin_image_review_failed(&self, image_id: Uuid, error: &str) -> Result<()> {
+        self.notify_all(NotificationKind::ReviewFailed, |channel| {
+            channel.notify_admin_image_review_failed(image_id, error)
+        })
+        .await
+    }
+
+    async fn notify_admin_mass_reports(&self, client_id: Uuid, report_count: u32) -> Result<()> {
+        self.notify_all(NotificationKind::MassReports, |channel| {
+            channel.notify_admin_mass_reports(client_id, report_count)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+    use chrono::Local;
+
+    use super::NotificationFanOut;
+    use crate::{
+        interface::admin_notification::{
+            AdminNotification, ImageReportInfo, NotificationError, Result,
+        },
+        layer::data::notification::notification_info::{ChannelFilter, NotificationKind},
+        util::{ReportReason, Uuid},
+    };
+
+    /// Channel recording the notifications it got, failing if `fail` is set.
+    #[derive(Default, Clone)]
+    struct ChannelMock {
+        received: Arc<Mutex<Vec<&'static str>>>,
+        fail: bool,
+    }
+
+    impl ChannelMock {
+        fn record(&self, kind: &'static str) -> Result<()> {
+            self.received.lock().unwrap().push(kind);
+            if self.fail {
+                Err(NotificationError::AddressError(
+                    "invalid".parse::<lettre::Address>().unwrap_err(),
+                ))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AdminNotification for ChannelMock {
+        async fn notify_admin_image_report(&self, _info: ImageReportInfo) {
+            self.record("report").ok();
+        }
+        async fn notify_admin_image_verified(&self, _image_id: Uuid) -> Result<()> {
+            self.record("verified")
+        }
+        async fn notify_admin_image_deleted(&self, _image_id: Uuid) -> Result<()> {
+            self.record("deleted")
+        }
+        async fn notify_admin_image_withdrawn(
+            &self,
+            _image_id: Uuid,
+            _client_id: Uuid,
+        ) -> Result<()> {
+            self.record("withdrawn")
+        }
+        async fn notify_admin_image_review_failed(
+            &self,
+            _image_id: Uuid,
+            _error: &str,
+        ) -> Result<()> {
+            self.record("review_failed")
+        }
+        async fn notify_admin_mass_reports(
+            &self,
+            _client_id: Uuid,
+            _report_count: u32,
+        ) -> Result<()> {
+            self.record("mass_reports")
+        }
+    }
+
+    fn report(reason: ReportReason) -> ImageReportInfo {
+        ImageReportInfo {
+            reason,
+            image_got_hidden: false,
+            image_id: Uuid::default(),
+            image_url: String::new(),
+            report_count: 1,
+            positive_rating_count: 0,
+            negative_rating_count: 0,
+            image_rank: 0.5,
+            report_barrier: 5,
+            client_id: Uuid::default(),
+            image_age: 0,
+            meal_name: String::new(),
+            meal_id: Uuid::default(),
+            report_date: Local::now().date_naive(),
+            other_image_urls: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fan_out() {
+        let all = ChannelMock::default();
+        let offensive_only = ChannelMock::default();
+        let deletions_only = ChannelMock {
+            fail: true,
+            ..Default::default()
+        };
+        let fan_out = NotificationFanOut::default()
+            .with_channel(ChannelFilter::default(), all.clone())
+            .with_channel(
+                ChannelFilter {
+                    reasons: vec![ReportReason::Offensive],
+                    ..Default::default()
+                },
+                offensive_only.clone(),
+            )
+            .with_channel(
+                ChannelFilter {
+                    kinds: vec![NotificationKind::Deleted],
+                    ..Default::default()
+                },
+                deletions_only.clone(),
+            );
+
+        fan_out
+            .notify_admin_image_report(report(ReportReason::Advert))
+            .await;
+        fan_out
+            .notify_admin_image_report(report(ReportReason::Offensive))
+            .await;
+        fan_out
+            .notify_admin_image_verified(Uuid::default())
+            .await
+            .unwrap();
+        // all channels get notified, but the error is passed on
+        assert!(fan_out
+            .notify_admin_image_deleted(Uuid::default())
+            .await
+            .is_err());
+
+        assert_eq!(
+            *all.received.lock().unwrap(),
+            vec!["report", "report", "verified", "deleted"]
+        );
+        assert_eq!(
+            *offensive_only.received.lock().unwrap(),
+            vec!["report", "verified", "deleted"]
+        );
+        assert_eq!(*deletions_only.received.lock().unwrap(), vec!["deleted"]);
+    }
+}
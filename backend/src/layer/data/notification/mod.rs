@@ -0,0 +1,4 @@
+//! This component is responsible for notifying administrators over several channels, like mail and webhooks.
+pub mod fan_out;
+pub mod notification_info;
+pub mod webhook_sender;
@@ -0,0 +1,138 @@
+//! See [`NotificationInfo`].
+
+use serde::Deserialize;
+
+use crate::{interface::admin_notification::ImageReportInfo, util::ReportReason};
+
+/// Kinds of notifications administrators can get.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// An image got reported, see [`ImageReportInfo`].
+    Report,
+    /// An image got verified.
+    Verified,
+    /// An image got deleted by an administrator.
+    Deleted,
+    /// An image got deleted by the client who uploaded it.
+    Withdrawn,
+    /// An image could not be reviewed automatically and needs to be reviewed by an administrator.
+    ReviewFailed,
+    /// A client reported at least as many images today as the mass report threshold.
+    MassReports,
+}
+
+/// Decides which notifications get sent over a channel.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ChannelFilter {
+    /// Kinds of notifications to send, all if empty.
+    pub kinds: Vec<NotificationKind>,
+    /// Only send reports for these reasons, all if empty.
+    pub reasons: Vec<ReportReason>,
+    /// Only send reports which got the image hidden.
+    pub hidden_only: bool,
+}
+
+impl ChannelFilter {
+    /// Whether notifications of this kind get sent.
+    #[must_use]
+    pub fn allows(&self, kind: NotificationKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+
+    /// Whether this report gets sent.
+    #[must_use]
+    pub fn allows_report(&self, info: &ImageReportInfo) -> bool {
+        self.allows(NotificationKind::Report)
+            && (self.reasons.is_empty() || self.reasons.contains(&info.reason))
+            && (info.image_got_hidden || !self.hidden_only)
+    }
+}
+
+/// Format of the requests sent to a webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// The notification as JSON object, with its kind in the field `event`.
+    #[default]
+    Generic,
+    /// A message for Slack-compatible incoming webhooks, e.g. of Slack or Mattermost.
+    Slack,
+    /// A message for Matrix incoming webhooks, e.g. of matrix-hookshot.
+    Matrix,
+}
+
+/// Information necessary to notify administrators via a webhook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookInfo {
+    /// Url the notifications get posted to.
+    pub url: String,
+    /// Format of the posted notifications.
+    pub format: WebhookFormat,
+    /// Notifications to send to this webhook.
+    pub filter: ChannelFilter,
+}
+
+/// Information about all channels administrators get notified over.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NotificationInfo {
+    /// Notifications to send by mail, see [`MailInfo`](crate::layer::data::mail::mail_info::MailInfo).
+    pub mail_filter: ChannelFilter,
+    /// Webhooks to notify additionally.
+    pub webhooks: Vec<WebhookInfo>,
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Local;
+
+    use super::{ChannelFilter, NotificationKind};
+    use crate::{
+        interface::admin_notification::ImageReportInfo,
+        util::{ReportReason, Uuid},
+    };
+
+    fn report(reason: ReportReason, hidden: bool) -> ImageReportInfo {
+        ImageReportInfo {
+            reason,
+            image_got_hidden: hidden,
+            image_id: Uuid::default(),
+            image_url: String::new(),
+            report_count: 1,
+            positive_rating_count: 0,
+            negative_rating_count: 0,
+            image_rank: 0.5,
+            report_barrier: 5,
+            client_id: Uuid::default(),
+            image_age: 0,
+            meal_name: String::new(),
+            meal_id: Uuid::default(),
+            report_date: Local::now().date_naive(),
+            other_image_urls: vec![],
+        }
+    }
+
+    #[test]
+    fn test_channel_filter() {
+        let all = ChannelFilter::default();
+        assert!(all.allows(NotificationKind::Withdrawn));
+        assert!(all.allows_report(&report(ReportReason::Advert, false)));
+
+        let filter = ChannelFilter {
+            kinds: vec![NotificationKind::Report, NotificationKind::Deleted],
+            reasons: vec![ReportReason::Offensive, ReportReason::Advert],
+            hidden_only: true,
+        };
+        assert!(filter.allows(NotificationKind::Deleted));
+        assert!(!filter.allows(NotificationKind::Verified));
+        assert!(filter.allows_report(&report(ReportReason::Offensive, true)));
+        assert!(!filter.allows_report(&report(ReportReason::Offensive, false)));
+        assert!(!filter.allows_report(&report(ReportReason::NoMeal, true)));
+
+        let no_reports = ChannelFilter {
+            kinds: vec![NotificationKind::Deleted],
+            ..Default::default()
+        };
+        assert!(!no_reports.allows_report(&report(ReportReason::Advert, true)));
+    }
+}
@@ -0,0 +1,370 @@
+//! Module responsible for notifying administrators via webhooks, e.g. of chat services.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use metrics::counter;
+use serde::Serialize;
+use serde_json::json;
+use tracing::error;
+
+use crate::{
+    interface::admin_notification::{AdminNotification, ImageReportInfo, Result},
+    util::{self, Uuid},
+};
+
+use super::notification_info::{WebhookFormat, WebhookInfo};
+
+/// Time after which calling a webhook gets aborted.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A notification as sent to generic webhooks.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event")]
+enum Event<'a> {
+    #[serde(rename = "image_report")]
+    Report(&'a ImageReportInfo),
+    #[serde(rename = "image_verified")]
+    Verified { image_id: Uuid },
+    #[serde(rename = "image_deleted")]
+    Deleted { image_id: Uuid, restore_url: String },
+    #[serde(rename = "image_withdrawn")]
+    Withdrawn { image_id: Uuid, client_id: Uuid },
+    #[serde(rename = "image_review_failed")]
+    ReviewFailed { image_id: Uuid, error: &'a str },
+    #[serde(rename = "mass_reports")]
+    MassReports { client_id: Uuid, report_count: u32 },
+}
+
+/// A notification as short message for chat services: a title and labeled links.
+struct ChatMessage {
+    title: String,
+    links: Vec<(&'static str, String)>,
+}
+
+impl Event<'_> {
+    fn chat_message(&self) -> ChatMessage {
+        match self {
+            Self::Report(info) => ChatMessage {
+                title: format!(
+                    "{} Image {}… of {} {}, {}x: {}",
+                    if info.image_got_hidden {
+                        "👻"
+                    } else {
+                        "📜"
+                    },
+                    short_id(info.image_id),
+                    info.meal_name,
+                    if info.image_got_hidden {
+                        "hidden"
+                    } else {
+                        "reported"
+                    },
+                    info.report_count,
+                    info.reason,
+                ),
+                links: vec![
+                    ("Image", info.image_url.clone()),
+                    ("Verify", admin_url("verify_image", info.image_id)),
+                    ("Delete", admin_url("delete_image", info.image_id)),
+                ],
+            },
+            Self::Verified { image_id } => ChatMessage {
+                title: format!("✅ Image {}… verified", short_id(*image_id)),
+                links: vec![("Image", util::image_id_to_url(*image_id))],
+            },
+            Self::Deleted {
+                image_id,
+                restore_url,
+            } => ChatMessage {
+                title: format!("❌ Image {}… deleted", short_id(*image_id)),
+                links: vec![("Restore", restore_url.clone())],
+            },
+            Self::Withdrawn {
+                image_id,
+                client_id,
+            } => ChatMessage {
+                title: format!(
+                    "🗑️ Image {}… deleted by its uploader {client_id}",
+                    short_id(*image_id)
+                ),
+                links: vec![],
+            },
+            Self::ReviewFailed { image_id, error } => ChatMessage {
+                title: format!(
+                    "⚠️ Image {}… hidden until reviewed manually: {error}",
+                    short_id(*image_id)
+                ),
+                links: vec![
+                    ("Image", util::image_id_to_url(*image_id)),
+                    ("Delete", admin_url("delete_image", *image_id)),
+                ],
+            },
+            Self::MassReports {
+                client_id,
+                report_count,
+            } => ChatMessage {
+                title: format!(
+                    "🚩 Client {client_id} reported {report_count} images today and might be abusing reports"
+                ),
+                links: vec![],
+            },
+        }
+    }
+}
+
+/// Class for posting notifications to a webhook.
+pub struct WebhookSender {
+    info: WebhookInfo,
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl AdminNotification for WebhookSender {
+    async fn notify_admin_image_report(&self, info: ImageReportInfo) {
+        if let Err(error) = self.send(&Event::Report(&info)).await {
+            error!(%info.image_id, %info.reason, self.info.url, "Error notifying administrator via webhook: {error}");
+        }
+    }
+
+    async fn notify_admin_image_verified(&self, image_id: Uuid) -> Result<()> {
+        self.send(&Event::Verified { image_id }).await
+    }
+
+    async fn notify_admin_image_deleted(&self, image_id: Uuid) -> Result<()> {
+        let restore_url = admin_url("restore_image", image_id);
+        self.send(&Event::Deleted {
+            image_id,
+            restore_url,
+        })
+        .await
+    }
+
+    async fn notify_admin_image_withdrawn(&self, image_id: Uuid, client_id: Uuid) -> Result<()> {
+        self.send(&Event::Withdrawn {
+            image_id,
+            client_id,
+        })
+        .await
+    }
+
+    async fn notify_admin_image_review_failed(&self, image_id: Uuid, error: &str) -> Result<()> {
+        self.send(&Event::ReviewFailed { image_id, error }).await
+    }
+
+    async fn notify_admin_mass_reports(&self, client_id: Uuid, report_count: u32) -> Result<()> {
+        self.send(&Event::MassReports {
+            client_id,
+            report_count,
+        })
+        .await
+    }
+}
+
+impl WebhookSender {
+    /// Creates a new [`WebhookSender`] posting to the webhook defined in info.
+    ///
+    /// # Errors
+    /// Returns an error, if the http client could not be created
+    pub fn new(info: WebhookInfo) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()?;
+        Ok(Self { info, client })
+    }
+
+    async fn send(&self, event: &Event<'_>) -> Result<()> {
+        let result = self.post(event).await;
+        if result.is_err() {
+            counter!("admin_notification_failures_total", "channel" => "webhook").increment(1);
+        }
+        result
+    }
+
+    async fn post(&self, event: &Event<'_>) -> Result<()> {
+        let body = match self.info.format {
+            WebhookFormat::Generic => json!(event),
+            WebhookFormat::Slack => slack_body(&event.chat_message()),
+            WebhookFormat::Matrix => matrix_body(&event.chat_message()),
+        };
+        self.client
+            .post(&self.info.url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+fn short_id(image_id: Uuid) -> String {
+    image_id.to_string()[..6].to_owned()
+}
+
+fn admin_url(action: &str, image_id: Uuid) -> String {
+    util::local_to_global_url(&format!("/admin/report/{action}/{image_id}"))
+}
+
+fn slack_body(message: &ChatMessage) -> serde_json::Value {
+    let links = message
+        .links
+        .iter()
+        .map(|(label, url)| format!("<{}|{label}>", escape_slack(url)))
+        .collect::<Vec<_>>()
+        .join(" · ");
+    json!({ "text": format!("{}\n{links}", escape_slack(&message.title)).trim_end() })
+}
+
+/// Escapes the control characters of Slack's `mrkdwn`, so user input like meal names cannot add links or mentions.
+fn escape_slack(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn matrix_body(message: &ChatMessage) -> serde_json::Value {
+    let text = message
+        .links
+        .iter()
+        .fold(message.title.clone(), |text, (label, url)| {
+            format!("{text}\n{label}: {url}")
+        });
+    let links = message
+        .links
+        .iter()
+        .map(|(label, url)| format!("<a href=\"{}\">{label}</a>", escape_html(url)))
+        .collect::<Vec<_>>()
+        .join(" · ");
+    let html = format!("{}<br>{links}", escape_html(&message.title));
+    json!({ "text": text, "html": html })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{routing::post, Json, Router};
+    use chrono::Local;
+    use serde_json::Value;
+
+    use super::WebhookSender;
+    use crate::{
+        interface::admin_notification::{AdminNotification, ImageReportInfo},
+        layer::data::notification::notification_info::{ChannelFilter, WebhookFormat, WebhookInfo},
+        util::{ReportReason, Uuid},
+    };
+
+    /// Starts a webhook stub recording all received bodies.
+    async fn start_stub() -> (String, Arc<Mutex<Vec<Value>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        let router = Router::new().route(
+            "/hook",
+            post(move |Json(body): Json<Value>| async move {
+                log.lock().unwrap().push(body);
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (format!("http://{addr}/hook"), received)
+    }
+
+    fn sender(url: &str, format: WebhookFormat) -> WebhookSender {
+        WebhookSender::new(WebhookInfo {
+            url: url.into(),
+            format,
+            filter: ChannelFilter::default(),
+        })
+        .unwrap()
+    }
+
+    fn report_info() -> ImageReportInfo {
+        ImageReportInfo {
+            reason: ReportReason::Advert,
+            image_got_hidden: true,
+            image_id: Uuid::from_u128(9_789_789),
+            image_url: "https://picsum.photos/500/200".into(),
+            report_count: 3,
+            positive_rating_count: 10,
+            negative_rating_count: 20,
+            image_rank: 0.1,
+            report_barrier: 3,
+            client_id: Uuid::from_u128(123),
+            image_age: 1,
+            meal_name: "Pommes <mit> Mayo".into(),
+            meal_id: Uuid::from_u128(567),
+            report_date: Local::now().date_naive(),
+            other_image_urls: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generic_webhook() {
+        let (url, received) = start_stub().await;
+        let sender = sender(&url, WebhookFormat::Generic);
+        let image_id = Uuid::from_u128(1);
+
+        sender.notify_admin_image_report(report_info()).await;
+        sender.notify_admin_image_deleted(image_id).await.unwrap();
+        sender
+            .notify_admin_image_withdrawn(image_id, Uuid::from_u128(2))
+            .await
+            .unwrap();
+
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.len(), 3);
+        assert_eq!(received[0]["event"], "image_report");
+        assert_eq!(received[0]["reason"], "Advert");
+        assert_eq!(received[0]["report_count"], 3);
+        assert_eq!(received[1]["event"], "image_deleted");
+        assert_eq!(received[1]["image_id"], image_id.to_string());
+        assert!(received[1]["restore_url"]
+            .as_str()
+            .unwrap()
+            .ends_with(&format!("/admin/report/restore_image/{image_id}")));
+        assert_eq!(received[2]["event"], "image_withdrawn");
+    }
+
+    #[tokio::test]
+    async fn test_chat_webhooks() {
+        let (url, received) = start_stub().await;
+
+        sender(&url, WebhookFormat::Slack)
+            .notify_admin_image_report(report_info())
+            .await;
+        sender(&url, WebhookFormat::Matrix)
+            .notify_admin_image_report(report_info())
+            .await;
+
+        let received = received.lock().unwrap().clone();
+        let slack = received[0]["text"].as_str().unwrap();
+        assert!(slack.contains("hidden, 3x: Advert"));
+        assert!(slack.contains("Pommes &lt;mit&gt; Mayo"));
+        assert!(slack.contains("<https://picsum.photos/500/200|Image>"));
+        let matrix_text = received[1]["text"].as_str().unwrap();
+        assert!(matrix_text.contains("Pommes <mit> Mayo"));
+        assert!(matrix_text.contains("Image: https://picsum.photos/500/200"));
+        let matrix_html = received[1]["html"].as_str().unwrap();
+        assert!(matrix_html.contains("Pommes &lt;mit&gt; Mayo"));
+        assert!(matrix_html.contains("<a href=\"https://picsum.photos/500/200\">Image</a>"));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_error() {
+        let (url, _) = start_stub().await;
+        let sender = sender(&format!("{url}/missing"), WebhookFormat::Generic);
+        assert!(sender
+            .notify_admin_image_verified(Uuid::default())
+            .await
+            .is_err());
+    }
+}
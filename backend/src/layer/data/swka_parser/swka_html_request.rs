@@ -1,10 +1,10 @@
 //! [`SwKaHtmlRequest`] for obtaining html code from urls.
 
-use crate::interface::mensa_parser::ParseError;
+use crate::{interface::mensa_parser::ParseError, util::trace_context_headers};
 use futures::future::join_all;
 use reqwest::Client;
 use std::time::Duration;
-use tracing::trace;
+use tracing::{instrument, trace};
 
 /// Class for requesting the meal plan's webpage.
 #[derive(Debug)]
@@ -46,10 +46,12 @@ impl SwKaHtmlRequest {
             .collect()
     }
 
+    #[instrument(skip(self))]
     async fn get_html(&self, url: &String) -> Result<String, ParseError> {
         let resp = self
             .client
             .get(url)
+            .headers(trace_context_headers())
             .send()
             .await
             .and_then(reqwest::Response::error_for_status)
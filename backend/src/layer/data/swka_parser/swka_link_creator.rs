@@ -37,6 +37,11 @@ impl SwKaLinkCreator {
         }
     }
 
+    /// Replaces the canteens urls get created for.
+    pub fn set_valid_canteens(&mut self, valid_canteens: Vec<String>) {
+        self.valid_canteens = valid_canteens;
+    }
+
     /// This method creates all urls for the given day.
     /// ## Return
     /// `Vec<String>` containing all urls.
@@ -54,6 +59,22 @@ impl SwKaLinkCreator {
             .collect()
     }
 
+    /// This method creates the url for the given canteen and day.
+    /// ## Return
+    /// The position of the canteen among all valid canteens and the url, `None` if the canteen is not valid.
+    #[must_use]
+    pub fn get_canteen_url(&self, day: Date, canteen: &str) -> Option<(u32, String)> {
+        let position = self.valid_canteens.iter().position(|c| c == canteen)?;
+        let url = self.get_urls(day).swap_remove(position);
+        Some((u32::try_from(position).ok()?, url))
+    }
+
+    /// Returns whether urls get created for the given canteen.
+    #[must_use]
+    pub fn is_valid_canteen(&self, canteen: &str) -> bool {
+        self.valid_canteens.iter().any(|c| c == canteen)
+    }
+
     /// This method creates all urls for the next four weeks.
     /// ## Return
     /// `Vec<String>` containing all urls.
@@ -138,4 +159,17 @@ mod tests {
         let result = test_util::get_creator().get_all_urls_for_next_weeks_from_date(date);
         assert_eq!(result, URLS_FOR_NEXT_WEEKS);
     }
+
+    #[tokio::test]
+    async fn test_get_canteen_url() {
+        let date = Date::from_ymd_opt(2023, 7, 10).unwrap();
+        let creator = test_util::get_creator();
+        assert_eq!(
+            creator.get_canteen_url(date, "mensa_moltke"),
+            Some((2, URLS_FOR_CURRENT_WEEK[2].to_owned()))
+        );
+        assert_eq!(creator.get_canteen_url(date, "mensa_unknown"), None);
+        assert!(creator.is_valid_canteen("mensa_moltke"));
+        assert!(!creator.is_valid_canteen("mensa_unknown"));
+    }
 }
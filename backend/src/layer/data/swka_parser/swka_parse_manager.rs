@@ -8,6 +8,9 @@ use crate::layer::data::swka_parser::swka_link_creator::SwKaLinkCreator;
 use crate::util::Date;
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
 
 /// Struct containing all information necessary to create a [`SwKaParseManager`].
 #[derive(Debug, Clone)]
@@ -27,7 +30,7 @@ pub struct SwKaInfo {
 /// Class for managing the request and parsing of meal plans.
 #[derive(Debug)]
 pub struct SwKaParseManager {
-    link_creator: SwKaLinkCreator,
+    link_creator: Arc<RwLock<SwKaLinkCreator>>,
     request: SwKaHtmlRequest,
     html_parser: HTMLParser,
 }
@@ -38,21 +41,29 @@ impl SwKaParseManager {
     /// If the request client creation fails an error 'll be returned.
     pub fn new(parse_info: SwKaInfo) -> Result<Self, ParseError> {
         Ok(Self {
-            link_creator: SwKaLinkCreator::new(
+            link_creator: Arc::new(RwLock::new(SwKaLinkCreator::new(
                 parse_info.base_url.clone(),
                 parse_info.valid_canteens.clone(),
                 parse_info.number_of_weeks_to_poll,
-            ),
+            ))),
             request: SwKaHtmlRequest::new(parse_info.client_timeout, parse_info.client_user_agent)?,
             html_parser: HTMLParser,
         })
     }
 
+    /// Returns a handle for changing the parsed canteens while this manager is in use.
+    #[must_use]
+    pub fn canteen_handle(&self) -> CanteenHandle {
+        CanteenHandle(self.link_creator.clone())
+    }
+
     /// Sorts all canteens by days and urls in a hashmap.<br>
     /// [`ParseCanteen`]s are grouped for each [`Date`].
+    /// Canteens get their position by the order of `urls`, starting at `first_position`.
     async fn parse_and_sort_canteens_by_days(
         &self,
         urls: Vec<String>,
+        first_position: u32,
     ) -> Result<HashMap<Date, Vec<ParseCanteen>>, ParseError> {
         let mut map: HashMap<Date, Vec<ParseCanteen>> = HashMap::new();
 
@@ -65,7 +76,8 @@ impl SwKaParseManager {
         {
             for (date, canteen) in self.html_parser.transform(
                 html,
-                u32::try_from(position).expect("u32 could not be casted from usize"),
+                first_position
+                    + u32::try_from(position).expect("u32 could not be casted from usize"),
             )? {
                 map.entry(date).or_default().push(canteen);
             }
@@ -74,6 +86,18 @@ impl SwKaParseManager {
     }
 }
 
+/// Handle for changing the canteens a [`SwKaParseManager`] parses, see [`SwKaInfo::valid_canteens`].
+#[derive(Debug, Clone)]
+pub struct CanteenHandle(Arc<RwLock<SwKaLinkCreator>>);
+
+impl CanteenHandle {
+    /// Replaces the parsed canteens. Parse runs in progress keep using the previous ones.
+    pub async fn set_canteens(&self, valid_canteens: Vec<String>) {
+        info!("Parsing canteens {}", valid_canteens.join(", "));
+        self.0.write().await.set_valid_canteens(valid_canteens);
+    }
+}
+
 #[async_trait]
 impl MealplanParser for SwKaParseManager {
     /// This method handles the parsing procedure for the given day.
@@ -86,8 +110,30 @@ impl MealplanParser for SwKaParseManager {
     /// ## Return
     /// All [`ParseCanteen`]s containing meal plan data for the given day or an error if something in the chain above fails.
     async fn parse(&self, day: Date) -> Result<Vec<ParseCanteen>, ParseError> {
+        let urls = self.link_creator.read().await.get_urls(day);
+        let mut map = self.parse_and_sort_canteens_by_days(urls, 0).await?;
+
+        Ok(map.remove(&day).unwrap_or_default())
+    }
+
+    /// This method handles the parsing procedure for the given day like [`Self::parse`], but only for the given canteen.<br>
+    /// `canteen: &str`<br>
+    /// The canteen's identifier in its meal plan's url, which has to be among the valid canteens.<br>
+    /// ## Return
+    /// The [`ParseCanteen`] containing meal plan data for the given day or an error if the canteen is not valid or something in the chain above fails.
+    async fn parse_canteen(
+        &self,
+        day: Date,
+        canteen: &str,
+    ) -> Result<Vec<ParseCanteen>, ParseError> {
+        let (position, url) = self
+            .link_creator
+            .read()
+            .await
+            .get_canteen_url(day, canteen)
+            .ok_or_else(|| ParseError::UnknownCanteen(canteen.into()))?;
         let mut map = self
-            .parse_and_sort_canteens_by_days(self.link_creator.get_urls(day))
+            .parse_and_sort_canteens_by_days(vec![url], position)
             .await?;
 
         Ok(map.remove(&day).unwrap_or_default())
@@ -101,19 +147,23 @@ impl MealplanParser for SwKaParseManager {
     /// ## Return
     /// All [`ParseCanteen`]s grouped by their [`Date`] or an error if something in the chain above fails.
     async fn parse_all(&self) -> Result<Vec<(Date, Vec<ParseCanteen>)>, ParseError> {
-        let map = self
-            .parse_and_sort_canteens_by_days(self.link_creator.get_all_urls())
-            .await?;
+        let urls = self.link_creator.read().await.get_all_urls();
+        let map = self.parse_and_sort_canteens_by_days(urls, 0).await?;
 
         Ok(map.into_iter().collect())
     }
+
+    /// Returns whether the canteen is among the valid canteens of the [`SwKaLinkCreator`].
+    async fn is_known_canteen(&self, canteen: &str) -> bool {
+        self.link_creator.read().await.is_valid_canteen(canteen)
+    }
 }
 
 #[cfg(test)]
 mod test {
     #![allow(clippy::unwrap_used)]
 
-    use crate::interface::mensa_parser::MealplanParser;
+    use crate::interface::mensa_parser::{MealplanParser, ParseError};
     use crate::layer::data::swka_parser::swka_parse_manager::SwKaParseManager;
     use crate::layer::data::swka_parser::test::const_test_data as test_util;
     use chrono::Local;
@@ -133,7 +183,7 @@ mod test {
     async fn test_sort_and_parse_canteens_with_valid_urls() {
         let manager = SwKaParseManager::new(test_util::get_parse_info()).unwrap();
         let result = manager
-            .parse_and_sort_canteens_by_days(get_valid_urls())
+            .parse_and_sort_canteens_by_days(get_valid_urls(), 0)
             .await;
         assert!(result.is_ok());
     }
@@ -143,7 +193,7 @@ mod test {
         let manager = SwKaParseManager::new(test_util::get_parse_info()).unwrap();
         let mut urls = get_valid_urls();
         urls.push(String::from("invalid"));
-        let result = manager.parse_and_sort_canteens_by_days(urls).await;
+        let result = manager.parse_and_sort_canteens_by_days(urls, 0).await;
         assert!(result.is_err());
     }
 
@@ -153,6 +203,28 @@ mod test {
         assert!(manager.parse(Local::now().date_naive()).await.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_parse_unknown_canteen() {
+        let manager = SwKaParseManager::new(test_util::get_parse_info()).unwrap();
+        let result = manager
+            .parse_canteen(Local::now().date_naive(), "mensa_unknown")
+            .await;
+        assert!(matches!(result, Err(ParseError::UnknownCanteen(_))));
+    }
+
+    #[tokio::test]
+    async fn test_set_canteens() {
+        let manager = SwKaParseManager::new(test_util::get_parse_info()).unwrap();
+        manager
+            .canteen_handle()
+            .set_canteens(vec!["mensa_adenauerring".into()])
+            .await;
+        let result = manager
+            .parse_canteen(Local::now().date_naive(), "mensa_erzberger")
+            .await;
+        assert!(matches!(result, Err(ParseError::UnknownCanteen(_))));
+    }
+
     #[tokio::test]
     async fn test_parse_all() {
         let manager = SwKaParseManager::new(test_util::get_parse_info()).unwrap();
@@ -0,0 +1,44 @@
+//! Module containing the limits for actions of a single client, see [`ClientLimitInfo`].
+
+use crate::util::ClientAction;
+
+const DEFAULT_REPORT_LIMIT: u32 = 50;
+const DEFAULT_UPLOAD_LIMIT: u32 = 20;
+const DEFAULT_RATING_LIMIT: u32 = 500;
+const DEFAULT_MASS_REPORT_THRESHOLD: u32 = 20;
+
+/// Structure containing the maximum number of actions a single client may do per day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientLimitInfo {
+    /// Maximum number of images a client may report per day.
+    pub report_limit: u32,
+    /// Maximum number of images a client may upload per day.
+    pub upload_limit: u32,
+    /// Maximum number of image votes and meal ratings a client may give per day.
+    pub rating_limit: u32,
+    /// Number of reports per day after which a client gets logged as possibly abusing reports.
+    pub mass_report_threshold: u32,
+}
+
+impl Default for ClientLimitInfo {
+    fn default() -> Self {
+        Self {
+            report_limit: DEFAULT_REPORT_LIMIT,
+            upload_limit: DEFAULT_UPLOAD_LIMIT,
+            rating_limit: DEFAULT_RATING_LIMIT,
+            mass_report_threshold: DEFAULT_MASS_REPORT_THRESHOLD,
+        }
+    }
+}
+
+impl ClientLimitInfo {
+    /// Returns the maximum number of times a client may do the given action per day.
+    #[must_use]
+    pub const fn limit(&self, action: ClientAction) -> u32 {
+        match action {
+            ClientAction::Report => self.report_limit,
+            ClientAction::Upload => self.upload_limit,
+            ClientAction::Rating => self.rating_limit,
+        }
+    }
+}
@@ -2,46 +2,46 @@
 
 use async_trait::async_trait;
 use chrono::Local;
-use tracing::info;
+use metrics::counter;
+use tracing::{debug, info, warn};
 
 use crate::{
     interface::{
         admin_notification::{AdminNotification, ImageReportInfo},
-        api_command::{Command, Result},
+        api_command::{Command, CommandError, Result},
         image_storage::ImageStorage,
-        image_validation::ImageValidation,
-        persistent_data::{model::Image, CommandDataAccess},
+        persistent_data::CommandDataAccess,
     },
-    util::{image_id_to_url, Date, ReportReason, Uuid},
+    util::{image_id_to_url, ClientAction, ReportReason, Uuid},
 };
 
-use super::image_preprocessing::{ImagePreprocessingInfo, ImagePreprocessor};
-
-const REPORT_FACTOR: f64 = 1.0 / 35.0;
+use super::{
+    client_limits::ClientLimitInfo,
+    image_preprocessing::{ImagePreprocessingInfo, ImagePreprocessor},
+    report_policy::{days_since, ReportPolicy},
+};
 
 /// Class responsible for executing api commands.
 #[derive(Debug)]
-pub struct CommandHandler<DataAccess, Notify, Storage, Validation>
+pub struct CommandHandler<DataAccess, Notify, Storage>
 where
     DataAccess: CommandDataAccess,
     Notify: AdminNotification,
     Storage: ImageStorage,
-    Validation: ImageValidation,
 {
     command_data: DataAccess,
     admin_notification: Notify,
     image_storage: Storage,
-    image_validation: Validation,
     image_preprocessor: ImagePreprocessor,
+    report_policy: ReportPolicy,
+    client_limits: ClientLimitInfo,
 }
 
-impl<DataAccess, Notify, Storage, Validation>
-    CommandHandler<DataAccess, Notify, Storage, Validation>
+impl<DataAccess, Notify, Storage> CommandHandler<DataAccess, Notify, Storage>
 where
     DataAccess: CommandDataAccess,
     Notify: AdminNotification,
     Storage: ImageStorage,
-    Validation: ImageValidation,
 {
     /// A function that creates a new [`CommandHandler`]
     ///
@@ -49,50 +49,100 @@ where
     /// Returns an error, if the api keys could not be gotten from `command_data`
     pub const fn new(
         image_preprocessing_info: ImagePreprocessingInfo,
+        report_policy: ReportPolicy,
+        client_limits: ClientLimitInfo,
         command_data: DataAccess,
         admin_notification: Notify,
         image_storage: Storage,
-        image_validation: Validation,
     ) -> Result<Self> {
         Ok(Self {
             command_data,
             admin_notification,
             image_storage,
-            image_validation,
             image_preprocessor: ImagePreprocessor::new(image_preprocessing_info),
+            report_policy,
+            client_limits,
         })
     }
 
-    fn will_be_hidden(image: &Image) -> bool {
-        Self::days_since(image.upload_date) <= 30
-            && image.report_count >= Self::get_report_barrier(image.upload_date)
+    /// Checks whether an action of a client should take effect.
+    /// Returns `false` for banned clients, whose actions get ignored without them noticing.
+    /// Actions taking effect have to be recorded with [`Self::record_action`] afterwards.
+    ///
+    /// # Errors
+    /// Returns [`CommandError::RateLimited`] if the client did this action too often today.
+    async fn check_client(&self, client_id: Uuid, action: ClientAction) -> Result<bool> {
+        if self.command_data.is_client_banned(client_id).await? {
+            debug!("Ignoring {action} of banned client {client_id}.");
+            return Ok(false);
+        }
+
+        let count = self
+            .command_data
+            .count_client_actions(client_id, action)
+            .await?;
+        if count >= self.client_limits.limit(action) {
+            warn!("Client {client_id} exceeded the daily limit of {action}.");
+            return Err(CommandError::RateLimited(action));
+        }
+        Ok(true)
     }
 
-    fn days_since(date: Date) -> i64 {
-        let today = Local::now().date_naive();
-        let difference = today.signed_duration_since(date);
-        difference.num_days()
+    /// Records an action which took effect for rate limiting.
+    /// Administrators get notified about each report of a client beyond the mass report threshold.
+    async fn record_action(&self, client_id: Uuid, action: ClientAction) -> Result<()> {
+        let count = self
+            .command_data
+            .record_client_action(client_id, action)
+            .await?;
+        if action == ClientAction::Report && count >= self.client_limits.mass_report_threshold {
+            warn!("Client {client_id} reported {count} images today and might be abusing reports.");
+            if let Err(e) = self
+                .admin_notification
+                .notify_admin_mass_reports(client_id, count)
+                .await
+            {
+                warn!(
+                    "Could not notify administrators about mass reports of client {client_id}: {e}"
+                );
+            }
+        }
+        Ok(())
     }
 
-    #[allow(
-        clippy::cast_possible_truncation,
-        clippy::cast_sign_loss,
-        clippy::cast_precision_loss
-    )]
-    fn get_report_barrier(date: Date) -> u32 {
-        let t = Self::days_since(date) as f64;
-        REPORT_FACTOR.mul_add(t * t, 5.0).floor() as u32
+    /// Uploads the image for [`Command::add_image`], which counts the outcomes.
+    async fn try_upload_image(
+        &self,
+        meal_id: Uuid,
+        image_type: Option<String>,
+        image_file: Vec<u8>,
+        client_id: Uuid,
+    ) -> Result<()> {
+        if !self.check_client(client_id, ClientAction::Upload).await? {
+            return Ok(());
+        }
+        let image = self
+            .image_preprocessor
+            .preprocess_image(image_file, image_type)?;
+
+        // link in database, the image stays pending until reviewed
+        let image_id = self.command_data.link_image(meal_id, client_id).await?;
+
+        // store to disk
+        if let Err(e) = self.image_storage.save_image(image_id, image).await {
+            self.command_data.revert_link_image(image_id).await?;
+            return Err(e.into());
+        }
+        self.record_action(client_id, ClientAction::Upload).await
     }
 }
 
 #[async_trait]
-impl<DataAccess, Notify, Storage, Image> Command
-    for CommandHandler<DataAccess, Notify, Storage, Image>
+impl<DataAccess, Notify, Storage> Command for CommandHandler<DataAccess, Notify, Storage>
 where
     DataAccess: CommandDataAccess,
     Notify: AdminNotification,
     Storage: ImageStorage,
-    Image: ImageValidation,
 {
     async fn report_image(
         &self,
@@ -100,29 +150,41 @@ where
         reason: ReportReason,
         client_id: Uuid,
     ) -> Result<()> {
+        let allowed = self.check_client(client_id, ClientAction::Report).await?;
         let mut info = self.command_data.get_image_info(image_id).await?;
         if !info.image.approved {
-            info.image.report_count += 1;
-            self.command_data
+            info.image.report_count = self
+                .command_data
                 .add_report(image_id, client_id, reason)
                 .await?;
-            let will_be_hidden = Self::will_be_hidden(&info.image);
-            if will_be_hidden {
-                self.command_data.hide_image(image_id).await?;
-                info!(image_info = ?info, "Automatically hid image {image_id} because reported {} times.", info.image.report_count);
+            if !allowed {
+                // the report got dismissed right away
+                return Ok(());
+            }
+            self.record_action(client_id, ClientAction::Report).await?;
+            let reasons = self.command_data.get_report_reasons(image_id).await?;
+            let uploader = self
+                .command_data
+                .get_uploader_stats(info.image.uploader_id)
+                .await?;
+            let decision = self.report_policy.evaluate(&info.image, &reasons, uploader);
+            if decision.hide {
+                let reason = format!("reported {} times", info.image.report_count);
+                self.command_data.hide_image(image_id, &reason).await?;
+                info!(image_info = ?info, ?decision, "Automatically hid image {image_id} because reported {} times.", info.image.report_count);
             }
             let report_info = ImageReportInfo {
                 reason,
                 image_id,
-                image_got_hidden: will_be_hidden,
+                image_got_hidden: decision.hide,
                 image_url: image_id_to_url(image_id),
                 report_count: info.image.report_count,
                 positive_rating_count: info.image.upvotes,
                 negative_rating_count: info.image.downvotes,
                 image_rank: info.image.rank,
-                report_barrier: Self::get_report_barrier(info.image.upload_date),
+                report_barrier: decision.report_barrier,
                 client_id,
-                image_age: Self::days_since(info.image.upload_date),
+                image_age: days_since(info.image.upload_date),
                 report_date: Local::now().date_naive(),
                 meal_id: info.image.meal_id,
                 meal_name: info.meal_name,
@@ -137,25 +199,37 @@ where
     }
 
     async fn add_image_upvote(&self, image_id: Uuid, client_id: Uuid) -> Result<()> {
+        if !self.check_client(client_id, ClientAction::Rating).await? {
+            return Ok(());
+        }
         self.command_data.add_upvote(image_id, client_id).await?;
-        Ok(())
+        self.record_action(client_id, ClientAction::Rating).await
     }
 
     async fn add_image_downvote(&self, image_id: Uuid, client_id: Uuid) -> Result<()> {
+        if !self.check_client(client_id, ClientAction::Rating).await? {
+            return Ok(());
+        }
         self.command_data.add_downvote(image_id, client_id).await?;
-        Ok(())
+        self.record_action(client_id, ClientAction::Rating).await
     }
 
     async fn remove_image_upvote(&self, image_id: Uuid, client_id: Uuid) -> Result<()> {
+        if !self.check_client(client_id, ClientAction::Rating).await? {
+            return Ok(());
+        }
         self.command_data.remove_upvote(image_id, client_id).await?;
-        Ok(())
+        self.record_action(client_id, ClientAction::Rating).await
     }
 
     async fn remove_image_downvote(&self, image_id: Uuid, client_id: Uuid) -> Result<()> {
+        if !self.check_client(client_id, ClientAction::Rating).await? {
+            return Ok(());
+        }
         self.command_data
             .remove_downvote(image_id, client_id)
             .await?;
-        Ok(())
+        self.record_action(client_id, ClientAction::Rating).await
     }
 
     async fn add_image(
@@ -165,46 +239,133 @@ where
         image_file: Vec<u8>,
         client_id: Uuid,
     ) -> Result<()> {
-        let image = self
-            .image_preprocessor
-            .preprocess_image(image_file, image_type)?;
+        let result = self
+            .try_upload_image(meal_id, image_type, image_file, client_id)
+            .await;
+        let outcome = match &result {
+            Ok(()) => "success",
+            Err(CommandError::ImagePreprocessingError(_)) => "invalid_image",
+            Err(CommandError::RateLimited(_)) => "rate_limited",
+            Err(_) => "error",
+        };
+        counter!("image_uploads_total", "outcome" => outcome).increment(1);
+        result
+    }
 
-        // verify with api
-        self.image_validation.validate_image(&image).await?;
+    async fn set_meal_rating(&self, meal_id: Uuid, rating: u32, client_id: Uuid) -> Result<()> {
+        if !self.check_client(client_id, ClientAction::Rating).await? {
+            return Ok(());
+        }
+        self.command_data
+            .add_rating(meal_id, client_id, rating)
+            .await?;
+        self.record_action(client_id, ClientAction::Rating).await
+    }
 
-        // link in database
-        let image_id = self.command_data.link_image(meal_id, client_id).await?;
+    async fn delete_image(&self, image_id: Uuid, deleted_by: &str) -> Result<()> {
+        // the file is kept until the image gets purged, so it can still be restored
+        self.command_data.delete_image(image_id, deleted_by).await?;
+        info!("Image {image_id} got deleted by {deleted_by}.");
+        // the image is deleted already, so a failed notification must not look like a failed deletion
+        if let Err(e) = self
+            .admin_notification
+            .notify_admin_image_deleted(image_id)
+            .await
+        {
+            warn!("Could not notify administrators about deleted image {image_id}: {e}");
+        }
+        Ok(())
+    }
 
-        // store to disk
-        if let Err(e) = self.image_storage.save_image(image_id, image).await {
-            self.command_data.revert_link_image(image_id).await?;
-            return Err(e.into());
+    async fn delete_my_image(&self, image_id: Uuid, client_id: Uuid) -> Result<()> {
+        let info = self.command_data.get_image_info(image_id).await?;
+        if info.image.uploader_id != client_id {
+            return Err(CommandError::NotUploader(image_id));
         }
 
+        // the uploader withdraws the image, so it gets removed for good instead of being kept for restoring
+        self.command_data
+            .withdraw_image(image_id, client_id)
+            .await?;
+        self.image_storage.delete_image(image_id).await?;
+        info!("Image {image_id} got deleted by its uploader.");
+        if let Err(e) = self
+            .admin_notification
+            .notify_admin_image_withdrawn(image_id, client_id)
+            .await
+        {
+            warn!("Could not notify administrators about withdrawn image {image_id}: {e}");
+        }
         Ok(())
     }
 
-    async fn set_meal_rating(&self, meal_id: Uuid, rating: u32, client_id: Uuid) -> Result<()> {
+    async fn erase_client_data(&self, client_id: Uuid, erased_by: &str) -> Result<()> {
+        let image_ids = self
+            .command_data
+            .erase_client_data(client_id, erased_by)
+            .await?;
+        for image_id in &image_ids {
+            // the data is gone already, so remaining files get cleaned up by the image reconciliation
+            if let Err(error) = self.image_storage.delete_image(*image_id).await {
+                warn!("Could not delete file of image {image_id}: {error}");
+            }
+        }
+        info!(
+            "Data of client {client_id}, including {} images, got erased by {erased_by}.",
+            image_ids.len()
+        );
+        Ok(())
+    }
+
+    async fn verify_image(&self, image_id: Uuid, verified_by: &str) -> Result<()> {
         self.command_data
-            .add_rating(meal_id, client_id, rating)
+            .verify_image(image_id, verified_by)
             .await?;
+        info!("Image {image_id} got verified by {verified_by}.");
+        if let Err(e) = self
+            .admin_notification
+            .notify_admin_image_verified(image_id)
+            .await
+        {
+            warn!("Could not notify administrators about verified image {image_id}: {e}");
+        }
         Ok(())
     }
 
-    async fn delete_image(&self, image_id: Uuid) -> Result<()> {
-        self.command_data.delete_image(image_id).await?;
-        self.image_storage.delete_image(image_id).await?;
-        self.admin_notification
-            .notify_admin_image_deleted(image_id)
+    async fn unhide_image(&self, image_id: Uuid, unhidden_by: &str) -> Result<()> {
+        self.command_data
+            .unhide_image(image_id, unhidden_by)
             .await?;
+        info!("Image {image_id} got unhidden by {unhidden_by}.");
         Ok(())
     }
 
-    async fn verify_image(&self, image_id: Uuid) -> Result<()> {
-        self.command_data.verify_image(image_id).await?;
-        self.admin_notification
-            .notify_admin_image_verified(image_id)
+    async fn restore_image(&self, image_id: Uuid, restored_by: &str) -> Result<()> {
+        self.command_data
+            .restore_image(image_id, restored_by)
+            .await?;
+        info!("Image {image_id} got restored by {restored_by}.");
+        Ok(())
+    }
+
+    async fn ban_client(
+        &self,
+        client_id: Uuid,
+        banned_by: &str,
+        reason: Option<String>,
+    ) -> Result<()> {
+        self.command_data
+            .ban_client(client_id, banned_by, reason.as_deref())
+            .await?;
+        info!("Client {client_id} got banned by {banned_by}.");
+        Ok(())
+    }
+
+    async fn unban_client(&self, client_id: Uuid, unbanned_by: &str) -> Result<()> {
+        self.command_data
+            .unban_client(client_id, unbanned_by)
             .await?;
+        info!("Client {client_id} got unbanned by {unbanned_by}.");
         Ok(())
     }
 }
@@ -214,20 +375,18 @@ mod test {
     #![allow(clippy::unwrap_used)]
     use std::sync::Arc;
 
-    use chrono::Local;
-
-    use crate::interface::api_command::{Command, Result};
-    use crate::interface::persistent_data::model::Image;
+    use crate::interface::api_command::{Command, CommandError, Result};
     use crate::layer::logic::api_command::mocks::{
-        CommandImageStorageMock, CommandImageValidationMock, IMAGE_ID_TO_FAIL, MEAL_ID_TO_FAIL,
+        CommandImageStorageMock, BANNED_CLIENT_ID, IMAGE_ID_TO_FAIL, MASS_REPORTING_CLIENT_ID,
+        MEAL_ID_TO_FAIL, RATE_LIMITED_CLIENT_ID,
     };
     use crate::layer::logic::api_command::{
         command_handler::CommandHandler,
         mocks::{CommandAdminNotificationMock, CommandDatabaseMock},
     };
-    use crate::util::{ReportReason, Uuid};
+    use crate::util::{ClientAction, ReportReason, Uuid};
 
-    use super::ImagePreprocessingInfo;
+    use super::{ClientLimitInfo, ImagePreprocessingInfo, ReportPolicy};
 
     #[tokio::test]
     async fn test_new() {
@@ -404,7 +563,33 @@ mod test {
 
         let image = Uuid::try_from("94cf40a7-ade4-4c1f-b718-89b2d418c2d0").unwrap();
 
-        handler.delete_image(image).await.unwrap();
+        handler.delete_image(image, "admin").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete_my_image() {
+        let handler = get_handler().unwrap();
+
+        let image = Uuid::try_from("94cf40a7-ade4-4c1f-b718-89b2d418c2d0").unwrap();
+        // the mocked image got uploaded by the default client id
+        handler
+            .delete_my_image(image, Uuid::default())
+            .await
+            .unwrap();
+
+        let other_client = Uuid::try_from("b637365e-9ec5-47cf-8e39-eab3e10de4e5").unwrap();
+        assert!(matches!(
+            handler.delete_my_image(image, other_client).await,
+            Err(CommandError::NotUploader(id)) if id == image
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_erase_client_data() {
+        let handler = get_handler().unwrap();
+
+        let client = Uuid::try_from("b637365e-9ec5-47cf-8e39-eab3e10de4e5").unwrap();
+        handler.erase_client_data(client, "admin").await.unwrap();
     }
 
     #[tokio::test]
@@ -413,7 +598,134 @@ mod test {
 
         let image = Uuid::try_from("94cf40a7-ade4-4c1f-b718-89b2d418c2d0").unwrap();
 
-        handler.verify_image(image).await.unwrap();
+        handler.verify_image(image, "admin").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_notification() {
+        let handler = get_handler_notifying(CommandAdminNotificationMock::failing()).unwrap();
+
+        let image = Uuid::try_from("94cf40a7-ade4-4c1f-b718-89b2d418c2d0").unwrap();
+
+        // the actions took effect, so they succeed even if administrators could not be notified
+        handler.verify_image(image, "admin").await.unwrap();
+        handler.delete_image(image, "admin").await.unwrap();
+        handler
+            .delete_my_image(image, Uuid::default())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unhide_image() {
+        let handler = get_handler().unwrap();
+
+        let image = Uuid::try_from("94cf40a7-ade4-4c1f-b718-89b2d418c2d0").unwrap();
+
+        handler.unhide_image(image, "admin").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_restore_image() {
+        let handler = get_handler().unwrap();
+
+        let image = Uuid::try_from("94cf40a7-ade4-4c1f-b718-89b2d418c2d0").unwrap();
+
+        handler.restore_image(image, "admin").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_banned_client() {
+        let handler = get_handler().unwrap();
+        let id = Uuid::default();
+        let image_file = include_bytes!("tests/test.jpg").to_vec();
+
+        // actions of banned clients succeed without effect, even for failing ids
+        handler
+            .report_image(id, ReportReason::Advert, BANNED_CLIENT_ID)
+            .await
+            .unwrap();
+        handler
+            .add_image_upvote(IMAGE_ID_TO_FAIL, BANNED_CLIENT_ID)
+            .await
+            .unwrap();
+        handler
+            .set_meal_rating(MEAL_ID_TO_FAIL, 2, BANNED_CLIENT_ID)
+            .await
+            .unwrap();
+        handler
+            .add_image(id, Some("image/png".into()), image_file, BANNED_CLIENT_ID)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_client() {
+        let handler = get_handler().unwrap();
+        let id = Uuid::default();
+
+        assert!(matches!(
+            handler
+                .report_image(id, ReportReason::Advert, RATE_LIMITED_CLIENT_ID)
+                .await,
+            Err(CommandError::RateLimited(ClientAction::Report))
+        ));
+        assert!(matches!(
+            handler.add_image_downvote(id, RATE_LIMITED_CLIENT_ID).await,
+            Err(CommandError::RateLimited(ClientAction::Rating))
+        ));
+        assert!(matches!(
+            handler
+                .add_image(id, None, vec![], RATE_LIMITED_CLIENT_ID)
+                .await,
+            Err(CommandError::RateLimited(ClientAction::Upload))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mass_reporting_client() {
+        let admin_notification = CommandAdminNotificationMock::default();
+        let handler = get_handler_notifying(admin_notification.clone()).unwrap();
+
+        handler
+            .report_image(Uuid::default(), ReportReason::Advert, Uuid::default())
+            .await
+            .unwrap();
+        assert!(admin_notification.get_mass_reports().is_empty());
+
+        // each report beyond the threshold gets notified
+        for _ in 0..2 {
+            handler
+                .report_image(
+                    Uuid::default(),
+                    ReportReason::Advert,
+                    MASS_REPORTING_CLIENT_ID,
+                )
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            admin_notification.get_mass_reports(),
+            vec![MASS_REPORTING_CLIENT_ID, MASS_REPORTING_CLIENT_ID]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ban_client() {
+        let handler = get_handler().unwrap();
+
+        handler
+            .ban_client(Uuid::default(), "admin", Some("spam".into()))
+            .await
+            .unwrap();
+        handler
+            .unban_client(BANNED_CLIENT_ID, "admin")
+            .await
+            .unwrap();
+        assert!(handler
+            .unban_client(Uuid::default(), "admin")
+            .await
+            .is_err());
     }
 
     #[tokio::test]
@@ -434,22 +746,20 @@ mod test {
             .await
             .unwrap();
         handler.set_meal_rating(id, 1, id).await.unwrap();
-        handler.verify_image(id).await.unwrap();
-        handler.delete_image(id).await.unwrap();
-    }
-
-    const fn get_handler() -> Result<
-        CommandHandler<
-            CommandDatabaseMock,
-            CommandAdminNotificationMock,
-            CommandImageStorageMock,
-            CommandImageValidationMock,
-        >,
-    > {
+        handler.verify_image(id, "admin").await.unwrap();
+        handler.delete_image(id, "admin").await.unwrap();
+    }
+
+    type Handler =
+        CommandHandler<CommandDatabaseMock, CommandAdminNotificationMock, CommandImageStorageMock>;
+
+    fn get_handler() -> Result<Handler> {
+        get_handler_notifying(CommandAdminNotificationMock::default())
+    }
+
+    fn get_handler_notifying(admin_notification: CommandAdminNotificationMock) -> Result<Handler> {
         let command_data = CommandDatabaseMock;
-        let admin_notification = CommandAdminNotificationMock;
         let image_storage = CommandImageStorageMock;
-        let image_validation = CommandImageValidationMock;
         let info = ImagePreprocessingInfo {
             max_image_height: 1000,
             max_image_width: 1000,
@@ -457,25 +767,11 @@ mod test {
 
         CommandHandler::new(
             info,
+            ReportPolicy::default(),
+            ClientLimitInfo::default(),
             command_data,
             admin_notification,
             image_storage,
-            image_validation,
         )
     }
-
-    #[test]
-    fn test_will_be_hidden() {
-        let image = Image {
-            upload_date: Local::now().date_naive(),
-            report_count: 10,
-            ..Default::default()
-        };
-        assert!(CommandHandler::<
-            CommandDatabaseMock,
-            CommandAdminNotificationMock,
-            CommandImageStorageMock,
-            CommandImageValidationMock,
-        >::will_be_hidden(&image));
-    }
 }
@@ -1,24 +1,29 @@
-//! This crate contains mocks of [`CommandDataAccess`], [`ImageStorage`], [`ImageValidation`] and [`AdminNotification`] for testing.
+//! This crate contains mocks of [`CommandDataAccess`], [`ImageStorage`] and [`AdminNotification`] for testing.
 #![allow(missing_docs)]
 
+use std::sync::{Arc, Mutex};
+
 use async_trait::async_trait;
 
 use crate::{
     interface::{
-        admin_notification::{self, AdminNotification, ImageReportInfo},
+        admin_notification::{self, AdminNotification, ImageReportInfo, NotificationError},
         image_storage::ImageStorage,
-        image_validation::ImageValidation,
         persistent_data::{
-            model::{ExtendedImage, Image},
+            model::{ExtendedImage, Image, RankPolicy, UploaderStats},
             CommandDataAccess, DataError, Result as DataResult,
         },
     },
-    util::{Date, ImageResource, ReportReason, Uuid},
+    util::{ClientAction, Date, ImageResource, ModerationStatus, ReportReason, Uuid},
 };
 
 pub const IMAGE_ID_TO_FAIL: Uuid = Uuid::from_u128(7u128);
 pub const MEAL_ID_TO_FAIL: Uuid = Uuid::from_u128(27u128);
 pub const INVALID_URL: &str = "hello";
+pub const BANNED_CLIENT_ID: Uuid = Uuid::from_u128(37u128);
+pub const RATE_LIMITED_CLIENT_ID: Uuid = Uuid::from_u128(38u128);
+/// Client whose reports today reach any mass report threshold, but no limit.
+pub const MASS_REPORTING_CLIENT_ID: Uuid = Uuid::from_u128(39u128);
 
 #[derive(Default, Debug)]
 pub struct CommandDatabaseMock;
@@ -38,6 +43,8 @@ impl CommandDataAccess for CommandDatabaseMock {
                 id: Uuid::default(),
                 meal_id: Uuid::default(),
                 reporting_users: Option::default(),
+                uploader_id: Uuid::default(),
+                moderation_status: ModerationStatus::Accepted,
             },
             meal_name: "Happy Meal".into(),
             other_image_urls: vec![],
@@ -46,7 +53,7 @@ impl CommandDataAccess for CommandDatabaseMock {
     }
 
     /// Marks an image as hidden. Hidden images cant be seen by users.
-    async fn hide_image(&self, image_id: Uuid) -> DataResult<()> {
+    async fn hide_image(&self, image_id: Uuid, _reason: &str) -> DataResult<()> {
         if IMAGE_ID_TO_FAIL == image_id {
             Err(DataError::NoSuchItem)
         } else {
@@ -60,14 +67,30 @@ impl CommandDataAccess for CommandDatabaseMock {
         image_id: Uuid,
         _client_id: Uuid,
         _reason: ReportReason,
-    ) -> DataResult<()> {
+    ) -> DataResult<u32> {
         if IMAGE_ID_TO_FAIL == image_id {
             Err(DataError::NoSuchItem)
         } else {
-            Ok(())
+            Ok(101)
+        }
+    }
+
+    async fn get_report_reasons(&self, image_id: Uuid) -> DataResult<Vec<ReportReason>> {
+        if IMAGE_ID_TO_FAIL == image_id {
+            Err(DataError::NoSuchItem)
+        } else {
+            Ok(vec![ReportReason::Advert])
         }
     }
 
+    async fn get_uploader_stats(&self, _user_id: Uuid) -> DataResult<UploaderStats> {
+        Ok(UploaderStats::default())
+    }
+
+    async fn set_rank_policy(&self, _policy: &RankPolicy) -> DataResult<()> {
+        Ok(())
+    }
+
     /// Adds an upvote to the given image. An user can only down- or upvote an image.
     async fn add_upvote(&self, image_id: Uuid, _user_id: Uuid) -> DataResult<()> {
         if IMAGE_ID_TO_FAIL == image_id {
@@ -117,6 +140,10 @@ impl CommandDataAccess for CommandDatabaseMock {
         Ok(())
     }
 
+    async fn withdraw_image(&self, _image_id: Uuid, _client_id: Uuid) -> DataResult<()> {
+        Ok(())
+    }
+
     /// Adds a rating to the database. The rating will be related to the given meal and the given user.
     async fn add_rating(&self, meal_id: Uuid, _user_id: Uuid, _rating: u32) -> DataResult<()> {
         if MEAL_ID_TO_FAIL == meal_id {
@@ -126,39 +153,144 @@ impl CommandDataAccess for CommandDatabaseMock {
         }
     }
 
-    async fn delete_image(&self, _image_id: Uuid) -> DataResult<()> {
+    async fn delete_image(&self, _image_id: Uuid, _deleted_by: &str) -> DataResult<()> {
+        Ok(())
+    }
+
+    async fn erase_client_data(&self, _client_id: Uuid, _erased_by: &str) -> DataResult<Vec<Uuid>> {
+        Ok(vec![Uuid::default()])
+    }
+
+    async fn verify_image(&self, _image_id: Uuid, _verified_by: &str) -> DataResult<()> {
+        Ok(())
+    }
+
+    async fn unhide_image(&self, _image_id: Uuid, _unhidden_by: &str) -> DataResult<()> {
         Ok(())
     }
 
-    async fn verify_image(&self, _image_id: Uuid) -> DataResult<()> {
+    async fn restore_image(&self, _image_id: Uuid, _restored_by: &str) -> DataResult<()> {
         Ok(())
     }
+
+    async fn count_client_actions(
+        &self,
+        client_id: Uuid,
+        _action: ClientAction,
+    ) -> DataResult<u32> {
+        if client_id == RATE_LIMITED_CLIENT_ID {
+            Ok(u32::MAX)
+        } else {
+            Ok(0)
+        }
+    }
+
+    async fn record_client_action(
+        &self,
+        client_id: Uuid,
+        _action: ClientAction,
+    ) -> DataResult<u32> {
+        if client_id == MASS_REPORTING_CLIENT_ID {
+            Ok(u32::MAX)
+        } else {
+            Ok(1)
+        }
+    }
+
+    async fn is_client_banned(&self, client_id: Uuid) -> DataResult<bool> {
+        Ok(client_id == BANNED_CLIENT_ID)
+    }
+
+    async fn ban_client(
+        &self,
+        _client_id: Uuid,
+        _banned_by: &str,
+        _reason: Option<&str>,
+    ) -> DataResult<()> {
+        Ok(())
+    }
+
+    async fn unban_client(&self, client_id: Uuid, _unbanned_by: &str) -> DataResult<()> {
+        if client_id == BANNED_CLIENT_ID {
+            Ok(())
+        } else {
+            Err(DataError::NoSuchItem)
+        }
+    }
 }
 
-#[derive(Default, Debug)]
-pub struct CommandAdminNotificationMock;
+#[derive(Default, Debug, Clone)]
+pub struct CommandAdminNotificationMock {
+    mass_reports: Arc<Mutex<Vec<Uuid>>>,
+    fail: bool,
+}
+
+impl CommandAdminNotificationMock {
+    /// Creates a mock failing to send notifications about deleted, verified and withdrawn images.
+    #[must_use]
+    pub fn failing() -> Self {
+        Self {
+            fail: true,
+            ..Default::default()
+        }
+    }
+
+    fn result(&self) -> admin_notification::Result<()> {
+        if self.fail {
+            Err(NotificationError::AddressError(
+                "invalid"
+                    .parse::<lettre::Address>()
+                    .expect_err("address without domain is invalid"),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// # Panics
+    /// if the mutex is poisoned
+    #[must_use]
+    pub fn get_mass_reports(&self) -> Vec<Uuid> {
+        self.mass_reports
+            .lock()
+            .expect("mutex not poisoned")
+            .clone()
+    }
+}
 
 #[async_trait]
 impl AdminNotification for CommandAdminNotificationMock {
     /// Notifies an administrator about a newly reported image and the response automatically taken.
     async fn notify_admin_image_report(&self, _info: ImageReportInfo) {}
     async fn notify_admin_image_deleted(&self, _image_id: Uuid) -> admin_notification::Result<()> {
-        Ok(())
+        self.result()
     }
     async fn notify_admin_image_verified(&self, _image_id: Uuid) -> admin_notification::Result<()> {
+        self.result()
+    }
+    async fn notify_admin_image_withdrawn(
+        &self,
+        _image_id: Uuid,
+        _client_id: Uuid,
+    ) -> admin_notification::Result<()> {
+        self.result()
+    }
+    async fn notify_admin_image_review_failed(
+        &self,
+        _image_id: Uuid,
+        _error: &str,
+    ) -> admin_notification::Result<()> {
         Ok(())
     }
-}
-
-#[derive(Default, Debug)]
-pub struct CommandImageValidationMock;
-
-#[async_trait]
-impl ImageValidation for CommandImageValidationMock {
-    async fn validate_image(
+    async fn notify_admin_mass_reports(
         &self,
-        _image: &ImageResource,
-    ) -> crate::interface::image_validation::Result<()> {
+        client_id: Uuid,
+        _report_count: u32,
+    ) -> admin_notification::Result<()> {
+        self.mass_reports
+            .lock()
+            .expect("mutex not poisoned")
+            .push(client_id);
         Ok(())
     }
 }
@@ -179,4 +311,15 @@ impl ImageStorage for CommandImageStorageMock {
     async fn delete_image(&self, _image_id: Uuid) -> crate::interface::image_storage::Result<()> {
         Ok(())
     }
+
+    async fn load_image(
+        &self,
+        _id: Uuid,
+    ) -> crate::interface::image_storage::Result<ImageResource> {
+        Ok(ImageResource::default())
+    }
+
+    async fn list_images(&self) -> crate::interface::image_storage::Result<Vec<Uuid>> {
+        Ok(Vec::new())
+    }
 }
@@ -1,4 +1,6 @@
 //! This component contains all the logic needed for API requests that do more than just request data queries.
+pub mod client_limits;
 pub mod command_handler;
 pub mod image_preprocessing;
 pub mod mocks;
+pub mod report_policy;
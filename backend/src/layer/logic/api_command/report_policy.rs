@@ -0,0 +1,295 @@
+//! Module deciding when reported images get hidden automatically, see [`ReportPolicy`].
+
+use chrono::Local;
+
+use crate::{
+    interface::persistent_data::model::{Image, RankPolicy, UploaderStats},
+    util::{Date, ReportReason},
+};
+
+const DEFAULT_BASE_BARRIER: f64 = 5.0;
+const DEFAULT_BARRIER_GROWTH_DIVISOR: f64 = 35.0;
+const DEFAULT_REPORT_WINDOW_DAYS: u32 = 30;
+const DEFAULT_TRUSTED_UPLOADER_APPROVED_IMAGES: u32 = 5;
+const DEFAULT_RANK_REPORT_PENALTY: f64 = 10.0;
+const DEFAULT_RANK_NEW_IMAGE_DAYS: u32 = 30;
+
+/// Structure containing all settings of the report policy.
+///
+/// The default settings hide an image once it got reported `5 + t² / 35` times, where `t` is the age of the image in days,
+/// as long as it is at most 30 days old.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportPolicyInfo {
+    /// Number of (weighted) reports necessary to hide an image uploaded today.
+    pub base_barrier: f64,
+    /// The barrier grows by `t² / barrier_growth_divisor` for images `t` days old.
+    pub barrier_growth_divisor: f64,
+    /// Images older than this many days do not get hidden because of their report count.
+    pub report_window_days: u32,
+    /// Weights of reports with the given reason, reasons not listed have a weight of 1.
+    pub reason_weights: Vec<(ReportReason, f64)>,
+    /// Images reported for one of these reasons get hidden immediately, regardless of their age.
+    pub immediate_hide_reasons: Vec<ReportReason>,
+    /// Number of verified images after which an uploader without removed images is trusted.
+    pub trusted_uploader_approved_images: u32,
+    /// Factor the barrier gets multiplied with for images of trusted uploaders.
+    pub trusted_uploader_factor: f64,
+    /// Factor the barrier gets multiplied with for images of uploaders which had images removed before.
+    pub suspicious_uploader_factor: f64,
+    /// How much a (weighted) report of an unverified image lowers its rank compared to a downvote.
+    pub rank_report_penalty: f64,
+    /// Number of days new images get ranked higher.
+    pub rank_new_image_days: u32,
+}
+
+impl Default for ReportPolicyInfo {
+    fn default() -> Self {
+        Self {
+            base_barrier: DEFAULT_BASE_BARRIER,
+            barrier_growth_divisor: DEFAULT_BARRIER_GROWTH_DIVISOR,
+            report_window_days: DEFAULT_REPORT_WINDOW_DAYS,
+            reason_weights: Vec::new(),
+            immediate_hide_reasons: Vec::new(),
+            trusted_uploader_approved_images: DEFAULT_TRUSTED_UPLOADER_APPROVED_IMAGES,
+            trusted_uploader_factor: 1.0,
+            suspicious_uploader_factor: 1.0,
+            rank_report_penalty: DEFAULT_RANK_REPORT_PENALTY,
+            rank_new_image_days: DEFAULT_RANK_NEW_IMAGE_DAYS,
+        }
+    }
+}
+
+/// Outcome of evaluating the reports of an image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReportDecision {
+    /// Whether the image should get hidden.
+    pub hide: bool,
+    /// Sum of the weights of all reports.
+    pub report_score: f64,
+    /// Score necessary to hide the image.
+    pub report_barrier: u32,
+}
+
+/// Class deciding whether reported images get hidden automatically.
+#[derive(Debug, Default)]
+pub struct ReportPolicy {
+    info: ReportPolicyInfo,
+}
+
+impl ReportPolicy {
+    /// Creates a new instance.
+    #[must_use]
+    pub const fn new(info: ReportPolicyInfo) -> Self {
+        Self { info }
+    }
+
+    /// Decides whether the given image should get hidden because of its reports.
+    /// `reasons` contains the reasons of all reports of this image, including the newest one.
+    #[must_use]
+    pub fn evaluate(
+        &self,
+        image: &Image,
+        reasons: &[ReportReason],
+        uploader: UploaderStats,
+    ) -> ReportDecision {
+        let report_score = self.report_score(reasons);
+        let report_barrier = self.report_barrier(image.upload_date, uploader);
+        let immediate = reasons
+            .iter()
+            .any(|r| self.info.immediate_hide_reasons.contains(r));
+        let in_window = days_since(image.upload_date) <= i64::from(self.info.report_window_days);
+
+        ReportDecision {
+            hide: immediate || (in_window && report_score >= f64::from(report_barrier)),
+            report_score,
+            report_barrier,
+        }
+    }
+
+    /// Returns the weight of a single report with the given reason.
+    #[must_use]
+    pub fn weight(&self, reason: ReportReason) -> f64 {
+        self.info
+            .reason_weights
+            .iter()
+            .find(|(r, _)| *r == reason)
+            .map_or(1.0, |(_, w)| *w)
+    }
+
+    /// Returns the sum of the weights of the given reports.
+    #[must_use]
+    pub fn report_score(&self, reasons: &[ReportReason]) -> f64 {
+        reasons.iter().map(|r| self.weight(*r)).sum()
+    }
+
+    /// Returns the report score necessary to hide an image uploaded at `upload_date` by an uploader with the given history.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn report_barrier(&self, upload_date: Date, uploader: UploaderStats) -> u32 {
+        let t = days_since(upload_date) as f64;
+        let barrier = (t * t).mul_add(
+            1.0 / self.info.barrier_growth_divisor,
+            self.info.base_barrier,
+        );
+        (barrier * self.uploader_factor(uploader)).floor() as u32
+    }
+
+    const fn uploader_factor(&self, uploader: UploaderStats) -> f64 {
+        if uploader.removed_images > 0 {
+            self.info.suspicious_uploader_factor
+        } else if uploader.approved_images >= self.info.trusted_uploader_approved_images {
+            self.info.trusted_uploader_factor
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns the settings for ranking images, which have to be stored in the database.
+    #[must_use]
+    pub fn rank_policy(&self) -> RankPolicy {
+        RankPolicy {
+            report_penalty: self.info.rank_report_penalty,
+            new_image_days: self.info.rank_new_image_days,
+            reason_weights: self.info.reason_weights.clone(),
+        }
+    }
+}
+
+/// Returns the number of days passed since `date`.
+#[must_use]
+pub fn days_since(date: Date) -> i64 {
+    let today = Local::now().date_naive();
+    today.signed_duration_since(date).num_days()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use chrono::{Days, Local};
+
+    use super::*;
+
+    fn image(report_count: u32, age: u64) -> Image {
+        Image {
+            upload_date: Local::now().date_naive() - Days::new(age),
+            report_count,
+            ..Default::default()
+        }
+    }
+
+    fn reports(reason: ReportReason, count: usize) -> Vec<ReportReason> {
+        vec![reason; count]
+    }
+
+    #[test]
+    fn test_default_barrier() {
+        let policy = ReportPolicy::default();
+        let uploader = UploaderStats::default();
+
+        let hide = |count, age| {
+            policy
+                .evaluate(
+                    &image(count, age),
+                    &reports(ReportReason::Other, count as usize),
+                    uploader,
+                )
+                .hide
+        };
+        assert!(hide(10, 0));
+        assert!(hide(5, 0));
+        assert!(!hide(4, 0));
+        // 5 + 10² / 35 = 7.86
+        assert!(!hide(6, 10));
+        assert!(hide(7, 10));
+        // too old to be hidden because of reports
+        assert!(!hide(100, 31));
+    }
+
+    #[test]
+    fn test_reason_weights() {
+        let policy = ReportPolicy::new(ReportPolicyInfo {
+            reason_weights: vec![
+                (ReportReason::Offensive, 2.5),
+                (ReportReason::WrongMeal, 0.5),
+            ],
+            ..Default::default()
+        });
+        let uploader = UploaderStats::default();
+
+        assert!((policy.weight(ReportReason::Offensive) - 2.5).abs() < f64::EPSILON);
+        assert!((policy.weight(ReportReason::Advert) - 1.0).abs() < f64::EPSILON);
+
+        let offensive = reports(ReportReason::Offensive, 2);
+        let decision = policy.evaluate(&image(2, 0), &offensive, uploader);
+        assert!(decision.hide);
+        assert!((decision.report_score - 5.0).abs() < f64::EPSILON);
+
+        let wrong_meal = reports(ReportReason::WrongMeal, 9);
+        assert!(!policy.evaluate(&image(9, 0), &wrong_meal, uploader).hide);
+    }
+
+    #[test]
+    fn test_immediate_hide_reasons() {
+        let policy = ReportPolicy::new(ReportPolicyInfo {
+            immediate_hide_reasons: vec![ReportReason::ViolatesRights],
+            ..Default::default()
+        });
+        let uploader = UploaderStats::default();
+
+        let reasons = [ReportReason::ViolatesRights];
+        assert!(policy.evaluate(&image(1, 0), &reasons, uploader).hide);
+        // also applies to old images
+        assert!(policy.evaluate(&image(1, 400), &reasons, uploader).hide);
+
+        let reasons = [ReportReason::Advert];
+        assert!(!policy.evaluate(&image(1, 0), &reasons, uploader).hide);
+    }
+
+    #[test]
+    fn test_uploader_reputation() {
+        let policy = ReportPolicy::new(ReportPolicyInfo {
+            trusted_uploader_approved_images: 3,
+            trusted_uploader_factor: 2.0,
+            suspicious_uploader_factor: 0.5,
+            ..Default::default()
+        });
+        let today = Local::now().date_naive();
+
+        let new_uploader = UploaderStats::default();
+        assert_eq!(policy.report_barrier(today, new_uploader), 5);
+
+        let trusted = UploaderStats {
+            approved_images: 3,
+            removed_images: 0,
+        };
+        assert_eq!(policy.report_barrier(today, trusted), 10);
+
+        // removed images outweigh approved ones
+        let suspicious = UploaderStats {
+            approved_images: 10,
+            removed_images: 1,
+        };
+        assert_eq!(policy.report_barrier(today, suspicious), 2);
+
+        let reasons = reports(ReportReason::Advert, 5);
+        assert!(policy.evaluate(&image(5, 0), &reasons, new_uploader).hide);
+        assert!(!policy.evaluate(&image(5, 0), &reasons, trusted).hide);
+    }
+
+    #[test]
+    fn test_rank_policy() {
+        let policy = ReportPolicy::new(ReportPolicyInfo {
+            reason_weights: vec![(ReportReason::Advert, 3.0)],
+            rank_report_penalty: 5.0,
+            ..Default::default()
+        });
+        let rank = policy.rank_policy();
+        assert_eq!(rank.reason_weights, vec![(ReportReason::Advert, 3.0)]);
+        assert!((rank.report_penalty - 5.0).abs() < f64::EPSILON);
+        assert_eq!(rank.new_image_days, 30);
+    }
+}
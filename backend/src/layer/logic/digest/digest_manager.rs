@@ -0,0 +1,203 @@
+//! See [`DigestManager`].
+use std::cmp::Reverse;
+
+use async_trait::async_trait;
+use tracing::{info, warn};
+
+use crate::{
+    interface::{
+        admin_notification::{DigestAction, DigestNotification, DigestReport, ModerationDigest},
+        digest::DigestScheduling,
+        persistent_data::{model::AuditEntry, DigestDataAccess},
+    },
+    util::{self, AuditAction},
+};
+
+/// Class responsible for sending digests of the moderation activity to administrators.
+pub struct DigestManager<DataAccess, Notify>
+where
+    DataAccess: DigestDataAccess,
+    Notify: DigestNotification,
+{
+    digest_data: DataAccess,
+    notification: Notify,
+}
+
+impl<DataAccess, Notify> DigestManager<DataAccess, Notify>
+where
+    DataAccess: DigestDataAccess,
+    Notify: DigestNotification,
+{
+    /// Creates a new instance summarizing the activity recorded in `digest_data` and sending it over `notification`.
+    pub const fn new(digest_data: DataAccess, notification: Notify) -> Self {
+        Self {
+            digest_data,
+            notification,
+        }
+    }
+}
+
+#[async_trait]
+impl<DataAccess, Notify> DigestScheduling for DigestManager<DataAccess, Notify>
+where
+    DataAccess: DigestDataAccess,
+    Notify: DigestNotification,
+{
+    async fn send_digest(&self) {
+        let entries = match self.digest_data.get_undigested_entries().await {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Could not load moderation activity for digest: {e}");
+                return;
+            }
+        };
+        let Some(last_entry_id) = entries.last().map(|e| e.id) else {
+            return;
+        };
+
+        let digest = build_digest(&entries);
+        if let Err(e) = self.notification.notify_admin_digest(&digest).await {
+            // entries stay undigested, so they get included next time
+            warn!("Could not send moderation digest: {e}");
+            return;
+        }
+        info!(
+            "Sent moderation digest with {} reported images.",
+            digest.reported_images.len()
+        );
+
+        if let Err(e) = self.digest_data.mark_digested(last_entry_id).await {
+            warn!("Could not mark moderation activity as digested: {e}");
+        }
+    }
+}
+
+fn build_digest(entries: &[AuditEntry]) -> ModerationDigest {
+    let mut digest = ModerationDigest {
+        since: entries.first().map(|e| e.time),
+        ..ModerationDigest::default()
+    };
+    for entry in entries {
+        let action = || DigestAction {
+            image_id: entry.target_id,
+            image_url: util::image_id_to_url(entry.target_id),
+            actor: entry.actor.clone(),
+            time: entry.time,
+        };
+        match entry.action {
+            AuditAction::ReportImage => add_report(&mut digest.reported_images, entry),
+            AuditAction::HideImage => digest.hidden_images.push(action()),
+            AuditAction::VerifyImage => digest.verified_images.push(action()),
+            AuditAction::DeleteImage => digest.deleted_images.push(action()),
+            _ => (),
+        }
+    }
+    digest
+        .reported_images
+        .sort_by_key(|r| Reverse(r.report_count));
+    digest
+}
+
+fn add_report(reports: &mut Vec<DigestReport>, entry: &AuditEntry) {
+    let index = reports
+        .iter()
+        .position(|r| r.image_id == entry.target_id)
+        .unwrap_or_else(|| {
+            reports.push(DigestReport {
+                image_id: entry.target_id,
+                image_url: util::image_id_to_url(entry.target_id),
+                report_count: 0,
+                reasons: Vec::new(),
+            });
+            reports.len() - 1
+        });
+    let report = &mut reports[index];
+    report.report_count += 1;
+    if let Some(reason) = &entry.reason {
+        if !report.reasons.contains(reason) {
+            report.reasons.push(reason.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::interface::digest::DigestScheduling;
+    use crate::interface::persistent_data::model::AuditEntry;
+    use crate::layer::logic::digest::mocks::{DigestDatabaseMock, DigestNotificationMock};
+    use crate::util::{AuditAction, Uuid, SYSTEM_ACTOR};
+
+    use super::DigestManager;
+
+    fn entry(id: i64, action: AuditAction, target: u128, reason: Option<&str>) -> AuditEntry {
+        AuditEntry {
+            id,
+            time: Utc.timestamp_opt(1_700_000_000 + id, 0).unwrap(),
+            actor: if action == AuditAction::HideImage {
+                SYSTEM_ACTOR.into()
+            } else {
+                "admin".into()
+            },
+            action,
+            target_id: Uuid::from_u128(target),
+            reason: reason.map(Into::into),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_digest() {
+        let data = DigestDatabaseMock::with_entries(vec![
+            entry(3, AuditAction::ReportImage, 1, Some("Advert")),
+            entry(4, AuditAction::ReportImage, 2, Some("Advert")),
+            entry(5, AuditAction::ReportImage, 2, Some("NoMeal")),
+            entry(6, AuditAction::ReportImage, 2, Some("Advert")),
+            entry(7, AuditAction::HideImage, 2, None),
+            entry(8, AuditAction::DeleteImage, 2, None),
+            entry(9, AuditAction::VerifyImage, 1, None),
+        ]);
+        let notification = DigestNotificationMock::default();
+        let manager = DigestManager::new(data.clone(), notification.clone());
+
+        manager.send_digest().await;
+
+        let sent = notification.get_sent();
+        assert_eq!(sent.len(), 1);
+        let digest = &sent[0];
+        assert_eq!(
+            digest.since,
+            Some(Utc.timestamp_opt(1_700_000_003, 0).unwrap())
+        );
+        // most reported first
+        assert_eq!(digest.reported_images[0].image_id, Uuid::from_u128(2));
+        assert_eq!(digest.reported_images[0].report_count, 3);
+        assert_eq!(digest.reported_images[0].reasons, vec!["Advert", "NoMeal"]);
+        assert_eq!(digest.reported_images[1].report_count, 1);
+        assert_eq!(digest.hidden_images[0].actor, SYSTEM_ACTOR);
+        assert_eq!(digest.deleted_images[0].image_id, Uuid::from_u128(2));
+        assert_eq!(digest.verified_images[0].image_id, Uuid::from_u128(1));
+        assert_eq!(data.get_digested(), Some(9));
+    }
+
+    #[tokio::test]
+    async fn test_no_digest_sent() {
+        // nothing happened
+        let notification = DigestNotificationMock::default();
+        let data = DigestDatabaseMock::default();
+        DigestManager::new(data.clone(), notification.clone())
+            .send_digest()
+            .await;
+        assert!(notification.get_sent().is_empty());
+        assert_eq!(data.get_digested(), None);
+
+        // sending failed, so the entries are kept for the next digest
+        let data =
+            DigestDatabaseMock::with_entries(vec![entry(1, AuditAction::DeleteImage, 1, None)]);
+        DigestManager::new(data.clone(), DigestNotificationMock::failing())
+            .send_digest()
+            .await;
+        assert_eq!(data.get_digested(), None);
+    }
+}
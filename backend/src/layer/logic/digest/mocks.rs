@@ -0,0 +1,90 @@
+//! This crate contains mocks of [`DigestDataAccess`] and [`DigestNotification`] for testing.
+#![allow(missing_docs)]
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::interface::{
+    admin_notification::{self, DigestNotification, ModerationDigest, NotificationError},
+    persistent_data::{model::AuditEntry, DigestDataAccess, Result as DataResult},
+};
+
+#[derive(Default, Debug, Clone)]
+pub struct DigestDatabaseMock {
+    entries: Vec<AuditEntry>,
+    digested: Arc<Mutex<Option<i64>>>,
+}
+
+impl DigestDatabaseMock {
+    #[must_use]
+    pub fn with_entries(entries: Vec<AuditEntry>) -> Self {
+        Self {
+            entries,
+            ..Self::default()
+        }
+    }
+
+    /// # Panics
+    /// if the mutex is poisoned
+    #[must_use]
+    pub fn get_digested(&self) -> Option<i64> {
+        *self.digested.lock().expect("mutex not poisoned")
+    }
+}
+
+#[async_trait]
+impl DigestDataAccess for DigestDatabaseMock {
+    async fn get_undigested_entries(&self) -> DataResult<Vec<AuditEntry>> {
+        Ok(self.entries.clone())
+    }
+
+    async fn mark_digested(&self, last_entry_id: i64) -> DataResult<()> {
+        *self.digested.lock().expect("mutex not poisoned") = Some(last_entry_id);
+        Ok(())
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct DigestNotificationMock {
+    sent: Arc<Mutex<Vec<ModerationDigest>>>,
+    fail: bool,
+}
+
+impl DigestNotificationMock {
+    #[must_use]
+    pub fn failing() -> Self {
+        Self {
+            fail: true,
+            ..Self::default()
+        }
+    }
+
+    /// # Panics
+    /// if the mutex is poisoned
+    #[must_use]
+    pub fn get_sent(&self) -> Vec<ModerationDigest> {
+        self.sent.lock().expect("mutex not poisoned").clone()
+    }
+}
+
+#[async_trait]
+impl DigestNotification for DigestNotificationMock {
+    async fn notify_admin_digest(
+        &self,
+        digest: &ModerationDigest,
+    ) -> admin_notification::Result<()> {
+        if self.fail {
+            return Err(NotificationError::AddressError(
+                "invalid"
+                    .parse::<lettre::Address>()
+                    .expect_err("not an address"),
+            ));
+        }
+        self.sent
+            .lock()
+            .expect("mutex not poisoned")
+            .push(digest.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,3 @@
+//! This component summarizes the moderation activity in a regular digest for administrators.
+pub mod digest_manager;
+pub mod mocks;
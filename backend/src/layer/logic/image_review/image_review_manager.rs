@@ -0,0 +1,387 @@
+//! See [`ImageReviewManager`].
+use async_trait::async_trait;
+use metrics::counter;
+use tokio::sync::Mutex;
+use tracing::{info, trace, warn};
+
+use crate::interface::{
+    admin_notification::AdminNotification,
+    image_review::ImageReviewScheduling,
+    image_storage::ImageStorage,
+    image_validation::ImageValidation,
+    persistent_data::{model::PendingImage, ImageReviewDataAccess, Result as DataResult},
+};
+use crate::util::Uuid;
+
+/// Structure containing all information necessary to construct an [`ImageReviewManager`].
+#[derive(Debug, Clone)]
+pub struct ImageReviewInfo {
+    /// Number of times the review or the verification of an image gets tried before giving up.
+    pub max_attempts: u32,
+    /// Number of days after which visible images get validated again.
+    pub reverification_days: u32,
+    /// Maximum number of images validated again per run, to limit the load on the validation api.
+    pub reverification_limit: u32,
+}
+
+/// Class responsible for reviewing uploaded images in the background.
+pub struct ImageReviewManager<DataAccess, Storage, Validation, Notification>
+where
+    DataAccess: ImageReviewDataAccess,
+    Storage: ImageStorage,
+    Validation: ImageValidation,
+    Notification: AdminNotification,
+{
+    review_data: DataAccess,
+    image_storage: Storage,
+    image_validation: Validation,
+    admin_notification: Notification,
+    info: ImageReviewInfo,
+    running: Mutex<()>,
+}
+
+impl<DataAccess, Storage, Validation, Notification>
+    ImageReviewManager<DataAccess, Storage, Validation, Notification>
+where
+    DataAccess: ImageReviewDataAccess,
+    Storage: ImageStorage,
+    Validation: ImageValidation,
+    Notification: AdminNotification,
+{
+    /// Creates a new instance reviewing images stored by `image_storage` using `image_validation`.
+    /// Administrators get notified over `admin_notification` about images which could not be reviewed.
+    pub fn new(
+        info: ImageReviewInfo,
+        review_data: DataAccess,
+        image_storage: Storage,
+        image_validation: Validation,
+        admin_notification: Notification,
+    ) -> Self {
+        Self {
+            review_data,
+            image_storage,
+            image_validation,
+            admin_notification,
+            info,
+            running: Mutex::default(),
+        }
+    }
+
+    /// Returns whether a failed validation was a rejection together with the reason.
+    async fn validate(&self, image: &PendingImage) -> Result<(), (bool, String)> {
+        match self.image_storage.load_image(image.id).await {
+            Ok(resource) => self
+                .image_validation
+                .validate_image(&resource, &image.meal_name)
+                .await
+                .map_err(|e| (e.is_rejection(), e.to_string())),
+            Err(e) => Err((false, e.to_string())),
+        }
+    }
+
+    async fn review_image(&self, image: PendingImage) {
+        let id = image.id;
+        let result = self.validate(&image).await;
+
+        let outcome = match &result {
+            Ok(()) => "accepted",
+            Err((true, _)) => "rejected",
+            Err((false, _)) => "failed",
+        };
+        counter!("image_reviews_total", "outcome" => outcome).increment(1);
+
+        let stored = match result {
+            Ok(()) => {
+                trace!("Accepted image {id}.");
+                self.review_data.accept_image(id).await
+            }
+            Err((true, reason)) => {
+                info!("Rejected image {id}: {reason}");
+                if let Err(e) = self.image_storage.delete_image(id).await {
+                    warn!("Could not delete file of rejected image {id}: {e}");
+                }
+                self.review_data.reject_image(id, &reason).await
+            }
+            Err((false, error)) => {
+                let attempt = image.review_attempts + 1;
+                if attempt >= self.info.max_attempts {
+                    warn!("Reviewing image {id} failed for the last time ({attempt}/{}), hiding it for a manual review: {error}", self.info.max_attempts);
+                    self.give_up_review(id, &error).await
+                } else {
+                    info!(
+                        "Reviewing image {id} failed ({attempt}/{}), retrying later: {error}",
+                        self.info.max_attempts
+                    );
+                    self.review_data.add_failed_review_attempt(id, &error).await
+                }
+            }
+        };
+
+        if let Err(e) = stored {
+            warn!("Could not store review result of image {id}: {e}");
+        }
+    }
+
+    /// Hides an image which could not be reviewed automatically, so administrators can review it.
+    async fn give_up_review(&self, id: Uuid, error: &str) -> DataResult<()> {
+        self.review_data.hide_unreviewable_image(id, error).await?;
+        if let Err(e) = self
+            .admin_notification
+            .notify_admin_image_review_failed(id, error)
+            .await
+        {
+            warn!("Could not notify administrators about unreviewed image {id}: {e}");
+        }
+        Ok(())
+    }
+
+    async fn reverify_image(&self, image: PendingImage) {
+        let id = image.id;
+        let result = self.validate(&image).await;
+
+        let outcome = match &result {
+            Ok(()) => "verified",
+            Err((true, _)) => "hidden",
+            Err((false, _)) => "failed",
+        };
+        counter!("image_reverifications_total", "outcome" => outcome).increment(1);
+
+        let stored = match result {
+            Ok(()) => {
+                trace!("Verified image {id} again.");
+                self.review_data.renew_verification(id).await
+            }
+            Err((true, reason)) => {
+                info!("Hid image {id} no longer passing the validation: {reason}");
+                self.review_data.hide_failing_image(id, &reason).await
+            }
+            Err((false, error)) => {
+                // verification date stays unchanged, so the image gets tried again next time
+                let attempt = image.review_attempts + 1;
+                if attempt >= self.info.max_attempts {
+                    warn!("Verifying image {id} again failed for the last time ({attempt}/{}), giving up: {error}", self.info.max_attempts);
+                } else {
+                    info!(
+                        "Verifying image {id} again failed ({attempt}/{}), retrying later: {error}",
+                        self.info.max_attempts
+                    );
+                }
+                self.review_data.add_failed_review_attempt(id, &error).await
+            }
+        };
+
+        if let Err(e) = stored {
+            warn!("Could not store verification result of image {id}: {e}");
+        }
+    }
+}
+
+#[async_trait]
+impl<DataAccess, Storage, Validation, Notification> ImageReviewScheduling
+    for ImageReviewManager<DataAccess, Storage, Validation, Notification>
+where
+    DataAccess: ImageReviewDataAccess,
+    Storage: ImageStorage,
+    Validation: ImageValidation,
+    Notification: AdminNotification,
+{
+    /// Reviews all pending images one after another.
+    /// If a review is still running, e.g. because the validation api is slow, this call does nothing.
+    async fn start_image_review(&self) {
+        let Ok(_guard) = self.running.try_lock() else {
+            info!("Image review still running, skipping.");
+            return;
+        };
+
+        let pending = match self
+            .review_data
+            .get_pending_images(self.info.max_attempts)
+            .await
+        {
+            Ok(pending) => pending,
+            Err(e) => {
+                warn!("Could not load pending images: {e}");
+                return;
+            }
+        };
+
+        if !pending.is_empty() {
+            info!("Reviewing {} pending images.", pending.len());
+        }
+
+        for image in pending {
+            self.review_image(image).await;
+        }
+    }
+
+    /// Validates the images not verified for the longest time one after another, at most as many as configured.
+    /// As this shares the lock with the review of pending images, only one of them runs at a time.
+    async fn start_image_reverification(&self) {
+        let Ok(_guard) = self.running.try_lock() else {
+            info!("Image review still running, skipping verification of older images.");
+            return;
+        };
+
+        let images = match self
+            .review_data
+            .get_images_to_reverify(
+                self.info.reverification_days,
+                self.info.reverification_limit,
+                self.info.max_attempts,
+            )
+            .await
+        {
+            Ok(images) => images,
+            Err(e) => {
+                warn!("Could not load images to verify again: {e}");
+                return;
+            }
+        };
+
+        if !images.is_empty() {
+            info!("Verifying {} images again.", images.len());
+        }
+
+        for image in images {
+            self.reverify_image(image).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interface::image_review::ImageReviewScheduling;
+    use crate::layer::logic::image_review::mocks::{
+        ImageReviewDatabaseMock, ImageReviewStorageMock, ImageValidationMock,
+        ReviewNotificationMock, ReviewResult, IMAGE_ID_FAILED_BEFORE, IMAGE_ID_TO_FAIL_LOADING,
+        IMAGE_ID_TO_REJECT, IMAGE_ID_WITH_API_ERROR,
+    };
+    use crate::util::Uuid;
+
+    use super::{ImageReviewInfo, ImageReviewManager};
+
+    type Manager = ImageReviewManager<
+        ImageReviewDatabaseMock,
+        ImageReviewStorageMock,
+        ImageValidationMock,
+        ReviewNotificationMock,
+    >;
+
+    fn get_manager(data: ImageReviewDatabaseMock, storage: ImageReviewStorageMock) -> Manager {
+        get_manager_notifying(data, storage, ReviewNotificationMock::default())
+    }
+
+    fn get_manager_notifying(
+        data: ImageReviewDatabaseMock,
+        storage: ImageReviewStorageMock,
+        notification: ReviewNotificationMock,
+    ) -> Manager {
+        ImageReviewManager::new(
+            ImageReviewInfo {
+                max_attempts: 3,
+                reverification_days: 180,
+                reverification_limit: 10,
+            },
+            data,
+            storage,
+            ImageValidationMock,
+            notification,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_start_image_review() {
+        let accepted = Uuid::from_u128(1);
+        let data = ImageReviewDatabaseMock::with_pending(&[
+            accepted,
+            IMAGE_ID_TO_REJECT,
+            IMAGE_ID_WITH_API_ERROR,
+            IMAGE_ID_TO_FAIL_LOADING,
+        ]);
+        let storage = ImageReviewStorageMock::default();
+        let manager = get_manager(data.clone(), storage.clone());
+
+        manager.start_image_review().await;
+
+        assert_eq!(
+            data.get_results(),
+            vec![
+                (accepted, ReviewResult::Accepted),
+                (IMAGE_ID_TO_REJECT, ReviewResult::Rejected),
+                (IMAGE_ID_WITH_API_ERROR, ReviewResult::Failed),
+                (IMAGE_ID_TO_FAIL_LOADING, ReviewResult::Failed),
+            ]
+        );
+        // only rejected images get deleted
+        assert_eq!(storage.get_deleted(), vec![IMAGE_ID_TO_REJECT]);
+    }
+
+    #[tokio::test]
+    async fn test_give_up_image_review() {
+        let data = ImageReviewDatabaseMock::with_pending(&[
+            IMAGE_ID_WITH_API_ERROR,
+            IMAGE_ID_FAILED_BEFORE,
+        ]);
+        let notification = ReviewNotificationMock::default();
+        let manager = get_manager_notifying(
+            data.clone(),
+            ImageReviewStorageMock::default(),
+            notification.clone(),
+        );
+
+        manager.start_image_review().await;
+
+        // the third failed attempt is the last one
+        assert_eq!(
+            data.get_results(),
+            vec![
+                (IMAGE_ID_WITH_API_ERROR, ReviewResult::Failed),
+                (IMAGE_ID_FAILED_BEFORE, ReviewResult::HiddenUnreviewable),
+            ]
+        );
+        assert_eq!(
+            notification.get_review_failed(),
+            vec![IMAGE_ID_FAILED_BEFORE]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_start_image_reverification() {
+        let verified = Uuid::from_u128(1);
+        let data = ImageReviewDatabaseMock::with_pending(&[
+            verified,
+            IMAGE_ID_TO_REJECT,
+            IMAGE_ID_WITH_API_ERROR,
+            IMAGE_ID_TO_FAIL_LOADING,
+        ]);
+        let storage = ImageReviewStorageMock::default();
+        let manager = get_manager(data.clone(), storage.clone());
+
+        manager.start_image_reverification().await;
+
+        // failed verifications only count as attempt, so they get retried
+        assert_eq!(
+            data.get_results(),
+            vec![
+                (verified, ReviewResult::Verified),
+                (IMAGE_ID_TO_REJECT, ReviewResult::Hidden),
+                (IMAGE_ID_WITH_API_ERROR, ReviewResult::Failed),
+                (IMAGE_ID_TO_FAIL_LOADING, ReviewResult::Failed),
+            ]
+        );
+        // hidden images stay stored, so administrators can show them again
+        assert!(storage.get_deleted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_no_pending_images() {
+        let data = ImageReviewDatabaseMock::default();
+        let storage = ImageReviewStorageMock::default();
+        let manager = get_manager(data.clone(), storage.clone());
+
+        manager.start_image_review().await;
+
+        assert!(data.get_results().is_empty());
+        assert!(storage.get_deleted().is_empty());
+    }
+}
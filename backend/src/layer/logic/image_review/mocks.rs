@@ -0,0 +1,242 @@
+//! This crate contains mocks of [`ImageReviewDataAccess`], [`ImageStorage`], [`ImageValidation`] and [`AdminNotification`] for testing.
+#![allow(missing_docs)]
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::{
+    interface::{
+        admin_notification::{self, AdminNotification, ImageReportInfo},
+        image_storage::{self, ImageError, ImageStorage},
+        image_validation::{self, ImageValidation, ImageValidationError},
+        persistent_data::{model::PendingImage, ImageReviewDataAccess, Result as DataResult},
+    },
+    util::{ImageResource, Uuid},
+};
+
+pub const IMAGE_ID_TO_REJECT: Uuid = Uuid::from_u128(17u128);
+pub const IMAGE_ID_WITH_API_ERROR: Uuid = Uuid::from_u128(18u128);
+pub const IMAGE_ID_TO_FAIL_LOADING: Uuid = Uuid::from_u128(19u128);
+/// Image whose review with an api error already failed twice.
+pub const IMAGE_ID_FAILED_BEFORE: Uuid = Uuid::from_u128(20u128);
+pub const MEAL_NAME_TO_REJECT: &str = "Stone Soup";
+pub const MEAL_NAME_WITH_API_ERROR: &str = "Error Pudding";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewResult {
+    Accepted,
+    Rejected,
+    Failed,
+    Verified,
+    Hidden,
+    HiddenUnreviewable,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct ImageReviewDatabaseMock {
+    pending: Arc<Mutex<Vec<PendingImage>>>,
+    results: Arc<Mutex<Vec<(Uuid, ReviewResult)>>>,
+}
+
+impl ImageReviewDatabaseMock {
+    /// Creates a mock with the given pending images, which are also returned as images to re-verify.
+    /// The meal name of [`IMAGE_ID_TO_REJECT`], [`IMAGE_ID_WITH_API_ERROR`] and [`IMAGE_ID_FAILED_BEFORE`] lets the [`ImageValidationMock`] fail.
+    #[must_use]
+    pub fn with_pending(ids: &[Uuid]) -> Self {
+        let pending = ids
+            .iter()
+            .map(|&id| PendingImage {
+                id,
+                meal_name: match id {
+                    IMAGE_ID_TO_REJECT => MEAL_NAME_TO_REJECT,
+                    IMAGE_ID_WITH_API_ERROR | IMAGE_ID_FAILED_BEFORE => MEAL_NAME_WITH_API_ERROR,
+                    _ => "Happy Meal",
+                }
+                .into(),
+                review_attempts: if id == IMAGE_ID_FAILED_BEFORE { 2 } else { 0 },
+            })
+            .collect();
+        Self {
+            pending: Arc::new(Mutex::new(pending)),
+            results: Arc::default(),
+        }
+    }
+
+    /// # Panics
+    /// if the mutex is poisoned
+    #[must_use]
+    pub fn get_results(&self) -> Vec<(Uuid, ReviewResult)> {
+        self.results.lock().expect("mutex not poisoned").clone()
+    }
+
+    fn add_result(&self, image_id: Uuid, result: ReviewResult) {
+        self.results
+            .lock()
+            .expect("mutex not poisoned")
+            .push((image_id, result));
+    }
+}
+
+#[async_trait]
+impl ImageReviewDataAccess for ImageReviewDatabaseMock {
+    async fn get_pending_images(&self, _max_attempts: u32) -> DataResult<Vec<PendingImage>> {
+        Ok(self.pending.lock().expect("mutex not poisoned").clone())
+    }
+
+    async fn accept_image(&self, image_id: Uuid) -> DataResult<()> {
+        self.add_result(image_id, ReviewResult::Accepted);
+        Ok(())
+    }
+
+    async fn reject_image(&self, image_id: Uuid, _reason: &str) -> DataResult<()> {
+        self.add_result(image_id, ReviewResult::Rejected);
+        Ok(())
+    }
+
+    async fn add_failed_review_attempt(&self, image_id: Uuid, _error: &str) -> DataResult<()> {
+        self.add_result(image_id, ReviewResult::Failed);
+        Ok(())
+    }
+
+    async fn hide_unreviewable_image(&self, image_id: Uuid, _error: &str) -> DataResult<()> {
+        self.add_result(image_id, ReviewResult::HiddenUnreviewable);
+        Ok(())
+    }
+
+    async fn get_images_to_reverify(
+        &self,
+        _days: u32,
+        limit: u32,
+        _max_attempts: u32,
+    ) -> DataResult<Vec<PendingImage>> {
+        let pending = self.pending.lock().expect("mutex not poisoned");
+        Ok(pending.iter().take(limit as usize).cloned().collect())
+    }
+
+    async fn renew_verification(&self, image_id: Uuid) -> DataResult<()> {
+        self.add_result(image_id, ReviewResult::Verified);
+        Ok(())
+    }
+
+    async fn hide_failing_image(&self, image_id: Uuid, _reason: &str) -> DataResult<()> {
+        self.add_result(image_id, ReviewResult::Hidden);
+        Ok(())
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct ImageReviewStorageMock {
+    deleted: Arc<Mutex<Vec<Uuid>>>,
+}
+
+impl ImageReviewStorageMock {
+    /// # Panics
+    /// if the mutex is poisoned
+    #[must_use]
+    pub fn get_deleted(&self) -> Vec<Uuid> {
+        self.deleted.lock().expect("mutex not poisoned").clone()
+    }
+}
+
+#[async_trait]
+impl ImageStorage for ImageReviewStorageMock {
+    async fn save_image(&self, _id: Uuid, _image: ImageResource) -> image_storage::Result<()> {
+        Ok(())
+    }
+
+    async fn delete_image(&self, id: Uuid) -> image_storage::Result<()> {
+        self.deleted.lock().expect("mutex not poisoned").push(id);
+        Ok(())
+    }
+
+    async fn load_image(&self, id: Uuid) -> image_storage::Result<ImageResource> {
+        if id == IMAGE_ID_TO_FAIL_LOADING {
+            Err(ImageError::IoError(std::io::ErrorKind::NotFound.into()))
+        } else {
+            Ok(ImageResource::default())
+        }
+    }
+
+    async fn list_images(&self) -> image_storage::Result<Vec<Uuid>> {
+        Ok(Vec::new())
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct ImageValidationMock;
+
+#[async_trait]
+impl ImageValidation for ImageValidationMock {
+    async fn validate_image(
+        &self,
+        _image: &ImageResource,
+        meal_name: &str,
+    ) -> image_validation::Result<()> {
+        match meal_name {
+            MEAL_NAME_TO_REJECT => {
+                Err(ImageValidationError::OpenAiRejectionError("no food".into()))
+            }
+            MEAL_NAME_WITH_API_ERROR => Err(ImageValidationError::RestRequestFailed),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct ReviewNotificationMock {
+    review_failed: Arc<Mutex<Vec<Uuid>>>,
+}
+
+impl ReviewNotificationMock {
+    /// # Panics
+    /// if the mutex is poisoned
+    #[must_use]
+    pub fn get_review_failed(&self) -> Vec<Uuid> {
+        self.review_failed
+            .lock()
+            .expect("mutex not poisoned")
+            .clone()
+    }
+}
+
+#[async_trait]
+impl AdminNotification for ReviewNotificationMock {
+    async fn notify_admin_image_report(&self, _info: ImageReportInfo) {}
+
+    async fn notify_admin_image_verified(&self, _image_id: Uuid) -> admin_notification::Result<()> {
+        Ok(())
+    }
+
+    async fn notify_admin_image_deleted(&self, _image_id: Uuid) -> admin_notification::Result<()> {
+        Ok(())
+    }
+
+    async fn notify_admin_image_withdrawn(
+        &self,
+        _image_id: Uuid,
+        _client_id: Uuid,
+    ) -> admin_notification::Result<()> {
+        Ok(())
+    }
+
+    async fn notify_admin_image_review_failed(
+        &self,
+        image_id: Uuid,
+        _error: &str,
+    ) -> admin_notification::Result<()> {
+        self.review_failed
+            .lock()
+            .expect("mutex not poisoned")
+            .push(image_id);
+        Ok(())
+    }
+
+    async fn notify_admin_mass_reports(
+        &self,
+        _client_id: Uuid,
+        _report_count: u32,
+    ) -> admin_notification::Result<()> {
+        Ok(())
+    }
+}
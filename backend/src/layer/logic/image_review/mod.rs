@@ -0,0 +1,3 @@
+//! This component reviews uploaded images in the background before they become visible.
+pub mod image_review_manager;
+pub mod mocks;
@@ -1,5 +1,5 @@
 //! See [`MealPlanManager`].
-use crate::interface::mealplan_management::MensaParseScheduling;
+use crate::interface::mealplan_management::{MensaParseScheduling, ParseRunError, Result};
 use crate::interface::mensa_parser::model::ParseCanteen;
 use crate::interface::mensa_parser::MealplanParser;
 use crate::interface::persistent_data::MealplanManagementDataAccess;
@@ -7,6 +7,7 @@ use crate::layer::logic::mealplan_management::relation_resolver::RelationResolve
 use crate::util::Date;
 use async_trait::async_trait;
 use chrono::Local;
+use metrics::counter;
 use tracing::{error, trace, warn};
 
 /// Class responsible for managing the meal plan update process.
@@ -32,17 +33,50 @@ where
         }
     }
 
-    async fn start_resolving(&self, parse_canteens: Vec<ParseCanteen>, date: Date) {
+    /// Returns whether all canteens got resolved without errors.
+    async fn start_resolving(&self, parse_canteens: Vec<ParseCanteen>, date: Date) -> bool {
+        let mut success = true;
         for parse_canteen in parse_canteens {
             let name = &parse_canteen.name.clone();
             match self.resolver.resolve(parse_canteen, date).await {
                 Ok(()) => trace!(%date, "resolved canteen '{name}' with no errors"),
-                Err(error) => warn!(%date, "resolved canteen '{name}' with errors: {error}"),
+                Err(error) => {
+                    warn!(%date, "resolved canteen '{name}' with errors: {error}");
+                    success = false;
+                }
+            }
+        }
+        success
+    }
+
+    /// Resolves the canteens like [`Self::start_resolving`] and records the outcome of the parse run.
+    /// Returns whether all canteens got resolved without errors.
+    async fn finish_parse_run(
+        &self,
+        kind: &'static str,
+        parse_canteens: Vec<(Date, Vec<ParseCanteen>)>,
+    ) -> bool {
+        let mut success = true;
+        for (date, canteens) in parse_canteens {
+            success &= self.start_resolving(canteens, date).await;
+        }
+        let outcome = if success { "success" } else { "resolve_error" };
+        record_parse_run(kind, outcome);
+
+        // single canteens do not tell whether parsing works in general
+        if success && kind != "canteen" {
+            if let Err(e) = self.resolver.db.mark_parse_succeeded().await {
+                warn!("Could not save successful parse run: {e}");
             }
         }
+        success
     }
 }
 
+fn record_parse_run(kind: &'static str, outcome: &'static str) {
+    counter!("mealplan_parse_runs_total", "kind" => kind, "outcome" => outcome).increment(1);
+}
+
 #[async_trait]
 impl<DataAccess, Parser> MensaParseScheduling for MealPlanManager<Parser, DataAccess>
 where
@@ -57,10 +91,12 @@ where
         let today = Local::now().date_naive();
         match self.parser.parse(today).await {
             Ok(parse_canteens) => {
-                self.start_resolving(parse_canteens, today).await;
+                self.finish_parse_run("update", vec![(today, parse_canteens)])
+                    .await;
             }
             Err(error) => {
                 error!(%today, "canteens parsed with error and can't be resolved: {error}");
+                record_parse_run("update", "parse_error");
             }
         }
     }
@@ -72,23 +108,52 @@ where
     async fn start_full_parsing(&self) {
         match self.parser.parse_all().await {
             Ok(parse_tuples) => {
-                for (date, parse_canteens) in parse_tuples {
-                    self.start_resolving(parse_canteens, date).await;
-                }
+                self.finish_parse_run("full", parse_tuples).await;
             }
             Err(error) => {
                 error!("canteens parsed with error and can't be resolved: {error}");
+                record_parse_run("full", "parse_error");
             }
         }
     }
+
+    /// This method starts the parsing procedure for the meal plans of the given date, possibly only of a single canteen.<br>
+    /// Unlike the scheduled parsing, errors get returned to the caller in addition to being logged.
+    async fn start_date_parsing(&self, date: Date, canteen: Option<&str>) -> Result<()> {
+        let (kind, parsed) = match canteen {
+            Some(canteen) => ("canteen", self.parser.parse_canteen(date, canteen).await),
+            None => ("date", self.parser.parse(date).await),
+        };
+        let parse_canteens = parsed.map_err(|error| {
+            error!(%date, "canteens parsed with error and can't be resolved: {error}");
+            record_parse_run(kind, "parse_error");
+            error
+        })?;
+
+        if self
+            .finish_parse_run(kind, vec![(date, parse_canteens)])
+            .await
+        {
+            Ok(())
+        } else {
+            Err(ParseRunError::ResolveError)
+        }
+    }
+
+    async fn is_known_canteen(&self, canteen: &str) -> bool {
+        self.parser.is_known_canteen(canteen).await
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::interface::mealplan_management::MensaParseScheduling;
+    use crate::interface::mealplan_management::{MensaParseScheduling, ParseRunError};
     use crate::layer::logic::mealplan_management::meal_plan_manager::MealPlanManager;
-    use crate::layer::logic::mealplan_management::test::meal_plan_parser_mock::MealPlanParserMock;
+    use crate::layer::logic::mealplan_management::test::meal_plan_parser_mock::{
+        MealPlanParserMock, UNKNOWN_CANTEEN,
+    };
     use crate::layer::logic::mealplan_management::test::mealplan_management_database_mock::MealplanManagementDatabaseMock;
+    use crate::util::Date;
 
     #[tokio::test]
     async fn test_valid_start_update_parsing() {
@@ -101,4 +166,21 @@ mod test {
         let manager = MealPlanManager::new(MealplanManagementDatabaseMock, MealPlanParserMock);
         manager.start_full_parsing().await;
     }
+
+    #[tokio::test]
+    async fn test_start_date_parsing() {
+        let manager = MealPlanManager::new(MealplanManagementDatabaseMock, MealPlanParserMock);
+        let date = Date::default();
+        assert!(manager.start_date_parsing(date, None).await.is_ok());
+        assert!(manager
+            .start_date_parsing(date, Some("mensa_adenauerring"))
+            .await
+            .is_ok());
+        assert!(matches!(
+            manager
+                .start_date_parsing(date, Some(UNKNOWN_CANTEEN))
+                .await,
+            Err(ParseRunError::ParseError(_))
+        ));
+    }
 }
@@ -10,7 +10,7 @@ pub struct RelationResolver<DataAccess>
 where
     DataAccess: MealplanManagementDataAccess,
 {
-    db: DataAccess,
+    pub(super) db: DataAccess,
 }
 
 impl<DataAccess> RelationResolver<DataAccess>
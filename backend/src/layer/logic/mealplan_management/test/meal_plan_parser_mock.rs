@@ -74,6 +74,9 @@ fn get_canteens(canteen_amount: u32, line_amount: u32, dish_amount: u32) -> Vec<
     canteens
 }
 
+/// Canteen the mock fails to parse.
+pub const UNKNOWN_CANTEEN: &str = "unknown";
+
 /// Mock of [`MealplanParser`].
 pub struct MealPlanParserMock;
 
@@ -83,8 +86,23 @@ impl MealplanParser for MealPlanParserMock {
     async fn parse(&self, _day: Date) -> Result<Vec<ParseCanteen>, ParseError> {
         Ok(get_canteens(5, 10, 2))
     }
+    /// Initiate a parse procedure for a single canteen. Returns a canteen struct containing mealplan data of the given date.
+    async fn parse_canteen(
+        &self,
+        _day: Date,
+        canteen: &str,
+    ) -> Result<Vec<ParseCanteen>, ParseError> {
+        if canteen == UNKNOWN_CANTEEN {
+            return Err(ParseError::UnknownCanteen(canteen.into()));
+        }
+        Ok(get_canteens(2, 10, 2))
+    }
     /// Initiate a parse procedure. Returns a tuple containing mealplan data of the next four weeks. The tuple contains a canteen struct with the related date.
     async fn parse_all(&self) -> Result<Vec<(Date, Vec<ParseCanteen>)>, ParseError> {
         Ok(vec![(Date::default(), get_canteens(5, 10, 2))])
     }
+    /// Returns whether the canteen is not [`UNKNOWN_CANTEEN`].
+    async fn is_known_canteen(&self, canteen: &str) -> bool {
+        canteen != UNKNOWN_CANTEEN
+    }
 }
@@ -132,4 +132,8 @@ impl MealplanManagementDataAccess for MealplanManagementDatabaseMock {
     ) -> Result<()> {
         Ok(())
     }
+
+    async fn mark_parse_succeeded(&self) -> Result<()> {
+        Ok(())
+    }
 }
@@ -2,4 +2,7 @@
 //! It is completely independent of the implementations of the other layers and does not interact with the outside world.
 
 pub mod api_command;
+pub mod digest;
+pub mod image_review;
 pub mod mealplan_management;
+pub mod retention;
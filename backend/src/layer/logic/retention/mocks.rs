@@ -0,0 +1,186 @@
+//! This crate contains mocks of [`RetentionDataAccess`] and [`ImageStorage`] for testing.
+#![allow(missing_docs)]
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::{
+    interface::{
+        image_storage::{self, ImageError, ImageStorage},
+        persistent_data::{model::StoredImage, Result as DataResult, RetentionDataAccess},
+    },
+    util::{ImageResource, Uuid},
+};
+
+pub const IMAGE_ID_WITHOUT_FILE: Uuid = Uuid::from_u128(21u128);
+pub const IMAGE_ID_TO_FAIL_DELETING: Uuid = Uuid::from_u128(22u128);
+
+pub const REMOVED_COUNT: u64 = 3;
+
+#[derive(Default, Debug, Clone)]
+pub struct RetentionDatabaseMock {
+    expired: Vec<Uuid>,
+    hidden: Vec<Uuid>,
+    stored: Vec<StoredImage>,
+    grace_days: Arc<Mutex<Option<u32>>>,
+    purged: Arc<Mutex<Vec<Uuid>>>,
+    deleted: Arc<Mutex<Vec<Uuid>>>,
+    committed: Arc<Mutex<u64>>,
+}
+
+impl RetentionDatabaseMock {
+    #[must_use]
+    pub fn with_expired(ids: &[Uuid]) -> Self {
+        Self {
+            expired: ids.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub fn with_hidden(mut self, ids: &[Uuid]) -> Self {
+        self.hidden = ids.to_vec();
+        self
+    }
+
+    #[must_use]
+    pub fn with_stored(images: &[StoredImage]) -> Self {
+        Self {
+            stored: images.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    /// # Panics
+    /// if the mutex is poisoned
+    #[must_use]
+    pub fn get_deleted(&self) -> Vec<Uuid> {
+        self.deleted.lock().expect("mutex not poisoned").clone()
+    }
+
+    /// Number of entries removed by the purge requests which were not dry runs.
+    ///
+    /// # Panics
+    /// if the mutex is poisoned
+    #[must_use]
+    pub fn get_committed(&self) -> u64 {
+        *self.committed.lock().expect("mutex not poisoned")
+    }
+
+    fn purge(&self, dry_run: bool) -> u64 {
+        if !dry_run {
+            *self.committed.lock().expect("mutex not poisoned") += REMOVED_COUNT;
+        }
+        REMOVED_COUNT
+    }
+
+    /// # Panics
+    /// if the mutex is poisoned
+    #[must_use]
+    pub fn get_grace_days(&self) -> Option<u32> {
+        *self.grace_days.lock().expect("mutex not poisoned")
+    }
+
+    /// # Panics
+    /// if the mutex is poisoned
+    #[must_use]
+    pub fn get_purged(&self) -> Vec<Uuid> {
+        self.purged.lock().expect("mutex not poisoned").clone()
+    }
+}
+
+#[async_trait]
+impl RetentionDataAccess for RetentionDatabaseMock {
+    async fn get_expired_deleted_images(&self, grace_days: u32) -> DataResult<Vec<Uuid>> {
+        *self.grace_days.lock().expect("mutex not poisoned") = Some(grace_days);
+        Ok(self.expired.clone())
+    }
+
+    async fn purge_image(&self, image_id: Uuid) -> DataResult<()> {
+        self.purged
+            .lock()
+            .expect("mutex not poisoned")
+            .push(image_id);
+        Ok(())
+    }
+
+    async fn purge_old_client_activity(&self, dry_run: bool) -> DataResult<u64> {
+        Ok(self.purge(dry_run))
+    }
+
+    async fn purge_old_reports(&self, _days: u32, dry_run: bool) -> DataResult<u64> {
+        Ok(self.purge(dry_run))
+    }
+
+    async fn purge_unserved_foods(&self, _days: u32, dry_run: bool) -> DataResult<u64> {
+        Ok(self.purge(dry_run))
+    }
+
+    async fn get_long_hidden_images(&self, _days: u32) -> DataResult<Vec<Uuid>> {
+        Ok(self.hidden.clone())
+    }
+
+    async fn get_stored_images(&self) -> DataResult<Vec<StoredImage>> {
+        Ok(self.stored.clone())
+    }
+
+    async fn delete_image(&self, image_id: Uuid, _reason: &str) -> DataResult<()> {
+        self.deleted
+            .lock()
+            .expect("mutex not poisoned")
+            .push(image_id);
+        Ok(())
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct RetentionStorageMock {
+    files: Vec<Uuid>,
+    deleted: Arc<Mutex<Vec<Uuid>>>,
+}
+
+impl RetentionStorageMock {
+    #[must_use]
+    pub fn with_files(ids: &[Uuid]) -> Self {
+        Self {
+            files: ids.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    /// # Panics
+    /// if the mutex is poisoned
+    #[must_use]
+    pub fn get_deleted(&self) -> Vec<Uuid> {
+        self.deleted.lock().expect("mutex not poisoned").clone()
+    }
+}
+
+#[async_trait]
+impl ImageStorage for RetentionStorageMock {
+    async fn save_image(&self, _id: Uuid, _image: ImageResource) -> image_storage::Result<()> {
+        Ok(())
+    }
+
+    async fn delete_image(&self, id: Uuid) -> image_storage::Result<()> {
+        match id {
+            IMAGE_ID_WITHOUT_FILE => Err(ImageError::IoError(std::io::ErrorKind::NotFound.into())),
+            IMAGE_ID_TO_FAIL_DELETING => Err(ImageError::IoError(
+                std::io::ErrorKind::PermissionDenied.into(),
+            )),
+            _ => {
+                self.deleted.lock().expect("mutex not poisoned").push(id);
+                Ok(())
+            }
+        }
+    }
+
+    async fn load_image(&self, _id: Uuid) -> image_storage::Result<ImageResource> {
+        Ok(ImageResource::default())
+    }
+
+    async fn list_images(&self) -> image_storage::Result<Vec<Uuid>> {
+        Ok(self.files.clone())
+    }
+}
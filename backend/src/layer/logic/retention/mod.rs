@@ -0,0 +1,3 @@
+//! This component removes data which is no longer needed, like deleted images after their grace period.
+pub mod mocks;
+pub mod retention_manager;
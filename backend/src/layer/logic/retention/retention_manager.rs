@@ -0,0 +1,382 @@
+//! See [`RetentionManager`].
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use chrono::Local;
+use tracing::{debug, info, warn};
+
+use crate::{
+    interface::{
+        image_storage::{ImageError, ImageStorage},
+        persistent_data::{Result as DataResult, RetentionDataAccess},
+        retention::{ReconciliationReport, RetentionReport, RetentionScheduling},
+    },
+    util::Uuid,
+};
+
+/// Structure containing all information necessary to construct a [`RetentionManager`].
+#[derive(Debug, Clone)]
+pub struct RetentionInfo {
+    /// Number of days a deleted image can be restored before it gets removed permanently.
+    pub deleted_image_grace_days: u32,
+    /// Number of days a hidden or rejected image is kept before it gets deleted.
+    pub hidden_image_days: u32,
+    /// Number of days image reports are kept.
+    pub report_days: u32,
+    /// Number of days meals and sides without images are kept after they got served the last time.
+    pub unserved_food_days: u32,
+    /// Whether to only report what would be removed, without removing anything.
+    pub dry_run: bool,
+}
+
+/// Class responsible for removing data which is no longer needed.
+pub struct RetentionManager<DataAccess, Storage>
+where
+    DataAccess: RetentionDataAccess,
+    Storage: ImageStorage,
+{
+    retention_data: DataAccess,
+    image_storage: Storage,
+    info: RetentionInfo,
+}
+
+impl<DataAccess, Storage> RetentionManager<DataAccess, Storage>
+where
+    DataAccess: RetentionDataAccess,
+    Storage: ImageStorage,
+{
+    /// Creates a new instance removing data from `retention_data` and images from `image_storage`.
+    pub const fn new(
+        info: RetentionInfo,
+        retention_data: DataAccess,
+        image_storage: Storage,
+    ) -> Self {
+        Self {
+            retention_data,
+            image_storage,
+            info,
+        }
+    }
+
+    /// Returns whether the image got purged.
+    async fn purge_image(&self, image_id: Uuid) -> bool {
+        match self.image_storage.delete_image(image_id).await {
+            Ok(()) => (),
+            // file already gone, e.g. removed by hand
+            Err(ImageError::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => {
+                warn!("Could not delete file of image {image_id}, retrying later: {e}");
+                return false;
+            }
+        }
+
+        if let Err(e) = self.retention_data.purge_image(image_id).await {
+            warn!("Could not purge deleted image {image_id}: {e}");
+            return false;
+        }
+        true
+    }
+}
+
+#[async_trait]
+impl<DataAccess, Storage> RetentionScheduling for RetentionManager<DataAccess, Storage>
+where
+    DataAccess: RetentionDataAccess,
+    Storage: ImageStorage,
+{
+    async fn start_retention(&self) -> RetentionReport {
+        let dry_run = self.info.dry_run;
+        let report = RetentionReport {
+            dry_run,
+            purged_images: self.purge_deleted_images().await,
+            deleted_hidden_images: self.delete_long_hidden_images().await,
+            removed_reports: count_removed(
+                self.retention_data
+                    .purge_old_reports(self.info.report_days, dry_run)
+                    .await,
+                "outdated reports",
+            ),
+            removed_client_activity: count_removed(
+                self.retention_data.purge_old_client_activity(dry_run).await,
+                "outdated client activity",
+            ),
+            removed_foods: count_removed(
+                self.retention_data
+                    .purge_unserved_foods(self.info.unserved_food_days, dry_run)
+                    .await,
+                "foods no longer served",
+            ),
+        };
+        info!("{report}");
+        report
+    }
+
+    async fn start_image_reconciliation(&self) -> ReconciliationReport {
+        let mut report = ReconciliationReport {
+            dry_run: self.info.dry_run,
+            ..Default::default()
+        };
+
+        // files get listed first as images get added to the database before their file gets saved,
+        // so every listed file of an uploaded image is already known to the database
+        let files = match self.image_storage.list_images().await {
+            Ok(files) => files.into_iter().collect::<HashSet<_>>(),
+            Err(e) => {
+                warn!("Could not list stored image files: {e}");
+                return report;
+            }
+        };
+        let images = match self.retention_data.get_stored_images().await {
+            Ok(images) => images,
+            Err(e) => {
+                warn!("Could not load stored images: {e}");
+                return report;
+            }
+        };
+
+        let known = images.iter().map(|image| image.id).collect::<HashSet<_>>();
+        report.orphaned_files = files.difference(&known).copied().collect();
+        report.orphaned_files.sort_unstable();
+        // files of images uploaded right now may not be saved yet
+        let today = Local::now().date_naive();
+        report.missing_files = images
+            .iter()
+            .filter(|image| !image.deleted && image.upload_date < today)
+            .filter(|image| !files.contains(&image.id))
+            .map(|image| image.id)
+            .collect();
+
+        if !report.dry_run {
+            self.remove_orphaned_files(&mut report.orphaned_files).await;
+            self.delete_images_without_file(&mut report.missing_files)
+                .await;
+        }
+
+        info!("{report}");
+        debug!(
+            "Files without image: {:?}, images without file: {:?}",
+            report.orphaned_files, report.missing_files
+        );
+        report
+    }
+}
+
+impl<DataAccess, Storage> RetentionManager<DataAccess, Storage>
+where
+    DataAccess: RetentionDataAccess,
+    Storage: ImageStorage,
+{
+    async fn purge_deleted_images(&self) -> usize {
+        let expired = match self
+            .retention_data
+            .get_expired_deleted_images(self.info.deleted_image_grace_days)
+            .await
+        {
+            Ok(expired) => expired,
+            Err(e) => {
+                warn!("Could not load deleted images: {e}");
+                return 0;
+            }
+        };
+
+        if self.info.dry_run {
+            return expired.len();
+        }
+
+        if !expired.is_empty() {
+            info!("Purging {} deleted images.", expired.len());
+        }
+
+        let mut purged = 0;
+        for image_id in expired {
+            if self.purge_image(image_id).await {
+                purged += 1;
+            }
+        }
+        purged
+    }
+
+    async fn delete_long_hidden_images(&self) -> usize {
+        let hidden = match self
+            .retention_data
+            .get_long_hidden_images(self.info.hidden_image_days)
+            .await
+        {
+            Ok(hidden) => hidden,
+            Err(e) => {
+                warn!("Could not load hidden images: {e}");
+                return 0;
+            }
+        };
+
+        if self.info.dry_run {
+            return hidden.len();
+        }
+
+        let reason = format!("hidden for {} days", self.info.hidden_image_days);
+        let mut deleted = 0;
+        for image_id in hidden {
+            match self.retention_data.delete_image(image_id, &reason).await {
+                Ok(()) => deleted += 1,
+                Err(e) => warn!("Could not delete hidden image {image_id}: {e}"),
+            }
+        }
+        deleted
+    }
+
+    /// Keeps only the files which actually got removed.
+    async fn remove_orphaned_files(&self, orphaned_files: &mut Vec<Uuid>) {
+        let mut removed = Vec::with_capacity(orphaned_files.len());
+        for &image_id in orphaned_files.iter() {
+            match self.image_storage.delete_image(image_id).await {
+                Ok(()) => removed.push(image_id),
+                Err(e) => warn!("Could not remove file {image_id} without image: {e}"),
+            }
+        }
+        *orphaned_files = removed;
+    }
+
+    /// Keeps only the images which actually got deleted.
+    async fn delete_images_without_file(&self, missing_files: &mut Vec<Uuid>) {
+        let mut deleted = Vec::with_capacity(missing_files.len());
+        for &image_id in missing_files.iter() {
+            match self
+                .retention_data
+                .delete_image(image_id, "image file missing")
+                .await
+            {
+                Ok(()) => deleted.push(image_id),
+                Err(e) => warn!("Could not delete image {image_id} without file: {e}"),
+            }
+        }
+        *missing_files = deleted;
+    }
+}
+
+fn count_removed(result: DataResult<u64>, removed: &str) -> u64 {
+    result.unwrap_or_else(|e| {
+        warn!("Could not remove {removed}: {e}");
+        0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, Local};
+
+    use crate::interface::persistent_data::model::StoredImage;
+    use crate::interface::retention::{RetentionReport, RetentionScheduling};
+    use crate::layer::logic::retention::mocks::{
+        RetentionDatabaseMock, RetentionStorageMock, IMAGE_ID_TO_FAIL_DELETING,
+        IMAGE_ID_WITHOUT_FILE, REMOVED_COUNT,
+    };
+    use crate::util::Uuid;
+
+    use super::{RetentionInfo, RetentionManager};
+
+    const fn info(dry_run: bool) -> RetentionInfo {
+        RetentionInfo {
+            deleted_image_grace_days: 14,
+            hidden_image_days: 90,
+            report_days: 365,
+            unserved_food_days: 730,
+            dry_run,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_retention() {
+        let expired = Uuid::from_u128(1);
+        let hidden = Uuid::from_u128(2);
+        let data = RetentionDatabaseMock::with_expired(&[
+            expired,
+            IMAGE_ID_WITHOUT_FILE,
+            IMAGE_ID_TO_FAIL_DELETING,
+        ])
+        .with_hidden(&[hidden]);
+        let storage = RetentionStorageMock::default();
+        let manager = RetentionManager::new(info(false), data.clone(), storage.clone());
+
+        let report = manager.start_retention().await;
+
+        assert_eq!(data.get_grace_days(), Some(14));
+        // images whose file could not be deleted are kept to retry later
+        assert_eq!(data.get_purged(), vec![expired, IMAGE_ID_WITHOUT_FILE]);
+        assert_eq!(storage.get_deleted(), vec![expired]);
+        assert_eq!(data.get_deleted(), vec![hidden]);
+        assert_eq!(
+            report,
+            RetentionReport {
+                dry_run: false,
+                purged_images: 2,
+                deleted_hidden_images: 1,
+                removed_reports: REMOVED_COUNT,
+                removed_client_activity: REMOVED_COUNT,
+                removed_foods: REMOVED_COUNT,
+            }
+        );
+        assert_eq!(data.get_committed(), 3 * REMOVED_COUNT);
+    }
+
+    #[tokio::test]
+    async fn test_retention_dry_run() {
+        let data = RetentionDatabaseMock::with_expired(&[Uuid::from_u128(1)])
+            .with_hidden(&[Uuid::from_u128(2)]);
+        let storage = RetentionStorageMock::default();
+        let manager = RetentionManager::new(info(true), data.clone(), storage.clone());
+
+        let report = manager.start_retention().await;
+
+        assert!(report.dry_run);
+        assert_eq!(report.purged_images, 1);
+        assert_eq!(report.deleted_hidden_images, 1);
+        assert_eq!(report.removed_foods, REMOVED_COUNT);
+        assert!(data.get_purged().is_empty());
+        assert!(data.get_deleted().is_empty());
+        assert!(storage.get_deleted().is_empty());
+        assert_eq!(data.get_committed(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_start_image_reconciliation() {
+        let today = Local::now().date_naive();
+        let yesterday = today - Days::new(1);
+        let stored = |id: u128, upload_date, deleted| StoredImage {
+            id: Uuid::from_u128(id),
+            upload_date,
+            deleted,
+        };
+        let data = RetentionDatabaseMock::with_stored(&[
+            stored(1, yesterday, false),
+            // missing file
+            stored(2, yesterday, false),
+            // file may not be saved yet
+            stored(3, today, false),
+            // already deleted
+            stored(4, yesterday, true),
+        ]);
+        let orphaned = Uuid::from_u128(5);
+        let storage = RetentionStorageMock::with_files(&[
+            Uuid::from_u128(1),
+            orphaned,
+            IMAGE_ID_TO_FAIL_DELETING,
+        ]);
+
+        let manager = RetentionManager::new(info(true), data.clone(), storage.clone());
+        let report = manager.start_image_reconciliation().await;
+        assert_eq!(report.missing_files, vec![Uuid::from_u128(2)]);
+        assert_eq!(
+            report.orphaned_files,
+            vec![orphaned, IMAGE_ID_TO_FAIL_DELETING]
+        );
+        assert!(storage.get_deleted().is_empty());
+        assert!(data.get_deleted().is_empty());
+
+        let manager = RetentionManager::new(info(false), data.clone(), storage.clone());
+        let report = manager.start_image_reconciliation().await;
+        // files which could not be removed are not reported
+        assert_eq!(report.orphaned_files, vec![orphaned]);
+        assert_eq!(storage.get_deleted(), vec![orphaned]);
+        assert_eq!(data.get_deleted(), vec![Uuid::from_u128(2)]);
+    }
+}
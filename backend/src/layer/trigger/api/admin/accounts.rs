@@ -0,0 +1,273 @@
+//! JSON endpoints of the admin api for logging in and managing the accounts of administrators.
+
+use axum::{
+    debug_handler,
+    extract::{Path, Query, State},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use axum_extra::headers::{Cookie, HeaderMapExt};
+use chrono::{DateTime, Utc};
+use hyper::{header::SET_COOKIE, HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    interface::persistent_data::{model::AdminAccount, DataError},
+    util::{hash_password, hash_token, AdminRole, Date},
+};
+
+use super::{
+    auth::{session_cookie, session_token, unauthenticated, AdminAuth, AdminUser, ADMIN_USER},
+    oidc::{state_cookie, OidcError, STATE_COOKIE},
+};
+
+/// Endpoints for logging in, which do not require authentication.
+pub(super) fn login_router(auth: AdminAuth) -> Router<()> {
+    Router::new()
+        .route("/login", post(login))
+        .route("/login/oidc", get(start_oidc_login))
+        .route("/login/oidc/callback", get(complete_oidc_login))
+        .with_state(auth)
+}
+
+/// Endpoints for the current session, available to all administrators.
+pub(super) fn session_router(auth: AdminAuth) -> Router<()> {
+    Router::new()
+        .route("/me", get(me))
+        .route("/logout", post(logout))
+        .with_state(auth)
+}
+
+/// Endpoints for managing accounts, only available to operators.
+pub(super) fn account_router(auth: AdminAuth) -> Router<()> {
+    Router::new()
+        .route("/accounts", get(list_accounts).post(save_account))
+        .route("/accounts/{username}/delete", post(delete_account))
+        .with_state(auth)
+}
+
+impl IntoResponse for OidcError {
+    fn into_response(self) -> Response {
+        let error = self.to_string();
+        warn!("On openid connect login: {error}");
+        let status = match self {
+            Self::Request(_) | Self::Url(_) => StatusCode::BAD_GATEWAY,
+            Self::UnknownState | Self::CodeRejected | Self::InvalidToken(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            Self::NoRole(_) => StatusCode::FORBIDDEN,
+            Self::NameTaken(_) => StatusCode::CONFLICT,
+        };
+        (status, error).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CallbackQuery {
+    code: String,
+    state: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionJson {
+    token: String,
+    expires_at: DateTime<Utc>,
+    username: String,
+    role: AdminRole,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountRequest {
+    username: String,
+    password: Option<String>,
+    role: AdminRole,
+}
+
+#[derive(Debug, Serialize)]
+struct AccountJson {
+    username: String,
+    role: AdminRole,
+    password_login: bool,
+    created_date: Date,
+}
+
+impl From<AdminAccount> for AccountJson {
+    fn from(value: AdminAccount) -> Self {
+        Self {
+            username: value.username,
+            role: value.role,
+            password_login: value.password_hash.is_some(),
+            created_date: value.created_date,
+        }
+    }
+}
+
+/// Creates a session and returns its token, which is also set as cookie.
+async fn new_session(auth: &AdminAuth, user: AdminUser) -> Result<Response, DataError> {
+    let (token, expires_at) = auth.create_session(&user.username).await?;
+    info!(
+        "Administrator {} logged in as {}.",
+        user.username, user.role
+    );
+    let cookie = session_cookie(Some(&token), auth.session_duration);
+    let session = SessionJson {
+        token,
+        expires_at,
+        username: user.username,
+        role: user.role,
+    };
+    Ok(([(SET_COOKIE, cookie)], Json(session)).into_response())
+}
+
+#[debug_handler]
+async fn login(
+    State(auth): State<AdminAuth>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Response, DataError> {
+    // the shared key has no account to attach sessions to
+    if request.username == ADMIN_USER {
+        return Ok(unauthenticated());
+    }
+    match auth
+        .check_password(&request.username, &request.password)
+        .await?
+    {
+        Some(user) => new_session(&auth, user).await,
+        None => Ok(unauthenticated()),
+    }
+}
+
+#[debug_handler]
+async fn start_oidc_login(State(auth): State<AdminAuth>) -> Result<Response, OidcError> {
+    let Some(oidc) = &auth.oidc else {
+        return Ok((
+            StatusCode::NOT_FOUND,
+            "Openid connect login is not configured.",
+        )
+            .into_response());
+    };
+    let (url, state) = oidc.start_login().await?;
+    Ok((
+        [(SET_COOKIE, state_cookie(Some(&state)))],
+        Redirect::to(url.as_str()),
+    )
+        .into_response())
+}
+
+#[debug_handler]
+async fn complete_oidc_login(
+    State(auth): State<AdminAuth>,
+    Query(query): Query<CallbackQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Response> {
+    let Some(oidc) = &auth.oidc else {
+        return Ok((
+            StatusCode::NOT_FOUND,
+            "Openid connect login is not configured.",
+        )
+            .into_response());
+    };
+    let cookie = headers.typed_get::<Cookie>();
+    let cookie_state = cookie.as_ref().and_then(|c| c.get(STATE_COOKIE));
+    let user = oidc
+        .complete_login(&query.code, &query.state, cookie_state)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    if user.username == ADMIN_USER {
+        return Err(OidcError::NameTaken(user.username).into_response());
+    }
+    // the account belongs to the subject, the role is managed by the provider
+    let account = auth
+        .admin_data
+        .save_oidc_account(&user.issuer, &user.subject, &user.username, user.role)
+        .await
+        .map_err(IntoResponse::into_response)?
+        .ok_or_else(|| OidcError::NameTaken(user.username).into_response())?;
+    let mut response = new_session(&auth, account.into())
+        .await
+        .map_err(IntoResponse::into_response)?;
+    response
+        .headers_mut()
+        .append(SET_COOKIE, state_cookie(None));
+    Ok(response)
+}
+
+#[debug_handler]
+async fn me(Extension(user): Extension<AdminUser>) -> Json<AdminUser> {
+    Json(user)
+}
+
+#[debug_handler]
+async fn logout(
+    State(auth): State<AdminAuth>,
+    Extension(user): Extension<AdminUser>,
+    headers: HeaderMap,
+) -> Result<Response, DataError> {
+    if let Some(token) = session_token(&headers) {
+        auth.admin_data
+            .delete_admin_session(&hash_token(&token))
+            .await?;
+    }
+    info!("Administrator {} logged out.", user.username);
+    let cookie = session_cookie(None, auth.session_duration);
+    Ok(([(SET_COOKIE, cookie)], "Successfully logged out").into_response())
+}
+
+#[debug_handler]
+async fn list_accounts(State(auth): State<AdminAuth>) -> Result<Json<Vec<AccountJson>>, DataError> {
+    let accounts = auth.admin_data.get_admin_accounts().await?;
+    Ok(Json(accounts.into_iter().map(Into::into).collect()))
+}
+
+#[debug_handler]
+async fn save_account(
+    State(auth): State<AdminAuth>,
+    Extension(user): Extension<AdminUser>,
+    Json(request): Json<AccountRequest>,
+) -> Result<Response, DataError> {
+    if request.username == ADMIN_USER || request.username.trim().is_empty() {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            format!("Invalid username {:?}", request.username),
+        )
+            .into_response());
+    }
+    let password_hash = request.password.as_deref().map(hash_password);
+    auth.admin_data
+        .save_admin_account(
+            &request.username,
+            password_hash.as_deref(),
+            request.role,
+            &user.username,
+        )
+        .await?;
+    info!(
+        "Account of administrator {} with role {} got saved by {}.",
+        request.username, request.role, user.username
+    );
+    Ok(format!("Successfully saved account {}", request.username).into_response())
+}
+
+#[debug_handler]
+async fn delete_account(
+    State(auth): State<AdminAuth>,
+    Extension(user): Extension<AdminUser>,
+    Path(username): Path<String>,
+) -> Result<String, DataError> {
+    auth.admin_data
+        .delete_admin_account(&username, &user.username)
+        .await?;
+    info!(
+        "Account of administrator {username} got deleted by {}.",
+        user.username
+    );
+    Ok(format!("Successfully deleted account {username}"))
+}
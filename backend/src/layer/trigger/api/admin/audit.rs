@@ -0,0 +1,146 @@
+//! JSON endpoint of the admin api for browsing the audit log.
+
+use axum::{
+    debug_handler,
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    interface::persistent_data::{
+        model::{AuditEntry, AuditFilter},
+        DataError,
+    },
+    util::{AuditAction, Date, Uuid},
+};
+
+use super::ArcAdminData;
+
+/// Number of entries returned if no limit is given.
+const DEFAULT_LIMIT: u32 = 100;
+/// Maximum number of entries returned at once.
+const MAX_LIMIT: u32 = 1000;
+
+pub(super) fn audit_router(admin_data: ArcAdminData) -> Router<()> {
+    Router::new()
+        .route("/audit_log", get(list_audit_log))
+        .with_state(admin_data)
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AuditQuery {
+    actor: Option<String>,
+    action: Option<AuditAction>,
+    target_id: Option<Uuid>,
+    from: Option<Date>,
+    to: Option<Date>,
+    limit: Option<u32>,
+    #[serde(default)]
+    offset: u32,
+}
+
+impl From<AuditQuery> for AuditFilter {
+    fn from(value: AuditQuery) -> Self {
+        Self {
+            actor: value.actor,
+            action: value.action,
+            target_id: value.target_id,
+            from: value.from,
+            to: value.to,
+            limit: value.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT),
+            offset: value.offset,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AuditEntryJson {
+    id: i64,
+    time: DateTime<Utc>,
+    actor: String,
+    action: AuditAction,
+    target_id: Uuid,
+    reason: Option<String>,
+}
+
+impl From<AuditEntry> for AuditEntryJson {
+    fn from(value: AuditEntry) -> Self {
+        Self {
+            id: value.id,
+            time: value.time,
+            actor: value.actor,
+            action: value.action,
+            target_id: value.target_id,
+            reason: value.reason,
+        }
+    }
+}
+
+#[debug_handler]
+async fn list_audit_log(
+    State(admin_data): State<ArcAdminData>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditEntryJson>>, DataError> {
+    let entries = admin_data.get_audit_log(&query.into()).await?;
+    Ok(Json(entries.into_iter().map(Into::into).collect()))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::sync::Arc;
+
+    use reqwest::StatusCode;
+    use serde_json::Value;
+
+    use super::audit_router;
+    use crate::layer::trigger::api::{mock::AdminDataMock, serve};
+
+    async fn start_router() -> String {
+        serve(audit_router(Arc::new(AdminDataMock))).await
+    }
+
+    #[tokio::test]
+    async fn test_list_audit_log() {
+        let url = start_router().await;
+
+        let entries: Value = reqwest::get(format!("{url}/audit_log"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(entries[0]["actor"], "admin");
+        assert_eq!(entries[0]["action"], "DELETE_IMAGE");
+
+        let target = "76b904fe-d0f1-4122-8832-d0e21acab86d";
+        let entries: Value = reqwest::get(format!(
+            "{url}/audit_log?actor=moderator&action=BAN_CLIENT&target_id={target}&from=2024-01-01"
+        ))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+        assert_eq!(entries[0]["actor"], "moderator");
+        assert_eq!(entries[0]["action"], "BAN_CLIENT");
+        assert_eq!(entries[0]["target_id"], target);
+
+        let entries: Value = reqwest::get(format!("{url}/audit_log?limit=0"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(entries, Value::Array(vec![]));
+
+        let status = reqwest::get(format!("{url}/audit_log?action=UNKNOWN"))
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
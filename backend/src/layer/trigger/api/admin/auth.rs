@@ -0,0 +1,202 @@
+//! Authentication of administrators using the admin api, see [`admin_auth_middleware`].
+
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use axum_extra::headers::{
+    authorization::{Basic, Bearer},
+    Authorization, Cookie, HeaderMapExt,
+};
+use chrono::{DateTime, Utc};
+use hyper::{header::WWW_AUTHENTICATE, HeaderMap, StatusCode};
+use serde::Serialize;
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+use crate::{
+    interface::persistent_data::{model::AdminAccount, DataError, Result as DataResult},
+    util::{generate_api_key, hash_token, verify_password, AdminRole},
+};
+
+use super::{oidc::OidcClient, ArcAdminData};
+
+/// User of the shared `ADMIN_KEY`, which has no account.
+pub(super) const ADMIN_USER: &str = "admin";
+/// Name of the cookie containing the session token.
+pub(super) const SESSION_COOKIE: &str = "mensa_admin_session";
+const XXX_AUTHENTICATE_CONTENT: &str = "Basic realm=MensaKaAdmin";
+
+/// Administrator making a request to the admin api.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(super) struct AdminUser {
+    pub(super) username: String,
+    pub(super) role: AdminRole,
+}
+
+impl From<AdminAccount> for AdminUser {
+    fn from(value: AdminAccount) -> Self {
+        Self {
+            username: value.username,
+            role: value.role,
+        }
+    }
+}
+
+/// Everything necessary for authenticating administrators and creating sessions.
+#[derive(Clone)]
+pub(super) struct AdminAuth {
+    /// Shared key of the user `admin`, empty if disabled.
+    pub(super) admin_key: String,
+    pub(super) admin_data: ArcAdminData,
+    pub(super) session_duration: Duration,
+    pub(super) oidc: Option<Arc<OidcClient>>,
+}
+
+impl AdminAuth {
+    /// Creates a new session for the given account, returning the session token and its expiry.
+    /// Only the hash of the token gets stored.
+    /// # Errors
+    /// if the session could not be stored
+    pub(super) async fn create_session(
+        &self,
+        username: &str,
+    ) -> DataResult<(String, DateTime<Utc>)> {
+        let token = generate_api_key();
+        let expires_at = Utc::now()
+            + chrono::Duration::from_std(self.session_duration).unwrap_or(chrono::Duration::MAX);
+        self.admin_data
+            .add_admin_session(&hash_token(&token), username, expires_at)
+            .await?;
+        Ok((token, expires_at))
+    }
+
+    /// Checks username and password of an account, or of the user `admin` with the shared key.
+    /// # Errors
+    /// if the account could not be loaded
+    pub(super) async fn check_password(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> DataResult<Option<AdminUser>> {
+        if username == ADMIN_USER && !self.admin_key.is_empty() {
+            let valid: bool = password.as_bytes().ct_eq(self.admin_key.as_bytes()).into();
+            return Ok(valid.then(|| AdminUser {
+                username: ADMIN_USER.into(),
+                role: AdminRole::Operator,
+            }));
+        }
+
+        let account = match self.admin_data.get_admin_account(username).await {
+            Ok(account) => account,
+            Err(DataError::NoSuchItem) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let Some(hash) = account.password_hash.clone() else {
+            return Ok(None);
+        };
+        // hashing takes a while on purpose
+        let password = password.to_owned();
+        let valid = tokio::task::spawn_blocking(move || verify_password(&password, &hash))
+            .await
+            .unwrap_or(false);
+        Ok(valid.then(|| account.into()))
+    }
+
+    async fn check_session(&self, token: &str) -> DataResult<Option<AdminUser>> {
+        match self.admin_data.get_admin_session(&hash_token(token)).await {
+            Ok(account) => Ok(Some(account.into())),
+            Err(DataError::NoSuchItem) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Returns the session token of a request, given as bearer token or cookie.
+pub(super) fn session_token(headers: &HeaderMap) -> Option<String> {
+    if let Some(Authorization(bearer)) = headers.typed_get::<Authorization<Bearer>>() {
+        return Some(bearer.token().to_owned());
+    }
+    headers
+        .typed_get::<Cookie>()
+        .and_then(|cookie| cookie.get(SESSION_COOKIE).map(ToOwned::to_owned))
+}
+
+/// Returns the value of a `Set-Cookie` header storing the session token, or removing it if `None`.
+pub(super) fn session_cookie(token: Option<&str>, max_age: Duration) -> HeaderValue {
+    let cookie = token.map_or_else(
+        || format!("{SESSION_COOKIE}=; Path=/admin; HttpOnly; Secure; SameSite=Strict; Max-Age=0"),
+        |token| {
+            format!(
+                "{SESSION_COOKIE}={token}; Path=/admin; HttpOnly; Secure; SameSite=Strict; Max-Age={}",
+                max_age.as_secs()
+            )
+        },
+    );
+    HeaderValue::from_str(&cookie).expect("session tokens contain no invalid characters")
+}
+
+pub(super) fn unauthenticated() -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        WWW_AUTHENTICATE,
+        HeaderValue::from_str(XXX_AUTHENTICATE_CONTENT).expect("contains no invalid characters"),
+    );
+    (
+        StatusCode::UNAUTHORIZED,
+        headers,
+        "Please authenticate to access the admin api!",
+    )
+        .into_response()
+}
+
+/// Middleware authenticating administrators by session token or HTTP basic auth.
+/// The [`AdminUser`] making the request gets added to the request extensions.
+pub(super) async fn admin_auth_middleware(
+    State(auth): State<AdminAuth>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let user = if let Some(token) = session_token(req.headers()) {
+        auth.check_session(&token).await
+    } else if let Some(Authorization(creds)) = req.headers().typed_get::<Authorization<Basic>>() {
+        auth.check_password(creds.username(), creds.password())
+            .await
+    } else {
+        Ok(None)
+    };
+
+    match user {
+        Ok(Some(user)) => {
+            req.extensions_mut().insert(user);
+            next.run(req).await
+        }
+        Ok(None) => unauthenticated(),
+        Err(e) => {
+            warn!("Could not authenticate administrator: {e}");
+            e.into_response()
+        }
+    }
+}
+
+/// Middleware rejecting requests of administrators without the given role.
+pub(super) async fn require_role(
+    State(role): State<AdminRole>,
+    Extension(user): Extension<AdminUser>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if user.role < role {
+        return (
+            StatusCode::FORBIDDEN,
+            format!("This action requires the role {role}."),
+        )
+            .into_response();
+    }
+    next.run(req).await
+}
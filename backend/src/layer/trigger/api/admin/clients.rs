@@ -0,0 +1,294 @@
+//! JSON endpoints of the admin api for finding and banning abusive clients.
+
+use axum::{
+    debug_handler,
+    extract::{Path, Query, State},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    interface::{
+        api_command::CommandError,
+        persistent_data::{
+            model::{ClientBan, ClientData, ReportingClient},
+            DataError,
+        },
+    },
+    util::{Date, Uuid},
+};
+
+use super::{auth::AdminUser, ArcAdminData, ArcCommand};
+
+/// Number of days the reports get counted for if not specified otherwise.
+const DEFAULT_DAYS: u32 = 1;
+
+#[derive(Clone)]
+struct ClientState {
+    command: ArcCommand,
+    admin_data: ArcAdminData,
+}
+
+#[derive(Clone)]
+struct SuspiciousState {
+    admin_data: ArcAdminData,
+    /// Number of reports a client has to make to be listed as suspicious if not specified otherwise.
+    mass_report_threshold: u32,
+}
+
+pub(super) fn client_router(
+    command: ArcCommand,
+    admin_data: ArcAdminData,
+    mass_report_threshold: u32,
+) -> Router<()> {
+    Router::new()
+        .route("/clients/suspicious", get(list_suspicious_clients))
+        .with_state(SuspiciousState {
+            admin_data: admin_data.clone(),
+            mass_report_threshold,
+        })
+        .route("/clients/banned", get(list_banned_clients))
+        .route("/clients/{client_id}/ban", post(ban_client))
+        .route("/clients/{client_id}/unban", post(unban_client))
+        .with_state(ClientState {
+            command,
+            admin_data,
+        })
+}
+
+/// Endpoints for exporting and erasing the data of clients, only available to operators.
+pub(super) fn client_data_router(command: ArcCommand, admin_data: ArcAdminData) -> Router<()> {
+    Router::new()
+        .route("/clients/{client_id}/data", get(export_client_data))
+        .route("/clients/{client_id}/erase", post(erase_client_data))
+        .with_state(ClientState {
+            command,
+            admin_data,
+        })
+}
+
+#[derive(Debug, Deserialize)]
+struct SuspiciousQuery {
+    min_reports: Option<u32>,
+    days: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReportingClientJson {
+    client_id: Uuid,
+    report_count: u32,
+    banned: bool,
+}
+
+impl From<ReportingClient> for ReportingClientJson {
+    fn from(value: ReportingClient) -> Self {
+        Self {
+            client_id: value.client_id,
+            report_count: value.report_count,
+            banned: value.banned,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ClientBanJson {
+    client_id: Uuid,
+    banned_by: String,
+    reason: Option<String>,
+    date: Date,
+}
+
+impl From<ClientBan> for ClientBanJson {
+    fn from(value: ClientBan) -> Self {
+        Self {
+            client_id: value.client_id,
+            banned_by: value.banned_by,
+            reason: value.reason,
+            date: value.date,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BanRequest {
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[debug_handler]
+async fn list_suspicious_clients(
+    State(state): State<SuspiciousState>,
+    Query(query): Query<SuspiciousQuery>,
+) -> Result<Json<Vec<ReportingClientJson>>, DataError> {
+    let clients = state
+        .admin_data
+        .get_mass_reporters(
+            query.min_reports.unwrap_or(state.mass_report_threshold),
+            query.days.unwrap_or(DEFAULT_DAYS),
+        )
+        .await?;
+    Ok(Json(clients.into_iter().map(Into::into).collect()))
+}
+
+#[debug_handler]
+async fn list_banned_clients(
+    State(state): State<ClientState>,
+) -> Result<Json<Vec<ClientBanJson>>, DataError> {
+    let bans = state.admin_data.get_banned_clients().await?;
+    Ok(Json(bans.into_iter().map(Into::into).collect()))
+}
+
+#[debug_handler]
+async fn ban_client(
+    State(state): State<ClientState>,
+    Path(client_id): Path<Uuid>,
+    Extension(user): Extension<AdminUser>,
+    Json(request): Json<BanRequest>,
+) -> Result<String, CommandError> {
+    state
+        .command
+        .ban_client(client_id, &user.username, request.reason)
+        .await?;
+    Ok(format!("Successfully banned client {client_id}"))
+}
+
+#[debug_handler]
+async fn unban_client(
+    State(state): State<ClientState>,
+    Path(client_id): Path<Uuid>,
+    Extension(user): Extension<AdminUser>,
+) -> Result<String, CommandError> {
+    state
+        .command
+        .unban_client(client_id, &user.username)
+        .await?;
+    Ok(format!("Successfully unbanned client {client_id}"))
+}
+
+#[debug_handler]
+async fn export_client_data(
+    State(state): State<ClientState>,
+    Path(client_id): Path<Uuid>,
+) -> Result<Json<ClientData>, DataError> {
+    let data = state.admin_data.get_client_data(client_id).await?;
+    Ok(Json(data))
+}
+
+#[debug_handler]
+async fn erase_client_data(
+    State(state): State<ClientState>,
+    Path(client_id): Path<Uuid>,
+    Extension(user): Extension<AdminUser>,
+) -> Result<String, CommandError> {
+    state
+        .command
+        .erase_client_data(client_id, &user.username)
+        .await?;
+    Ok(format!("Successfully erased data of client {client_id}"))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::sync::Arc;
+
+    use reqwest::StatusCode;
+    use serde_json::{json, Value};
+
+    use super::{client_data_router, client_router};
+    use crate::{
+        layer::trigger::api::{
+            admin::as_admin,
+            mock::{AdminDataMock, CommandMock, FAIL_ID},
+            serve,
+        },
+        util::{AdminRole, Uuid},
+    };
+
+    async fn start_router() -> String {
+        let router = client_router(Arc::new(CommandMock), Arc::new(AdminDataMock), 7).merge(
+            client_data_router(Arc::new(CommandMock), Arc::new(AdminDataMock)),
+        );
+        serve(as_admin(router, AdminRole::Operator)).await
+    }
+
+    #[tokio::test]
+    async fn test_list_clients() {
+        let url = start_router().await;
+
+        let clients: Value = reqwest::get(format!("{url}/clients/suspicious?min_reports=5"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(clients[0]["report_count"], 5);
+        assert_eq!(clients[0]["banned"], false);
+
+        // the mass report threshold is used by default
+        let clients: Value = reqwest::get(format!("{url}/clients/suspicious"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(clients[0]["report_count"], 7);
+
+        let bans: Value = reqwest::get(format!("{url}/clients/banned"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(bans[0]["banned_by"], "admin");
+    }
+
+    #[tokio::test]
+    async fn test_ban_client() {
+        let url = start_router().await;
+        let client = reqwest::Client::new();
+        let id = Uuid::default();
+
+        let status = |id: Uuid, action: &'static str, body: Value| {
+            let request = client
+                .post(format!("{url}/clients/{id}/{action}"))
+                .json(&body);
+            async move { request.send().await.unwrap().status() }
+        };
+
+        assert_eq!(
+            status(id, "ban", json!({ "reason": "spam" })).await,
+            StatusCode::OK
+        );
+        assert_eq!(status(id, "ban", json!({})).await, StatusCode::OK);
+        assert_eq!(status(id, "unban", json!({})).await, StatusCode::OK);
+        assert_eq!(
+            status(FAIL_ID, "unban", json!({})).await,
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_data() {
+        let url = start_router().await;
+        let id = Uuid::from_u128(42);
+
+        let data: Value = reqwest::get(format!("{url}/clients/{id}/data"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(data["client_id"], id.to_string());
+        assert_eq!(data["images"], json!([]));
+
+        let status = reqwest::Client::new()
+            .post(format!("{url}/clients/{id}/erase"))
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, StatusCode::OK);
+    }
+}
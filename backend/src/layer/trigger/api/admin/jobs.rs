@@ -0,0 +1,225 @@
+//! JSON endpoints of the admin api for controlling the scheduled jobs and parsing meal plans on demand.
+
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    response::IntoResponse,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use chrono::Local;
+use hyper::StatusCode;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::{
+    interface::{mealplan_management::ParseRunError, mensa_parser::ParseError},
+    layer::trigger::scheduling::control::{JobInfo, SchedulerControl, SchedulerError},
+    util::Date,
+};
+
+use super::auth::AdminUser;
+
+pub(super) fn job_router(scheduler: SchedulerControl) -> Router<()> {
+    Router::new()
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/{name}/run", post(run_job))
+        .route("/jobs/{name}/pause", post(pause_job))
+        .route("/jobs/{name}/resume", post(resume_job))
+        .route("/parse", post(parse))
+        .with_state(scheduler)
+}
+
+impl IntoResponse for SchedulerError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match &self {
+            Self::UnknownJob(_) => StatusCode::NOT_FOUND,
+            Self::AlreadyRunning(_) => StatusCode::CONFLICT,
+            Self::ParseRunError(ParseRunError::ParseError(ParseError::UnknownCanteen(_))) => {
+                StatusCode::BAD_REQUEST
+            }
+            Self::ParseRunError(_)
+            | Self::InvalidSchedule(..)
+            | Self::DataError(_)
+            | Self::LockLost(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let error = self.to_string();
+        warn!("On Admin API request: {error}");
+        (status, error).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ParseRequest {
+    /// Today if not set.
+    date: Option<Date>,
+    /// All canteens if not set.
+    canteen: Option<String>,
+}
+
+#[debug_handler]
+async fn list_jobs(
+    State(scheduler): State<SchedulerControl>,
+) -> Result<Json<Vec<JobInfo>>, SchedulerError> {
+    Ok(Json(scheduler.jobs().await?))
+}
+
+#[debug_handler]
+async fn run_job(
+    State(scheduler): State<SchedulerControl>,
+    Path(name): Path<String>,
+    Extension(user): Extension<AdminUser>,
+) -> Result<(StatusCode, String), SchedulerError> {
+    scheduler.run_now(&name).await?;
+    info!("Job {name} got started by {}.", user.username);
+    Ok((StatusCode::ACCEPTED, format!("Started job {name}")))
+}
+
+#[debug_handler]
+async fn pause_job(
+    State(scheduler): State<SchedulerControl>,
+    Path(name): Path<String>,
+    Extension(user): Extension<AdminUser>,
+) -> Result<String, SchedulerError> {
+    scheduler.pause(&name).await?;
+    info!("Job {name} got paused by {}.", user.username);
+    Ok(format!("Successfully paused job {name}"))
+}
+
+#[debug_handler]
+async fn resume_job(
+    State(scheduler): State<SchedulerControl>,
+    Path(name): Path<String>,
+    Extension(user): Extension<AdminUser>,
+) -> Result<String, SchedulerError> {
+    scheduler.resume(&name).await?;
+    info!("Job {name} got resumed by {}.", user.username);
+    Ok(format!("Successfully resumed job {name}"))
+}
+
+#[debug_handler]
+async fn parse(
+    State(scheduler): State<SchedulerControl>,
+    Extension(user): Extension<AdminUser>,
+    Json(request): Json<ParseRequest>,
+) -> Result<(StatusCode, String), SchedulerError> {
+    let date = request.date.unwrap_or_else(|| Local::now().date_naive());
+    let canteens = request.canteen.as_deref().unwrap_or("all canteens");
+    scheduler
+        .parse_date(date, request.canteen.as_deref())
+        .await?;
+    info!(
+        "Parsing of {canteens} at {date} got started by {}.",
+        user.username
+    );
+    Ok((
+        StatusCode::ACCEPTED,
+        format!("Started parsing {canteens} at {date}"),
+    ))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::time::Duration;
+
+    use reqwest::StatusCode;
+    use serde_json::{json, Value};
+
+    use super::job_router;
+    use crate::{
+        layer::trigger::{
+            api::{admin::as_admin, serve},
+            scheduling::{
+                mocks::{
+                    DigestMock, ImageReviewMock, MensaParseMock, RetentionMock, SchedulerLeaseMock,
+                    UNKNOWN_CANTEEN,
+                },
+                scheduler::{ScheduleInfo, Scheduler},
+            },
+        },
+        util::AdminRole,
+    };
+
+    const NEVER: &str = "0 0 0 31 2 *";
+
+    async fn start_router(mensa_parse: MensaParseMock) -> String {
+        let info = ScheduleInfo {
+            full_parse_schedule: NEVER.into(),
+            update_parse_schedule: NEVER.into(),
+            image_review_schedule: NEVER.into(),
+            image_reverification_schedule: NEVER.into(),
+            retention_schedule: NEVER.into(),
+            image_reconciliation_schedule: NEVER.into(),
+            digest_schedule: None,
+            leader_lease: Duration::from_secs(1),
+        };
+        let scheduler = Scheduler::new(
+            info,
+            mensa_parse,
+            ImageReviewMock::default(),
+            RetentionMock::default(),
+            DigestMock::default(),
+            SchedulerLeaseMock::default(),
+        )
+        .await;
+        serve(as_admin(
+            job_router(scheduler.control()),
+            AdminRole::Operator,
+        ))
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_control_jobs() {
+        let mensa_parse = MensaParseMock::default();
+        let url = start_router(mensa_parse.clone()).await;
+        let client = reqwest::Client::new();
+        let status = |path: &str| {
+            let request = client.post(format!("{url}{path}"));
+            async move { request.send().await.unwrap().status() }
+        };
+
+        assert_eq!(status("/jobs/update_parsing/pause").await, StatusCode::OK);
+        let jobs: Value = reqwest::get(format!("{url}/jobs"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(jobs[0]["name"], "update_parsing");
+        assert_eq!(jobs[0]["paused"], true);
+        assert_eq!(jobs[1]["paused"], false);
+
+        assert_eq!(status("/jobs/update_parsing/resume").await, StatusCode::OK);
+        assert_eq!(status("/jobs/full_parsing/run").await, StatusCode::ACCEPTED);
+        assert_eq!(status("/jobs/unknown/run").await, StatusCode::NOT_FOUND);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(mensa_parse.get_full_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_parse() {
+        let mensa_parse = MensaParseMock::default();
+        let url = start_router(mensa_parse.clone()).await;
+        let client = reqwest::Client::new();
+        let parse = |body: Value| {
+            let request = client.post(format!("{url}/parse")).json(&body);
+            async move { request.send().await.unwrap().status() }
+        };
+
+        assert_eq!(parse(json!({})).await, StatusCode::ACCEPTED);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            parse(json!({ "date": "2026-10-19", "canteen": "mensa_adenauerring" })).await,
+            StatusCode::ACCEPTED
+        );
+        assert_eq!(
+            parse(json!({ "canteen": UNKNOWN_CANTEEN })).await,
+            StatusCode::BAD_REQUEST
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // unknown canteens are rejected before parsing
+        assert_eq!(mensa_parse.get_date_calls(), 2);
+    }
+}
@@ -0,0 +1,213 @@
+//! JSON endpoints of the admin api for managing the api keys of clients.
+
+use std::sync::Arc;
+
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    interface::persistent_data::{model::ApiKeyInfo, DataError},
+    layer::trigger::api::key_store::ApiKeyStore,
+    util::{ApiKeyScope, Date, Uuid},
+};
+
+use super::auth::AdminUser;
+
+pub(super) fn key_router(key_store: Arc<ApiKeyStore>) -> Router<()> {
+    Router::new()
+        .route("/api_keys", get(list_api_keys).post(add_api_key))
+        .route("/api_keys/{key_id}/rotate", post(rotate_api_key))
+        .route("/api_keys/{key_id}/revoke", post(revoke_api_key))
+        .route(
+            "/api_keys/{key_id}/compatibility",
+            post(set_compatibility_mode),
+        )
+        .with_state(key_store)
+}
+
+#[derive(Debug, Deserialize)]
+struct NewApiKeyRequest {
+    #[serde(default)]
+    description: String,
+    scope: Option<ApiKeyScope>,
+    expiry_date: Option<Date>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompatibilityRequest {
+    enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct NewApiKeyJson {
+    id: Uuid,
+    key: String,
+}
+
+#[debug_handler]
+async fn list_api_keys(
+    State(key_store): State<Arc<ApiKeyStore>>,
+) -> Result<Json<Vec<ApiKeyInfo>>, DataError> {
+    // include requests not saved yet
+    if let Err(e) = key_store.flush_usage().await {
+        warn!("Could not save api key usage: {e}");
+    }
+    let keys = key_store.auth_data().get_api_key_infos().await?;
+    Ok(Json(keys))
+}
+
+#[debug_handler]
+async fn add_api_key(
+    State(key_store): State<Arc<ApiKeyStore>>,
+    Extension(user): Extension<AdminUser>,
+    Json(request): Json<NewApiKeyRequest>,
+) -> Result<Json<NewApiKeyJson>, DataError> {
+    let scope = request.scope.unwrap_or(ApiKeyScope::ReadOnly);
+    let (id, key) = key_store
+        .add_key(
+            &request.description,
+            scope,
+            request.expiry_date,
+            &user.username,
+        )
+        .await?;
+    info!(
+        "Api key {id} with scope {scope} got added by {}.",
+        user.username
+    );
+    Ok(Json(NewApiKeyJson { id, key }))
+}
+
+#[debug_handler]
+async fn rotate_api_key(
+    State(key_store): State<Arc<ApiKeyStore>>,
+    Path(key_id): Path<Uuid>,
+    Extension(user): Extension<AdminUser>,
+) -> Result<Json<NewApiKeyJson>, DataError> {
+    let key = key_store.rotate_key(key_id, &user.username).await?;
+    info!("Api key {key_id} got rotated by {}.", user.username);
+    Ok(Json(NewApiKeyJson { id: key_id, key }))
+}
+
+#[debug_handler]
+async fn revoke_api_key(
+    State(key_store): State<Arc<ApiKeyStore>>,
+    Path(key_id): Path<Uuid>,
+    Extension(user): Extension<AdminUser>,
+) -> Result<String, DataError> {
+    key_store.revoke_key(key_id, &user.username).await?;
+    info!("Api key {key_id} got revoked by {}.", user.username);
+    Ok(format!("Successfully revoked api key {key_id}"))
+}
+
+#[debug_handler]
+async fn set_compatibility_mode(
+    State(key_store): State<Arc<ApiKeyStore>>,
+    Path(key_id): Path<Uuid>,
+    Extension(user): Extension<AdminUser>,
+    Json(request): Json<CompatibilityRequest>,
+) -> Result<String, DataError> {
+    key_store
+        .set_compatibility_mode(key_id, request.enabled, &user.username)
+        .await?;
+    let state = if request.enabled {
+        "enabled"
+    } else {
+        "disabled"
+    };
+    info!(
+        "Compatibility mode of api key {key_id} got {state} by {}.",
+        user.username
+    );
+    Ok(format!(
+        "Successfully {state} compatibility mode of api key {key_id}"
+    ))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::sync::Arc;
+
+    use reqwest::StatusCode;
+    use serde_json::{json, Value};
+
+    use super::key_router;
+    use crate::{
+        layer::trigger::api::{
+            admin::as_admin,
+            key_store::ApiKeyStore,
+            mock::{AuthDataMock, FAIL_ID},
+            serve,
+        },
+        util::{AdminRole, Uuid},
+    };
+
+    async fn start_router() -> String {
+        let key_store = ApiKeyStore::new(Arc::new(AuthDataMock::default()));
+        serve(as_admin(
+            key_router(Arc::new(key_store)),
+            AdminRole::Operator,
+        ))
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_manage_api_keys() {
+        let url = start_router().await;
+        let client = reqwest::Client::new();
+
+        let keys: Value = reqwest::get(format!("{url}/api_keys"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(keys[0]["request_count"], 42);
+        assert_eq!(keys[0]["scope"], "MODERATE");
+
+        let new_key: Value = client
+            .post(format!("{url}/api_keys"))
+            .json(&json!({ "description": "test", "scope": "UPLOAD", "expiry_date": "2030-01-01" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(new_key["key"].as_str().unwrap().len(), 64);
+
+        let id = Uuid::from_u128(1);
+        let rotated: Value = client
+            .post(format!("{url}/api_keys/{id}/rotate"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_ne!(rotated["key"], new_key["key"]);
+
+        let compatibility = client
+            .post(format!("{url}/api_keys/{id}/compatibility"))
+            .json(&json!({ "enabled": false }))
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(compatibility, StatusCode::OK);
+
+        let status = |id: Uuid| {
+            let request = client.post(format!("{url}/api_keys/{id}/revoke"));
+            async move { request.send().await.unwrap().status() }
+        };
+        assert_eq!(status(id).await, StatusCode::OK);
+        assert_eq!(status(FAIL_ID).await, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,345 @@
+//! JSON endpoints of the admin api for moderating uploaded images.
+
+use axum::{
+    debug_handler,
+    extract::{Query, State},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    interface::persistent_data::{
+        model::{ImageReport, ModerationFilter, ModerationImage, ModerationQueue},
+        DataError,
+    },
+    util::{image_id_to_url, Date, ModerationStatus, ReportReason, Uuid},
+};
+
+use super::{auth::AdminUser, ArcAdminData, ArcCommand};
+
+/// Number of images returned if no limit is given.
+const DEFAULT_LIMIT: u32 = 50;
+/// Maximum number of images returned at once.
+const MAX_LIMIT: u32 = 500;
+
+#[derive(Clone)]
+struct ModerationState {
+    command: ArcCommand,
+    admin_data: ArcAdminData,
+}
+
+pub(super) fn moderation_router(command: ArcCommand, admin_data: ArcAdminData) -> Router<()> {
+    Router::new()
+        .route("/images", get(list_images))
+        .route("/images/verify", post(verify_images))
+        .route("/images/delete", post(delete_images))
+        .route("/images/unhide", post(unhide_images))
+        .route("/images/restore", post(restore_images))
+        .with_state(ModerationState {
+            command,
+            admin_data,
+        })
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Queue {
+    #[default]
+    Reported,
+    Hidden,
+    Unverified,
+    Deleted,
+}
+
+impl From<Queue> for ModerationQueue {
+    fn from(value: Queue) -> Self {
+        match value {
+            Queue::Reported => Self::Reported,
+            Queue::Hidden => Self::Hidden,
+            Queue::Unverified => Self::Unverified,
+            Queue::Deleted => Self::Deleted,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ImageQuery {
+    #[serde(default)]
+    queue: Queue,
+    meal_id: Option<Uuid>,
+    reason: Option<ReportReason>,
+    from: Option<Date>,
+    to: Option<Date>,
+    limit: Option<u32>,
+    #[serde(default)]
+    offset: u32,
+}
+
+impl From<ImageQuery> for ModerationFilter {
+    fn from(value: ImageQuery) -> Self {
+        Self {
+            queue: value.queue.into(),
+            meal_id: value.meal_id,
+            reason: value.reason,
+            from: value.from,
+            to: value.to,
+            limit: value.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT),
+            offset: value.offset,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ReportJson {
+    client_id: Uuid,
+    reason: ReportReason,
+    date: Date,
+}
+
+impl From<ImageReport> for ReportJson {
+    fn from(value: ImageReport) -> Self {
+        Self {
+            client_id: value.client_id,
+            reason: value.reason,
+            date: value.date,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ModerationImageJson {
+    id: Uuid,
+    url: String,
+    meal_id: Uuid,
+    meal_name: String,
+    uploader_id: Uuid,
+    upload_date: Date,
+    approved: bool,
+    hidden: bool,
+    deletion_date: Option<Date>,
+    moderation_status: ModerationStatus,
+    rank: f32,
+    upvotes: u32,
+    downvotes: u32,
+    report_count: u32,
+    other_image_urls: Vec<String>,
+    reports: Vec<ReportJson>,
+}
+
+impl From<ModerationImage> for ModerationImageJson {
+    fn from(value: ModerationImage) -> Self {
+        let image = value.info.image;
+        Self {
+            id: image.id,
+            url: image_id_to_url(image.id),
+            meal_id: image.meal_id,
+            meal_name: value.info.meal_name,
+            uploader_id: image.uploader_id,
+            upload_date: image.upload_date,
+            approved: image.approved,
+            hidden: value.hidden,
+            deletion_date: value.deletion_date,
+            moderation_status: image.moderation_status,
+            rank: image.rank,
+            upvotes: image.upvotes,
+            downvotes: image.downvotes,
+            report_count: image.report_count,
+            other_image_urls: value.info.other_image_urls,
+            reports: value.reports.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BulkRequest {
+    image_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct BulkResponse {
+    succeeded: Vec<Uuid>,
+    failed: Vec<BulkFailure>,
+}
+
+#[derive(Debug, Serialize)]
+struct BulkFailure {
+    image_id: Uuid,
+    error: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BulkAction {
+    Verify,
+    Delete,
+    Unhide,
+    Restore,
+}
+
+#[debug_handler]
+async fn list_images(
+    State(state): State<ModerationState>,
+    Query(query): Query<ImageQuery>,
+) -> Result<Json<Vec<ModerationImageJson>>, DataError> {
+    let images = state
+        .admin_data
+        .get_moderation_images(&query.into())
+        .await?;
+    Ok(Json(images.into_iter().map(Into::into).collect()))
+}
+
+#[debug_handler]
+async fn verify_images(
+    State(state): State<ModerationState>,
+    Extension(user): Extension<AdminUser>,
+    Json(request): Json<BulkRequest>,
+) -> Json<BulkResponse> {
+    Json(run_bulk(&state.command, BulkAction::Verify, &user, request.image_ids).await)
+}
+
+#[debug_handler]
+async fn delete_images(
+    State(state): State<ModerationState>,
+    Extension(user): Extension<AdminUser>,
+    Json(request): Json<BulkRequest>,
+) -> Json<BulkResponse> {
+    Json(run_bulk(&state.command, BulkAction::Delete, &user, request.image_ids).await)
+}
+
+#[debug_handler]
+async fn unhide_images(
+    State(state): State<ModerationState>,
+    Extension(user): Extension<AdminUser>,
+    Json(request): Json<BulkRequest>,
+) -> Json<BulkResponse> {
+    Json(run_bulk(&state.command, BulkAction::Unhide, &user, request.image_ids).await)
+}
+
+#[debug_handler]
+async fn restore_images(
+    State(state): State<ModerationState>,
+    Extension(user): Extension<AdminUser>,
+    Json(request): Json<BulkRequest>,
+) -> Json<BulkResponse> {
+    Json(
+        run_bulk(
+            &state.command,
+            BulkAction::Restore,
+            &user,
+            request.image_ids,
+        )
+        .await,
+    )
+}
+
+/// Applies the action to every image, one failing image does not stop the others.
+async fn run_bulk(
+    command: &ArcCommand,
+    action: BulkAction,
+    user: &AdminUser,
+    image_ids: Vec<Uuid>,
+) -> BulkResponse {
+    let mut response = BulkResponse::default();
+    let username = user.username.as_str();
+    for image_id in image_ids {
+        let result = match action {
+            BulkAction::Verify => command.verify_image(image_id, username).await,
+            BulkAction::Delete => command.delete_image(image_id, username).await,
+            BulkAction::Unhide => command.unhide_image(image_id, username).await,
+            BulkAction::Restore => command.restore_image(image_id, username).await,
+        };
+        match result {
+            Ok(()) => response.succeeded.push(image_id),
+            Err(e) => {
+                warn!("On Admin API request: {action:?} of image {image_id} failed: {e}");
+                response.failed.push(BulkFailure {
+                    image_id,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::sync::Arc;
+
+    use reqwest::StatusCode;
+    use serde_json::{json, Value};
+
+    use super::moderation_router;
+    use crate::{
+        layer::trigger::api::{
+            admin::as_admin,
+            mock::{AdminDataMock, CommandMock, FAIL_ID},
+            serve,
+        },
+        util::{AdminRole, Uuid},
+    };
+
+    async fn start_router() -> String {
+        let router = as_admin(
+            moderation_router(Arc::new(CommandMock), Arc::new(AdminDataMock)),
+            AdminRole::Moderator,
+        );
+        serve(router).await
+    }
+
+    #[tokio::test]
+    async fn test_list_images() {
+        let url = start_router().await;
+
+        let images: Value = reqwest::get(format!(
+            "{url}/images?queue=hidden&reason=Advert&from=2024-01-01&limit=10"
+        ))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+        assert_eq!(images[0]["hidden"], true);
+        assert_eq!(images[0]["reports"][0]["reason"], "Advert");
+
+        let status = reqwest::get(format!("{url}/images?queue=unknown"))
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_actions() {
+        let url = start_router().await;
+        let client = reqwest::Client::new();
+        let id = Uuid::default();
+
+        for action in ["verify", "delete", "unhide", "restore"] {
+            let response: Value = client
+                .post(format!("{url}/images/{action}"))
+                .json(&json!({ "image_ids": [id] }))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(response["succeeded"], json!([id]), "{action}");
+        }
+
+        let response: Value = client
+            .post(format!("{url}/images/delete"))
+            .json(&json!({ "image_ids": [id, FAIL_ID] }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(response["succeeded"], json!([id]));
+        assert_eq!(response["failed"][0]["image_id"], json!(FAIL_ID));
+    }
+}
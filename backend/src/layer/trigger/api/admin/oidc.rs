@@ -0,0 +1,317 @@
+//! Login of administrators via an openid connect provider using the authorization code flow, see [`OidcClient`].
+
+use std::{collections::HashMap, time::Duration};
+
+use axum::http::HeaderValue;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::Sha256;
+use thiserror::Error;
+use tokio::sync::OnceCell;
+
+use crate::util::{generate_api_key, AdminRole};
+
+/// Time a login has to be completed in after being started.
+const LOGIN_TIMEOUT: Duration = Duration::from_mins(10);
+const DISCOVERY_PATH: &str = ".well-known/openid-configuration";
+/// Name of the cookie binding a started login to the browser it got started in.
+pub(super) const STATE_COOKIE: &str = "mensa_admin_oidc_state";
+
+/// Settings for logging in to the admin api via an openid connect provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcInfo {
+    /// Issuer url of the provider, under which `/.well-known/openid-configuration` can be found.
+    pub issuer_url: String,
+    /// Id of this server as registered at the provider.
+    pub client_id: String,
+    /// Secret of this server as registered at the provider.
+    pub client_secret: String,
+    /// Url of the callback endpoint `/admin/login/oidc/callback` as reachable by administrators.
+    pub redirect_url: String,
+    /// Claim of the id token containing the groups or roles of the administrator.
+    pub roles_claim: String,
+    /// Group granting the role [`AdminRole::Moderator`].
+    pub moderator_group: String,
+    /// Group granting the role [`AdminRole::Operator`].
+    pub operator_group: String,
+}
+
+/// Errors occurring while logging in via openid connect.
+#[derive(Debug, Error)]
+pub(super) enum OidcError {
+    #[error("error communicating with the openid connect provider: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("invalid url of the openid connect provider: {0}")]
+    Url(String),
+    #[error("unknown or expired login, please try again")]
+    UnknownState,
+    #[error("the openid connect provider rejected the login, please try again")]
+    CodeRejected,
+    #[error("invalid id token: {0}")]
+    InvalidToken(&'static str),
+    #[error("the user {0} has no role granting access to the admin api")]
+    NoRole(String),
+    #[error("the username {0} is already taken by another account")]
+    NameTaken(String),
+}
+
+/// Administrator who logged in at the openid connect provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct OidcUser {
+    /// Issuer of the id token.
+    pub(super) issuer: String,
+    /// Identifier of the administrator at the issuer, which never changes.
+    pub(super) subject: String,
+    /// Name the administrator chose at the provider, only used for new accounts.
+    pub(super) username: String,
+    pub(super) role: AdminRole,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    nonce: Option<String>,
+    preferred_username: Option<String>,
+    #[serde(flatten)]
+    other: HashMap<String, Value>,
+}
+
+/// Client of an openid connect provider.
+///
+/// Started logins are not stored, instead their state is signed with the client secret,
+/// so a login can be completed by every server sharing the configuration.
+pub(super) struct OidcClient {
+    info: OidcInfo,
+    http: reqwest::Client,
+    metadata: OnceCell<ProviderMetadata>,
+}
+
+impl OidcClient {
+    /// Creates a new client. The provider gets contacted on the first login.
+    pub(super) fn new(info: OidcInfo) -> Self {
+        Self {
+            info,
+            http: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+        }
+    }
+
+    async fn metadata(&self) -> Result<&ProviderMetadata, OidcError> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/{DISCOVERY_PATH}",
+                    self.info.issuer_url.trim_end_matches('/')
+                );
+                let metadata = self.http.get(url).send().await?.error_for_status()?;
+                Ok(metadata.json().await?)
+            })
+            .await
+    }
+
+    /// Starts a login, returning the url of the provider the administrator has to be redirected to
+    /// and the state, which has to be stored in the [`STATE_COOKIE`] of the browser.
+    /// # Errors
+    /// if the provider could not be reached
+    pub(super) async fn start_login(&self) -> Result<(Url, String), OidcError> {
+        let metadata = self.metadata().await?;
+        let expires = Utc::now().timestamp() + LOGIN_TIMEOUT.as_secs().cast_signed();
+        let payload = format!("{}.{expires}", generate_api_key());
+        let state = format!("{payload}.{}", self.sign("state", &payload));
+        let nonce = self.sign("nonce", &state);
+
+        let url = Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            [
+                ("response_type", "code"),
+                ("scope", "openid profile"),
+                ("client_id", &self.info.client_id),
+                ("redirect_uri", &self.info.redirect_url),
+                ("state", &state),
+                ("nonce", &nonce),
+            ],
+        )
+        .map_err(|e| OidcError::Url(e.to_string()))?;
+        Ok((url, state))
+    }
+
+    /// Completes a login with the code returned by the provider, returning the logged in administrator.
+    /// `cookie_state` is the state stored in the [`STATE_COOKIE`] of the browser, which has to match the returned state.
+    /// The signature of the id token is checked against the keys published by the provider.
+    /// # Errors
+    /// if the login is unknown, the id token invalid or the administrator has no role
+    pub(super) async fn complete_login(
+        &self,
+        code: &str,
+        state: &str,
+        cookie_state: Option<&str>,
+    ) -> Result<OidcUser, OidcError> {
+        // a login started in another browser must not complete in this one
+        if cookie_state != Some(state) {
+            return Err(OidcError::UnknownState);
+        }
+        let nonce = self.check_state(state)?;
+
+        let metadata = self.metadata().await?;
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", &self.info.redirect_url),
+                ("client_id", &self.info.client_id),
+                ("client_secret", &self.info.client_secret),
+            ])
+            .send()
+            .await?;
+        // e.g. if the code was already used
+        if response.status().is_client_error() {
+            return Err(OidcError::CodeRejected);
+        }
+        let response: TokenResponse = response.error_for_status()?.json().await?;
+
+        let claims = self.decode_claims(&response.id_token, metadata).await?;
+        if claims.nonce.as_deref() != Some(&nonce) {
+            return Err(OidcError::InvalidToken("wrong nonce"));
+        }
+
+        let username = claims
+            .preferred_username
+            .unwrap_or_else(|| claims.sub.clone());
+        let groups: Vec<&str> = match claims.other.get(&self.info.roles_claim) {
+            Some(Value::Array(groups)) => groups.iter().filter_map(Value::as_str).collect(),
+            Some(Value::String(group)) => vec![group],
+            _ => vec![],
+        };
+        let role = if groups.contains(&self.info.operator_group.as_str()) {
+            AdminRole::Operator
+        } else if groups.contains(&self.info.moderator_group.as_str()) {
+            AdminRole::Moderator
+        } else {
+            return Err(OidcError::NoRole(username));
+        };
+        Ok(OidcUser {
+            issuer: claims.iss,
+            subject: claims.sub,
+            username,
+            role,
+        })
+    }
+
+    fn mac(&self, purpose: &str, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.info.client_secret.as_bytes())
+            .expect("hmac accepts keys of any length");
+        mac.update(format!("{purpose}.{payload}").as_bytes());
+        mac
+    }
+
+    /// Signs `payload` for `purpose` with the client secret.
+    fn sign(&self, purpose: &str, payload: &str) -> String {
+        URL_SAFE_NO_PAD.encode(self.mac(purpose, payload).finalize().into_bytes())
+    }
+
+    /// Checks the signature and expiry of a state returned by [`Self::start_login`], returning the nonce of the login.
+    fn check_state(&self, state: &str) -> Result<String, OidcError> {
+        let (payload, signature) = state.rsplit_once('.').ok_or(OidcError::UnknownState)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| OidcError::UnknownState)?;
+        self.mac("state", payload)
+            .verify_slice(&signature)
+            .map_err(|_| OidcError::UnknownState)?;
+
+        let expires: i64 = payload
+            .rsplit_once('.')
+            .and_then(|(_, expires)| expires.parse().ok())
+            .ok_or(OidcError::UnknownState)?;
+        if expires < Utc::now().timestamp() {
+            return Err(OidcError::UnknownState);
+        }
+        Ok(self.sign("nonce", state))
+    }
+
+    /// Decodes an id token after checking its signature, issuer, audience and expiry.
+    /// Tokens signed with a shared secret have to be signed with the client secret,
+    /// all others with a key published by the provider.
+    async fn decode_claims(
+        &self,
+        id_token: &str,
+        metadata: &ProviderMetadata,
+    ) -> Result<IdTokenClaims, OidcError> {
+        let header = jsonwebtoken::decode_header(id_token)
+            .map_err(|_| OidcError::InvalidToken("not a jwt"))?;
+        let key = if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            DecodingKey::from_secret(self.info.client_secret.as_bytes())
+        } else {
+            let keys: JwkSet = self
+                .http
+                .get(&metadata.jwks_uri)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            let key = match &header.kid {
+                Some(kid) => keys.find(kid),
+                None => keys.keys.first(),
+            }
+            .ok_or(OidcError::InvalidToken("unknown signing key"))?;
+            DecodingKey::from_jwk(key)
+                .map_err(|_| OidcError::InvalidToken("invalid signing key"))?
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.info.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        jsonwebtoken::decode(id_token, &key, &validation)
+            .map(|token| token.claims)
+            .map_err(|e| {
+                OidcError::InvalidToken(match e.kind() {
+                    jsonwebtoken::errors::ErrorKind::InvalidIssuer => "wrong issuer",
+                    jsonwebtoken::errors::ErrorKind::InvalidAudience => "wrong audience",
+                    jsonwebtoken::errors::ErrorKind::ExpiredSignature => "expired",
+                    jsonwebtoken::errors::ErrorKind::InvalidSignature => "invalid signature",
+                    _ => "invalid claims",
+                })
+            })
+    }
+}
+
+/// Returns the value of a `Set-Cookie` header storing the state of a started login, or removing it if `None`.
+/// It has to be sent along with the redirect from the provider, so it can not be `SameSite=Strict`.
+pub(super) fn state_cookie(state: Option<&str>) -> HeaderValue {
+    let max_age = if state.is_some() {
+        LOGIN_TIMEOUT.as_secs()
+    } else {
+        0
+    };
+    let cookie = format!(
+        "{STATE_COOKIE}={}; Path=/admin/login/oidc; HttpOnly; Secure; SameSite=Lax; Max-Age={max_age}",
+        state.unwrap_or_default()
+    );
+    HeaderValue::from_str(&cookie).expect("states contain no invalid characters")
+}
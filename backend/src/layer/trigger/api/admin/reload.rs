@@ -0,0 +1,97 @@
+//! Endpoint of the admin api for reloading the settings while the server keeps running.
+
+use std::sync::Arc;
+
+use axum::{
+    debug_handler, extract::State, response::IntoResponse, routing::post, Extension, Router,
+};
+use hyper::StatusCode;
+use tracing::{info, warn};
+
+use crate::interface::config_reload::{ConfigReload, ReloadError};
+
+use super::auth::AdminUser;
+
+type ArcConfigReload = Arc<dyn ConfigReload>;
+
+pub(super) fn reload_router(config_reload: ArcConfigReload) -> Router<()> {
+    Router::new()
+        .route("/reload", post(reload))
+        .with_state(config_reload)
+}
+
+impl IntoResponse for ReloadError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match &self {
+            Self::InvalidConfig(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::NotApplied(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let error = self.to_string();
+        warn!("On Admin API request: {error}");
+        (status, error).into_response()
+    }
+}
+
+#[debug_handler]
+async fn reload(
+    State(config_reload): State<ArcConfigReload>,
+    Extension(user): Extension<AdminUser>,
+) -> Result<String, ReloadError> {
+    info!(
+        "Reloading of the settings got started by {}.",
+        user.username
+    );
+    config_reload.reload().await?;
+    Ok("Successfully reloaded settings".into())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    use async_trait::async_trait;
+    use reqwest::StatusCode;
+
+    use super::reload_router;
+    use crate::{
+        interface::config_reload::{ConfigReload, ReloadError, Result},
+        layer::trigger::api::{admin::as_admin, serve},
+        util::AdminRole,
+    };
+
+    #[derive(Default)]
+    struct ConfigReloadMock {
+        invalid: AtomicBool,
+    }
+
+    #[async_trait]
+    impl ConfigReload for ConfigReloadMock {
+        async fn reload(&self) -> Result<()> {
+            if self.invalid.load(Ordering::Relaxed) {
+                Err(ReloadError::InvalidConfig("HTTP_PORT".into()))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reload() {
+        let config_reload = Arc::new(ConfigReloadMock::default());
+        let router = as_admin(reload_router(config_reload.clone()), AdminRole::Operator);
+        let url = serve(router).await;
+        let client = reqwest::Client::new();
+        let status = || {
+            let request = client.post(format!("{url}/reload"));
+            async move { request.send().await.unwrap().status() }
+        };
+
+        assert_eq!(status().await, StatusCode::OK);
+        config_reload.invalid.store(true, Ordering::Relaxed);
+        assert_eq!(status().await, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
@@ -1,7 +1,7 @@
 //! Module responsible for authenticating graphql requests.
 //! For more details, see <https://github.com/kronos-et-al/MensaApp/blob/main/doc/ApiAuth.md>.
 
-use std::{error::Error, fmt::Display, io::Cursor};
+use std::{error::Error, fmt::Display, io::Cursor, sync::Arc};
 
 use axum::{
     body::to_bytes,
@@ -18,14 +18,21 @@ use base64::{
     engine::general_purpose::{self, STANDARD},
     Engine,
 };
+use chrono::Local;
 use hmac::{Hmac, Mac};
 use hyper::{body::Bytes, StatusCode};
 use mime::Mime;
 use multer::{parse_boundary, Multipart};
 use sha2::Sha512;
+use subtle::ConstantTimeEq;
 use thiserror::Error;
 
-use crate::{interface::persistent_data::model::ApiKey, util::Uuid};
+use crate::{
+    interface::persistent_data::{model::ApiKey, DataError},
+    util::{ApiKeyScope, Date, Uuid},
+};
+
+use super::{key_store::ApiKeyStore, replay_guard::ReplayGuard};
 
 pub(super) type AuthResult<T> = Result<T, AuthError>;
 
@@ -43,6 +50,9 @@ pub enum AuthError {
     /// No or invalid authentication provided but the request needs to be authenticated.
     #[error("One of the queries/mutations you requested requires authentication. Your auth info: {0:?} See {url} for more details.", url = AUTH_DOC_URL)]
     MissingOrInvalidAuth(AuthInfo),
+    /// The api key is valid but not allowed to make the request.
+    #[error("One of the queries/mutations you requested requires an api key with scope {0}, but yours only has scope {1}. See {url} for more details.", url = AUTH_DOC_URL)]
+    InsufficientScope(ApiKeyScope, ApiKeyScope),
 }
 
 /// Reasons why authentication failed.
@@ -57,8 +67,16 @@ pub enum AuthFailReason {
     HashNotInBase64,
     /// Api key is not valid.
     InvalidApiKey,
+    /// Api key is no longer valid since the given day.
+    ExpiredApiKey(Date),
     /// Provided HMAC hash does not match with request.
-    HashNotMatching(Vec<u8>),
+    HashNotMatching,
+    /// The api key requires requests to contain a timestamp and nonce, but they are missing.
+    MissingReplayProtection,
+    /// The request was made at the given time (in seconds since the unix epoch), which is too long ago.
+    RequestExpired(i64),
+    /// The nonce was already used in an earlier request.
+    NonceReused,
 }
 
 /// Structure containing all information necessary for authenticating a client.
@@ -66,8 +84,8 @@ pub enum AuthFailReason {
 pub struct AuthInfo {
     /// Identifier of client making request, if provided.
     pub client_id: Option<Uuid>,
-    /// Reason why authentication failed (if so).
-    pub authenticated: Result<(), AuthFailReason>,
+    /// Scope of the api key used for authentication, or the reason why authentication failed (if so).
+    pub authenticated: Result<ApiKeyScope, AuthFailReason>,
     /// Identifier of api key used for authentication.
     /// For more details see <https://github.com/kronos-et-al/MensaApp/blob/main/doc/ApiAuth.md>.
     pub api_ident: String,
@@ -78,7 +96,7 @@ pub struct AuthInfo {
 impl Display for AuthInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let auth_status = match &self.authenticated {
-            Ok(()) => "authenticated".into(),
+            Ok(scope) => format!("authenticated ({scope})"),
             Err(e) => format!("unauthenticated ({e:?})"),
         };
         write!(
@@ -105,6 +123,17 @@ pub(super) struct MensaAuthHeader {
     /// SHA-512 hash of all request parameters, the client id and the name of the request.
     /// This hash has to be checked to authenticate a command.
     pub(super) hash: String,
+    /// Timestamp and nonce included in the hash, if provided.
+    pub(super) replay_protection: Option<ReplayProtection>,
+}
+
+/// Information making each authenticated request unique, so it can not be replayed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ReplayProtection {
+    /// Time the request was made at, in seconds since the unix epoch.
+    pub(super) timestamp: i64,
+    /// Random string which may only be used once.
+    pub(super) nonce: String,
 }
 
 impl Credentials for MensaAuthHeader {
@@ -127,6 +156,8 @@ pub(super) enum AuthMiddlewareError {
     MultipartError(#[from] multer::Error),
     #[error("multipart request needs `operations` part")]
     MissingOperationsPart,
+    #[error("could not access api keys: {0}")]
+    DataError(#[from] DataError),
 }
 
 impl IntoResponse for AuthMiddlewareError {
@@ -138,7 +169,11 @@ impl IntoResponse for AuthMiddlewareError {
 pub(super) async fn auth_middleware(
     content_type: Option<TypedHeader<ContentType>>,
     auth: Option<TypedHeader<Authorization<MensaAuthHeader>>>,
-    extract::State((body_limit, api_keys)): extract::State<(usize, Vec<ApiKey>)>,
+    extract::State((body_limit, key_store, replay_guard)): extract::State<(
+        usize,
+        Arc<ApiKeyStore>,
+        Arc<ReplayGuard>,
+    )>,
     req: Request<axum::body::Body>,
     next: Next,
 ) -> Result<impl IntoResponse, AuthMiddlewareError> {
@@ -156,8 +191,25 @@ pub(super) async fn auth_middleware(
         _ => &body_bytes,
     };
 
+    let api_key = match auth_header.as_ref().filter(|a| !a.api_ident.is_empty()) {
+        Some(header) => key_store.find_key(&header.api_ident).await?,
+        None => None,
+    };
+    let authenticated = match (
+        authenticate(auth_header.as_ref(), api_key.as_ref(), bytes_to_hash),
+        &auth_header,
+    ) {
+        (Ok(key), Some(header)) => check_replay(header, key, &replay_guard)
+            .await?
+            .map(|()| key),
+        (result, _) => result,
+    };
+    if let Ok(key) = authenticated {
+        key_store.record_usage(key.id).await;
+    }
+
     let auth = AuthInfo {
-        authenticated: authenticate(auth_header.as_ref(), &api_keys, bytes_to_hash),
+        authenticated: authenticated.map(|k| k.scope),
 
         client_id: auth_header.as_ref().map(|a| a.client_id),
         api_ident: auth_header
@@ -202,25 +254,39 @@ async fn extract_operation_bytes_from_multipart(
     operations_bytes.ok_or(AuthMiddlewareError::MissingOperationsPart)
 }
 
-fn authenticate(
+/// Checks the hash of the request using `api_key`, which has to be the key with the identifier given in `info`.
+fn authenticate<'a>(
     info: Option<&MensaAuthHeader>,
-    api_keys: &[ApiKey],
+    api_key: Option<&'a ApiKey>,
     bytes_to_hash: &[u8],
-) -> Result<(), AuthFailReason> {
+) -> Result<&'a ApiKey, AuthFailReason> {
     let auth = info.ok_or(AuthFailReason::NoAuthHeader)?;
 
     if auth.api_ident.is_empty() || auth.hash.is_empty() {
         return Err(AuthFailReason::MissingApiIdentOrHash);
     }
 
-    let api_key = &api_keys
-        .iter()
-        .find(|k| k.key.starts_with(&auth.api_ident))
-        .ok_or(AuthFailReason::InvalidApiKey)?
-        .key;
+    let api_key = api_key
+        .filter(|k| k.ident == auth.api_ident)
+        .ok_or(AuthFailReason::InvalidApiKey)?;
+
+    if let Some(expiry_date) = api_key.expiry_date {
+        if expiry_date < Local::now().date_naive() {
+            return Err(AuthFailReason::ExpiredApiKey(expiry_date));
+        }
+    }
 
-    let mut hmac =
-        Hmac::<Sha512>::new_from_slice(api_key.as_bytes()).expect("HMAC can take keys of any size");
+    let mut hmac = Hmac::<Sha512>::new_from_slice(api_key.key.as_bytes())
+        .expect("HMAC can take keys of any size");
+    if let Some(replay) = &auth.replay_protection {
+        hmac.update(
+            format!(
+                "{}{AUTH_SEPARATOR}{}{AUTH_SEPARATOR}",
+                replay.timestamp, replay.nonce
+            )
+            .as_bytes(),
+        );
+    }
     hmac.update(bytes_to_hash);
     let hash = hmac.finalize().into_bytes().to_vec();
 
@@ -228,10 +294,30 @@ fn authenticate(
         .decode(&auth.hash)
         .map_err(|_| AuthFailReason::HashNotInBase64)?;
 
-    if hash == given_hash {
-        Ok(())
+    if bool::from(hash.ct_eq(&given_hash)) {
+        Ok(api_key)
     } else {
-        Err(AuthFailReason::HashNotMatching(hash))
+        Err(AuthFailReason::HashNotMatching)
+    }
+}
+
+/// Checks whether an authenticated request is no replay of an earlier one.
+/// Requests without timestamp and nonce are only accepted with api keys in compatibility mode.
+/// # Errors
+/// if the used nonces could not be accessed
+async fn check_replay(
+    auth: &MensaAuthHeader,
+    api_key: &ApiKey,
+    replay_guard: &ReplayGuard,
+) -> Result<Result<(), AuthFailReason>, DataError> {
+    match &auth.replay_protection {
+        Some(replay) => {
+            replay_guard
+                .check(api_key.id, replay.timestamp, &replay.nonce)
+                .await
+        }
+        None if api_key.compatibility_mode => Ok(Ok(())),
+        None => Ok(Err(AuthFailReason::MissingReplayProtection)),
     }
 }
 
@@ -256,11 +342,20 @@ fn read_auth_from_header(header: &str) -> Option<MensaAuthHeader> {
     let client_id = Uuid::try_from(*parts.first()?).ok()?;
     let api_ident = *parts.get(1)?;
     let hash = *parts.get(2)?;
+    let replay_protection = match parts.get(3..)? {
+        [] => None,
+        [timestamp, nonce] if !nonce.is_empty() => Some(ReplayProtection {
+            timestamp: timestamp.parse().ok()?,
+            nonce: (*nonce).into(),
+        }),
+        _ => return None,
+    };
 
     Some(MensaAuthHeader {
         client_id,
         api_ident: api_ident.into(),
         hash: hash.into(),
+        replay_protection,
     })
 }
 
@@ -269,6 +364,7 @@ mod tests {
     #![allow(clippy::unwrap_used)]
 
     use super::*;
+    use crate::layer::trigger::api::mock::AuthDataMock;
     use std::str::FromStr;
 
     #[test]
@@ -314,10 +410,35 @@ mod tests {
             client_id: Uuid::try_from("1d75d380-cf07-4edb-9046-a2d981bc219d").unwrap(),
             api_ident: "abc".into(),
             hash: "123".into(),
+            replay_protection: None,
         });
         assert_eq!(expected_auth_info, auth_info);
     }
 
+    #[test]
+    fn test_auth_info_parsing_replay_protection() {
+        let client_id = Uuid::new_v4();
+        let encode = |message: String| {
+            format!(
+                "{AUTH_TYPE} {}",
+                general_purpose::STANDARD.encode(format!("{client_id}:abc:1234{message}"))
+            )
+        };
+
+        let auth_info = read_auth_from_header(&encode(":1700000000:nonce".into())).unwrap();
+        assert_eq!(
+            auth_info.replay_protection,
+            Some(ReplayProtection {
+                timestamp: 1_700_000_000,
+                nonce: "nonce".into()
+            })
+        );
+
+        assert!(read_auth_from_header(&encode(":1700000000".into())).is_none());
+        assert!(read_auth_from_header(&encode(":1700000000:".into())).is_none());
+        assert!(read_auth_from_header(&encode(":yesterday:nonce".into())).is_none());
+    }
+
     #[test]
     fn test_is_multipart() {
         assert!(!is_multipart(ContentType::jpeg()));
@@ -370,7 +491,7 @@ mod tests {
 
     #[test]
     fn test_authenticate() {
-        assert!(authenticate(None, &[], &[]).is_err());
+        assert!(authenticate(None, None, &[]).is_err());
 
         let bytes = &[8u8, 123u8, 11u8, 61u8, 222u8];
         let api_key = "1234567890";
@@ -385,21 +506,126 @@ mod tests {
 
         let header = MensaAuthHeader {
             client_id: Uuid::from_str("e997c2e3-68e1-4b6d-b328-4adcd573c834").unwrap(),
-            api_ident: "123".into(),
+            api_ident: api_key.into(),
             hash: hash64,
+            replay_protection: None,
+        };
+
+        let key = |ident: &str, key: &str| ApiKey {
+            id: Uuid::default(),
+            ident: ident.into(),
+            key: key.into(),
+            description: String::new(),
+            scope: ApiKeyScope::Moderate,
+            expiry_date: None,
+            compatibility_mode: true,
+        };
+        let valid = key(api_key, api_key);
+        assert!(authenticate(Some(&header), Some(&valid), bytes).is_ok());
+        assert!(matches!(
+            authenticate(Some(&header), Some(&key("abc", "abc")), bytes),
+            Err(AuthFailReason::InvalidApiKey)
+        ));
+        assert!(matches!(
+            authenticate(Some(&header), None, bytes),
+            Err(AuthFailReason::InvalidApiKey)
+        ));
+
+        // identifiers have to match exactly
+        for ident in ["123", "12345678901", "1"] {
+            let header = MensaAuthHeader {
+                api_ident: ident.into(),
+                ..header.clone()
+            };
+            assert!(matches!(
+                authenticate(Some(&header), Some(&valid), bytes),
+                Err(AuthFailReason::InvalidApiKey)
+            ));
+        }
+
+        let expired = ApiKey {
+            expiry_date: Some(Date::default()),
+            ..valid
+        };
+        assert!(matches!(
+            authenticate(Some(&header), Some(&expired), bytes),
+            Err(AuthFailReason::ExpiredApiKey(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replay_protection() {
+        let bytes = b"{}";
+        let api_key = ApiKey {
+            id: Uuid::default(),
+            ident: "1234567890".into(),
+            key: "1234567890".into(),
+            description: String::new(),
+            scope: ApiKeyScope::Moderate,
+            expiry_date: None,
+            compatibility_mode: false,
         };
+        let replay_guard = ReplayGuard::new(
+            std::time::Duration::from_mins(1),
+            Arc::new(AuthDataMock::default()),
+        );
+        let timestamp = chrono::Utc::now().timestamp();
 
-        let key_list = &[
-            ApiKey {
-                description: String::new(),
-                key: String::from("abc"),
-            },
-            ApiKey {
-                description: String::new(),
-                key: api_key.into(),
-            },
-        ];
-
-        assert!(authenticate(Some(&header), key_list, bytes).is_ok());
+        let hash = Hmac::<Sha512>::new_from_slice(api_key.key.as_bytes())
+            .unwrap()
+            .chain_update(format!("{timestamp}:nonce:"))
+            .chain_update(bytes)
+            .finalize()
+            .into_bytes();
+        let header = MensaAuthHeader {
+            client_id: Uuid::default(),
+            api_ident: api_key.ident.clone(),
+            hash: STANDARD.encode(hash),
+            replay_protection: Some(ReplayProtection {
+                timestamp,
+                nonce: "nonce".into(),
+            }),
+        };
+        let key = authenticate(Some(&header), Some(&api_key), bytes).unwrap();
+        assert!(check_replay(&header, key, &replay_guard)
+            .await
+            .unwrap()
+            .is_ok());
+        assert!(matches!(
+            check_replay(&header, key, &replay_guard).await.unwrap(),
+            Err(AuthFailReason::NonceReused)
+        ));
+
+        // timestamp and nonce are part of the hash
+        let changed = MensaAuthHeader {
+            replay_protection: Some(ReplayProtection {
+                timestamp,
+                nonce: "other".into(),
+            }),
+            ..header.clone()
+        };
+        assert!(matches!(
+            authenticate(Some(&changed), Some(&api_key), bytes),
+            Err(AuthFailReason::HashNotMatching)
+        ));
+
+        let legacy = MensaAuthHeader {
+            replay_protection: None,
+            ..header
+        };
+        assert!(matches!(
+            check_replay(&legacy, &api_key, &replay_guard)
+                .await
+                .unwrap(),
+            Err(AuthFailReason::MissingReplayProtection)
+        ));
+        let compatible = ApiKey {
+            compatibility_mode: true,
+            ..api_key
+        };
+        assert!(check_replay(&legacy, &compatible, &replay_guard)
+            .await
+            .unwrap()
+            .is_ok());
     }
 }
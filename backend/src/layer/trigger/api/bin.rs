@@ -1,14 +1,13 @@
-use std::env::temp_dir;
+use std::{env::temp_dir, time::Duration};
 
 use dotenvy::dotenv;
 use mensa_app_backend::layer::{
     logic::api_command::{
+        client_limits::ClientLimitInfo,
         command_handler::CommandHandler,
         image_preprocessing::ImagePreprocessingInfo,
-        mocks::{
-            CommandAdminNotificationMock, CommandDatabaseMock, CommandImageStorageMock,
-            CommandImageValidationMock,
-        },
+        mocks::{CommandAdminNotificationMock, CommandDatabaseMock, CommandImageStorageMock},
+        report_policy::ReportPolicy,
     },
     trigger::api::{mock::AuthDataMock, server::ApiServerInfo, *},
 };
@@ -36,6 +35,12 @@ async fn main() {
         rate_limit: None,
         max_body_size: 10 << 20,
         admin_key: "admin".into(),
+        admin_session_duration: Duration::from_hours(12),
+        oidc: None,
+        api_key_refresh_interval: Duration::from_mins(1),
+        max_request_age: Duration::from_mins(5),
+        rate_limits: rate_limit::RateLimitInfo::default(),
+        mass_report_threshold: 20,
     };
 
     let image_pre_info = ImagePreprocessingInfo {
@@ -48,15 +53,18 @@ async fn main() {
         mock::RequestDatabaseMock,
         CommandHandler::new(
             image_pre_info,
+            ReportPolicy::default(),
+            ClientLimitInfo::default(),
             CommandDatabaseMock,
-            CommandAdminNotificationMock,
+            CommandAdminNotificationMock::default(),
             CommandImageStorageMock,
-            CommandImageValidationMock,
         )
         .expect("could not create command mock"),
-        AuthDataMock,
-    )
-    .await;
+        AuthDataMock::default(),
+        mock::AdminDataMock,
+        vec![],
+        None,
+    );
     server.start().await;
     tokio::signal::ctrl_c()
         .await
@@ -0,0 +1,197 @@
+//! Endpoints for container orchestrators checking whether the server is alive and ready to serve requests.
+//!
+//! - `/health/live` answers as long as the server is running.
+//! - `/health/ready` additionally checks all dependencies required for serving requests, see [`HealthCheck::is_required`].
+//! - `/health` reports all dependencies, including optional ones like the mail server.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use axum::{http::StatusCode, routing::get, Json, Router};
+use futures::future::join_all;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::interface::health::HealthCheck;
+
+/// Time after which a dependency not answering counts as not usable.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const OK: &str = "ok";
+
+/// Result of checking the dependencies of the server.
+#[derive(Debug, Serialize)]
+struct Readiness {
+    /// Whether all required dependencies are usable.
+    ready: bool,
+    /// Either [`OK`] or the problem, by the name of the dependency.
+    checks: BTreeMap<&'static str, String>,
+}
+
+struct HealthState {
+    checks: Vec<Box<dyn HealthCheck>>,
+    /// Readiness at the last check, so changes get logged only once instead of on every probe.
+    was_ready: AtomicBool,
+}
+
+/// Router serving the health endpoints running the given `checks`.
+pub(super) fn health_router(checks: Vec<Box<dyn HealthCheck>>) -> Router<()> {
+    let state = Arc::new(HealthState {
+        checks,
+        was_ready: AtomicBool::new(true),
+    });
+    let ready_state = state.clone();
+    Router::new()
+        .route("/health/live", get(|| async { OK }))
+        .route(
+            "/health/ready",
+            get(move || async move { ready_state.check(true).await }),
+        )
+        .route(
+            "/health",
+            get(move || async move { state.check(false).await }),
+        )
+}
+
+impl HealthState {
+    /// Runs the required checks, or all if not `only_required`.
+    async fn check(&self, only_required: bool) -> (StatusCode, Json<Readiness>) {
+        let checks = self
+            .checks
+            .iter()
+            .filter(|check| !only_required || check.is_required());
+        let results = join_all(checks.map(|check| async {
+            let result = match tokio::time::timeout(CHECK_TIMEOUT, check.check_health()).await {
+                Ok(Ok(())) => OK.to_owned(),
+                Ok(Err(e)) => e.to_string(),
+                Err(_) => format!("no answer within {}s", CHECK_TIMEOUT.as_secs()),
+            };
+            (check.is_required(), check.name(), result)
+        }))
+        .await;
+
+        let ready = results
+            .iter()
+            .all(|(required, _, result)| !required || result == OK);
+        let checks: BTreeMap<_, _> = results
+            .into_iter()
+            .map(|(_, name, result)| (name, result))
+            .collect();
+        if self.was_ready.swap(ready, Ordering::Relaxed) != ready {
+            if ready {
+                info!("Server is ready again");
+            } else {
+                warn!("Server is not ready: {checks:?}");
+            }
+        }
+
+        let status = if ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        (status, Json(Readiness { ready, checks }))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    use crate::interface::health::{HealthCheck, HealthError, Result};
+
+    use super::{super as api, health_router};
+
+    /// Check of a dependency which is either working or never got parsed.
+    struct CheckMock(bool);
+
+    /// Check of an optional dependency which is not reachable.
+    struct OptionalCheckMock;
+
+    #[async_trait]
+    impl HealthCheck for OptionalCheckMock {
+        fn name(&self) -> &'static str {
+            "optional"
+        }
+
+        fn is_required(&self) -> bool {
+            false
+        }
+
+        async fn check_health(&self) -> Result<()> {
+            Err(HealthError::Unreachable("down".into()))
+        }
+    }
+
+    #[async_trait]
+    impl HealthCheck for CheckMock {
+        fn name(&self) -> &'static str {
+            if self.0 {
+                "working"
+            } else {
+                "broken"
+            }
+        }
+
+        async fn check_health(&self) -> Result<()> {
+            if self.0 {
+                Ok(())
+            } else {
+                Err(HealthError::NeverParsed)
+            }
+        }
+    }
+
+    async fn serve(checks: Vec<Box<dyn HealthCheck>>) -> String {
+        format!("{}/health", api::serve(health_router(checks)).await)
+    }
+
+    #[tokio::test]
+    async fn test_health_endpoints() {
+        let url = serve(vec![Box::new(CheckMock(true))]).await;
+        let live = reqwest::get(format!("{url}/live")).await.unwrap();
+        assert!(live.status().is_success());
+
+        let ready = reqwest::get(format!("{url}/ready")).await.unwrap();
+        assert!(ready.status().is_success());
+        assert_eq!(
+            ready.json::<Value>().await.unwrap(),
+            json!({"ready": true, "checks": {"working": "ok"}})
+        );
+
+        let url = serve(vec![Box::new(CheckMock(true)), Box::new(CheckMock(false))]).await;
+        let ready = reqwest::get(format!("{url}/ready")).await.unwrap();
+        assert_eq!(ready.status(), 503);
+        assert_eq!(
+            ready.json::<Value>().await.unwrap(),
+            json!({"ready": false, "checks": {"working": "ok", "broken": "no successful parse yet"}})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_optional_checks() {
+        let url = serve(vec![Box::new(CheckMock(true)), Box::new(OptionalCheckMock)]).await;
+
+        // optional dependencies are not checked for readiness
+        let ready = reqwest::get(format!("{url}/ready")).await.unwrap();
+        assert!(ready.status().is_success());
+        assert_eq!(
+            ready.json::<Value>().await.unwrap(),
+            json!({"ready": true, "checks": {"working": "ok"}})
+        );
+
+        let health = reqwest::get(&url).await.unwrap();
+        assert!(health.status().is_success());
+        assert_eq!(
+            health.json::<Value>().await.unwrap(),
+            json!({"ready": true, "checks": {"working": "ok", "optional": "not reachable: down"}})
+        );
+    }
+}
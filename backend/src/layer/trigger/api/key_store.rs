@@ -0,0 +1,160 @@
+//! Module keeping the api keys used for authentication up to date, see [`ApiKeyStore`].
+
+use std::{collections::HashMap, mem, sync::Arc};
+
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::{
+    interface::persistent_data::{model::ApiKey, AuthDataAccess, Result as DataResult},
+    util::{api_key_ident, generate_api_key, ApiKeyScope, Date, Uuid},
+};
+
+/// Type for sharing the data access for api keys.
+pub(super) type ArcAuthData = Arc<dyn AuthDataAccess + Send + Sync>;
+
+/// Access to the api keys, which get looked up in the database for every request, so changes apply on all instances immediately.
+/// Requests made with each key get counted here until they are written to the database by [`Self::flush_usage`].
+pub(super) struct ApiKeyStore {
+    auth_data: ArcAuthData,
+    usage: Mutex<HashMap<Uuid, u64>>,
+}
+
+impl ApiKeyStore {
+    /// Creates a new store using the api keys of `auth_data`.
+    pub(super) fn new(auth_data: ArcAuthData) -> Self {
+        Self {
+            auth_data,
+            usage: Mutex::default(),
+        }
+    }
+
+    /// Returns the data access the keys get loaded from.
+    pub(super) fn auth_data(&self) -> &ArcAuthData {
+        &self.auth_data
+    }
+
+    /// Returns the api key with exactly the given identifier, if it did not get revoked.
+    /// # Errors
+    /// if the api key could not be read
+    pub(super) async fn find_key(&self, ident: &str) -> DataResult<Option<ApiKey>> {
+        self.auth_data.get_api_key(ident).await
+    }
+
+    /// Counts an authenticated request made with the given api key.
+    pub(super) async fn record_usage(&self, key_id: Uuid) {
+        *self.usage.lock().await.entry(key_id).or_default() += 1;
+    }
+
+    /// Writes all requests counted since the last call to the database.
+    /// # Errors
+    /// if the usage could not be saved, in which case the counted requests get kept for the next try
+    pub(super) async fn flush_usage(&self) -> DataResult<()> {
+        let usage = mem::take(&mut *self.usage.lock().await);
+        if usage.is_empty() {
+            return Ok(());
+        }
+
+        let entries: Vec<_> = usage.iter().map(|(id, count)| (*id, *count)).collect();
+        if let Err(e) = self.auth_data.add_api_key_usage(&entries).await {
+            let mut current = self.usage.lock().await;
+            for (id, count) in usage {
+                *current.entry(id).or_default() += count;
+            }
+            drop(current);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Creates a new api key and makes it usable immediately. Returns the id and the new key, which does not get stored.
+    /// `added_by` gets recorded as the one responsible.
+    /// # Errors
+    /// if the api key could not be stored
+    pub(super) async fn add_key(
+        &self,
+        description: &str,
+        scope: ApiKeyScope,
+        expiry_date: Option<Date>,
+        added_by: &str,
+    ) -> DataResult<(Uuid, String)> {
+        let key = generate_api_key();
+        let id = self
+            .auth_data
+            .add_api_key(
+                api_key_ident(&key),
+                &key,
+                description,
+                scope,
+                expiry_date,
+                added_by,
+            )
+            .await?;
+        Ok((id, key))
+    }
+
+    /// Replaces the given api key by a new one, which is usable immediately while the old one is not. Returns the new key.
+    /// `rotated_by` gets recorded as the one responsible.
+    /// # Errors
+    /// if there is no such api key or it could not be stored
+    pub(super) async fn rotate_key(&self, id: Uuid, rotated_by: &str) -> DataResult<String> {
+        let key = generate_api_key();
+        self.auth_data
+            .rotate_api_key(id, api_key_ident(&key), &key, rotated_by)
+            .await?;
+        Ok(key)
+    }
+
+    /// Revokes the given api key, so it can not be used anymore.
+    /// `revoked_by` gets recorded as the one responsible.
+    /// # Errors
+    /// if there is no such api key or it could not be revoked
+    pub(super) async fn revoke_key(&self, id: Uuid, revoked_by: &str) -> DataResult<()> {
+        self.auth_data.revoke_api_key(id, revoked_by).await
+    }
+
+    /// Sets whether requests without replay protection are accepted with the given api key.
+    /// `changed_by` gets recorded as the one responsible.
+    /// # Errors
+    /// if there is no such api key or it could not be changed
+    pub(super) async fn set_compatibility_mode(
+        &self,
+        id: Uuid,
+        enabled: bool,
+        changed_by: &str,
+    ) -> DataResult<()> {
+        self.auth_data
+            .set_api_key_compatibility_mode(id, enabled, changed_by)
+            .await
+    }
+
+    /// Saves the usage statistics, logging any errors.
+    pub(super) async fn save_usage(&self) {
+        if let Err(e) = self.flush_usage().await {
+            warn!("Could not save api key usage: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::sync::Arc;
+
+    use super::ApiKeyStore;
+    use crate::{layer::trigger::api::mock::AuthDataMock, util::Uuid};
+
+    #[tokio::test]
+    async fn test_key_store() {
+        let store = ApiKeyStore::new(Arc::new(AuthDataMock::default()));
+        assert!(store.find_key("1234567890").await.unwrap().is_some());
+        assert!(store.find_key("123").await.unwrap().is_none());
+
+        store.record_usage(Uuid::from_u128(1)).await;
+        store.record_usage(Uuid::from_u128(1)).await;
+        assert_eq!(store.usage.lock().await.get(&Uuid::from_u128(1)), Some(&2));
+
+        store.save_usage().await;
+        assert!(store.usage.lock().await.is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+//! Prometheus metrics of the whole application, served under `/metrics`.
+//!
+//! Metrics get recorded with the macros of the [`metrics`] crate throughout the application,
+//! these are collected by the recorder installed with [`install_recorder`].
+
+use std::{
+    sync::{Arc, OnceLock},
+    time::Instant,
+};
+
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextResolve, ResolveInfo},
+    ServerResult, Value,
+};
+use async_trait::async_trait;
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+    routing::get,
+    Router,
+};
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, histogram, Unit};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+/// Histogram buckets in seconds, from fast requests to full parsing runs.
+const DURATION_BUCKETS: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Installs the global recorder collecting all metrics, if not done yet.
+/// Returns a handle for rendering the collected metrics.
+///
+/// # Panics
+/// If another global metrics recorder got installed before.
+pub fn install_recorder() -> PrometheusHandle {
+    HANDLE
+        .get_or_init(|| {
+            let handle = PrometheusBuilder::new()
+                .set_buckets_for_metric(Matcher::Suffix("seconds".into()), &DURATION_BUCKETS)
+                .expect("buckets are not empty")
+                .install_recorder()
+                .expect("no other metrics recorder should be installed");
+            describe_metrics();
+            handle
+        })
+        .clone()
+}
+
+fn describe_metrics() {
+    describe_counter!("http_requests_total", "Number of handled http requests.");
+    describe_histogram!(
+        "http_request_duration_seconds",
+        Unit::Seconds,
+        "Time taken to handle http requests."
+    );
+    describe_counter!(
+        "graphql_operations_total",
+        "Number of resolved top-level graphql queries and mutations."
+    );
+    describe_histogram!(
+        "graphql_operation_duration_seconds",
+        Unit::Seconds,
+        "Time taken to resolve top-level graphql queries and mutations."
+    );
+    describe_gauge!(
+        "db_pool_connections",
+        "Number of open database connections, by whether they are idle or in use."
+    );
+    describe_histogram!(
+        "scheduled_job_duration_seconds",
+        Unit::Seconds,
+        "Time taken by scheduled jobs like parsing and image review."
+    );
+    describe_counter!(
+        "mealplan_parse_runs_total",
+        "Number of meal plan parsing runs, by kind and outcome."
+    );
+    describe_counter!(
+        "image_uploads_total",
+        "Number of image uploads, by outcome."
+    );
+    describe_counter!(
+        "image_reviews_total",
+        "Number of automatic image reviews, by outcome."
+    );
+    describe_counter!(
+        "image_reverifications_total",
+        "Number of repeated validations of visible images, by outcome."
+    );
+    describe_counter!(
+        "admin_notification_failures_total",
+        "Number of administrator notifications which could not be sent, by channel."
+    );
+}
+
+/// Router serving the metrics rendered by `handle` in the prometheus text format.
+pub(super) fn metrics_router(handle: PrometheusHandle) -> Router<()> {
+    Router::new().route("/metrics", get(move || async move { handle.render() }))
+}
+
+/// Middleware recording the number and duration of http requests per route.
+pub(super) async fn track_requests(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    // the route, not the actual path, to keep the number of label values bounded
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_owned(), |path| path.as_str().to_owned());
+    let method = request.method().to_string();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    counter!("http_requests_total", "method" => method, "route" => route.clone(), "status" => status)
+        .increment(1);
+    histogram!("http_request_duration_seconds", "route" => route).record(start.elapsed());
+    response
+}
+
+/// Graphql extension recording the number, outcome and duration of all top-level queries and mutations.
+pub(super) struct GraphQLMetrics;
+
+impl ExtensionFactory for GraphQLMetrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(Self)
+    }
+}
+
+#[async_trait]
+impl Extension for GraphQLMetrics {
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        if info.path_node.parent.is_some() || info.is_for_introspection {
+            return next.run(ctx, info).await;
+        }
+
+        // field names are given by the schema, unlike operation names chosen by clients
+        let operation = info.name.to_owned();
+        let start = Instant::now();
+        let result = next.run(ctx, info).await;
+
+        let outcome = if result.is_ok() { "success" } else { "error" };
+        counter!("graphql_operations_total", "operation" => operation.clone(), "outcome" => outcome)
+            .increment(1);
+        histogram!("graphql_operation_duration_seconds", "operation" => operation)
+            .record(start.elapsed());
+        result
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use axum::{middleware, routing::get, Router};
+
+    use super::{install_recorder, metrics_router, track_requests};
+    use crate::layer::trigger::api::serve;
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let handle = install_recorder();
+        let router = Router::new()
+            .route("/hello/{name}", get(|| async { "hello" }))
+            .layer(middleware::from_fn(track_requests))
+            .merge(metrics_router(handle));
+        let url = serve(router).await;
+
+        reqwest::get(format!("{url}/hello/world")).await.unwrap();
+        let metrics = reqwest::get(format!("{url}/metrics"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        assert!(metrics
+            .contains(r#"http_requests_total{method="GET",route="/hello/{name}",status="200"} 1"#));
+        assert!(metrics.contains("http_request_duration_seconds_bucket"));
+        assert!(metrics.contains("# HELP http_requests_total"));
+    }
+}
@@ -1,17 +1,30 @@
 //! This crate contains mocks of [`RequestDataAccess`] and [`Command`] for testing.
 #![allow(missing_docs)]
 
+use std::{
+    collections::HashSet,
+    sync::{LazyLock, Mutex},
+};
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use crate::interface::persistent_data::model::EnvironmentInfo;
-use crate::util::{Additive, Allergen, Date, FoodType, Price, ReportReason};
+use crate::util::{
+    hash_password, hash_token, Additive, AdminRole, Allergen, ApiKeyScope, AuditAction, Date,
+    FoodType, ModerationStatus, Price, ReportReason,
+};
 use crate::{
     interface::{
         api_command::{Command, Result as CommandResult},
         persistent_data::{
-            model::{ApiKey, Canteen, Image, Line, Meal, Side},
-            AuthDataAccess, RequestDataAccess, Result as DataResult,
+            model::{
+                AdminAccount, ApiKey, ApiKeyInfo, AuditEntry, AuditFilter, Canteen, ClientBan,
+                ClientData, ExtendedImage, Image, ImageReport, Line, Meal, ModerationFilter,
+                ModerationImage, ReportingClient, Side,
+            },
+            AdminDataAccess, AuthDataAccess, DataError, RequestDataAccess, Result as DataResult,
         },
     },
     util::NutritionData,
@@ -19,6 +32,13 @@ use crate::{
 
 const INVALID_UUID: &str = "invalid uuid:";
 
+/// Password of the administrator accounts `moderator` and `operator` of [`AdminDataMock`].
+pub const ADMIN_PASSWORD: &str = "password";
+/// Token of a session of the administrator `moderator` known to [`AdminDataMock`].
+pub const ADMIN_SESSION_TOKEN: &str = "session";
+
+static ADMIN_PASSWORD_HASH: LazyLock<String> = LazyLock::new(|| hash_password(ADMIN_PASSWORD));
+
 pub struct RequestDatabaseMock;
 
 #[async_trait]
@@ -219,6 +239,8 @@ impl RequestDataAccess for RequestDatabaseMock {
             upload_date: Date::default(),
             meal_id: Uuid::default(),
             reporting_users: Option::default(),
+            uploader_id: Uuid::default(),
+            moderation_status: ModerationStatus::Accepted,
         };
         let d2 = Image {
             id: Uuid::parse_str("e4e1c2f5-881c-4e1f-8618-ca8f6f3bf1d2").expect(INVALID_UUID),
@@ -230,6 +252,8 @@ impl RequestDataAccess for RequestDatabaseMock {
             upload_date: Date::default(),
             meal_id: Uuid::default(),
             reporting_users: Option::default(),
+            uploader_id: Uuid::default(),
+            moderation_status: ModerationStatus::Accepted,
         };
         let d3 = Image {
             id: Uuid::parse_str("9f0a4fb0-c233-4a16-8f3a-2bbbf735ef07").expect(INVALID_UUID),
@@ -241,10 +265,29 @@ impl RequestDataAccess for RequestDatabaseMock {
             upload_date: Date::default(),
             meal_id: Uuid::default(),
             reporting_users: Option::default(),
+            uploader_id: Uuid::default(),
+            moderation_status: ModerationStatus::Accepted,
         };
         Ok(vec![d1, d2, d3])
     }
 
+    async fn get_client_data(&self, client_id: Uuid) -> DataResult<ClientData> {
+        Ok(ClientData {
+            client_id,
+            ..Default::default()
+        })
+    }
+
+    async fn get_my_images(&self, client_id: Uuid) -> DataResult<Vec<Image>> {
+        let image = Image {
+            id: Uuid::parse_str("be7a7c58-1fd3-4432-9669-e87603629aeb").expect(INVALID_UUID),
+            uploader_id: client_id,
+            moderation_status: ModerationStatus::Pending,
+            ..Default::default()
+        };
+        Ok(vec![image])
+    }
+
     async fn get_personal_rating(
         &self,
         _meal_id: Uuid,
@@ -359,7 +402,7 @@ impl Command for CommandMock {
         Ok(())
     }
 
-    async fn delete_image(&self, image_id: Uuid) -> CommandResult<()> {
+    async fn delete_image(&self, image_id: Uuid, _deleted_by: &str) -> CommandResult<()> {
         if image_id == FAIL_ID {
             Err(crate::interface::api_command::CommandError::DataError(
                 crate::interface::persistent_data::DataError::NoSuchItem,
@@ -369,25 +412,293 @@ impl Command for CommandMock {
         }
     }
 
-    async fn verify_image(&self, _image_id: Uuid) -> CommandResult<()> {
+    async fn verify_image(&self, _image_id: Uuid, _verified_by: &str) -> CommandResult<()> {
         Ok(())
     }
+
+    async fn restore_image(&self, image_id: Uuid, _restored_by: &str) -> CommandResult<()> {
+        self.unhide_image(image_id, "").await
+    }
+
+    async fn ban_client(
+        &self,
+        client_id: Uuid,
+        _banned_by: &str,
+        _reason: Option<String>,
+    ) -> CommandResult<()> {
+        self.unhide_image(client_id, "").await
+    }
+
+    async fn unban_client(&self, client_id: Uuid, _unbanned_by: &str) -> CommandResult<()> {
+        self.unhide_image(client_id, "").await
+    }
+
+    async fn delete_my_image(&self, image_id: Uuid, _client_id: Uuid) -> CommandResult<()> {
+        self.unhide_image(image_id, "").await
+    }
+
+    async fn erase_client_data(&self, client_id: Uuid, _erased_by: &str) -> CommandResult<()> {
+        self.unhide_image(client_id, "").await
+    }
+
+    async fn unhide_image(&self, image_id: Uuid, _unhidden_by: &str) -> CommandResult<()> {
+        if image_id == FAIL_ID {
+            Err(crate::interface::api_command::CommandError::DataError(
+                crate::interface::persistent_data::DataError::NoSuchItem,
+            ))
+        } else {
+            Ok(())
+        }
+    }
 }
 
-pub struct AuthDataMock;
+/// Mock of [`AuthDataAccess`] with two api keys, remembering used nonces forever.
+#[derive(Default)]
+pub struct AuthDataMock {
+    nonces: Mutex<HashSet<(Uuid, String)>>,
+}
 
 #[async_trait]
 impl AuthDataAccess for AuthDataMock {
-    async fn get_api_keys(&self) -> DataResult<Vec<ApiKey>> {
-        Ok(vec![
+    async fn get_api_key(&self, ident: &str) -> DataResult<Option<ApiKey>> {
+        Ok([
             ApiKey {
+                id: Uuid::from_u128(1),
+                ident: "1234567890".into(),
                 key: "1234567890".into(),
                 description: String::new(),
+                scope: ApiKeyScope::Moderate,
+                expiry_date: None,
+                compatibility_mode: true,
             },
             ApiKey {
+                id: Uuid::from_u128(2),
+                ident: "YWpzZGg4Mn".into(),
                 key: "YWpzZGg4MnozNzhkMnppZGFzYXNkMiBzYWZzYSBzPGE5MDk4".into(),
                 description: String::new(),
+                scope: ApiKeyScope::ReadOnly,
+                expiry_date: None,
+                compatibility_mode: false,
+            },
+        ]
+        .into_iter()
+        .find(|key| key.ident == ident))
+    }
+
+    async fn get_api_key_infos(&self) -> DataResult<Vec<ApiKeyInfo>> {
+        Ok(vec![ApiKeyInfo {
+            id: Uuid::from_u128(1),
+            ident: "1234567890".into(),
+            description: String::new(),
+            scope: ApiKeyScope::Moderate,
+            legacy: true,
+            compatibility_mode: true,
+            created_date: Date::default(),
+            expiry_date: None,
+            revoked_date: None,
+            request_count: 42,
+            last_used: None,
+        }])
+    }
+
+    async fn add_api_key(
+        &self,
+        _ident: &str,
+        _key: &str,
+        _description: &str,
+        _scope: ApiKeyScope,
+        _expiry_date: Option<Date>,
+        _added_by: &str,
+    ) -> DataResult<Uuid> {
+        Ok(Uuid::from_u128(3))
+    }
+
+    async fn rotate_api_key(
+        &self,
+        id: Uuid,
+        _ident: &str,
+        _key: &str,
+        rotated_by: &str,
+    ) -> DataResult<()> {
+        self.revoke_api_key(id, rotated_by).await
+    }
+
+    async fn revoke_api_key(&self, id: Uuid, _revoked_by: &str) -> DataResult<()> {
+        if id == FAIL_ID {
+            Err(crate::interface::persistent_data::DataError::NoSuchItem)
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn set_api_key_compatibility_mode(
+        &self,
+        id: Uuid,
+        _enabled: bool,
+        changed_by: &str,
+    ) -> DataResult<()> {
+        self.revoke_api_key(id, changed_by).await
+    }
+
+    async fn add_api_key_usage(&self, _usage: &[(Uuid, u64)]) -> DataResult<()> {
+        Ok(())
+    }
+
+    async fn use_api_key_nonce(
+        &self,
+        key_id: Uuid,
+        nonce: &str,
+        _expires_at: DateTime<Utc>,
+    ) -> DataResult<bool> {
+        Ok(self
+            .nonces
+            .lock()
+            .expect("failed to lock mutex for `nonces`")
+            .insert((key_id, nonce.into())))
+    }
+
+    async fn remove_expired_api_key_nonces(&self) -> DataResult<()> {
+        Ok(())
+    }
+}
+
+pub struct AdminDataMock;
+
+#[async_trait]
+impl AdminDataAccess for AdminDataMock {
+    async fn get_moderation_images(
+        &self,
+        _filter: &ModerationFilter,
+    ) -> DataResult<Vec<ModerationImage>> {
+        Ok(vec![ModerationImage {
+            info: ExtendedImage {
+                image: Image {
+                    id: Uuid::from_u128(1),
+                    report_count: 1,
+                    moderation_status: ModerationStatus::Accepted,
+                    ..Default::default()
+                },
+                meal_name: "dummy_meal".into(),
+                other_image_urls: vec![],
             },
+            hidden: true,
+            deletion_date: None,
+            reports: vec![ImageReport {
+                client_id: Uuid::from_u128(2),
+                reason: ReportReason::Advert,
+                date: Date::default(),
+            }],
+        }])
+    }
+
+    async fn get_mass_reporters(
+        &self,
+        min_reports: u32,
+        _days: u32,
+    ) -> DataResult<Vec<ReportingClient>> {
+        Ok(vec![ReportingClient {
+            client_id: Uuid::from_u128(2),
+            report_count: min_reports,
+            banned: false,
+        }])
+    }
+
+    async fn get_audit_log(&self, filter: &AuditFilter) -> DataResult<Vec<AuditEntry>> {
+        let entry = AuditEntry {
+            id: 1,
+            time: DateTime::default(),
+            actor: filter.actor.clone().unwrap_or_else(|| "admin".into()),
+            action: filter.action.unwrap_or(AuditAction::DeleteImage),
+            target_id: filter.target_id.unwrap_or_default(),
+            reason: None,
+        };
+        Ok(vec![entry; filter.limit.min(1) as usize])
+    }
+
+    async fn get_banned_clients(&self) -> DataResult<Vec<ClientBan>> {
+        Ok(vec![ClientBan {
+            client_id: Uuid::from_u128(3),
+            banned_by: "admin".into(),
+            reason: None,
+            date: Date::default(),
+        }])
+    }
+
+    async fn get_client_data(&self, client_id: Uuid) -> DataResult<ClientData> {
+        Ok(ClientData {
+            client_id,
+            ..Default::default()
+        })
+    }
+
+    async fn get_admin_accounts(&self) -> DataResult<Vec<AdminAccount>> {
+        Ok(vec![
+            self.get_admin_account("moderator").await?,
+            self.get_admin_account("operator").await?,
         ])
     }
+
+    async fn get_admin_account(&self, username: &str) -> DataResult<AdminAccount> {
+        let role = match username {
+            "moderator" => AdminRole::Moderator,
+            "operator" => AdminRole::Operator,
+            _ => return Err(DataError::NoSuchItem),
+        };
+        Ok(AdminAccount {
+            username: username.into(),
+            password_hash: Some(ADMIN_PASSWORD_HASH.clone()),
+            role,
+            created_date: Date::default(),
+        })
+    }
+
+    async fn save_admin_account(
+        &self,
+        _username: &str,
+        _password_hash: Option<&str>,
+        _role: AdminRole,
+        _saved_by: &str,
+    ) -> DataResult<()> {
+        Ok(())
+    }
+
+    async fn save_oidc_account(
+        &self,
+        _issuer: &str,
+        _subject: &str,
+        username: &str,
+        role: AdminRole,
+    ) -> DataResult<Option<AdminAccount>> {
+        Ok(Some(AdminAccount {
+            username: username.into(),
+            password_hash: None,
+            role,
+            created_date: Date::default(),
+        }))
+    }
+
+    async fn delete_admin_account(&self, username: &str, _deleted_by: &str) -> DataResult<()> {
+        self.get_admin_account(username).await.map(|_| ())
+    }
+
+    async fn add_admin_session(
+        &self,
+        _token_hash: &str,
+        _username: &str,
+        _expires_at: DateTime<Utc>,
+    ) -> DataResult<()> {
+        Ok(())
+    }
+
+    async fn get_admin_session(&self, token_hash: &str) -> DataResult<AdminAccount> {
+        if token_hash == hash_token(ADMIN_SESSION_TOKEN) {
+            self.get_admin_account("moderator").await
+        } else {
+            Err(DataError::NoSuchItem)
+        }
+    }
+
+    async fn delete_admin_session(&self, _token_hash: &str) -> DataResult<()> {
+        Ok(())
+    }
 }
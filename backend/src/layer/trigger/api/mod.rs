@@ -1,12 +1,27 @@
 //! This component contains the web server that enables API requests and represents the entry point for these.
 
-mod admin;
+pub mod admin;
 pub mod auth;
+mod health;
+mod key_store;
+pub mod metrics;
 pub mod mock;
 pub mod mutation;
 pub mod query;
+pub mod rate_limit;
+mod replay_guard;
 pub mod server;
 mod types;
 pub mod util;
 
 mod tests;
+
+/// Serves `router` on a free local port for testing, returning its base url.
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+async fn serve(router: axum::Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{addr}")
+}
@@ -1,7 +1,7 @@
 //! See [`MutationRoot`].
 
 use super::util::{read_and_validate_upload, ApiUtil};
-use crate::util::{ReportReason, Uuid};
+use crate::util::{ApiKeyScope, ReportReason, Uuid};
 use async_graphql::{Context, Object, Result, Upload};
 
 use tracing::{instrument, trace};
@@ -13,12 +13,13 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     /// This mutation adds an image to the specified main dish.
-    /// The user has to be authenticated.
-    /// Images will be visible to other users.
+    /// The user has to be authenticated with an api key of scope `UPLOAD`.
+    /// Images will be visible to other users after they passed an automatic validation.
+    /// Until then, only the uploader can see the image, see `Image.moderationStatus`.
     ///
     /// By uploading an image you agree to license it under the [CC0](https://creativecommons.org/publicdomain/zero/1.0/) license and pledge to have the right to do so.
     ///
-    /// The image may not contain inappropriate content, otherwise the request may fail.
+    /// The image may not contain inappropriate content, otherwise it will be rejected.
     ///
     /// If the image was added is successfully, `true` is returned.
     #[instrument(skip(self, ctx, image), fields(file_name = image.value(ctx)?.filename, file_type = image.value(ctx)?.content_type))]
@@ -30,7 +31,7 @@ impl MutationRoot {
         #[graphql(desc = "Sha512 hash of the uploaded image file. Encoded as Base64.")]
         hash: String,
     ) -> Result<bool> {
-        ctx.check_authentication()?;
+        ctx.check_scope(ApiKeyScope::Upload)?;
 
         trace!("Mutated `addImage`");
         let command = ctx.get_command();
@@ -47,7 +48,7 @@ impl MutationRoot {
     }
 
     /// This mutation either adds a rating to the specified main dish (if no such rating existed), or modifies an existing one.
-    /// The user has to be authenticated.
+    /// The user has to be authenticated with an api key of scope `UPLOAD`.
     /// If the main dish does not exist, or any other error occurs in the process, an error message is returned.
     /// If the rating was successfully added or changed, 'true' is returned.
     #[instrument(skip(self, ctx))]
@@ -57,7 +58,7 @@ impl MutationRoot {
         #[graphql(desc = "Id of the meal to rate to.")] meal_id: Uuid,
         #[graphql(desc = "The new rating of the main dish.")] rating: u32,
     ) -> Result<bool> {
-        ctx.check_authentication()?;
+        ctx.check_scope(ApiKeyScope::Upload)?;
 
         trace!("Mutated `setRating`");
         let command = ctx.get_command();
@@ -68,7 +69,7 @@ impl MutationRoot {
     }
 
     /// This mutation adds an upvote to the specified image.
-    /// The user has to be authenticated.
+    /// The user has to be authenticated with an api key of scope `UPLOAD`.
     /// If the image does not exist, or any other error occurs in the process, an error message is returned.
     /// If the upvote was successfully added, 'true' is returned.
     #[instrument(skip(self, ctx))]
@@ -77,7 +78,7 @@ impl MutationRoot {
         ctx: &Context<'_>,
         #[graphql(desc = "Id of the image to add the upvote to.")] image_id: Uuid,
     ) -> Result<bool> {
-        ctx.check_authentication()?;
+        ctx.check_scope(ApiKeyScope::Upload)?;
 
         trace!("Mutated `addUpvote`");
         let command = ctx.get_command();
@@ -88,7 +89,7 @@ impl MutationRoot {
     }
 
     /// This mutation removes the upvote from the specified image.
-    /// The user has to be authenticated.
+    /// The user has to be authenticated with an api key of scope `UPLOAD`.
     /// If the image does not exist, or any other error occurs in the process, an error message is returned.
     /// If the upvote was successfully removed, 'true' is returned.
     #[instrument(skip(self, ctx))]
@@ -97,7 +98,7 @@ impl MutationRoot {
         ctx: &Context<'_>,
         #[graphql(desc = "Id of the image to remove the upvote from.")] image_id: Uuid,
     ) -> Result<bool> {
-        ctx.check_authentication()?;
+        ctx.check_scope(ApiKeyScope::Upload)?;
 
         trace!("Mutated `removeUpvote`");
         let command = ctx.get_command();
@@ -108,7 +109,7 @@ impl MutationRoot {
     }
 
     /// This mutation adds a downvote to the specified image.
-    /// The user has to be authenticated.
+    /// The user has to be authenticated with an api key of scope `UPLOAD`.
     /// If the image does not exist, or any other error occurs in the process, an error message is returned.
     /// If the downvote was successfully added, 'true' is returned.
     #[instrument(skip(self, ctx))]
@@ -117,7 +118,7 @@ impl MutationRoot {
         ctx: &Context<'_>,
         #[graphql(desc = "Id of the image to add the downvote to.")] image_id: Uuid,
     ) -> Result<bool> {
-        ctx.check_authentication()?;
+        ctx.check_scope(ApiKeyScope::Upload)?;
 
         trace!("Mutated `addDownvote`");
         let command = ctx.get_command();
@@ -128,7 +129,7 @@ impl MutationRoot {
     }
 
     /// This mutation removes the downvote from the specified image.
-    /// The user has to be authenticated.
+    /// The user has to be authenticated with an api key of scope `UPLOAD`.
     /// If the image does not exist, or any other error occurs in the process, an error message is returned.
     /// If the downvote was successfully removed, 'true' is returned.
     #[instrument(skip(self, ctx))]
@@ -137,7 +138,7 @@ impl MutationRoot {
         ctx: &Context<'_>,
         #[graphql(desc = "Id of the image to remove the downvote from.")] image_id: Uuid,
     ) -> Result<bool> {
-        ctx.check_authentication()?;
+        ctx.check_scope(ApiKeyScope::Upload)?;
 
         trace!("Mutated `removeDownvote`");
         let command = ctx.get_command();
@@ -148,7 +149,7 @@ impl MutationRoot {
     }
 
     /// This mutation adds a report to the specified image.
-    /// The user has to be authenticated.
+    /// The user has to be authenticated with an api key of scope `MODERATE`.
     /// If the image does not exist, or any other error occurs in the process, an error message is returned.
     /// If the report was successfully added, 'true' is returned.
     #[instrument(skip(self, ctx))]
@@ -158,7 +159,7 @@ impl MutationRoot {
         #[graphql(desc = "Id of the image to report.")] image_id: Uuid,
         #[graphql(desc = "The reason for reporting the image.")] reason: ReportReason,
     ) -> Result<bool> {
-        ctx.check_authentication()?;
+        ctx.check_scope(ApiKeyScope::Moderate)?;
 
         trace!("Mutated `reportImage`");
         let command = ctx.get_command();
@@ -167,4 +168,42 @@ impl MutationRoot {
         command.report_image(image_id, reason, client_id).await?;
         Ok(true)
     }
+
+    /// This mutation permanently deletes an image uploaded by the requesting client.
+    /// The user has to be authenticated with an api key of scope `UPLOAD` and must have uploaded the image.
+    /// If the image does not exist, was uploaded by someone else, or any other error occurs in the process, an error message is returned.
+    /// If the image was successfully deleted, 'true' is returned.
+    #[instrument(skip(self, ctx))]
+    async fn delete_my_image(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Id of the image to delete.")] image_id: Uuid,
+    ) -> Result<bool> {
+        ctx.check_scope(ApiKeyScope::Upload)?;
+
+        trace!("Mutated `deleteMyImage`");
+        let command = ctx.get_command();
+        let client_id = ctx.get_client_id()?;
+
+        command.delete_my_image(image_id, client_id).await?;
+        Ok(true)
+    }
+
+    /// This mutation erases all data stored about the requesting client, see `exportMyData`.
+    /// Its ratings, votes and uploaded images get deleted, its reports are kept without reference to the client.
+    /// The user has to be authenticated with an api key of scope `UPLOAD`.
+    /// If the data was successfully erased, 'true' is returned.
+    #[instrument(skip(self, ctx))]
+    async fn erase_my_data(&self, ctx: &Context<'_>) -> Result<bool> {
+        ctx.check_scope(ApiKeyScope::Upload)?;
+
+        trace!("Mutated `eraseMyData`");
+        let command = ctx.get_command();
+        let client_id = ctx.get_client_id()?;
+
+        command
+            .erase_client_data(client_id, &client_id.to_string())
+            .await?;
+        Ok(true)
+    }
 }
@@ -1,11 +1,15 @@
 //! See [`QueryRoot`].
-use async_graphql::{Context, Object, Result};
+use async_graphql::{Context, Json, Object, Result};
 use tracing::{instrument, trace};
 
-use crate::util::{Date, Uuid};
+use crate::{
+    interface::persistent_data::model::ClientData,
+    util::{Date, Uuid},
+};
 
 use super::{
-    types::auth_info::AuthInfo, types::canteen::Canteen, types::meal::Meal, util::ApiUtil,
+    types::auth_info::AuthInfo, types::canteen::Canteen, types::image::Image, types::meal::Meal,
+    util::ApiUtil,
 };
 
 /// Class implementing `GraphQL`s root queries.
@@ -62,6 +66,39 @@ impl QueryRoot {
         Ok(meal)
     }
 
+    /// This query returns all images uploaded by the requesting client, including those which are still pending or got rejected.
+    /// The most recently uploaded images come first.
+    /// The user has to be authenticated.
+    #[instrument(skip(self, ctx))]
+    async fn get_my_images(&self, ctx: &Context<'_>) -> Result<Vec<Image>> {
+        ctx.check_authentication()?;
+
+        trace!("Queried `getMyImages`");
+        let data = ctx.get_data_access();
+        let client_id = ctx.get_client_id()?;
+        let images = data
+            .get_my_images(client_id)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        Ok(images)
+    }
+
+    /// This query returns all data stored about the requesting client as JSON object,
+    /// including its meal ratings, image votes, reports and uploaded images.
+    /// The user has to be authenticated.
+    #[instrument(skip(self, ctx))]
+    async fn export_my_data(&self, ctx: &Context<'_>) -> Result<Json<ClientData>> {
+        ctx.check_authentication()?;
+
+        trace!("Queried `exportMyData`");
+        let data = ctx.get_data_access();
+        let client_id = ctx.get_client_id()?;
+        let client_data = data.get_client_data(client_id).await?;
+        Ok(Json(client_data))
+    }
+
     /// This query returns the version of this API schema. It can also be used for health checks.
     #[instrument(skip(self, _ctx))]
     #[allow(clippy::no_effect_underscore_binding)] // clippy does not detect that `_ctx` is required by the object macro.
@@ -0,0 +1,518 @@
+//! Module limiting the requests of single clients, api keys and ip addresses, see [`RateLimiter`].
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    net::{IpAddr, SocketAddr},
+    num::NonZeroU32,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_graphql::{
+    parser::{
+        parse_query,
+        types::{DocumentOperations, FragmentDefinition, OperationType, Selection, SelectionSet},
+        Positioned,
+    },
+    Name,
+};
+use axum::response::IntoResponse;
+use hyper::{header::RETRY_AFTER, HeaderMap, StatusCode};
+use tokio::sync::{Mutex, RwLock};
+use tracing::debug;
+
+use crate::util::Uuid;
+
+use super::auth::AuthInfo;
+
+const DEFAULT_CLIENT_LIMIT: u32 = 300;
+const DEFAULT_IP_LIMIT: u32 = 1200;
+const DEFAULT_MUTATION_LIMIT: u32 = 60;
+const DEFAULT_IMAGE_UPLOAD_COST: u32 = 10;
+const DEFAULT_MAX_QUERY_DEPTH: usize = 15;
+const DEFAULT_MAX_QUERY_COMPLEXITY: usize = 500;
+
+/// Name of the mutation uploading images, which has its own cost.
+const ADD_IMAGE_MUTATION: &str = "addImage";
+/// Header containing the client ip if the server runs behind a reverse proxy.
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+/// Interval in which buckets which are full again get removed.
+const CLEANUP_INTERVAL: Duration = Duration::from_mins(1);
+
+/// Structure containing all limits of graphql requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    /// Number of authenticated requests per minute a single client id may make, `None` means unlimited.
+    pub client_limit: Option<NonZeroU32>,
+    /// Number of authenticated requests per minute all clients using the same api key may make, `None` means unlimited.
+    pub api_key_limit: Option<NonZeroU32>,
+    /// Number of requests per minute a single ip address may make, `None` means unlimited.
+    /// As all clients behind a carrier-grade NAT share an address, this should be well above `client_limit`.
+    pub ip_limit: Option<NonZeroU32>,
+    /// Budget for mutations per minute of a single authenticated client id or ip address, `None` means unlimited.
+    pub mutation_limit: Option<NonZeroU32>,
+    /// Part of the mutation budget used by uploading an image, all other mutations cost 1.
+    pub image_upload_cost: u32,
+    /// Whether the ip address of clients gets read from the `X-Forwarded-For` header set by a reverse proxy.
+    pub trust_forwarded_for: bool,
+    /// Maximum nesting depth of graphql queries.
+    pub max_query_depth: usize,
+    /// Maximum complexity of graphql queries, where each requested field counts 1.
+    pub max_query_complexity: usize,
+}
+
+impl Default for RateLimitInfo {
+    fn default() -> Self {
+        Self {
+            client_limit: NonZeroU32::new(DEFAULT_CLIENT_LIMIT),
+            api_key_limit: None,
+            ip_limit: NonZeroU32::new(DEFAULT_IP_LIMIT),
+            mutation_limit: NonZeroU32::new(DEFAULT_MUTATION_LIMIT),
+            image_upload_cost: DEFAULT_IMAGE_UPLOAD_COST,
+            trust_forwarded_for: false,
+            max_query_depth: DEFAULT_MAX_QUERY_DEPTH,
+            max_query_complexity: DEFAULT_MAX_QUERY_COMPLEXITY,
+        }
+    }
+}
+
+/// Response to requests exceeding one of the limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct RateLimited {
+    /// Which limit got exceeded.
+    limit: &'static str,
+    /// Time until the request would be allowed.
+    retry_after: Duration,
+}
+
+impl IntoResponse for RateLimited {
+    fn into_response(self) -> axum::response::Response {
+        let seconds = self.retry_after.as_secs_f64().ceil().max(1.0);
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(RETRY_AFTER, format!("{seconds}"))],
+            format!(
+                "Too many requests ({} limit exceeded), please retry after {seconds} seconds.",
+                self.limit
+            ),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Debug)]
+struct BucketMap<K> {
+    buckets: HashMap<K, Bucket>,
+    last_cleanup: Instant,
+}
+
+/// Token buckets of one kind of limit, one for each key.
+#[derive(Debug)]
+struct Buckets<K> {
+    name: &'static str,
+    capacity: f64,
+    per_second: f64,
+    map: Mutex<BucketMap<K>>,
+}
+
+impl<K: Eq + Hash> Buckets<K> {
+    fn new(name: &'static str, per_minute: Option<NonZeroU32>) -> Option<Self> {
+        let capacity = f64::from(per_minute?.get());
+        Some(Self {
+            name,
+            capacity,
+            per_second: capacity / 60.0,
+            map: Mutex::new(BucketMap {
+                buckets: HashMap::new(),
+                last_cleanup: Instant::now(),
+            }),
+        })
+    }
+
+    /// Takes `cost` tokens from the bucket of `key`, or returns how long to wait until they are available.
+    async fn take(&self, key: K, cost: u32) -> Result<(), RateLimited> {
+        let now = Instant::now();
+        let cost = f64::from(cost).min(self.capacity);
+        let mut map = self.map.lock().await;
+
+        if now.duration_since(map.last_cleanup) > CLEANUP_INTERVAL {
+            // full buckets behave like new ones
+            map.buckets.retain(|_, b| {
+                now.duration_since(b.updated)
+                    .as_secs_f64()
+                    .mul_add(self.per_second, b.tokens)
+                    < self.capacity
+            });
+            map.last_cleanup = now;
+        }
+
+        let bucket = map.buckets.entry(key).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+        });
+        bucket.tokens = now
+            .duration_since(bucket.updated)
+            .as_secs_f64()
+            .mul_add(self.per_second, bucket.tokens)
+            .min(self.capacity);
+        bucket.updated = now;
+
+        let result = if bucket.tokens >= cost {
+            bucket.tokens -= cost;
+            Ok(())
+        } else {
+            Err(RateLimited {
+                limit: self.name,
+                retry_after: Duration::from_secs_f64((cost - bucket.tokens) / self.per_second),
+            })
+        };
+        drop(map);
+        result
+    }
+}
+
+/// [`RateLimiter`] which can be replaced by one with other limits while requests are being handled.
+pub(super) type SharedRateLimiter = Arc<RwLock<Arc<RateLimiter>>>;
+
+/// Class limiting graphql requests using token buckets for each client id, api key and ip address.
+/// Mutations additionally use a separate budget, as they are more expensive.
+///
+/// Client ids are chosen by the clients themselves, so they are only used for authenticated requests.
+/// Otherwise, anyone could use up the budget of another client by sending its id, or avoid the limit by using a new id for each request.
+/// Unauthenticated requests are only limited by ip address.
+#[derive(Debug)]
+pub(super) struct RateLimiter {
+    limits: RateLimitInfo,
+    clients: Option<Buckets<Uuid>>,
+    api_keys: Option<Buckets<String>>,
+    ips: Option<Buckets<IpAddr>>,
+    client_mutations: Option<Buckets<Uuid>>,
+    ip_mutations: Option<Buckets<IpAddr>>,
+    image_upload_cost: u32,
+    trust_forwarded_for: bool,
+}
+
+impl RateLimiter {
+    /// Creates a new instance with the given limits.
+    pub(super) fn new(info: &RateLimitInfo) -> Self {
+        Self {
+            limits: info.clone(),
+            clients: Buckets::new("client", info.client_limit),
+            api_keys: Buckets::new("api key", info.api_key_limit),
+            ips: Buckets::new("ip", info.ip_limit),
+            client_mutations: Buckets::new("client mutation", info.mutation_limit),
+            ip_mutations: Buckets::new("ip mutation", info.mutation_limit),
+            image_upload_cost: info.image_upload_cost,
+            trust_forwarded_for: info.trust_forwarded_for,
+        }
+    }
+
+    /// Returns the limits this instance was created with.
+    pub(super) const fn limits(&self) -> &RateLimitInfo {
+        &self.limits
+    }
+
+    /// Returns the ip address of the client, which is read from the `X-Forwarded-For` header if configured.
+    /// Only the last address gets used, as it is the one added by the reverse proxy.
+    /// All addresses before it are sent by the client, who can choose them freely.
+    pub(super) fn client_ip(&self, addr: SocketAddr, headers: &HeaderMap) -> IpAddr {
+        self.trust_forwarded_for
+            .then(|| {
+                headers
+                    .get_all(FORWARDED_FOR_HEADER)
+                    .iter()
+                    .next_back()?
+                    .to_str()
+                    .ok()
+            })
+            .flatten()
+            .and_then(|forwarded| forwarded.rsplit(',').next()?.trim().parse().ok())
+            .unwrap_or_else(|| addr.ip())
+    }
+
+    /// Checks whether the given request is allowed and uses up the budgets for it.
+    /// # Errors
+    /// if one of the limits is exceeded
+    pub(super) async fn check(
+        &self,
+        auth_info: &AuthInfo,
+        ip: IpAddr,
+        request: &async_graphql::Request,
+    ) -> Result<(), RateLimited> {
+        let result = self.take(auth_info, ip, request).await;
+        if let Err(limited) = result {
+            debug!("Request of {ip} got rate limited: {limited:?}");
+        }
+        result
+    }
+
+    async fn take(
+        &self,
+        auth_info: &AuthInfo,
+        ip: IpAddr,
+        request: &async_graphql::Request,
+    ) -> Result<(), RateLimited> {
+        if let Some(ips) = &self.ips {
+            ips.take(ip, 1).await?;
+        }
+        let client_id = auth_info
+            .authenticated
+            .is_ok()
+            .then_some(auth_info.client_id)
+            .flatten();
+        if let (Some(clients), Some(client_id)) = (&self.clients, client_id) {
+            clients.take(client_id, 1).await?;
+        }
+        // only count requests which are authenticated, so no one can use up the budget of someone else's api key
+        if let (Some(api_keys), Ok(_)) = (&self.api_keys, &auth_info.authenticated) {
+            api_keys.take(auth_info.api_ident.clone(), 1).await?;
+        }
+
+        let cost = self.mutation_cost(request);
+        if cost > 0 {
+            if let Some(ip_mutations) = &self.ip_mutations {
+                ip_mutations.take(ip, cost).await?;
+            }
+            if let (Some(client_mutations), Some(client_id)) = (&self.client_mutations, client_id) {
+                client_mutations.take(client_id, cost).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the part of the mutation budget the request uses, which is 0 for queries.
+    /// Invalid requests cost nothing here, as they get rejected anyway.
+    fn mutation_cost(&self, request: &async_graphql::Request) -> u32 {
+        let Ok(document) = parse_query(&request.query) else {
+            return 0;
+        };
+        let operation = match &document.operations {
+            DocumentOperations::Single(operation) => Some(operation),
+            DocumentOperations::Multiple(operations) => request
+                .operation_name
+                .as_ref()
+                .and_then(|name| operations.iter().find(|(n, _)| n.as_str() == name))
+                .map(|(_, operation)| operation),
+        };
+        let Some(operation) = operation else {
+            return 0;
+        };
+        if operation.node.ty != OperationType::Mutation {
+            return 0;
+        }
+
+        self.selection_cost(
+            &operation.node.selection_set.node,
+            &document.fragments,
+            &mut Vec::new(),
+        )
+    }
+
+    /// Returns the cost of all mutations selected in `selection_set`, including those inside fragments.
+    /// `used_fragments` contains the fragments currently being resolved, so cyclic fragments cost nothing more.
+    fn selection_cost<'a>(
+        &self,
+        selection_set: &'a SelectionSet,
+        fragments: &'a HashMap<Name, Positioned<FragmentDefinition>>,
+        used_fragments: &mut Vec<&'a Name>,
+    ) -> u32 {
+        selection_set
+            .items
+            .iter()
+            .map(|selection| match &selection.node {
+                Selection::Field(field) if field.node.name.node == ADD_IMAGE_MUTATION => {
+                    self.image_upload_cost
+                }
+                Selection::Field(_) => 1,
+                Selection::InlineFragment(fragment) => self.selection_cost(
+                    &fragment.node.selection_set.node,
+                    fragments,
+                    used_fragments,
+                ),
+                Selection::FragmentSpread(spread) => {
+                    let name = &spread.node.fragment_name.node;
+                    match fragments.get(name) {
+                        Some(fragment) if !used_fragments.contains(&name) => {
+                            used_fragments.push(name);
+                            let cost = self.selection_cost(
+                                &fragment.node.selection_set.node,
+                                fragments,
+                                used_fragments,
+                            );
+                            used_fragments.pop();
+                            cost
+                        }
+                        _ => 0,
+                    }
+                }
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::{
+        net::{IpAddr, Ipv4Addr, SocketAddr},
+        num::NonZeroU32,
+    };
+
+    use hyper::HeaderMap;
+
+    use super::{RateLimitInfo, RateLimiter};
+    use crate::{
+        layer::trigger::api::auth::{AuthFailReason, AuthInfo},
+        util::{ApiKeyScope, Uuid},
+    };
+
+    fn auth_info(client: u128) -> AuthInfo {
+        AuthInfo {
+            client_id: Some(Uuid::from_u128(client)),
+            authenticated: Ok(ApiKeyScope::Moderate),
+            api_ident: String::new(),
+            hash: String::new(),
+        }
+    }
+
+    fn unauthenticated(client: u128) -> AuthInfo {
+        AuthInfo {
+            authenticated: Err(AuthFailReason::NoAuthHeader),
+            ..auth_info(client)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_limit() {
+        let limiter = RateLimiter::new(&RateLimitInfo {
+            client_limit: NonZeroU32::new(3),
+            ip_limit: None,
+            ..Default::default()
+        });
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let request = async_graphql::Request::new("{ apiVersion }");
+
+        for _ in 0..3 {
+            assert!(limiter.check(&auth_info(1), ip, &request).await.is_ok());
+        }
+        let rejected = limiter
+            .check(&auth_info(1), ip, &request)
+            .await
+            .unwrap_err();
+        // one token gets refilled every 20 seconds
+        assert!(rejected.retry_after.as_secs() <= 20);
+        // other clients are not affected
+        assert!(limiter.check(&auth_info(2), ip, &request).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unauthenticated_client_id() {
+        let limiter = RateLimiter::new(&RateLimitInfo {
+            client_limit: NonZeroU32::new(1),
+            ip_limit: NonZeroU32::new(3),
+            ..Default::default()
+        });
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let other_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let request = async_graphql::Request::new("{ apiVersion }");
+
+        // sending the id of another client does not use up its budget
+        assert!(limiter
+            .check(&unauthenticated(1), ip, &request)
+            .await
+            .is_ok());
+        assert!(limiter
+            .check(&unauthenticated(1), ip, &request)
+            .await
+            .is_ok());
+        assert!(limiter
+            .check(&auth_info(1), other_ip, &request)
+            .await
+            .is_ok());
+
+        // changing the id does not avoid the ip limit
+        assert!(limiter
+            .check(&unauthenticated(2), ip, &request)
+            .await
+            .is_ok());
+        assert!(limiter
+            .check(&unauthenticated(3), ip, &request)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mutation_budget() {
+        let limiter = RateLimiter::new(&RateLimitInfo {
+            mutation_limit: NonZeroU32::new(12),
+            image_upload_cost: 10,
+            ..Default::default()
+        });
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let upload = async_graphql::Request::new(
+            r#"mutation { addImage(mealId: "1d75d380-cf07-4edb-9046-a2d981bc219d", image: null, hash: "") }"#,
+        );
+        let rating = async_graphql::Request::new(
+            r#"mutation { setRating(mealId: "1d75d380-cf07-4edb-9046-a2d981bc219d", rating: 3) }"#,
+        );
+        let query = async_graphql::Request::new("{ apiVersion }");
+
+        assert_eq!(limiter.mutation_cost(&upload), 10);
+        assert_eq!(limiter.mutation_cost(&rating), 1);
+        assert_eq!(limiter.mutation_cost(&query), 0);
+
+        assert!(limiter.check(&auth_info(1), ip, &upload).await.is_ok());
+        assert!(limiter.check(&auth_info(1), ip, &upload).await.is_err());
+        assert!(limiter.check(&auth_info(1), ip, &rating).await.is_ok());
+        // queries have their own budget
+        assert!(limiter.check(&auth_info(1), ip, &query).await.is_ok());
+    }
+
+    #[test]
+    fn test_mutation_cost_fragments() {
+        let limiter = RateLimiter::new(&RateLimitInfo {
+            image_upload_cost: 10,
+            ..Default::default()
+        });
+        let spread = async_graphql::Request::new(
+            r#"mutation { ...upload } fragment upload on MutationRoot { addImage(mealId: "1d75d380-cf07-4edb-9046-a2d981bc219d", image: null, hash: "") }"#,
+        );
+        let inline = async_graphql::Request::new(
+            r#"mutation { ... on MutationRoot { a: addImage(mealId: "1d75d380-cf07-4edb-9046-a2d981bc219d", image: null, hash: "") b: addImage(mealId: "1d75d380-cf07-4edb-9046-a2d981bc219d", image: null, hash: "") } }"#,
+        );
+        let cyclic = async_graphql::Request::new(
+            "mutation { ...a } fragment a on MutationRoot { __typename ...a }",
+        );
+
+        assert_eq!(limiter.mutation_cost(&spread), 10);
+        assert_eq!(limiter.mutation_cost(&inline), 20);
+        assert_eq!(limiter.mutation_cost(&cyclic), 1);
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1234);
+        let mut headers = HeaderMap::new();
+        // the first address is chosen by the client, the last one is added by the proxy
+        headers.insert("x-forwarded-for", "10.0.0.1, 10.0.0.2".parse().unwrap());
+
+        let limiter = RateLimiter::new(&RateLimitInfo::default());
+        assert_eq!(limiter.client_ip(addr, &headers), addr.ip());
+
+        let limiter = RateLimiter::new(&RateLimitInfo {
+            trust_forwarded_for: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            limiter.client_ip(addr, &headers),
+            "10.0.0.2".parse::<IpAddr>().unwrap()
+        );
+    }
+}
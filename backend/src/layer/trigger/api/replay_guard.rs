@@ -0,0 +1,122 @@
+//! Module protecting against replayed requests, see [`ReplayGuard`].
+
+use std::{
+    sync::atomic::{AtomicI64, Ordering},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tracing::warn;
+
+use crate::{interface::persistent_data::Result as DataResult, util::Uuid};
+
+use super::{auth::AuthFailReason, key_store::ArcAuthData};
+
+/// Class rejecting requests which are too old or use a nonce which was already used.
+///
+/// Used nonces are stored in the database, so a request can not be replayed at another instance either.
+/// They only need to be remembered as long as requests using them are fresh enough to be accepted.
+pub(super) struct ReplayGuard {
+    max_age: u64,
+    auth_data: ArcAuthData,
+    /// Time the expired nonces got removed last, in seconds since the unix epoch.
+    last_cleanup: AtomicI64,
+}
+
+impl ReplayGuard {
+    /// Creates a new instance accepting requests which are at most `max_age` old or in the future.
+    pub(super) fn new(max_age: Duration, auth_data: ArcAuthData) -> Self {
+        Self {
+            max_age: max_age.as_secs(),
+            auth_data,
+            last_cleanup: AtomicI64::default(),
+        }
+    }
+
+    /// Checks whether a request made at `timestamp` (in seconds since the unix epoch) is fresh
+    /// and the nonce was not used with this api key before.
+    /// # Errors
+    /// if the used nonces could not be accessed.
+    /// Returns the [`AuthFailReason`] if the request is too old or the nonce was already used.
+    pub(super) async fn check(
+        &self,
+        key_id: Uuid,
+        timestamp: i64,
+        nonce: &str,
+    ) -> DataResult<Result<(), AuthFailReason>> {
+        let now = Utc::now().timestamp();
+        if now.abs_diff(timestamp) > self.max_age {
+            return Ok(Err(AuthFailReason::RequestExpired(timestamp)));
+        }
+
+        // at most once per freshness window, as nonces stay for that long anyway
+        let last_cleanup = self.last_cleanup.load(Ordering::Relaxed);
+        if now.abs_diff(last_cleanup) >= self.max_age
+            && self
+                .last_cleanup
+                .compare_exchange(last_cleanup, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            if let Err(e) = self.auth_data.remove_expired_api_key_nonces().await {
+                warn!("Could not remove expired nonces: {e}");
+            }
+        }
+
+        // the request gets accepted until it is `max_age` old
+        let expires_at =
+            DateTime::from_timestamp(timestamp.saturating_add_unsigned(self.max_age), 0)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        if self
+            .auth_data
+            .use_api_key_nonce(key_id, nonce, expires_at)
+            .await?
+        {
+            Ok(Ok(()))
+        } else {
+            Ok(Err(AuthFailReason::NonceReused))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::{sync::Arc, time::Duration};
+
+    use chrono::Utc;
+
+    use super::ReplayGuard;
+    use crate::{
+        layer::trigger::api::{auth::AuthFailReason, mock::AuthDataMock},
+        util::Uuid,
+    };
+
+    #[tokio::test]
+    async fn test_replay_guard() {
+        let guard = ReplayGuard::new(Duration::from_mins(1), Arc::new(AuthDataMock::default()));
+        let now = Utc::now().timestamp();
+        let key = Uuid::from_u128(1);
+
+        assert!(guard.check(key, now, "abc").await.unwrap().is_ok());
+        assert!(matches!(
+            guard.check(key, now, "abc").await.unwrap(),
+            Err(AuthFailReason::NonceReused)
+        ));
+        // nonces are remembered per api key
+        assert!(guard
+            .check(Uuid::from_u128(2), now, "abc")
+            .await
+            .unwrap()
+            .is_ok());
+        assert!(guard.check(key, now - 30, "def").await.unwrap().is_ok());
+
+        assert!(matches!(
+            guard.check(key, now - 120, "ghi").await.unwrap(),
+            Err(AuthFailReason::RequestExpired(_))
+        ));
+        assert!(matches!(
+            guard.check(key, now + 120, "ghi").await.unwrap(),
+            Err(AuthFailReason::RequestExpired(_))
+        ));
+    }
+}
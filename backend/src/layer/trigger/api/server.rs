@@ -4,7 +4,7 @@ use std::{
     fmt::Display,
     future::{Future, IntoFuture},
     mem,
-    net::{Ipv6Addr, SocketAddrV6},
+    net::{Ipv6Addr, SocketAddr, SocketAddrV6},
     num::NonZeroU64,
     path::PathBuf,
     pin::Pin,
@@ -20,30 +20,39 @@ use async_graphql::{
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
 use axum::{
     error_handling::HandleErrorLayer,
-    extract::DefaultBodyLimit,
+    extract::{ConnectInfo, DefaultBodyLimit},
     handler::Handler,
     middleware,
-    response::{self, IntoResponse},
+    response::{self, IntoResponse, Response},
     routing::get,
     BoxError, Extension, Router,
 };
 
-use hyper::StatusCode;
-use tokio::sync::Notify;
+use hyper::{HeaderMap, StatusCode};
+use tokio::sync::{Notify, RwLock};
 use tower::{buffer::BufferLayer, limit::RateLimitLayer, ServiceBuilder};
 use tower_http::services::ServeDir;
-use tracing::{debug, info, info_span, Instrument};
+use tracing::{debug, info, info_span, warn, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 use crate::{
     interface::{
         api_command::Command,
-        persistent_data::{model::ApiKey, AuthDataAccess, RequestDataAccess},
+        config_reload::ConfigReload,
+        health::HealthCheck,
+        persistent_data::{AdminDataAccess, AuthDataAccess, RequestDataAccess},
     },
     layer::trigger::api::{
-        admin::{admin_router, ArcCommand},
+        admin::{admin_router, oidc::OidcInfo, ArcAdminData, ArcCommand},
         auth::auth_middleware,
+        health::health_router,
+        key_store::ApiKeyStore,
+        metrics::{install_recorder, metrics_router, track_requests, GraphQLMetrics},
+        rate_limit::{RateLimitInfo, RateLimiter, SharedRateLimiter},
+        replay_guard::ReplayGuard,
     },
-    util::{local_to_global_url, IMAGE_BASE_PATH},
+    layer::trigger::scheduling::control::SchedulerControl,
+    util::{local_to_global_url, trace_context_from_headers, IMAGE_BASE_PATH},
 };
 
 use super::{
@@ -65,8 +74,20 @@ pub struct ApiServerInfo {
     pub rate_limit: Option<NonZeroU64>,
     /// Maximum accepted http body size
     pub max_body_size: u64,
-    /// Api key for accessing the admin api
+    /// Shared key of the user `admin` for accessing the admin api, empty if disabled.
     pub admin_key: String,
+    /// Time after which sessions of administrators expire.
+    pub admin_session_duration: Duration,
+    /// Settings for logging in to the admin api via openid connect, `None` if disabled.
+    pub oidc: Option<OidcInfo>,
+    /// Interval in which api keys get reloaded and their usage statistics saved.
+    pub api_key_refresh_interval: Duration,
+    /// Maximum age of requests with timestamp and nonce, older ones are rejected as possible replays.
+    pub max_request_age: Duration,
+    /// Limits of graphql requests per client, api key and ip address as well as of the query size.
+    pub rate_limits: RateLimitInfo,
+    /// Number of reports per day after which clients get listed as suspicious if not specified otherwise.
+    pub mass_report_threshold: u32,
 }
 
 enum State {
@@ -91,31 +112,79 @@ pub struct ApiServer {
     server_info: ApiServerInfo,
     schema: GraphQLSchema,
     state: State,
-    api_keys: Vec<ApiKey>,
+    key_store: Arc<ApiKeyStore>,
     command_copy: Arc<dyn Command + Send + Sync>,
+    admin_data: ArcAdminData,
+    health_checks: Vec<Box<dyn HealthCheck>>,
+    scheduler: Option<SchedulerControl>,
+    rate_limiter: SharedRateLimiter,
+    config_reload: Option<Arc<dyn ConfigReload>>,
+}
+
+/// Handle for applying changed settings to an [`ApiServer`] while it is running.
+#[derive(Clone)]
+pub struct ApiReloadHandle {
+    rate_limiter: SharedRateLimiter,
+}
+
+impl ApiReloadHandle {
+    /// Uses the given limits for all following graphql requests.
+    /// Budgets already used up only carry over if the limits did not change.
+    /// The global rate limit and the limits of the query size only change with a restart.
+    pub async fn reload(&self, rate_limits: &RateLimitInfo) {
+        let mut rate_limiter = self.rate_limiter.write().await;
+        if rate_limiter.limits() != rate_limits {
+            *rate_limiter = Arc::new(RateLimiter::new(rate_limits));
+            drop(rate_limiter);
+            info!("Using new rate limits {rate_limits:?}");
+        }
+    }
 }
 
 impl ApiServer {
     /// Creates a new Object with given access to datastore and logic for commands.
-    /// # Panics
-    /// if api keys could not be read from database
-    pub async fn new(
+    /// The server only reports to be ready if all `health_checks` pass.
+    /// Scheduled jobs can only be controlled over the admin api if a `scheduler` is given.
+    pub fn new(
         server_info: ApiServerInfo,
         data_access: impl RequestDataAccess + 'static,
         command: impl Command + 'static,
-        auth: impl AuthDataAccess,
+        auth: impl AuthDataAccess + 'static,
+        admin_data: impl AdminDataAccess + 'static,
+        health_checks: Vec<Box<dyn HealthCheck>>,
+        scheduler: Option<SchedulerControl>,
     ) -> Self {
         let command_arc = Arc::new(command);
-        let schema: GraphQLSchema = construct_schema(data_access, command_arc.clone());
+        let schema: GraphQLSchema =
+            construct_schema(data_access, command_arc.clone(), &server_info.rate_limits);
+        let rate_limiter = Arc::new(RwLock::new(Arc::new(RateLimiter::new(
+            &server_info.rate_limits,
+        ))));
         Self {
             server_info,
             schema,
             state: State::Created,
-            api_keys: auth
-                .get_api_keys()
-                .await
-                .expect("could not get api keys from database"),
+            key_store: Arc::new(ApiKeyStore::new(Arc::new(auth))),
             command_copy: command_arc,
+            admin_data: Arc::new(admin_data),
+            health_checks,
+            scheduler,
+            rate_limiter,
+            config_reload: None,
+        }
+    }
+
+    /// Lets operators reload the settings over the admin api using `config_reload`.
+    /// This has to happen before the server gets started.
+    pub fn set_config_reload(&mut self, config_reload: Arc<dyn ConfigReload>) {
+        self.config_reload = Some(config_reload);
+    }
+
+    /// Returns a handle for applying changed rate limits while the server is running.
+    #[must_use]
+    pub fn reload_handle(&self) -> ApiReloadHandle {
+        ApiReloadHandle {
+            rate_limiter: self.rate_limiter.clone(),
         }
     }
 
@@ -136,8 +205,14 @@ impl ApiServer {
             .try_into()
             .expect("max body size should fit in usize");
 
-        let auth =
-            middleware::from_fn_with_state((max_body_size, self.api_keys.clone()), auth_middleware);
+        let replay_guard = Arc::new(ReplayGuard::new(
+            self.server_info.max_request_age,
+            self.key_store.auth_data().clone(),
+        ));
+        let auth = middleware::from_fn_with_state(
+            (max_body_size, self.key_store.clone(), replay_guard),
+            auth_middleware,
+        );
 
         let rate_limit = ServiceBuilder::new()
             .layer(HandleErrorLayer::new(|err: BoxError| async move {
@@ -155,8 +230,12 @@ impl ApiServer {
             ));
 
         let admin_router = admin_router(
-            self.server_info.admin_key.clone(),
+            &self.server_info,
             self.command_copy.clone() as ArcCommand,
+            self.admin_data.clone(),
+            self.key_store.clone(),
+            self.scheduler.clone(),
+            self.config_reload.clone(),
         );
 
         let app = Router::new()
@@ -165,10 +244,15 @@ impl ApiServer {
                 get(graphql_playground).post(graphql_handler.layer(auth)),
             )
             .layer(Extension(self.schema.clone()))
+            .layer(Extension(self.rate_limiter.clone()))
             .nest("/admin", admin_router)
             .nest_service(IMAGE_BASE_PATH, ServeDir::new(&self.server_info.image_dir))
+            .layer(middleware::from_fn(track_requests))
+            .merge(metrics_router(install_recorder()))
             .layer(rate_limit)
-            .layer(DefaultBodyLimit::max(max_body_size));
+            .layer(DefaultBodyLimit::max(max_body_size))
+            // probes of the orchestrator should not be rate limited
+            .merge(health_router(mem::take(&mut self.health_checks)));
 
         let socket = std::net::SocketAddr::V6(SocketAddrV6::new(
             Ipv6Addr::UNSPECIFIED,
@@ -180,7 +264,10 @@ impl ApiServer {
         let listener = tokio::net::TcpListener::bind(socket)
             .await
             .expect("bind to tcp socket");
-        let server = axum::serve(listener, app);
+        let server = axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        );
 
         let shutdown_notify = Arc::new(Notify::new());
         let shutdown_notify_sender = shutdown_notify.clone();
@@ -189,6 +276,11 @@ impl ApiServer {
             server.with_graceful_shutdown(async move { shutdown_notify_sender.notified().await });
 
         let join_handle = tokio::spawn(with_shutdown.into_future());
+        let refresh_handle = tokio::spawn(save_api_key_usage(
+            self.key_store.clone(),
+            self.server_info.api_key_refresh_interval,
+        ));
+        let key_store = self.key_store.clone();
 
         let shutdown = async move {
             shutdown_notify.notify_one();
@@ -196,6 +288,10 @@ impl ApiServer {
                 .await
                 .expect("web server should not have panicked")
                 .expect("error while waiting for webserver to finish");
+            refresh_handle.abort();
+            if let Err(e) = key_store.flush_usage().await {
+                warn!("Could not save api key usage: {e}");
+            }
         };
 
         self.state = State::Running(Box::pin(shutdown));
@@ -221,10 +317,22 @@ impl ApiServer {
     }
 }
 
+/// Regularly saves the usage statistics of the api keys.
+async fn save_api_key_usage(key_store: Arc<ApiKeyStore>, interval: Duration) {
+    let mut interval = tokio::time::interval(interval);
+    // nothing got counted yet
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        key_store.save_usage().await;
+    }
+}
+
 /// Constructs the graphql schema with all its settings.
 pub(super) fn construct_schema(
     data_access: impl RequestDataAccess + 'static,
     command: impl Command + 'static,
+    limits: &RateLimitInfo,
 ) -> GraphQLSchema {
     let data_access_box: DataBox = Box::new(data_access);
     let command_box: CommandBox = Box::new(command);
@@ -233,6 +341,9 @@ pub(super) fn construct_schema(
         .data(data_access_box)
         .data(command_box)
         .extension(Tracing)
+        .extension(GraphQLMetrics)
+        .limit_depth(limits.max_query_depth)
+        .limit_complexity(limits.max_query_complexity)
         .finish()
 }
 
@@ -245,15 +356,27 @@ async fn graphql_playground() -> impl IntoResponse {
 async fn graphql_handler(
     Extension(auth_info): Extension<AuthInfo>,
     Extension(schema): Extension<GraphQLSchema>,
+    Extension(rate_limiter): Extension<SharedRateLimiter>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     request: GraphQLRequest,
-) -> GraphQLResponse {
-    let request = request.into_inner().data(auth_info.clone() as AuthInfo);
+) -> Response {
+    let request = request.into_inner();
+    // requests in progress keep the limiter they started with when it gets replaced
+    let rate_limiter = rate_limiter.read().await.clone();
+    let ip = rate_limiter.client_ip(addr, &headers);
+    if let Err(limited) = rate_limiter.check(&auth_info, ip, &request).await {
+        return limited.into_response();
+    }
+    let request = request.data(auth_info.clone() as AuthInfo);
 
     let span = info_span!(
         "incoming graphql request",
         variables = %request.variables,
         auth_info = %auth_info
     );
+    // continue the trace of the calling app, if any. fails if exporting traces is disabled.
+    span.set_parent(trace_context_from_headers(&headers)).ok();
 
     async {
         let response = schema.execute(request).await;
@@ -268,7 +391,7 @@ async fn graphql_handler(
                     .join("\n")
             );
         }
-        response.into()
+        GraphQLResponse::from(response).into_response()
     }
     .instrument(span)
     .await
@@ -278,7 +401,7 @@ async fn graphql_handler(
 mod tests {
     #![allow(clippy::unwrap_used)]
 
-    use std::{env::temp_dir, io::Cursor, path::PathBuf};
+    use std::{env::temp_dir, io::Cursor, path::PathBuf, time::Duration};
 
     use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
     use base64::{engine::general_purpose, Engine};
@@ -290,44 +413,72 @@ mod tests {
 
     use crate::{
         layer::trigger::api::{
-            mock::{AuthDataMock, CommandMock, RequestDatabaseMock},
+            mock::{AdminDataMock, AuthDataMock, CommandMock, RequestDatabaseMock},
             server::ApiServer,
         },
         util::{ImageResource, Uuid},
     };
 
-    use super::{ApiServerInfo, IMAGE_BASE_PATH};
+    use super::{ApiServerInfo, RateLimitInfo, IMAGE_BASE_PATH};
 
     const TEST_PORT: u16 = 12345;
     const BODY_SIZE: u64 = 10 << 20;
 
-    async fn get_test_server() -> ApiServer {
+    fn get_test_server() -> ApiServer {
         let info = ApiServerInfo {
             port: TEST_PORT,
             image_dir: temp_dir(),
             rate_limit: None,
             max_body_size: BODY_SIZE,
             admin_key: "admin".into(),
+            admin_session_duration: Duration::from_hours(1),
+            oidc: None,
+            api_key_refresh_interval: Duration::from_mins(1),
+            max_request_age: Duration::from_mins(5),
+            rate_limits: RateLimitInfo::default(),
+            mass_report_threshold: 20,
         };
-        ApiServer::new(info, RequestDatabaseMock, CommandMock, AuthDataMock).await
+        ApiServer::new(
+            info,
+            RequestDatabaseMock,
+            CommandMock,
+            AuthDataMock::default(),
+            AdminDataMock,
+            vec![],
+            None,
+        )
     }
 
-    async fn get_test_server_with_images(image_dir: PathBuf) -> ApiServer {
+    fn get_test_server_with_images(image_dir: PathBuf) -> ApiServer {
         let info = ApiServerInfo {
             port: TEST_PORT,
             image_dir,
             rate_limit: None,
             max_body_size: BODY_SIZE,
             admin_key: "admin".into(),
+            admin_session_duration: Duration::from_hours(1),
+            oidc: None,
+            api_key_refresh_interval: Duration::from_mins(1),
+            max_request_age: Duration::from_mins(5),
+            rate_limits: RateLimitInfo::default(),
+            mass_report_threshold: 20,
         };
-        ApiServer::new(info, RequestDatabaseMock, CommandMock, AuthDataMock).await
+        ApiServer::new(
+            info,
+            RequestDatabaseMock,
+            CommandMock,
+            AuthDataMock::default(),
+            AdminDataMock,
+            vec![],
+            None,
+        )
     }
 
     #[tokio::test]
     #[serial]
     /// Test whether api version is available as health check.
     async fn test_graphql() {
-        let mut server = get_test_server().await;
+        let mut server = get_test_server();
         server.start().await;
 
         let test_request = r#"
@@ -367,7 +518,7 @@ mod tests {
     #[serial]
     /// Test whether the graphql playground is served.
     async fn test_playground() {
-        let mut server = get_test_server().await;
+        let mut server = get_test_server();
         server.start().await;
 
         let result = reqwest::get(format!("http://localhost:{TEST_PORT}"))
@@ -386,7 +537,7 @@ mod tests {
     #[should_panic = "tried to shutdown server but in state finished"]
     /// test what happens when server is shutdown but not running.
     async fn test_not_running() {
-        let mut server = get_test_server().await;
+        let mut server = get_test_server();
 
         server.shutdown().await;
     }
@@ -396,7 +547,7 @@ mod tests {
     #[should_panic = "tried to start graphql server while in state running"]
     #[serial]
     async fn test_double_start() {
-        let mut server = get_test_server().await;
+        let mut server = get_test_server();
         server.start().await;
         server.start().await;
     }
@@ -432,7 +583,7 @@ mod tests {
                 .collect::<Vec<_>>()
         );
 
-        let mut server = get_test_server_with_images(image_folder.path().to_owned()).await;
+        let mut server = get_test_server_with_images(image_folder.path().to_owned());
         server.start().await;
 
         // save image after server is started to check "dynamic" file requests
@@ -469,7 +620,7 @@ mod tests {
     #[tokio::test]
     #[serial]
     async fn test_large_image_upload() {
-        let mut server = get_test_server().await;
+        let mut server = get_test_server();
         server.start().await;
 
         let image = include_bytes!("test_data/test_real.jpg").as_ref();
@@ -528,8 +679,22 @@ mod tests {
             rate_limit: None,
             max_body_size: 1 << 10,
             admin_key: "admin".into(),
+            admin_session_duration: Duration::from_hours(1),
+            oidc: None,
+            api_key_refresh_interval: Duration::from_mins(1),
+            max_request_age: Duration::from_mins(5),
+            rate_limits: RateLimitInfo::default(),
+            mass_report_threshold: 20,
         };
-        let mut server = ApiServer::new(info, RequestDatabaseMock, CommandMock, AuthDataMock).await;
+        let mut server = ApiServer::new(
+            info,
+            RequestDatabaseMock,
+            CommandMock,
+            AuthDataMock::default(),
+            AdminDataMock,
+            vec![],
+            None,
+        );
 
         server.start().await;
 
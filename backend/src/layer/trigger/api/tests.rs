@@ -5,9 +5,10 @@ use super::auth::AuthInfo;
 use crate::layer::trigger::api::auth::AuthFailReason;
 use crate::layer::trigger::api::mutation::MutationRoot;
 use crate::layer::trigger::api::query::QueryRoot;
+use crate::layer::trigger::api::rate_limit::RateLimitInfo;
 use crate::layer::trigger::api::server::construct_schema;
 use crate::layer::trigger::api::util::{CommandBox, DataBox};
-use crate::util::Uuid;
+use crate::util::{ApiKeyScope, Uuid};
 use async_graphql::{EmptySubscription, Request, Schema, UploadValue, Variables};
 use serde_json::json;
 use sha2::{Digest, Sha512};
@@ -125,11 +126,11 @@ async fn test_gql_request(request: &'static str) {
     let request = Request::from(request).data(AuthInfo {
         client_id: Some(Uuid::default()),
         api_ident: String::new(),
-        authenticated: Ok(()),
+        authenticated: Ok(ApiKeyScope::Moderate),
         hash: String::new(),
     });
 
-    let schema = construct_schema(RequestDatabaseMock, CommandMock);
+    let schema = construct_schema(RequestDatabaseMock, CommandMock, &RateLimitInfo::default());
     let response = schema.execute(request).await;
     assert!(response.is_ok(), "request returned {:?}", response.errors);
 }
@@ -160,7 +161,7 @@ async fn test_add_image() {
         .data(AuthInfo {
             client_id: Some(Uuid::default()),
             api_ident: String::new(),
-            authenticated: Ok(()),
+            authenticated: Ok(ApiKeyScope::Moderate),
             hash: String::new(),
         })
         .variables(Variables::from_json(json!( {
@@ -176,7 +177,7 @@ async fn test_add_image() {
         },
     );
 
-    let schema = construct_schema(RequestDatabaseMock, CommandMock);
+    let schema = construct_schema(RequestDatabaseMock, CommandMock, &RateLimitInfo::default());
     let response = schema.execute(request).await;
     assert!(response.is_ok(), "request returned {:?}", response.errors);
 }
@@ -215,8 +216,78 @@ async fn test_report_image() {
     test_gql_request(request).await;
 }
 
+#[tokio::test]
+async fn test_api_key_scopes() {
+    let execute = |scope, request: &'static str| async move {
+        let request = Request::from(request).data(AuthInfo {
+            client_id: Some(Uuid::default()),
+            api_ident: String::new(),
+            authenticated: Ok(scope),
+            hash: String::new(),
+        });
+        construct_schema(RequestDatabaseMock, CommandMock, &RateLimitInfo::default())
+            .execute(request)
+            .await
+    };
+    let rating =
+        r#"mutation { setRating(mealId:"1d75d380-cf07-4edb-9046-a2d981bc219d", rating:3) }"#;
+    let report = r#"mutation { reportImage(imageId:"1d75d380-cf07-4edb-9046-a2d981bc219d", reason:OFFENSIVE) }"#;
+
+    assert!(execute(ApiKeyScope::ReadOnly, "{ apiVersion }")
+        .await
+        .is_ok());
+    assert!(execute(ApiKeyScope::ReadOnly, rating).await.is_err());
+    assert!(execute(ApiKeyScope::Upload, rating).await.is_ok());
+    assert!(execute(ApiKeyScope::Upload, report).await.is_err());
+    assert!(execute(ApiKeyScope::Moderate, report).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_delete_my_image() {
+    let request = r#"
+        mutation {
+            deleteMyImage(imageId:"1d75d380-cf07-4edb-9046-a2d981bc219d")
+        }
+    "#;
+    test_gql_request(request).await;
+}
+
+#[tokio::test]
+async fn test_erase_my_data() {
+    let request = r"
+        mutation {
+            eraseMyData
+        }
+    ";
+    test_gql_request(request).await;
+}
+
 // ---------------------- queries -----------------------
 
+#[tokio::test]
+async fn test_export_my_data() {
+    let request = r"
+        {
+            exportMyData
+        }
+    ";
+    test_gql_request(request).await;
+}
+
+#[tokio::test]
+async fn test_get_my_images() {
+    let request = r"
+        {
+            getMyImages {
+                id
+                mealId
+                moderationStatus
+            }
+        }
+    ";
+    test_gql_request(request).await;
+}
+
 #[tokio::test]
 async fn test_api_version() {
     let request = r"
@@ -379,7 +450,7 @@ async fn test_get_auth_info_empty() {
         hash: String::new(),
     });
 
-    let schema = construct_schema(RequestDatabaseMock, CommandMock);
+    let schema = construct_schema(RequestDatabaseMock, CommandMock, &RateLimitInfo::default());
     let response = schema.execute(request).await;
     assert!(response.is_ok(), "request returned {:?}", response.errors);
 }
@@ -423,6 +494,41 @@ async fn test_recursive_meal_line_ok() {
     test_gql_request(request).await;
 }
 
+#[tokio::test]
+async fn test_query_limits() {
+    let request = r"
+    {
+      getCanteens {
+        lines {
+          canteen {
+            lines {
+              canteen {
+                id
+              }
+            }
+          }
+        }
+      }
+    }
+    ";
+
+    let limits = RateLimitInfo {
+        max_query_depth: 4,
+        ..Default::default()
+    };
+    let schema = construct_schema(RequestDatabaseMock, CommandMock, &limits);
+    assert!(schema.execute(request).await.is_err());
+
+    let limits = RateLimitInfo {
+        max_query_complexity: 4,
+        ..Default::default()
+    };
+    let schema = construct_schema(RequestDatabaseMock, CommandMock, &limits);
+    assert!(schema.execute(request).await.is_err());
+
+    test_gql_request(request).await;
+}
+
 #[tokio::test]
 async fn test_get_auth_info() {
     let request = r"
@@ -441,7 +547,7 @@ async fn test_get_auth_info() {
         client_id: Some(Uuid::try_from("1d75d380-cf07-4edb-9046-a2d981bc219d").unwrap()),
         api_ident: "abc".into(),
         hash: "123".into(),
-        authenticated: Ok(()),
+        authenticated: Ok(ApiKeyScope::Moderate),
     };
 
     let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
@@ -1,6 +1,6 @@
 use async_graphql::SimpleObject;
 
-use crate::util::Uuid;
+use crate::util::{ApiKeyScope, Uuid};
 
 /// Object containing all information about whether the current request was authenticated.
 /// For more informatiun about request authentication, see <https://github.com/kronos-et-al/MensaApp/blob/main/doc/ApiAuth.md>.
@@ -10,6 +10,8 @@ pub(in super::super) struct AuthInfo {
     pub(crate) client_id: Option<Uuid>,
     /// Whether this request was authenticated.
     pub(crate) authenticated: bool,
+    /// Scope of the api key this request was authenticated with.
+    pub(crate) scope: Option<ApiKeyScope>,
     /// Provided api key identifier.
     pub(crate) api_ident: String,
     /// Provided HMAC hash over request body.
@@ -24,6 +26,7 @@ impl From<super::super::auth::AuthInfo> for AuthInfo {
             api_ident: value.api_ident,
             auth_error: value.authenticated.as_ref().err().map(|e| format!("{e:?}")),
             authenticated: value.authenticated.is_ok(),
+            scope: value.authenticated.ok(),
             client_id: value.client_id,
             hash: value.hash,
         }
@@ -1,7 +1,7 @@
 use crate::{
     interface::persistent_data::model,
     layer::trigger::api::util::ApiUtil,
-    util::{image_id_to_url, Uuid},
+    util::{image_id_to_url, ModerationStatus, Uuid},
 };
 use async_graphql::{ComplexObject, Context, Result, SimpleObject};
 use tracing::instrument;
@@ -19,6 +19,12 @@ pub(in super::super) struct Image {
     upvotes: u32,
     /// The amount of users, who downvoted the image.
     downvotes: u32,
+    /// The id of the meal this image belongs to.
+    meal_id: Uuid,
+    #[graphql(skip)]
+    uploader_id: Uuid,
+    #[graphql(skip)]
+    status: ModerationStatus,
 }
 
 #[ComplexObject]
@@ -41,6 +47,15 @@ impl Image {
         let downvote = data.get_personal_downvote(self.id, client_id).await?;
         Ok(downvote)
     }
+
+    /// The state of the automatic moderation of this image.
+    /// Only available for images uploaded by the requesting client, `null` otherwise.
+    /// Other users only get images which got accepted.
+    #[instrument(skip(ctx))]
+    async fn moderation_status(&self, ctx: &Context<'_>) -> Option<ModerationStatus> {
+        let client_id = ctx.get_client_id().ok()?;
+        (client_id == self.uploader_id).then_some(self.status)
+    }
 }
 
 impl From<model::Image> for Image {
@@ -50,7 +65,10 @@ impl From<model::Image> for Image {
             downvotes: value.downvotes,
             upvotes: value.upvotes,
             rank: value.rank,
+            meal_id: value.meal_id,
             url: image_id_to_url(value.id),
+            uploader_id: value.uploader_id,
+            status: value.moderation_status,
         }
     }
 }
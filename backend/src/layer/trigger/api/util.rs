@@ -7,7 +7,7 @@ use thiserror::Error;
 
 use crate::{
     interface::{api_command::Command, persistent_data::RequestDataAccess},
-    util::Uuid,
+    util::{ApiKeyScope, Uuid},
 };
 
 use super::auth::{self, AuthInfo};
@@ -32,6 +32,11 @@ pub trait ApiUtil {
     /// if no valid authentication present
     fn check_authentication(&self) -> auth::AuthResult<()>;
 
+    /// Returns whether this request is authenticated correctly with an api key allowing requests of the given scope.
+    /// # Errors
+    /// if no valid authentication present or the api key does not have the required scope
+    fn check_scope(&self, scope: ApiKeyScope) -> auth::AuthResult<()>;
+
     /// Gets the provided client id, if any.
     /// # Errors
     /// if no client id was provided in the authorization header
@@ -60,6 +65,15 @@ impl<'a> ApiUtil for Context<'a> {
         }
     }
 
+    fn check_scope(&self, scope: ApiKeyScope) -> auth::AuthResult<()> {
+        let auth_info = self.get_auth_info();
+        match auth_info.authenticated {
+            Ok(given) if given >= scope => Ok(()),
+            Ok(given) => Err(auth::AuthError::InsufficientScope(scope, given)),
+            Err(_) => Err(auth::AuthError::MissingOrInvalidAuth(auth_info.clone())),
+        }
+    }
+
     fn get_client_id(&self) -> auth::AuthResult<Uuid> {
         self.data_unchecked::<AuthInfo>()
             .client_id
@@ -0,0 +1,393 @@
+//! See [`SchedulerControl`].
+use std::{future::Future, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use thiserror::Error;
+use tokio_cron_scheduler::JobScheduler;
+use tracing::{info, warn};
+
+use crate::{
+    interface::{
+        mealplan_management::{MensaParseScheduling, ParseRunError},
+        mensa_parser::ParseError,
+        persistent_data::{DataError, SchedulerLeaseDataAccess},
+    },
+    util::Date,
+};
+
+use super::{
+    job::{JobEntry, RunLock, ScheduledJob},
+    leader::Leadership,
+    scheduler::{add_job, ScheduleInfo, DIGEST, PARSE_LOCK},
+};
+
+/// Result returned when controlling the scheduler, potentially containing a [`SchedulerError`].
+pub type Result<T> = std::result::Result<T, SchedulerError>;
+
+/// Enum describing why a job could not be controlled.
+#[derive(Debug, Error)]
+pub enum SchedulerError {
+    /// There is no job with the given name.
+    #[error("there is no job named `{0}`")]
+    UnknownJob(String),
+    /// The job or a job sharing data with it is currently running.
+    #[error("job `{0}` cannot run now because it or a job modifying the same data is running")]
+    AlreadyRunning(String),
+    /// The job could not be added to the scheduler with its new schedule.
+    #[error("could not schedule job `{0}`: {1}")]
+    InvalidSchedule(String, String),
+    /// A parse run could not be started on demand.
+    #[error("parsing failed: {0}")]
+    ParseRunError(#[from] ParseRunError),
+    /// The lock or the state shared with other instances could not be accessed.
+    #[error("could not access shared state of jobs: {0}")]
+    DataError(#[from] DataError),
+    /// The lock shared with other instances got lost while running, so the run got cancelled.
+    #[error("run of `{0}` got cancelled as its lock got lost")]
+    LockLost(String),
+}
+
+/// Current state of a job of the [`Scheduler`](super::scheduler::Scheduler).
+#[derive(Debug, Serialize)]
+pub struct JobInfo {
+    /// Name identifying the job.
+    pub name: &'static str,
+    /// Cron-like schedule the job runs at.
+    pub schedule: String,
+    /// Next time the job is scheduled to run, `None` if the scheduler is not running.
+    pub next_run: Option<DateTime<Utc>>,
+    /// Whether scheduled runs get skipped on all instances.
+    pub paused: bool,
+    /// Whether the job is running right now on this instance.
+    pub running: bool,
+}
+
+/// Handle for inspecting and controlling the jobs of a [`Scheduler`](super::scheduler::Scheduler).
+/// Clones control the same scheduler.
+#[derive(Clone)]
+pub struct SchedulerControl {
+    scheduler: JobScheduler,
+    jobs: Arc<[JobEntry]>,
+    mensa_parse: Arc<dyn MensaParseScheduling>,
+    parse_lock: RunLock,
+    leadership: Arc<Leadership>,
+}
+
+impl SchedulerControl {
+    pub(super) fn new(
+        scheduler: JobScheduler,
+        jobs: Arc<[JobEntry]>,
+        mensa_parse: Arc<dyn MensaParseScheduling>,
+        parse_lock: RunLock,
+        leadership: Arc<Leadership>,
+    ) -> Self {
+        Self {
+            scheduler,
+            jobs,
+            mensa_parse,
+            parse_lock,
+            leadership,
+        }
+    }
+
+    /// Returns the current state of all jobs.
+    /// # Errors
+    /// if the paused jobs could not be read from the database.
+    pub async fn jobs(&self) -> Result<Vec<JobInfo>> {
+        let paused = self.parse_lock.lease_data().get_paused_jobs().await?;
+        let mut scheduler = self.scheduler.clone();
+        let mut infos = Vec::with_capacity(self.jobs.len());
+        for JobEntry { job, cron } in self.jobs.iter() {
+            let (id, schedule) = cron.lock().await.clone();
+            let next_run = scheduler
+                .next_tick_for_job(id)
+                .await
+                .inspect_err(|e| warn!("Could not get next run of job {}: {e}", job.name))
+                .ok()
+                .flatten();
+            infos.push(JobInfo {
+                name: job.name,
+                schedule,
+                next_run,
+                paused: paused.iter().any(|name| name == job.name),
+                running: job.is_running(),
+            });
+        }
+        Ok(infos)
+    }
+
+    /// Skips all scheduled runs of the given job on all instances until it gets resumed.
+    /// # Errors
+    /// if there is no such job or the state could not be stored.
+    pub async fn pause(&self, name: &str) -> Result<()> {
+        self.job(name)?.set_paused(true).await?;
+        Ok(())
+    }
+
+    /// Lets the given job run as scheduled again.
+    /// # Errors
+    /// if there is no such job or the state could not be stored.
+    pub async fn resume(&self, name: &str) -> Result<()> {
+        self.job(name)?.set_paused(false).await?;
+        Ok(())
+    }
+
+    /// Starts the given job in the background, even if it is paused.
+    /// # Errors
+    /// if there is no such job or it cannot run right now.
+    pub async fn run_now(&self, name: &str) -> Result<()> {
+        if self.job(name)?.run_now().await? {
+            Ok(())
+        } else {
+            Err(SchedulerError::AlreadyRunning(name.into()))
+        }
+    }
+
+    /// Starts parsing the meal plan of the given date in the background, possibly only of a single canteen.
+    /// Whether it could be parsed and saved only gets logged.
+    /// # Errors
+    /// if the canteen is unknown or another parse run or retention is in progress on any instance.
+    pub async fn parse_date(&self, date: Date, canteen: Option<&str>) -> Result<()> {
+        if let Some(canteen) = canteen {
+            if !self.mensa_parse.is_known_canteen(canteen).await {
+                return Err(ParseRunError::from(ParseError::UnknownCanteen(canteen.into())).into());
+            }
+        }
+        let Some(guard) = self.parse_lock.try_lock().await? else {
+            return Err(SchedulerError::AlreadyRunning(PARSE_LOCK.into()));
+        };
+
+        let mensa_parse = self.mensa_parse.clone();
+        let canteen = canteen.map(ToOwned::to_owned);
+        tokio::spawn(async move {
+            let canteens = canteen.as_deref().unwrap_or("all canteens");
+            match guard
+                .run(mensa_parse.start_date_parsing(date, canteen.as_deref()))
+                .await
+            {
+                Some(Ok(())) => info!("Parsed meal plan of {canteens} at {date}."),
+                Some(Err(e)) => warn!("Parsing meal plan of {canteens} at {date} failed: {e}"),
+                // the lost lock already got logged
+                None => {}
+            }
+        });
+        Ok(())
+    }
+
+    /// Changes the schedules of all jobs to those in `info`. Runs in progress are not interrupted.
+    /// Jobs cannot be added or removed this way, so enabling or disabling the digest requires a restart.
+    /// # Errors
+    /// if a job could not be scheduled, in which case it keeps its previous schedule.
+    pub async fn reschedule(&self, info: &ScheduleInfo) -> Result<()> {
+        for JobEntry { job, cron } in self.jobs.iter() {
+            let Some(schedule) = info.schedule(job.name) else {
+                warn!("Job {} can only be disabled with a restart.", job.name);
+                continue;
+            };
+            let mut cron = cron.lock().await;
+            if cron.1 == schedule {
+                continue;
+            }
+            let id = add_job(&self.scheduler, &self.leadership, job.clone(), schedule)
+                .await
+                .map_err(|e| SchedulerError::InvalidSchedule(job.name.into(), e.to_string()))?;
+            if let Err(e) = self.scheduler.remove(&cron.0).await {
+                warn!(
+                    "Could not remove previous schedule of job {}: {e}",
+                    job.name
+                );
+            }
+            *cron = (id, schedule.to_owned());
+            drop(cron);
+            info!("Job {} now runs at `{schedule}`.", job.name);
+        }
+
+        if info.digest_schedule.is_some() && self.job(DIGEST).is_err() {
+            warn!("Job {DIGEST} can only be enabled with a restart.");
+        }
+        Ok(())
+    }
+
+    fn job(&self, name: &str) -> Result<&Arc<ScheduledJob>> {
+        self.jobs
+            .iter()
+            .find(|entry| entry.job.name == name)
+            .map(|entry| &entry.job)
+            .ok_or_else(|| SchedulerError::UnknownJob(name.into()))
+    }
+}
+
+/// Runs `action` while holding the lock with the given name, e.g. [`PARSE_LOCK`] or the name of a job.
+///
+/// The [`Scheduler`](super::scheduler::Scheduler) holds the same locks while running its jobs,
+/// so actions run outside of a scheduler never overlap with runs on any instance sharing the database.
+/// # Errors
+/// if the lock is held or could not be taken, or if it got lost while running.
+pub async fn run_exclusively<T>(
+    lease_data: Arc<dyn SchedulerLeaseDataAccess>,
+    lock: &str,
+    lease: Duration,
+    action: impl Future<Output = T> + Send,
+) -> Result<T> {
+    run_locked(&RunLock::new(lock, lease_data, lease), lock, action).await
+}
+
+async fn run_locked<T>(
+    lock: &RunLock,
+    name: &str,
+    action: impl Future<Output = T> + Send,
+) -> Result<T> {
+    let Some(guard) = lock.try_lock().await? else {
+        return Err(SchedulerError::AlreadyRunning(name.into()));
+    };
+    guard
+        .run(action)
+        .await
+        .ok_or_else(|| SchedulerError::LockLost(name.into()))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use crate::{
+        interface::persistent_data::SchedulerLeaseDataAccess,
+        layer::trigger::scheduling::{
+            mocks::{
+                DigestMock, ImageReviewMock, MensaParseMock, RetentionMock, SchedulerLeaseMock,
+                UNKNOWN_CANTEEN,
+            },
+            scheduler::{ScheduleInfo, Scheduler, FULL_PARSING, PARSE_LOCK, UPDATE_PARSING},
+        },
+        util::Date,
+    };
+
+    use super::{run_exclusively, SchedulerError};
+
+    const NEVER: &str = "0 0 0 31 2 *";
+
+    #[tokio::test]
+    async fn test_control_jobs() {
+        let info = ScheduleInfo {
+            full_parse_schedule: NEVER.into(),
+            update_parse_schedule: "*/1 * * * * *".into(),
+            image_review_schedule: NEVER.into(),
+            image_reverification_schedule: NEVER.into(),
+            retention_schedule: NEVER.into(),
+            image_reconciliation_schedule: NEVER.into(),
+            digest_schedule: None,
+            leader_lease: Duration::from_secs(1),
+        };
+        let mensa_parse = MensaParseMock::default();
+        let mut scheduler = Scheduler::new(
+            info,
+            mensa_parse.clone(),
+            ImageReviewMock::default(),
+            RetentionMock::default(),
+            DigestMock::default(),
+            SchedulerLeaseMock::default(),
+        )
+        .await;
+        let control = scheduler.control();
+
+        let jobs = control.jobs().await.unwrap();
+        assert_eq!(jobs.len(), 6);
+        assert_eq!(jobs[0].name, UPDATE_PARSING);
+        assert!(!jobs[0].paused);
+
+        control.pause(UPDATE_PARSING).await.unwrap();
+        assert!(control.jobs().await.unwrap()[0].paused);
+        scheduler.start().await;
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        assert_eq!(mensa_parse.get_update_calls(), 0);
+        assert!(control.jobs().await.unwrap()[0].next_run.is_some());
+
+        control.resume(UPDATE_PARSING).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        assert!(mensa_parse.get_update_calls() > 0);
+
+        control.run_now(FULL_PARSING).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(mensa_parse.get_full_calls(), 1);
+        assert!(matches!(
+            control.pause("unknown").await,
+            Err(SchedulerError::UnknownJob(_))
+        ));
+
+        control.parse_date(Date::default(), None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(
+            control
+                .parse_date(Date::default(), Some(UNKNOWN_CANTEEN))
+                .await,
+            Err(SchedulerError::ParseRunError(_))
+        ));
+        assert_eq!(mensa_parse.get_date_calls(), 1);
+
+        scheduler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_reschedule() {
+        let mut info = ScheduleInfo {
+            full_parse_schedule: NEVER.into(),
+            update_parse_schedule: NEVER.into(),
+            image_review_schedule: NEVER.into(),
+            image_reverification_schedule: NEVER.into(),
+            retention_schedule: NEVER.into(),
+            image_reconciliation_schedule: NEVER.into(),
+            digest_schedule: None,
+            leader_lease: Duration::from_secs(1),
+        };
+        let mensa_parse = MensaParseMock::default();
+        let mut scheduler = Scheduler::new(
+            info.clone(),
+            mensa_parse.clone(),
+            ImageReviewMock::default(),
+            RetentionMock::default(),
+            DigestMock::default(),
+            SchedulerLeaseMock::default(),
+        )
+        .await;
+        let control = scheduler.control();
+        scheduler.start().await;
+
+        info.update_parse_schedule = "*/1 * * * * *".into();
+        control.reschedule(&info).await.unwrap();
+        assert_eq!(
+            control.jobs().await.unwrap()[0].schedule,
+            info.update_parse_schedule
+        );
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        assert!(mensa_parse.get_update_calls() > 0);
+
+        info.full_parse_schedule = "invalid".into();
+        assert!(matches!(
+            control.reschedule(&info).await,
+            Err(SchedulerError::InvalidSchedule(_, _))
+        ));
+        assert_eq!(control.jobs().await.unwrap()[1].schedule, NEVER);
+
+        scheduler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_run_exclusively() {
+        let lease_data: Arc<dyn SchedulerLeaseDataAccess> = Arc::new(SchedulerLeaseMock::default());
+        let lease = Duration::from_secs(1);
+
+        // the lock is held across instances, not only within one
+        let nested = run_exclusively(lease_data.clone(), PARSE_LOCK, lease, async {
+            run_exclusively(lease_data.clone(), PARSE_LOCK, lease, async {}).await
+        })
+        .await
+        .unwrap();
+        assert!(matches!(nested, Err(SchedulerError::AlreadyRunning(_))));
+
+        run_exclusively(lease_data, PARSE_LOCK, lease, async {})
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,293 @@
+//! See [`ScheduledJob`].
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use metrics::histogram;
+use tokio::{
+    sync::{Mutex, OwnedMutexGuard},
+    time::interval_at,
+};
+use tracing::{debug, info, info_span, trace, warn, Instrument};
+
+use crate::{
+    interface::persistent_data::{Result as DataResult, SchedulerLeaseDataAccess},
+    util::Uuid,
+};
+
+use super::leader::renewal_period;
+
+type JobAction = Box<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Lock held while a job runs, preventing overlapping runs of jobs sharing it on all instances sharing a database.
+///
+/// Within an instance, a mutex is used. Between instances, the lock is a lease in the database,
+/// which gets renewed while the job runs, so it ends if the instance crashes.
+/// Clones share the same lock.
+#[derive(Clone)]
+pub(super) struct RunLock {
+    lease_name: String,
+    local: Arc<Mutex<()>>,
+    lease_data: Arc<dyn SchedulerLeaseDataAccess>,
+    lease: Duration,
+}
+
+impl RunLock {
+    pub(super) fn new(
+        name: &str,
+        lease_data: Arc<dyn SchedulerLeaseDataAccess>,
+        lease: Duration,
+    ) -> Self {
+        Self {
+            lease_name: format!("run:{name}"),
+            local: Arc::default(),
+            lease_data,
+            lease,
+        }
+    }
+
+    /// Returns the database access the lease is held with.
+    pub(super) fn lease_data(&self) -> &Arc<dyn SchedulerLeaseDataAccess> {
+        &self.lease_data
+    }
+
+    /// Takes the lock, waiting for runs on this instance to finish.
+    /// Returns `None` if it is held by another instance.
+    /// # Errors
+    /// if the lease could not be acquired in the database
+    pub(super) async fn lock(&self) -> DataResult<Option<RunGuard>> {
+        let local = self.local.clone().lock_owned().await;
+        self.acquire(local).await
+    }
+
+    /// Takes the lock, returning `None` if it is held on this or another instance.
+    /// # Errors
+    /// if the lease could not be acquired in the database
+    pub(super) async fn try_lock(&self) -> DataResult<Option<RunGuard>> {
+        let Ok(local) = self.local.clone().try_lock_owned() else {
+            return Ok(None);
+        };
+        self.acquire(local).await
+    }
+
+    async fn acquire(&self, local: OwnedMutexGuard<()>) -> DataResult<Option<RunGuard>> {
+        let holder = Uuid::new_v4();
+        let start = Instant::now();
+        let acquired = self
+            .lease_data
+            .try_acquire_lease(&self.lease_name, holder, self.lease)
+            .await?;
+        Ok(acquired.then(|| RunGuard {
+            lock: self.clone(),
+            holder,
+            valid_until: start + self.lease,
+            _local: local,
+        }))
+    }
+}
+
+/// A held [`RunLock`], see [`RunGuard::run`].
+pub(super) struct RunGuard {
+    lock: RunLock,
+    holder: Uuid,
+    /// End of the lease as known to this instance.
+    valid_until: Instant,
+    _local: OwnedMutexGuard<()>,
+}
+
+impl RunGuard {
+    /// Runs `action` while renewing the lease, then releases the lock.
+    ///
+    /// If the lease gets lost, e.g. because the database was not reachable for a whole lease duration,
+    /// another instance might start running already, so `action` gets cancelled and `None` returned.
+    pub(super) async fn run<T>(mut self, action: impl Future<Output = T>) -> Option<T> {
+        let result = tokio::select! {
+            result = action => Some(result),
+            () = self.keep_lease() => None,
+        };
+        if result.is_none() {
+            warn!(
+                "Cancelled run holding {} as its lease got lost.",
+                self.lock.lease_name
+            );
+        }
+        if let Err(e) = self
+            .lock
+            .lease_data
+            .release_lease(&self.lock.lease_name, self.holder)
+            .await
+        {
+            warn!("Could not release lease {}: {e}", self.lock.lease_name);
+        }
+        result
+    }
+
+    /// Renews the lease regularly, returning once it got lost.
+    async fn keep_lease(&mut self) {
+        let period = renewal_period(self.lock.lease);
+        let mut renewals = interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            renewals.tick().await;
+            let start = Instant::now();
+            match self
+                .lock
+                .lease_data
+                .try_acquire_lease(&self.lock.lease_name, self.holder, self.lock.lease)
+                .await
+            {
+                Ok(true) => self.valid_until = start + self.lock.lease,
+                Ok(false) => return,
+                Err(e) => {
+                    warn!("Could not renew lease {}: {e}", self.lock.lease_name);
+                    if Instant::now() >= self.valid_until {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A [`ScheduledJob`] together with how it is added to the cron scheduler.
+pub(super) struct JobEntry {
+    pub(super) job: Arc<ScheduledJob>,
+    /// Id of the job at the cron scheduler and the cron-like schedule it got added with.
+    pub(super) cron: Mutex<(Uuid, String)>,
+}
+
+/// Sets a flag while a job runs, resetting it when dropped, even if the job panics.
+struct RunningFlag<'a>(&'a AtomicBool);
+
+impl<'a> RunningFlag<'a> {
+    fn set(flag: &'a AtomicBool) -> Self {
+        flag.store(true, Ordering::Relaxed);
+        Self(flag)
+    }
+}
+
+impl Drop for RunningFlag<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Action run regularly by the [`Scheduler`](super::scheduler::Scheduler), which can also be paused or run on demand.
+///
+/// Whether a job is paused is stored in the database, so it applies to all instances and is kept over restarts.
+pub(super) struct ScheduledJob {
+    pub(super) name: &'static str,
+    running: AtomicBool,
+    lock: RunLock,
+    /// Only log at trace level, for jobs running very often.
+    quiet: bool,
+    action: JobAction,
+}
+
+impl ScheduledJob {
+    /// Creates a new job running `action` on `target`, never at the same time as other jobs or actions holding `lock`.
+    pub(super) fn new<T, F, Fut>(
+        name: &'static str,
+        lock: RunLock,
+        target: Arc<T>,
+        action: F,
+    ) -> Self
+    where
+        T: ?Sized + Send + Sync + 'static,
+        F: Fn(Arc<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            name,
+            running: AtomicBool::new(false),
+            lock,
+            quiet: false,
+            action: Box::new(move || Box::pin(action(target.clone()))),
+        }
+    }
+
+    /// Only logs runs of this job at trace level.
+    pub(super) const fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    /// Sets whether scheduled runs of this job get skipped on all instances.
+    /// # Errors
+    /// if the state could not be stored in the database
+    pub(super) async fn set_paused(&self, paused: bool) -> DataResult<()> {
+        self.lock.lease_data.set_job_paused(self.name, paused).await
+    }
+
+    pub(super) fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Runs the job as scheduled, unless it is paused.
+    /// If it or a job sharing its lock is still running on this instance, this waits for them to finish.
+    /// If they run on another instance, the run gets skipped.
+    pub(super) async fn run_scheduled(&self) {
+        match self.lock.lease_data.get_paused_jobs().await {
+            Ok(paused) if paused.iter().any(|name| name == self.name) => {
+                debug!("Skipped paused job {}.", self.name);
+                return;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!(
+                    "Skipped job {} as it could not be checked whether it is paused: {e}",
+                    self.name
+                );
+                return;
+            }
+        }
+        match self.lock.lock().await {
+            Ok(Some(guard)) => self.run(guard).await,
+            Ok(None) => info!(
+                "Skipped job {} as it or a job modifying the same data runs on another instance.",
+                self.name
+            ),
+            Err(e) => warn!("Skipped job {} as it could not be locked: {e}", self.name),
+        }
+    }
+
+    /// Starts the job in the background, even if paused.
+    /// Returns `false` if it or a job sharing its lock is already running on any instance.
+    /// # Errors
+    /// if the lock could not be taken in the database
+    pub(super) async fn run_now(self: &Arc<Self>) -> DataResult<bool> {
+        let Some(guard) = self.lock.try_lock().await? else {
+            return Ok(false);
+        };
+        let job = self.clone();
+        tokio::spawn(async move { job.run(guard).await });
+        Ok(true)
+    }
+
+    async fn run(&self, guard: RunGuard) {
+        let running = RunningFlag::set(&self.running);
+        if self.quiet {
+            trace!("Started job {}.", self.name);
+        } else {
+            info!("Started job {}.", self.name);
+        }
+        let start = Instant::now();
+
+        guard
+            .run((self.action)().instrument(info_span!("scheduled_job", job = self.name)))
+            .await;
+
+        drop(running);
+        histogram!("scheduled_job_duration_seconds", "job" => self.name).record(start.elapsed());
+        if self.quiet {
+            trace!("Finished job {} in {:?}.", self.name, start.elapsed());
+        } else {
+            info!("Finished job {} in {:?}.", self.name, start.elapsed());
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! See [`Leadership`].
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::{sync::RwLock, task::JoinHandle, time::interval_at};
+use tracing::{info, warn};
+
+use crate::{interface::persistent_data::SchedulerLeaseDataAccess, util::Uuid};
+
+/// Name of the lease the instance running the scheduled jobs holds.
+const LEASE_NAME: &str = "scheduler";
+/// How many times the lease gets renewed within its duration, so single failed renewals do not lose it.
+const RENEWALS_PER_LEASE: u32 = 3;
+/// Shortest time between renewals, so a lease of zero does not renew continuously.
+const MIN_RENEWAL_PERIOD: Duration = Duration::from_millis(10);
+
+/// Time between two renewals of a lease of the given duration.
+pub(super) fn renewal_period(lease: Duration) -> Duration {
+    (lease / RENEWALS_PER_LEASE).max(MIN_RENEWAL_PERIOD)
+}
+
+/// Leadership of this instance among all instances sharing a database, as only the leader may run scheduled jobs.
+///
+/// The leader renews its lease regularly. If it stops doing so, e.g. because it crashed,
+/// another instance takes over once the lease expired.
+pub(super) struct Leadership {
+    lease_data: Arc<dyn SchedulerLeaseDataAccess>,
+    holder: Uuid,
+    lease: Duration,
+    /// End of the lease as known to this instance, `None` if it is not the leader.
+    valid_until: RwLock<Option<Instant>>,
+}
+
+impl Leadership {
+    pub(super) fn new(lease_data: Arc<dyn SchedulerLeaseDataAccess>, lease: Duration) -> Self {
+        Self {
+            lease_data,
+            holder: Uuid::new_v4(),
+            lease,
+            valid_until: RwLock::default(),
+        }
+    }
+
+    /// Whether this instance holds the lease right now.
+    pub(super) async fn is_leader(&self) -> bool {
+        self.valid_until
+            .read()
+            .await
+            .is_some_and(|valid_until| valid_until > Instant::now())
+    }
+
+    /// Tries to become leader or to stay leader for another lease duration.
+    pub(super) async fn renew(&self) {
+        // the lease ends in the database after this, so it never gets assumed to last longer than it does
+        let start = Instant::now();
+        let was_leader = self.is_leader().await;
+        match self
+            .lease_data
+            .try_acquire_lease(LEASE_NAME, self.holder, self.lease)
+            .await
+        {
+            Ok(true) => {
+                *self.valid_until.write().await = Some(start + self.lease);
+                if !was_leader {
+                    info!("This instance now runs the scheduled jobs.");
+                }
+            }
+            Ok(false) => {
+                *self.valid_until.write().await = None;
+                if was_leader {
+                    warn!("Another instance took over running the scheduled jobs.");
+                }
+            }
+            // the lease stays valid until it expires, maybe the next renewal succeeds
+            Err(e) => warn!("Could not renew lease for running scheduled jobs: {e}"),
+        }
+    }
+
+    /// Renews the lease in the background until the returned task gets aborted.
+    pub(super) fn spawn_renewal(self: &Arc<Self>) -> JoinHandle<()> {
+        let leadership = self.clone();
+        let period = renewal_period(self.lease);
+        tokio::spawn(async move {
+            let mut renewals = interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                renewals.tick().await;
+                leadership.renew().await;
+            }
+        })
+    }
+
+    /// Gives up the lease, so another instance can take over right away.
+    pub(super) async fn resign(&self) {
+        let was_leader = self.valid_until.write().await.take().is_some();
+        if !was_leader {
+            return;
+        }
+        if let Err(e) = self.lease_data.release_lease(LEASE_NAME, self.holder).await {
+            warn!("Could not release lease for running scheduled jobs: {e}");
+        }
+    }
+}
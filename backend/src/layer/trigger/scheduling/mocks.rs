@@ -1,17 +1,36 @@
-//! This crate contains mocks of [`MensaParseScheduling`] and [`ImageReviewScheduling`] for testing.
+//! This crate contains mocks of [`MensaParseScheduling`], [`ImageReviewScheduling`], [`RetentionScheduling`], [`DigestScheduling`] and [`SchedulerLeaseDataAccess`] for testing.
 #![cfg(test)]
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use tracing::debug;
 
-use crate::interface::mealplan_management::MensaParseScheduling;
+use crate::{
+    interface::{
+        digest::DigestScheduling,
+        image_review::ImageReviewScheduling,
+        mealplan_management::{MensaParseScheduling, Result},
+        mensa_parser::ParseError,
+        persistent_data::{self, SchedulerLeaseDataAccess},
+        retention::{ReconciliationReport, RetentionReport, RetentionScheduling},
+    },
+    util::{Date, Uuid},
+};
+
+/// Canteen [`MensaParseMock`] fails to parse.
+pub const UNKNOWN_CANTEEN: &str = "unknown";
 
 /// Mock for [`MensaParseScheduling`].
 #[derive(Default, Clone)]
+#[allow(clippy::struct_field_names)]
 pub struct MensaParseMock {
     update_calls: Arc<Mutex<u32>>,
     full_calls: Arc<Mutex<u32>>,
+    date_calls: Arc<Mutex<u32>>,
 }
 
 impl MensaParseMock {
@@ -38,6 +57,18 @@ impl MensaParseMock {
             .lock()
             .expect("failed to lock mutex for `full_calls` counter")
     }
+
+    /// A function to get the number of calls to [`MensaParseScheduling::start_date_parsing`]
+    ///
+    /// # Panics
+    /// if the mutex could not be acquired
+    #[must_use]
+    pub fn get_date_calls(&self) -> u32 {
+        *self
+            .date_calls
+            .lock()
+            .expect("failed to lock mutex for `date_calls` counter")
+    }
 }
 
 #[async_trait]
@@ -64,4 +95,214 @@ impl MensaParseScheduling for MensaParseMock {
             .expect("failed to lock mutex for `full_calls` counter") += 1;
         return;
     }
+
+    async fn start_date_parsing(&self, _date: Date, canteen: Option<&str>) -> Result<()> {
+        debug!("start_date_parsing");
+        *self
+            .date_calls
+            .lock()
+            .expect("failed to lock mutex for `date_calls` counter") += 1;
+        match canteen {
+            Some(UNKNOWN_CANTEEN) => Err(ParseError::UnknownCanteen(UNKNOWN_CANTEEN.into()).into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn is_known_canteen(&self, canteen: &str) -> bool {
+        canteen != UNKNOWN_CANTEEN
+    }
+}
+
+/// Mock for [`ImageReviewScheduling`].
+#[derive(Default, Clone)]
+pub struct ImageReviewMock {
+    review_calls: Arc<Mutex<u32>>,
+    reverification_calls: Arc<Mutex<u32>>,
+}
+
+impl ImageReviewMock {
+    /// A function to get the number of calls to [`ImageReviewScheduling::start_image_review`]
+    ///
+    /// # Panics
+    /// if the mutex could not be acquired
+    #[must_use]
+    pub fn get_review_calls(&self) -> u32 {
+        *self
+            .review_calls
+            .lock()
+            .expect("failed to lock mutex for `review_calls` counter")
+    }
+
+    /// A function to get the number of calls to [`ImageReviewScheduling::start_image_reverification`]
+    ///
+    /// # Panics
+    /// if the mutex could not be acquired
+    #[must_use]
+    pub fn get_reverification_calls(&self) -> u32 {
+        *self
+            .reverification_calls
+            .lock()
+            .expect("failed to lock mutex for `reverification_calls` counter")
+    }
+}
+
+#[async_trait]
+impl ImageReviewScheduling for ImageReviewMock {
+    async fn start_image_review(&self) {
+        debug!("start_image_review");
+        *self
+            .review_calls
+            .lock()
+            .expect("failed to lock mutex for `review_calls` counter") += 1;
+    }
+
+    async fn start_image_reverification(&self) {
+        debug!("start_image_reverification");
+        *self
+            .reverification_calls
+            .lock()
+            .expect("failed to lock mutex for `reverification_calls` counter") += 1;
+    }
+}
+
+/// Mock for [`RetentionScheduling`].
+#[derive(Default, Clone)]
+pub struct RetentionMock {
+    retention_calls: Arc<Mutex<u32>>,
+    reconciliation_calls: Arc<Mutex<u32>>,
+}
+
+impl RetentionMock {
+    /// A function to get the number of calls to [`RetentionScheduling::start_retention`]
+    ///
+    /// # Panics
+    /// if the mutex could not be acquired
+    #[must_use]
+    pub fn get_retention_calls(&self) -> u32 {
+        *self
+            .retention_calls
+            .lock()
+            .expect("failed to lock mutex for `retention_calls` counter")
+    }
+
+    /// A function to get the number of calls to [`RetentionScheduling::start_image_reconciliation`]
+    ///
+    /// # Panics
+    /// if the mutex could not be acquired
+    #[must_use]
+    pub fn get_reconciliation_calls(&self) -> u32 {
+        *self
+            .reconciliation_calls
+            .lock()
+            .expect("failed to lock mutex for `reconciliation_calls` counter")
+    }
+}
+
+#[async_trait]
+impl RetentionScheduling for RetentionMock {
+    async fn start_retention(&self) -> RetentionReport {
+        debug!("start_retention");
+        *self
+            .retention_calls
+            .lock()
+            .expect("failed to lock mutex for `retention_calls` counter") += 1;
+        RetentionReport::default()
+    }
+
+    async fn start_image_reconciliation(&self) -> ReconciliationReport {
+        debug!("start_image_reconciliation");
+        *self
+            .reconciliation_calls
+            .lock()
+            .expect("failed to lock mutex for `reconciliation_calls` counter") += 1;
+        ReconciliationReport::default()
+    }
+}
+
+/// Mock for [`DigestScheduling`].
+#[derive(Default, Clone)]
+pub struct DigestMock {
+    digest_calls: Arc<Mutex<u32>>,
+}
+
+impl DigestMock {
+    /// A function to get the number of calls to [`DigestScheduling::send_digest`]
+    ///
+    /// # Panics
+    /// if the mutex could not be acquired
+    #[must_use]
+    pub fn get_digest_calls(&self) -> u32 {
+        *self
+            .digest_calls
+            .lock()
+            .expect("failed to lock mutex for `digest_calls` counter")
+    }
+}
+
+#[async_trait]
+impl DigestScheduling for DigestMock {
+    async fn send_digest(&self) {
+        debug!("send_digest");
+        *self
+            .digest_calls
+            .lock()
+            .expect("failed to lock mutex for `digest_calls` counter") += 1;
+    }
+}
+
+/// Mock for [`SchedulerLeaseDataAccess`]. Clones share their leases like instances sharing a database, but they never expire.
+#[derive(Default, Clone)]
+pub struct SchedulerLeaseMock {
+    holders: Arc<Mutex<HashMap<String, Uuid>>>,
+    paused: Arc<Mutex<BTreeSet<String>>>,
+}
+
+#[async_trait]
+impl SchedulerLeaseDataAccess for SchedulerLeaseMock {
+    async fn try_acquire_lease(
+        &self,
+        name: &str,
+        holder: Uuid,
+        _duration: Duration,
+    ) -> persistent_data::Result<bool> {
+        let mut holders = self
+            .holders
+            .lock()
+            .expect("failed to lock mutex for lease `holders`");
+        Ok(*holders.entry(name.into()).or_insert(holder) == holder)
+    }
+
+    async fn release_lease(&self, name: &str, holder: Uuid) -> persistent_data::Result<()> {
+        let mut holders = self
+            .holders
+            .lock()
+            .expect("failed to lock mutex for lease `holders`");
+        if holders.get(name) == Some(&holder) {
+            holders.remove(name);
+        }
+        drop(holders);
+        Ok(())
+    }
+
+    async fn get_paused_jobs(&self) -> persistent_data::Result<Vec<String>> {
+        let paused = self
+            .paused
+            .lock()
+            .expect("failed to lock mutex for `paused` jobs");
+        Ok(paused.iter().cloned().collect())
+    }
+
+    async fn set_job_paused(&self, name: &str, paused: bool) -> persistent_data::Result<()> {
+        let mut jobs = self
+            .paused
+            .lock()
+            .expect("failed to lock mutex for `paused` jobs");
+        if paused {
+            jobs.insert(name.into());
+        } else {
+            jobs.remove(name);
+        }
+        drop(jobs);
+        Ok(())
+    }
 }
@@ -1,3 +1,6 @@
 //! This component triggers regular events such as querying the current menu.
+pub mod control;
+mod job;
+mod leader;
 pub mod mocks;
 pub mod scheduler;
@@ -1,20 +1,67 @@
 //! Module containing the scheduler to schedule regular actions.
-use std::{sync::Arc, time::Instant};
-
-use crate::interface::mealplan_management::MensaParseScheduling;
-
-use tokio::sync::Notify;
-use tokio_cron_scheduler::{Job, JobScheduler};
-use tracing::{info, info_span, Instrument};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    interface::{
+        digest::DigestScheduling, image_review::ImageReviewScheduling,
+        mealplan_management::MensaParseScheduling, persistent_data::SchedulerLeaseDataAccess,
+        retention::RetentionScheduling,
+    },
+    util::Uuid,
+};
+
+use tokio::{
+    sync::{Mutex, Notify},
+    task::JoinHandle,
+};
+use tokio_cron_scheduler::{Job, JobScheduler, JobSchedulerError};
+use tracing::{debug, info};
+
+use super::{
+    control::SchedulerControl,
+    job::{JobEntry, RunLock, ScheduledJob},
+    leader::Leadership,
+};
 
 /// Structure containing [cron](https://cron.help/)-like schedules for running actions regularly.
 ///
 /// **Important:** Unlike regular cron expressions, seconds also have to be specified, see [here](https://lib.rs/crates/tokio-cron-scheduler).
+#[derive(Debug, Clone)]
 pub struct ScheduleInfo {
     /// Cron-like schedule for running the meal plan update process for the current day's meal plan, see [`MensaParseScheduling`].
     pub update_parse_schedule: String,
     /// Cron-like schedule for running the meal plan update process for all available meal plan data, see [`MensaParseScheduling`].
     pub full_parse_schedule: String,
+    /// Cron-like schedule for reviewing newly uploaded images, see [`ImageReviewScheduling`].
+    pub image_review_schedule: String,
+    /// Cron-like schedule for validating visible images again which were not verified for some time, see [`ImageReviewScheduling`].
+    pub image_reverification_schedule: String,
+    /// Cron-like schedule for removing data which is no longer needed, see [`RetentionScheduling`].
+    pub retention_schedule: String,
+    /// Cron-like schedule for comparing stored image files with the images in the database, see [`RetentionScheduling`].
+    pub image_reconciliation_schedule: String,
+    /// Cron-like schedule for sending moderation digests to administrators, see [`DigestScheduling`]. No digests get sent if not set.
+    pub digest_schedule: Option<String>,
+    /// How long the instance running the scheduled jobs stays leader without renewing its lease.
+    /// When it stops, e.g. because it crashed, another instance takes over after at most this duration.
+    pub leader_lease: Duration,
+}
+
+impl ScheduleInfo {
+    /// Returns the schedule of the job with the given name, `None` if there is no such job or it is disabled.
+    #[must_use]
+    pub fn schedule(&self, job: &str) -> Option<&str> {
+        match job {
+            UPDATE_PARSING => Some(&self.update_parse_schedule),
+            FULL_PARSING => Some(&self.full_parse_schedule),
+            IMAGE_REVIEW => Some(&self.image_review_schedule),
+            IMAGE_REVERIFICATION => Some(&self.image_reverification_schedule),
+            RETENTION => Some(&self.retention_schedule),
+            IMAGE_RECONCILIATION => Some(&self.image_reconciliation_schedule),
+            DIGEST => self.digest_schedule.as_deref(),
+            _ => None,
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -24,10 +71,36 @@ enum State {
     Stopped,
 }
 
+/// Name of the job parsing the current day's meal plan.
+pub const UPDATE_PARSING: &str = "update_parsing";
+/// Name of the job parsing all available meal plan data.
+pub const FULL_PARSING: &str = "full_parsing";
+/// Name of the job reviewing newly uploaded images.
+pub const IMAGE_REVIEW: &str = "image_review";
+/// Name of the job validating visible images again.
+pub const IMAGE_REVERIFICATION: &str = "image_reverification";
+/// Name of the job removing data which is no longer needed.
+pub const RETENTION: &str = "retention";
+/// Name of the job comparing stored image files with the images in the database.
+pub const IMAGE_RECONCILIATION: &str = "image_reconciliation";
+/// Name of the job sending moderation digests.
+pub const DIGEST: &str = "digest";
+/// Name of the lock shared by parse runs and retention.
+pub const PARSE_LOCK: &str = "parsing";
+
 /// Class fro planning regular events.
+///
+/// When multiple instances share a database, only one of them runs the scheduled jobs, see [`SchedulerLeaseDataAccess`].
+/// Jobs started on demand through [`SchedulerControl`] run on the instance they got started on.
+/// Either way, a job never runs while it or a job modifying the same data runs on any instance.
 pub struct Scheduler {
-    scheduler: JobScheduler,
+    cron: JobScheduler,
     state: State,
+    jobs: Arc<[JobEntry]>,
+    mensa_parse: Arc<dyn MensaParseScheduling>,
+    parse_lock: RunLock,
+    leadership: Arc<Leadership>,
+    lease_renewal: Option<JoinHandle<()>>,
 }
 
 impl Scheduler {
@@ -40,68 +113,125 @@ impl Scheduler {
     pub async fn new(
         info: ScheduleInfo,
         parse_scheduling: impl MensaParseScheduling + 'static,
+        image_review_scheduling: impl ImageReviewScheduling + 'static,
+        retention_scheduling: impl RetentionScheduling + 'static,
+        digest_scheduling: impl DigestScheduling + 'static,
+        lease_data: impl SchedulerLeaseDataAccess + 'static,
     ) -> Self {
         let scheduler = JobScheduler::new()
             .await
             .expect("cannot initialize scheduler");
 
-        // === mensa parsing ===
-
-        let mensa_parse = Arc::new(parse_scheduling);
-        let timezone = chrono::Local::now().timezone();
-
-        // mensa update parsing
-        let mensa_parse_update = mensa_parse.clone();
-        let update_parse_job =
-            Job::new_async_tz(&info.update_parse_schedule, timezone, move |_, _| {
-                let mensa_parse = mensa_parse_update.clone();
-                Box::pin(
-                    async move {
-                        info!("Started mensa update parsing.");
-                        let start: Instant = Instant::now();
-
-                        mensa_parse.start_update_parsing().await;
-
-                        info!("Finished mensa update parsing in {:?}.", start.elapsed());
-                    }
-                    .instrument(info_span!("update_parsing")),
-                )
-            })
-            .expect("could not create schedule for image reviewing");
-
-        scheduler
-            .add(update_parse_job)
-            .await
-            .expect("could not add job for update parsing to scheduler");
-
-        // mensa full parsing
-        let full_parse_job = Job::new_async_tz(&info.full_parse_schedule, timezone, move |_, _| {
-            let mensa_parse = mensa_parse.clone();
-            Box::pin(
-                async move {
-                    info!("Started mensa full parsing.");
-                    let start: Instant = Instant::now();
-
-                    mensa_parse.start_full_parsing().await;
-
-                    info!("Finished mensa full parsing in {:?}.", start.elapsed());
-                }
-                .instrument(info_span!("full_parsing")),
+        let lease_data: Arc<dyn SchedulerLeaseDataAccess> = Arc::new(lease_data);
+        let lock = |name| RunLock::new(name, lease_data.clone(), info.leader_lease);
+
+        // parse runs and retention never overlap, so they do not race on the same meal plans
+        let mensa_parse: Arc<dyn MensaParseScheduling> = Arc::new(parse_scheduling);
+        let parse_lock = lock(PARSE_LOCK);
+
+        let image_review: Arc<dyn ImageReviewScheduling> = Arc::new(image_review_scheduling);
+        let retention: Arc<dyn RetentionScheduling> = Arc::new(retention_scheduling);
+
+        let mut jobs = vec![
+            ScheduledJob::new(
+                UPDATE_PARSING,
+                parse_lock.clone(),
+                mensa_parse.clone(),
+                |parse| async move {
+                    parse.start_update_parsing().await;
+                },
+            ),
+            ScheduledJob::new(
+                FULL_PARSING,
+                parse_lock.clone(),
+                mensa_parse.clone(),
+                |parse| async move {
+                    parse.start_full_parsing().await;
+                },
+            ),
+            ScheduledJob::new(
+                IMAGE_REVIEW,
+                lock(IMAGE_REVIEW),
+                image_review.clone(),
+                |review| async move {
+                    review.start_image_review().await;
+                },
             )
-        })
-        .expect("could not create schedule for image reviewing");
+            .quiet(),
+            ScheduledJob::new(
+                IMAGE_REVERIFICATION,
+                lock(IMAGE_REVERIFICATION),
+                image_review,
+                |review| async move {
+                    review.start_image_reverification().await;
+                },
+            ),
+            ScheduledJob::new(
+                RETENTION,
+                parse_lock.clone(),
+                retention.clone(),
+                |retention| async move {
+                    retention.start_retention().await;
+                },
+            ),
+            ScheduledJob::new(
+                IMAGE_RECONCILIATION,
+                lock(IMAGE_RECONCILIATION),
+                retention,
+                |retention| async move {
+                    retention.start_image_reconciliation().await;
+                },
+            ),
+        ];
+        if info.digest_schedule.is_some() {
+            jobs.push(ScheduledJob::new(
+                DIGEST,
+                lock(DIGEST),
+                Arc::new(digest_scheduling),
+                |digest| async move { digest.send_digest().await },
+            ));
+        }
 
-        scheduler
-            .add(full_parse_job)
-            .await
-            .expect("could not add job for full parsing to scheduler");
+        let leadership = Arc::new(Leadership::new(lease_data.clone(), info.leader_lease));
+        let mut added_jobs = Vec::with_capacity(jobs.len());
+        for job in jobs {
+            let job = Arc::new(job);
+            let name = job.name;
+            let schedule = info
+                .schedule(name)
+                .unwrap_or_else(|| panic!("no schedule for {name}"));
+            let id = add_job(&scheduler, &leadership, job.clone(), schedule)
+                .await
+                .unwrap_or_else(|e| panic!("could not add job for {name} to scheduler: {e}"));
+            added_jobs.push(JobEntry {
+                job,
+                cron: Mutex::new((id, schedule.to_owned())),
+            });
+        }
 
         Self {
-            scheduler,
+            cron: scheduler,
             state: State::Created,
+            jobs: added_jobs.into(),
+            mensa_parse,
+            parse_lock,
+            leadership,
+            lease_renewal: None,
         }
     }
 
+    /// Returns a handle for inspecting and controlling the jobs of this scheduler, e.g. over the admin api.
+    #[must_use]
+    pub fn control(&self) -> SchedulerControl {
+        SchedulerControl::new(
+            self.cron.clone(),
+            self.jobs.clone(),
+            self.mensa_parse.clone(),
+            self.parse_lock.clone(),
+            self.leadership.clone(),
+        )
+    }
+
     /// Starts the scheduler. It runs in the background until it is stopped with [`Self::shutdown()`].
     ///
     /// # Panics
@@ -112,7 +242,9 @@ impl Scheduler {
             State::Created,
             "scheduler should only be started once"
         );
-        self.scheduler
+        self.leadership.renew().await;
+        self.lease_renewal = Some(self.leadership.spawn_renewal());
+        self.cron
             .start()
             .await
             .expect("scheduler should only be started once");
@@ -133,39 +265,90 @@ impl Scheduler {
         let shutdown_finished = Arc::new(Notify::new());
 
         let shutdown_sender = shutdown_finished.clone();
-        self.scheduler.set_shutdown_handler(Box::new(move || {
+        self.cron.set_shutdown_handler(Box::new(move || {
             let shutdown_sender = shutdown_sender.clone();
             Box::pin(async move { shutdown_sender.notify_one() })
         }));
 
-        self.scheduler
+        self.cron
             .shutdown()
             .await
             .expect("could not shut down scheduler");
 
         // wait until shutdown finished
         shutdown_finished.notified().await;
+        if let Some(lease_renewal) = self.lease_renewal.take() {
+            lease_renewal.abort();
+        }
+        self.leadership.resign().await;
         self.state = State::Stopped;
         info!("Scheduler shutdown complete.");
     }
 }
 
+/// Adds `job` to the cron scheduler, so it runs at `schedule` as long as this instance is the leader.
+/// Returns the id of the job at the cron scheduler.
+pub(super) async fn add_job(
+    scheduler: &JobScheduler,
+    leadership: &Arc<Leadership>,
+    job: Arc<ScheduledJob>,
+    schedule: &str,
+) -> Result<Uuid, JobSchedulerError> {
+    let name = job.name;
+    let leadership = leadership.clone();
+    let cron_job = Job::new_async_tz(schedule, chrono::Local, move |_, _| {
+        let job = job.clone();
+        let leadership = leadership.clone();
+        Box::pin(async move {
+            if leadership.is_leader().await {
+                job.run_scheduled().await;
+            } else {
+                debug!("Skipped job {name} as another instance runs the scheduled jobs.");
+            }
+        })
+    })?;
+
+    scheduler.add(cron_job).await
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
 
-    use crate::layer::trigger::scheduling::mocks::MensaParseMock;
+    use crate::layer::trigger::scheduling::mocks::{
+        DigestMock, ImageReviewMock, MensaParseMock, RetentionMock, SchedulerLeaseMock,
+    };
 
     use super::*;
+
+    const LEASE: Duration = Duration::from_millis(300);
+    const NEVER: &str = "0 0 0 31 2 *";
     #[tokio::test]
     async fn test_scheduling() {
         let info = ScheduleInfo {
             full_parse_schedule: "*/1 * * * * *".into(),
             update_parse_schedule: "*/2 * * * * *".into(),
+            image_review_schedule: "*/5 * * * * *".into(),
+            image_reverification_schedule: "*/5 * * * * *".into(),
+            retention_schedule: "*/5 * * * * *".into(),
+            image_reconciliation_schedule: "*/5 * * * * *".into(),
+            digest_schedule: Some("*/5 * * * * *".into()),
+            leader_lease: LEASE,
         };
         let mensa_parser = MensaParseMock::default();
-
-        let mut scheduler = Scheduler::new(info, mensa_parser.clone()).await;
+        let image_review = ImageReviewMock::default();
+        let retention = RetentionMock::default();
+        let digest = DigestMock::default();
+
+        let mut scheduler = Scheduler::new(
+            info,
+            mensa_parser.clone(),
+            image_review.clone(),
+            retention.clone(),
+            digest.clone(),
+            SchedulerLeaseMock::default(),
+        )
+        .await;
 
         scheduler.start().await;
 
@@ -184,6 +367,81 @@ mod tests {
             "update parse was not called right amount: {}",
             mensa_parser.get_update_calls()
         );
+        assert!(
+            (1..=3).contains(&image_review.get_review_calls()),
+            "image review was not called right amount: {}",
+            image_review.get_review_calls()
+        );
+        assert!(
+            (1..=3).contains(&image_review.get_reverification_calls()),
+            "image reverification was not called right amount: {}",
+            image_review.get_reverification_calls()
+        );
+        assert!(
+            (1..=3).contains(&retention.get_retention_calls()),
+            "retention was not called right amount: {}",
+            retention.get_retention_calls()
+        );
+        assert!(
+            (1..=3).contains(&retention.get_reconciliation_calls()),
+            "image reconciliation was not called right amount: {}",
+            retention.get_reconciliation_calls()
+        );
+        assert!(
+            (1..=3).contains(&digest.get_digest_calls()),
+            "digest was not called right amount: {}",
+            digest.get_digest_calls()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_leader_failover() {
+        let info = || ScheduleInfo {
+            full_parse_schedule: "*/1 * * * * *".into(),
+            update_parse_schedule: NEVER.into(),
+            image_review_schedule: NEVER.into(),
+            image_reverification_schedule: NEVER.into(),
+            retention_schedule: NEVER.into(),
+            image_reconciliation_schedule: NEVER.into(),
+            digest_schedule: None,
+            leader_lease: LEASE,
+        };
+        let lease_data = SchedulerLeaseMock::default();
+        let leader_parser = MensaParseMock::default();
+        let follower_parser = MensaParseMock::default();
+
+        let mut leader = Scheduler::new(
+            info(),
+            leader_parser.clone(),
+            ImageReviewMock::default(),
+            RetentionMock::default(),
+            DigestMock::default(),
+            lease_data.clone(),
+        )
+        .await;
+        let mut follower = Scheduler::new(
+            info(),
+            follower_parser.clone(),
+            ImageReviewMock::default(),
+            RetentionMock::default(),
+            DigestMock::default(),
+            lease_data,
+        )
+        .await;
+        leader.start().await;
+        follower.start().await;
+
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert!(leader_parser.get_full_calls() > 0);
+        assert_eq!(follower_parser.get_full_calls(), 0);
+
+        leader.shutdown().await;
+        let leader_calls = leader_parser.get_full_calls();
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert_eq!(leader_parser.get_full_calls(), leader_calls);
+        assert!(follower_parser.get_full_calls() > 0);
+
+        follower.shutdown().await;
     }
 
     #[tokio::test]
@@ -192,10 +450,24 @@ mod tests {
         let info = ScheduleInfo {
             full_parse_schedule: "*/1 * * * * *".into(),
             update_parse_schedule: "*/2 * * * * *".into(),
+            image_review_schedule: "*/5 * * * * *".into(),
+            image_reverification_schedule: "*/5 * * * * *".into(),
+            retention_schedule: "*/5 * * * * *".into(),
+            image_reconciliation_schedule: "*/5 * * * * *".into(),
+            digest_schedule: Some("*/5 * * * * *".into()),
+            leader_lease: LEASE,
         };
         let mensa_parser = MensaParseMock::default();
 
-        let mut scheduler = Scheduler::new(info, mensa_parser.clone()).await;
+        let mut scheduler = Scheduler::new(
+            info,
+            mensa_parser.clone(),
+            ImageReviewMock::default(),
+            RetentionMock::default(),
+            DigestMock::default(),
+            SchedulerLeaseMock::default(),
+        )
+        .await;
         scheduler.start().await;
         scheduler.start().await;
         scheduler.shutdown().await;
@@ -207,10 +479,24 @@ mod tests {
         let info = ScheduleInfo {
             full_parse_schedule: "*/1 * * * * *".into(),
             update_parse_schedule: "*/2 * * * * *".into(),
+            image_review_schedule: "*/5 * * * * *".into(),
+            image_reverification_schedule: "*/5 * * * * *".into(),
+            retention_schedule: "*/5 * * * * *".into(),
+            image_reconciliation_schedule: "*/5 * * * * *".into(),
+            digest_schedule: Some("*/5 * * * * *".into()),
+            leader_lease: LEASE,
         };
         let mensa_parser = MensaParseMock::default();
 
-        let mut scheduler = Scheduler::new(info, mensa_parser.clone()).await;
+        let mut scheduler = Scheduler::new(
+            info,
+            mensa_parser.clone(),
+            ImageReviewMock::default(),
+            RetentionMock::default(),
+            DigestMock::default(),
+            SchedulerLeaseMock::default(),
+        )
+        .await;
         scheduler.shutdown().await;
     }
 }
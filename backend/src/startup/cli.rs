@@ -1,20 +1,56 @@
-//! Module containing code for command line-only actions.
+//! Module containing the command line interface and code for command line-only actions.
 
+use std::{path::PathBuf, sync::Arc};
+
+use chrono::Local;
+use clap::{Args, Parser, Subcommand};
 use colored::Colorize;
 use futures::StreamExt;
 use hyper::{header::CONTENT_TYPE, Method};
+use serde::{de::IntoDeserializer, Deserialize};
 use thiserror::Error;
 use tracing::{info, warn};
 
 use crate::{
-    interface::image_storage::{self, ImageStorage},
+    interface::{
+        admin_notification::NotificationError,
+        api_command::{Command as ApiCommand, CommandError},
+        health::{HealthCheck, HealthError},
+        image_storage::{self, ImageStorage},
+        mealplan_management::{MensaParseScheduling, ParseRunError},
+        mensa_parser::ParseError,
+        persistent_data::{AdminDataAccess, AuthDataAccess, DataError},
+        retention::RetentionScheduling,
+    },
     layer::{
-        data::file_handler::FileHandler,
-        logic::api_command::image_preprocessing::{ImagePreprocessingError, ImagePreprocessor},
+        data::{
+            database::{auth::PersistentAuthData, factory::DataAccessFactory},
+            file_handler::FileHandler,
+            mail::mail_sender::MailSender,
+            notification::fan_out::NotificationFanOut,
+            swka_parser::swka_parse_manager::SwKaParseManager,
+        },
+        logic::{
+            api_command::{
+                command_handler::CommandHandler,
+                image_preprocessing::{ImagePreprocessingError, ImagePreprocessor},
+                report_policy::ReportPolicy,
+            },
+            mealplan_management::meal_plan_manager::MealPlanManager,
+            retention::retention_manager::RetentionManager,
+        },
+        trigger::scheduling::{
+            control::{run_exclusively, SchedulerError},
+            scheduler::{IMAGE_RECONCILIATION, PARSE_LOCK},
+        },
     },
+    util::{api_key_ident, generate_api_key, ApiKeyScope, Date, Uuid},
 };
 
-use super::{config::ConfigReader, server::ServerError};
+use super::{
+    config::{ConfigReader, SettingSource},
+    server::ServerError,
+};
 
 /// Error while running a subcommand.
 #[derive(Debug, Error)]
@@ -34,66 +70,407 @@ pub enum SubcommandError {
     /// Error while storing image locally.
     #[error("could not save image")]
     ImageStorageError(#[from] image_storage::ImageError),
+    /// Error while reading or writing data.
+    #[error("error accessing data: {0}")]
+    DataError(#[from] DataError),
+    /// Error while creating the mail sender.
+    #[error("error while creating the mail sender: {0}")]
+    NotificationError(#[from] NotificationError),
+    /// Error while creating the command component or running a command.
+    #[error("error while running command: {0}")]
+    CommandError(#[from] CommandError),
+    /// Error while creating the mensa parser component.
+    #[error("error while creating mensa parser component: {0}")]
+    ParseError(#[from] ParseError),
+    /// Error while parsing meal plans.
+    #[error("error while parsing: {0}")]
+    ParseRunError(#[from] ParseRunError),
+    /// The job could not run without overlapping with runs on other instances.
+    #[error("error while running job: {0}")]
+    SchedulerError(#[from] SchedulerError),
+    /// The database is not usable.
+    #[error("database is not healthy: {0}")]
+    HealthError(#[from] HealthError),
+    /// Some database migrations are not applied.
+    #[error("{0} database migrations are not applied")]
+    PendingMigrations(usize),
+    /// Error while converting data to JSON.
+    #[error("error while converting to JSON: {0}")]
+    JsonError(#[from] serde_json::Error),
 }
 
-/// Command arguments to show the help page.
-pub const HELP: &[&str] = &["--help", "-h", "-?"];
-/// Command argument to run database migrations.
-pub const MIGRATE: &str = "--migrate";
+/// Name recorded in the audit log for actions taken from the command line.
+pub const CLI_ACTOR: &str = "cli";
 
-/// Command arguments to igrate images from image hoster (flickr) to local storage.
-pub const MIGRATE_IMAGES: &str = "--migrate-images";
+/// Command line interface of the backend. Without a command, the server gets started.
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about,
+    after_help = concat!(
+        "For more information, see ",
+        env!("CARGO_PKG_REPOSITORY"),
+        "\nLicensed under the ",
+        env!("CARGO_PKG_LICENSE"),
+        " license."
+    )
+)]
+pub struct Cli {
+    /// Reads settings which are not set as environment variables from this TOML file.
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Prints the settings in use, without secrets, and checks them for invalid values.
+    #[arg(long)]
+    pub print_config: bool,
+    /// What to do, runs the server if not given.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
 
-/// Prints information about the binary and shows available commands.
-pub fn print_help() {
-    const COMMAND_WIDTH: usize = 20;
-    println!(
-        "{}",
-        "==================================================".bright_black()
-    );
-    println!(
-        "{}",
-        format!("   MensaApp Backend v{} 🥘  ", env!("CARGO_PKG_VERSION")).green()
-    );
-    println!(
-        "{}",
-        "==================================================".bright_black()
-    );
-    println!("This binary runs the backend to for the mensa app,");
-    println!("including a graphql server.");
-    println!("For more information, ");
-    println!("see {}", env!("CARGO_PKG_REPOSITORY"));
-    println!();
-    println!(
-        "{}",
-        format!("Licensed under the {} license.", env!("CARGO_PKG_LICENSE")).italic()
-    );
-    println!();
-    println!();
-    println!("{}", "Available commands:".blue());
-    println!(
-        "{:<COMMAND_WIDTH$} {}",
-        "help".bold(),
-        HELP.join(" ").as_str().bright_black()
-    );
-    println!("          shows this page");
-    println!();
-    println!(
-        "{:<COMMAND_WIDTH$} {}",
-        "migrate".bold(),
-        MIGRATE.bright_black()
-    );
-    println!("          runs the database migrations");
-    println!("          before continuing like normal");
-    println!();
-    println!(
-        "{:<COMMAND_WIDTH$} {}",
-        "migrate images".bold(),
-        MIGRATE_IMAGES.bright_black()
+/// Commands of the [`Cli`].
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Runs the server, which is the default without a command.
+    Serve(ServeArgs),
+    /// Operational tasks, which exit when done.
+    #[command(flatten)]
+    Operation(OperationCommand),
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Self::Serve(ServeArgs::default())
+    }
+}
+
+/// Arguments for running the server.
+#[derive(Debug, Default, Args)]
+pub struct ServeArgs {
+    /// Runs the database migrations before starting.
+    #[arg(long)]
+    pub migrate: bool,
+}
+
+/// Commands of the [`Cli`] for operational tasks, see [`run_command`].
+#[derive(Debug, Subcommand)]
+pub enum OperationCommand {
+    /// Manages the database schema.
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Parses meal plans right away.
+    #[command(subcommand)]
+    Parse(ParseCommand),
+    /// Manages the api keys.
+    #[command(subcommand, name = "apikey")]
+    ApiKey(ApiKeyCommand),
+    /// Moderates and maintains the images.
+    #[command(subcommand)]
+    Image(ImageCommand),
+    /// Checks the database.
+    #[command(subcommand)]
+    Db(DbCommand),
+    /// Exports or erases the data stored about a client.
+    #[command(subcommand)]
+    Client(ClientCommand),
+}
+
+/// Commands for managing the database schema.
+#[derive(Debug, Subcommand)]
+pub enum MigrateCommand {
+    /// Applies all pending database migrations.
+    Up,
+    /// Reverts the last applied database migration.
+    Down,
+    /// Lists all database migrations and whether they are applied.
+    Status,
+    /// Migrates images from the image hoster (flickr) to local storage.
+    Images,
+}
+
+/// Commands for parsing meal plans.
+#[derive(Debug, Subcommand)]
+pub enum ParseCommand {
+    /// Parses the meal plan of a single day and stores it.
+    Now {
+        /// Day to parse in the format yyyy-mm-dd, today if not given.
+        #[arg(long)]
+        date: Option<Date>,
+        /// Only parses this canteen, all canteens if not given.
+        #[arg(long)]
+        canteen: Option<String>,
+    },
+}
+
+/// Commands for managing api keys.
+#[derive(Debug, Subcommand)]
+pub enum ApiKeyCommand {
+    /// Adds an api key and prints it.
+    Add(NewApiKeyArgs),
+    /// Prints all api keys and their usage as JSON.
+    List,
+    /// Replaces an api key by a new one and prints it.
+    Rotate {
+        /// Id of the api key.
+        key_id: Uuid,
+    },
+    /// Revokes an api key.
+    Revoke {
+        /// Id of the api key.
+        key_id: Uuid,
+    },
+}
+
+/// Settings of an api key to add via the command line.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct NewApiKeyArgs {
+    /// Description of the api key.
+    pub description: String,
+    /// Requests allowed with the api key: `READ_ONLY`, `UPLOAD` or `MODERATE`.
+    #[arg(long, default_value = "READ_ONLY", value_parser = parse_scope)]
+    pub scope: ApiKeyScope,
+    /// Last day the api key is valid in the format yyyy-mm-dd, if it should expire.
+    #[arg(long = "expires", value_name = "DATE")]
+    pub expiry_date: Option<Date>,
+}
+
+/// Commands for moderating and maintaining images.
+#[derive(Debug, Subcommand)]
+pub enum ImageCommand {
+    /// Marks an image as verified, so reports do not hide it.
+    Verify {
+        /// Id of the image.
+        image_id: Uuid,
+    },
+    /// Deletes an image, which can be restored until its grace period is over.
+    Delete {
+        /// Id of the image.
+        image_id: Uuid,
+    },
+    /// Compares the stored image files with the images in the database and removes those without counterpart.
+    Reconcile,
+}
+
+/// Commands for checking the database.
+#[derive(Debug, Subcommand)]
+pub enum DbCommand {
+    /// Checks whether the database is reachable and all migrations are applied.
+    Check,
+}
+
+/// Commands for handling data requests of clients.
+#[derive(Debug, Subcommand)]
+pub enum ClientCommand {
+    /// Prints all data stored about a client as JSON.
+    Export {
+        /// Id of the client.
+        client_id: Uuid,
+    },
+    /// Erases all data stored about a client, including its images.
+    Erase {
+        /// Id of the client.
+        client_id: Uuid,
+    },
+}
+
+fn parse_scope(scope: &str) -> Result<ApiKeyScope, String> {
+    ApiKeyScope::deserialize(
+        IntoDeserializer::<serde::de::value::Error>::into_deserializer(scope.trim()),
+    )
+    .map_err(|_| "expected one of READ_ONLY, UPLOAD or MODERATE".into())
+}
+
+/// Runs the given operational task.
+/// # Errors
+/// if the task could not be done, see [`SubcommandError`].
+pub async fn run_command(
+    config: &ConfigReader,
+    command: OperationCommand,
+) -> Result<(), SubcommandError> {
+    match command {
+        OperationCommand::Migrate(MigrateCommand::Up) => connect(config).await?.migrate().await?,
+        OperationCommand::Migrate(MigrateCommand::Down) => revert_migration(config).await?,
+        OperationCommand::Migrate(MigrateCommand::Status) => print_migrations(config).await?,
+        OperationCommand::Migrate(MigrateCommand::Images) => migrate_images(config).await?,
+        OperationCommand::Parse(ParseCommand::Now { date, canteen }) => {
+            parse_now(config, date, canteen.as_deref()).await?;
+        }
+        OperationCommand::ApiKey(command) => run_api_key_command(config, command).await?,
+        OperationCommand::Image(ImageCommand::Verify { image_id }) => {
+            command_handler(config)
+                .await?
+                .verify_image(image_id, CLI_ACTOR)
+                .await?;
+            info!("Verified image {image_id}.");
+        }
+        OperationCommand::Image(ImageCommand::Delete { image_id }) => {
+            command_handler(config)
+                .await?
+                .delete_image(image_id, CLI_ACTOR)
+                .await?;
+            info!("Deleted image {image_id}.");
+        }
+        OperationCommand::Image(ImageCommand::Reconcile) => reconcile_images(config).await?,
+        OperationCommand::Db(DbCommand::Check) => check_database(config).await?,
+        OperationCommand::Client(ClientCommand::Export { client_id }) => {
+            export_client_data(config, client_id).await?;
+        }
+        OperationCommand::Client(ClientCommand::Erase { client_id }) => {
+            command_handler(config)
+                .await?
+                .erase_client_data(client_id, CLI_ACTOR)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn run_api_key_command(
+    config: &ConfigReader,
+    command: ApiKeyCommand,
+) -> Result<(), SubcommandError> {
+    match command {
+        ApiKeyCommand::Add(args) => add_api_key(config, &args).await,
+        ApiKeyCommand::List => list_api_keys(config).await,
+        ApiKeyCommand::Rotate { key_id } => rotate_api_key(config, key_id).await,
+        ApiKeyCommand::Revoke { key_id } => revoke_api_key(config, key_id).await,
+    }
+}
+
+/// Connects to the database without running migrations.
+async fn connect(config: &ConfigReader) -> Result<DataAccessFactory, SubcommandError> {
+    Ok(DataAccessFactory::new(config.read_database_info().map_err(Box::new)?, false).await?)
+}
+
+/// Creates the component handling commands like on the api, including notifications of administrators.
+/// Mails get sent before the command returns, as there is no server retrying them later.
+async fn command_handler(config: &ConfigReader) -> Result<impl ApiCommand, SubcommandError> {
+    let factory = connect(config).await?;
+    let mail = MailSender::without_retries(config.read_mail_info().map_err(Box::new)?)?;
+    let notification =
+        NotificationFanOut::new(config.read_notification_info().map_err(Box::new)?, mail)?;
+    let file_handler = FileHandler::new(config.read_file_handler_info().await.map_err(Box::new)?);
+    let report_policy = ReportPolicy::new(config.read_report_policy_info().map_err(Box::new)?);
+    Ok(CommandHandler::new(
+        config.read_image_preprocessing_info(),
+        report_policy,
+        config.read_client_limit_info(),
+        factory.get_command_data_access(),
+        notification,
+        file_handler,
+    )?)
+}
+
+/// Reverts the last applied database migration.
+async fn revert_migration(config: &ConfigReader) -> Result<(), SubcommandError> {
+    if let Some(migration) = connect(config).await?.revert_last_migration().await? {
+        info!(
+            "Reverted migration {} {}.",
+            migration.version, migration.description
+        );
+    } else {
+        info!("No migration applied, nothing to revert.");
+    }
+    Ok(())
+}
+
+/// Prints all database migrations and whether they are applied.
+async fn print_migrations(config: &ConfigReader) -> Result<(), SubcommandError> {
+    for migration in connect(config).await?.migration_status().await? {
+        let status = if migration.applied {
+            "applied".green()
+        } else {
+            "pending".yellow()
+        };
+        println!("{} {status} {}", migration.version, migration.description);
+    }
+    Ok(())
+}
+
+/// Parses the meal plan of the given day, or today, like the scheduled parsing does.
+/// Fails if parsing or retention runs on any instance at the same time.
+async fn parse_now(
+    config: &ConfigReader,
+    date: Option<Date>,
+    canteen: Option<&str>,
+) -> Result<(), SubcommandError> {
+    let factory = connect(config).await?;
+    let parser = SwKaParseManager::new(config.read_swka_info().map_err(Box::new)?)?;
+    let mealplan_management =
+        MealPlanManager::new(factory.get_mealplan_management_data_access(), parser);
+
+    let date = date.unwrap_or_else(|| Local::now().date_naive());
+    run_exclusively(
+        Arc::new(factory.get_scheduler_lease_data_access()),
+        PARSE_LOCK,
+        config.read_schedule_info().map_err(Box::new)?.leader_lease,
+        mealplan_management.start_date_parsing(date, canteen),
+    )
+    .await??;
+    info!("Parsed meal plan of {date}.");
+    Ok(())
+}
+
+/// Removes stored image files without image in the database and images without file.
+/// Fails if the reconciliation runs on any instance at the same time.
+async fn reconcile_images(config: &ConfigReader) -> Result<(), SubcommandError> {
+    let factory = connect(config).await?;
+    let file_handler = FileHandler::new(config.read_file_handler_info().await.map_err(Box::new)?);
+    let retention = RetentionManager::new(
+        config.read_retention_info(),
+        factory.get_retention_data_access(),
+        file_handler,
     );
-    println!("          migrates images from hoster");
-    println!("          to local storage");
-    println!();
+
+    let report = run_exclusively(
+        Arc::new(factory.get_scheduler_lease_data_access()),
+        IMAGE_RECONCILIATION,
+        config.read_schedule_info().map_err(Box::new)?.leader_lease,
+        retention.start_image_reconciliation(),
+    )
+    .await?;
+    info!("{report}");
+    Ok(())
+}
+
+/// Checks whether the database is reachable and all migrations are applied.
+async fn check_database(config: &ConfigReader) -> Result<(), SubcommandError> {
+    let factory = connect(config).await?;
+    factory.get_database_health().check_health().await?;
+
+    let pending = factory
+        .migration_status()
+        .await?
+        .iter()
+        .filter(|migration| !migration.applied)
+        .count();
+    if pending > 0 {
+        return Err(SubcommandError::PendingMigrations(pending));
+    }
+    info!("Database is reachable and up to date.");
+    Ok(())
+}
+
+/// Prints all settings with the value the server would use and where it comes from, redacting secrets.
+pub fn print_config(config: &ConfigReader) {
+    for setting in config.effective_config() {
+        let source = match setting.source {
+            SettingSource::Environment => "environment",
+            SettingSource::ConfigFile => "config file",
+            SettingSource::Default => "default",
+            SettingSource::Unset => "not set",
+        };
+        match setting.value {
+            Some(value) => println!(
+                "{} = {value:?} {}",
+                setting.name,
+                format!("# {source}").bright_black()
+            ),
+            None => println!(
+                "{}",
+                format!("# {} is {source}", setting.name).bright_black()
+            ),
+        }
+    }
 }
 
 /// migrates images from image hoster to local storage.
@@ -102,7 +479,7 @@ pub fn print_help() {
 /// - invalid database config
 /// # Panics
 /// never
-pub async fn migrate_images(config: &ConfigReader) -> Result<(), SubcommandError> {
+async fn migrate_images(config: &ConfigReader) -> Result<(), SubcommandError> {
     info!("Starting image migration...");
 
     let image_preprocessing = ImagePreprocessor::new(config.read_image_preprocessing_info());
@@ -165,16 +542,157 @@ pub async fn migrate_images(config: &ConfigReader) -> Result<(), SubcommandError
     Ok(())
 }
 
+/// Prints all data stored about the given client as JSON.
+/// # Errors
+/// - invalid database config
+async fn export_client_data(config: &ConfigReader, client_id: Uuid) -> Result<(), SubcommandError> {
+    let factory = connect(config).await?;
+
+    let data = factory
+        .get_admin_data_access()
+        .get_client_data(client_id)
+        .await?;
+    println!("{}", serde_json::to_string_pretty(&data)?);
+    Ok(())
+}
+
+/// Adds a new api key and prints it, as it only gets stored encrypted.
+/// # Errors
+/// - invalid database config
+async fn add_api_key(config: &ConfigReader, args: &NewApiKeyArgs) -> Result<(), SubcommandError> {
+    let auth_data = auth_data(config).await?;
+
+    let key = generate_api_key();
+    let id = auth_data
+        .add_api_key(
+            api_key_ident(&key),
+            &key,
+            &args.description,
+            args.scope,
+            args.expiry_date,
+            CLI_ACTOR,
+        )
+        .await?;
+    info!("Added api key {id} with scope {}.", args.scope);
+    println!("{key}");
+    Ok(())
+}
+
+/// Prints information and usage statistics of all api keys as JSON.
+/// # Errors
+/// - invalid database config
+async fn list_api_keys(config: &ConfigReader) -> Result<(), SubcommandError> {
+    let keys = auth_data(config).await?.get_api_key_infos().await?;
+    println!("{}", serde_json::to_string_pretty(&keys)?);
+    Ok(())
+}
+
+/// Replaces the secret of the given api key by a new one and prints it.
+/// # Errors
+/// - invalid database config
+async fn rotate_api_key(config: &ConfigReader, key_id: Uuid) -> Result<(), SubcommandError> {
+    let key = generate_api_key();
+    auth_data(config)
+        .await?
+        .rotate_api_key(key_id, api_key_ident(&key), &key, CLI_ACTOR)
+        .await?;
+    info!("Rotated api key {key_id}.");
+    println!("{key}");
+    Ok(())
+}
+
+/// Revokes the given api key.
+/// # Errors
+/// - invalid database config
+async fn revoke_api_key(config: &ConfigReader, key_id: Uuid) -> Result<(), SubcommandError> {
+    auth_data(config)
+        .await?
+        .revoke_api_key(key_id, CLI_ACTOR)
+        .await?;
+    info!("Revoked api key {key_id}.");
+    Ok(())
+}
+
+/// Connects to the database for managing api keys.
+async fn auth_data(config: &ConfigReader) -> Result<PersistentAuthData, SubcommandError> {
+    let cipher = config.read_api_key_cipher().map_err(Box::new)?;
+    Ok(connect(config).await?.get_auth_data_access(cipher))
+}
+
 #[cfg(test)]
 mod tests {
+    use clap::{CommandFactory, Parser};
 
-    use crate::startup::config::ConfigReader;
+    use crate::{startup::config::ConfigReader, util::ApiKeyScope};
 
-    use super::{migrate_images, print_help};
+    use super::{
+        migrate_images, print_config, ApiKeyCommand, Cli, Command, MigrateCommand, NewApiKeyArgs,
+        OperationCommand, ParseCommand,
+    };
 
     #[test]
     fn test_print_cli() {
-        print_help();
+        Cli::command().debug_assert();
+        print_config(&ConfigReader::default());
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let cli = Cli::try_parse_from(["backend"]).expect("valid args");
+        assert!(cli.command.is_none());
+
+        let cli = Cli::try_parse_from(["backend", "serve", "--migrate", "--config", "config.toml"])
+            .expect("valid args");
+        assert!(matches!(cli.command, Some(Command::Serve(args)) if args.migrate));
+        assert_eq!(cli.config, Some("config.toml".into()));
+
+        let cli = Cli::try_parse_from(["backend", "migrate", "status"]).expect("valid args");
+        assert!(matches!(
+            cli.command,
+            Some(Command::Operation(OperationCommand::Migrate(
+                MigrateCommand::Status
+            )))
+        ));
+
+        let cli = Cli::try_parse_from([
+            "backend",
+            "parse",
+            "now",
+            "--date",
+            "2024-01-31",
+            "--canteen",
+            "mensa_moltke",
+        ])
+        .expect("valid args");
+        let Some(Command::Operation(OperationCommand::Parse(ParseCommand::Now { date, canteen }))) =
+            cli.command
+        else {
+            panic!("parse command expected");
+        };
+        assert_eq!(date, "2024-01-31".parse().ok());
+        assert_eq!(canteen.as_deref(), Some("mensa_moltke"));
+
+        let cli = Cli::try_parse_from(["backend", "apikey", "add", "app", "--scope", "UPLOAD"])
+            .expect("valid args");
+        let Some(Command::Operation(OperationCommand::ApiKey(ApiKeyCommand::Add(args)))) =
+            cli.command
+        else {
+            panic!("api key command expected");
+        };
+        assert_eq!(
+            args,
+            NewApiKeyArgs {
+                description: "app".into(),
+                scope: ApiKeyScope::Upload,
+                expiry_date: None,
+            }
+        );
+
+        assert!(
+            Cli::try_parse_from(["backend", "apikey", "add", "app", "--scope", "ALL"]).is_err()
+        );
+        assert!(Cli::try_parse_from(["backend", "image", "verify", "no-uuid"]).is_err());
+        assert!(Cli::try_parse_from(["backend", "migrate"]).is_err());
     }
 
     #[tokio::test]
@@ -1,27 +1,82 @@
 //! See [`ConfigReader`].
 use super::{
-    cli::{HELP, MIGRATE, MIGRATE_IMAGES},
-    logging::LogInfo,
+    config_file::read_config_file,
+    logging::{LogInfo, OtlpInfo},
     server::{Result, ServerError},
 };
-use crate::interface::image_validation::{GeminiInfo, ImageValidationInfo, SafeSearchInfo};
+use crate::interface::image_validation::{
+    GeminiInfo, ImageValidationInfo, OpenAiInfo, SafeSearchInfo,
+};
 use crate::layer::{
     data::{
-        database::factory::DatabaseInfo, file_handler::FileHandlerInfo, mail::mail_info::MailInfo,
+        database::{
+            cipher::{ApiKeyCipher, API_KEY_CIPHER_KEY_LENGTH},
+            factory::DatabaseInfo,
+        },
+        file_handler::FileHandlerInfo,
+        mail::mail_info::MailInfo,
+        notification::notification_info::{
+            ChannelFilter, NotificationInfo, NotificationKind, WebhookFormat, WebhookInfo,
+        },
         swka_parser::swka_parse_manager::SwKaInfo,
     },
-    logic::api_command::image_preprocessing::ImagePreprocessingInfo,
-    trigger::{api::server::ApiServerInfo, scheduling::scheduler::ScheduleInfo},
+    logic::{
+        api_command::{
+            client_limits::ClientLimitInfo, image_preprocessing::ImagePreprocessingInfo,
+            report_policy::ReportPolicyInfo,
+        },
+        image_review::image_review_manager::ImageReviewInfo,
+        retention::retention_manager::RetentionInfo,
+    },
+    trigger::{
+        api::{admin::oidc::OidcInfo, rate_limit::RateLimitInfo, server::ApiServerInfo},
+        scheduling::scheduler::ScheduleInfo,
+    },
 };
+use crate::util::ReportReason;
+use base64::{engine::general_purpose::STANDARD, Engine};
 use dotenvy::dotenv;
-use std::{env, num::NonZeroU64, path::PathBuf, time::Duration};
+use regex::Regex;
+use serde::{de::DeserializeOwned, de::IntoDeserializer};
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Display,
+    num::{NonZeroU32, NonZeroU64},
+    path::PathBuf,
+    str::FromStr,
+    sync::LazyLock,
+    time::Duration,
+};
+use tokio_cron_scheduler::Job;
 use tracing::info;
+use tracing_subscriber::EnvFilter;
 
 const DEFAULT_CANTEENS: &str = "mensa_adenauerring,mensa_gottesaue,mensa_moltke,mensa_x1moltkestrasse,mensa_erzberger,mensa_tiefenbronner,mensa_holzgarten";
 const DEFAULT_BASE_URL: &str = "https://www.sw-ka.de/de/hochschulgastronomie/speiseplan/";
 const DEFAULT_UPDATE_PARSE_SCHEDULE: &str = "0 */15 7-15 * * *";
 const DEFAULT_NIGHTLY_SCHEDULE: &str = "0 0 2 * * *";
+const DEFAULT_IMAGE_REVIEW_SCHEDULE: &str = "*/30 * * * * *";
+const DEFAULT_IMAGE_REVIEW_ATTEMPTS: u32 = 5;
+const DEFAULT_IMAGE_REVERIFICATION_SCHEDULE: &str = "0 0 5 * * *";
+const DEFAULT_REVERIFICATION_DAYS: u32 = 180;
+const DEFAULT_REVERIFICATION_LIMIT: u32 = 100;
+const DEFAULT_RETENTION_SCHEDULE: &str = "0 30 3 * * *";
+const DEFAULT_IMAGE_RECONCILIATION_SCHEDULE: &str = "0 0 4 * * Sun";
+const DEFAULT_LEADER_LEASE_SECONDS: NonZeroU64 =
+    NonZeroU64::new(30).expect("default lease should be positive");
+const DEFAULT_DELETED_IMAGE_GRACE_DAYS: u32 = 14;
+const DEFAULT_HIDDEN_IMAGE_RETENTION_DAYS: u32 = 90;
+const DEFAULT_REPORT_RETENTION_DAYS: u32 = 365;
+const DEFAULT_UNSERVED_FOOD_RETENTION_DAYS: u32 = 730;
+const DEFAULT_RETENTION_DRY_RUN: bool = false;
+/// Report reasons still mailed immediately in digest mode, if `MAIL_REASONS` is not set.
+const DIGEST_IMMEDIATE_REASONS: [ReportReason; 2] =
+    [ReportReason::Offensive, ReportReason::ViolatesRights];
 const DEFAULT_LOG_CONFIG: &str = "warn,mensa_app_backend=info";
+/// Includes graphql resolvers and `sqlx` query events, so both show up in traces.
+const DEFAULT_OTLP_FILTER: &str =
+    "warn,mensa_app_backend=info,async_graphql=info,sqlx::query=debug";
 const DEFAULT_USER_AGENT: &str = concat!("MensaKa ", env!("CARGO_PKG_VERSION"));
 const DEFAULT_CLIENT_TIMEOUT: u64 = 6000;
 const DEFAULT_HTTP_PORT: u16 = 80;
@@ -31,36 +86,209 @@ const DEFAULT_MAX_IMAGE_WIDTH: u32 = 1920;
 const DEFAULT_MAX_IMAGE_HEIGHT: u32 = 1080;
 const DEFAULT_USE_SAFE_SEARCH: bool = false;
 const DEFAULT_USE_GEMINI: bool = false;
+const DEFAULT_USE_OPENAI: bool = false;
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_OPENAI_MODEL: &str = "gpt-4o-mini";
+const DEFAULT_OPENAI_TEXT_REQUEST: &str = "Is there a dish from a Mensaria in the picture?";
+const DEFAULT_OPENAI_REJECT_MEAL_MISMATCH: bool = false;
 const DEFAULT_IMAGE_ACCEPTANCE_VALUES: &str = "0,0,0,0,0";
 const DEFAULT_UPLOAD_SIZE: u64 = 10 << 20; // 10 MiB
+const DEFAULT_API_KEY_REFRESH_INTERVAL: u64 = 60;
+const DEFAULT_AUTH_MAX_REQUEST_AGE: u64 = 300;
+const DEFAULT_ADMIN_SESSION_HOURS: u64 = 12;
+/// A day between nightly full parses plus some margin for slow runs.
+const DEFAULT_MAX_PARSE_AGE_HOURS: u64 = 26;
+/// Largest number of hours accepted for durations, so times computed from them cannot overflow.
+const MAX_HOURS: u64 = 100 * 365 * 24;
+const DEFAULT_OIDC_ROLES_CLAIM: &str = "groups";
+const DEFAULT_OIDC_MODERATOR_GROUP: &str = "mensa-moderator";
+const DEFAULT_OIDC_OPERATOR_GROUP: &str = "mensa-operator";
+
+/// Key for encrypting the secrets of api keys in the database.
+const API_KEY_ENCRYPTION_KEY: &str = "API_KEY_ENCRYPTION_KEY";
+/// Environment variable containing the path of the config file, if not given as program argument.
+const CONFIG_FILE_VAR: &str = "CONFIG_FILE";
+/// Shown instead of the value of secret settings.
+const REDACTED: &str = "<redacted>";
+
+/// Names of the settings of numbered webhooks, which are only part of [`settings`] if the webhook is set.
+static WEBHOOK_SETTING: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new("^WEBHOOK_[1-9][0-9]*_(URL|FORMAT|EVENTS|REASONS|HIDDEN_ONLY)$")
+        .expect("valid regex")
+});
 
 /// Class for reading configuration from environment variables.
-pub struct ConfigReader {}
+///
+/// Environment variables can also be set in a `.env` file or in a TOML config file.
+/// Both only provide variables which are not set already, so actual environment variables always take precedence.
+/// Settings of the config file are kept in the reader instead of the environment, so reading the file again does not change the environment while other threads may read it.
+#[derive(Debug)]
+pub struct ConfigReader {
+    /// Path of the config file, if any.
+    config_file: Option<PathBuf>,
+    /// Settings of the config file by name.
+    file_settings: HashMap<String, String>,
+}
 
 impl Default for ConfigReader {
+    /// Creates a reader for the environment variables, including those set in a `.env` file, but ignoring any config file.
+    ///
+    /// Like [`ConfigReader::new`], this has to be called before other threads get started.
     fn default() -> Self {
         dotenv().ok();
-        Self {}
+        Self {
+            config_file: None,
+            file_settings: HashMap::new(),
+        }
     }
 }
 
+/// Where the value of a setting comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingSource {
+    /// An environment variable, possibly set in the `.env` file.
+    Environment,
+    /// The config file.
+    ConfigFile,
+    /// The setting is not set, so its default is used.
+    Default,
+    /// The setting is not set and has no default.
+    Unset,
+}
+
+/// A setting with the value the server uses, see [`ConfigReader::effective_config`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveSetting {
+    /// Name of the environment variable.
+    pub name: String,
+    /// Value of the setting, secrets are redacted.
+    pub value: Option<String>,
+    /// Where the value comes from.
+    pub source: SettingSource,
+}
+
 impl ConfigReader {
-    /// Checks program arguments whether a migration should get run.
-    #[must_use]
-    pub fn should_migrate(&self) -> bool {
-        env::args().any(|arg| arg == MIGRATE)
+    /// Creates a reader for the environment variables, including those set in a `.env` file.
+    /// Settings which are not set there get taken from the TOML config file at `config_file` or in the `CONFIG_FILE` variable, if any.
+    ///
+    /// As the `.env` file gets loaded into the environment, this has to be called before other threads, e.g. of the async runtime, get started.
+    /// # Errors
+    /// when the config file could not be read or contains unknown settings.
+    pub fn new(config_file: Option<PathBuf>) -> Result<Self> {
+        dotenv().ok();
+        let mut config = Self {
+            config_file: config_file.or_else(|| env::var_os(CONFIG_FILE_VAR).map(PathBuf::from)),
+            file_settings: HashMap::new(),
+        };
+        config.file_settings = config.read_config_file()?;
+        crate::util::set_base_url(
+            config
+                .read_var("BASE_URL")
+                .unwrap_or_else(|_| crate::util::DEFAULT_BASE_URL.into()),
+        );
+        Ok(config)
+    }
+
+    /// Reads the config file again, returning a reader using the changed settings in it.
+    /// Environment variables and the `.env` file are not read again, as they do not change while running.
+    /// # Errors
+    /// when the config file could not be read or the new settings are invalid.
+    pub fn reload(&self) -> Result<Self> {
+        let config = Self {
+            config_file: self.config_file.clone(),
+            file_settings: self.read_config_file()?,
+        };
+        config.validate()?;
+        Ok(config)
     }
 
-    /// Queries the program arguments to check whether the help page should be shown.
+    /// Reads all settings of the config file.
+    fn read_config_file(&self) -> Result<HashMap<String, String>> {
+        let Some(path) = &self.config_file else {
+            return Ok(HashMap::new());
+        };
+
+        let settings = read_config_file(path)?;
+        let unknown: Vec<_> = settings
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| !self.is_known_setting(name))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(ServerError::ConfigFileError {
+                path: path.display().to_string(),
+                reason: format!("unknown settings {}", unknown.join(", ")),
+            });
+        }
+        Ok(settings.into_iter().collect())
+    }
+
+    /// Checks the format of all settings which are set, so invalid values do not get replaced by their default silently.
+    /// # Errors
+    /// [`ServerError::InvalidConfig`] describing every invalid setting.
+    pub fn validate(&self) -> Result<()> {
+        let errors: Vec<_> = self
+            .settings()
+            .iter()
+            .filter_map(|setting| setting.check(self).err())
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ServerError::InvalidConfig(errors))
+        }
+    }
+
+    /// Returns all settings with the value the server uses and where it comes from.
     #[must_use]
-    pub fn should_print_help(&self) -> bool {
-        env::args().any(|arg| HELP.contains(&arg.as_str()))
+    pub fn effective_config(&self) -> Vec<EffectiveSetting> {
+        self.settings()
+            .into_iter()
+            .map(|setting| {
+                let (value, source) = if let Ok(value) = env::var(&setting.name) {
+                    (Some(value), SettingSource::Environment)
+                } else if let Some(value) = self.file_settings.get(&setting.name) {
+                    (Some(value.clone()), SettingSource::ConfigFile)
+                } else {
+                    setting
+                        .default
+                        .clone()
+                        .map_or((None, SettingSource::Unset), |default| {
+                            (Some(default), SettingSource::Default)
+                        })
+                };
+                EffectiveSetting {
+                    value: value.map(|value| {
+                        if setting.secret {
+                            REDACTED.into()
+                        } else {
+                            value
+                        }
+                    }),
+                    name: setting.name,
+                    source,
+                }
+            })
+            .collect()
     }
 
-    /// Queries the program arguments to check whether image migration should be run.
+    /// Reads the limits of graphql requests from environment variables, see [`Self::read_api_info`].
     #[must_use]
-    pub fn should_migrate_images(&self) -> bool {
-        env::args().any(|arg| arg == MIGRATE_IMAGES)
+    pub fn read_rate_limit_info(&self) -> RateLimitInfo {
+        let default = RateLimitInfo::default();
+        RateLimitInfo {
+            client_limit: self.read_limit("CLIENT_RATE_LIMIT", default.client_limit),
+            api_key_limit: self.read_limit("API_KEY_RATE_LIMIT", default.api_key_limit),
+            ip_limit: self.read_limit("IP_RATE_LIMIT", default.ip_limit),
+            mutation_limit: self.read_limit("MUTATION_RATE_LIMIT", default.mutation_limit),
+            image_upload_cost: self.read_parsed_or("IMAGE_UPLOAD_COST", default.image_upload_cost),
+            trust_forwarded_for: self
+                .read_var_to_bool("TRUST_FORWARDED_FOR")
+                .unwrap_or(default.trust_forwarded_for),
+            max_query_depth: self.read_parsed_or("GRAPHQL_MAX_DEPTH", default.max_query_depth),
+            max_query_complexity: self
+                .read_parsed_or("GRAPHQL_MAX_COMPLEXITY", default.max_query_complexity),
+        }
     }
 
     /// Reads the logging configuration from environment variables.
@@ -69,8 +297,19 @@ impl ConfigReader {
     pub fn read_log_info(&self) -> Result<LogInfo> {
         // note: no logging here because logging is not yet initialized.
         let info = LogInfo {
-            log_config: read_var("LOG_CONFIG").unwrap_or_else(|_| DEFAULT_LOG_CONFIG.into()),
-            loki_url: read_var("LOKI_URL").ok(),
+            log_config: self
+                .read_var("LOG_CONFIG")
+                .unwrap_or_else(|_| DEFAULT_LOG_CONFIG.into()),
+            loki_url: self.read_var("LOKI_URL").ok(),
+            otlp: self
+                .read_var("OTLP_ENDPOINT")
+                .ok()
+                .map(|endpoint| OtlpInfo {
+                    endpoint,
+                    filter: self
+                        .read_var("OTLP_FILTER")
+                        .unwrap_or_else(|_| DEFAULT_OTLP_FILTER.into()),
+                }),
         };
         Ok(info)
     }
@@ -80,25 +319,28 @@ impl ConfigReader {
     /// when the environment variables are not set and no default is provided internally.
     pub fn read_database_info(&self) -> Result<DatabaseInfo> {
         let info = DatabaseInfo {
-            connection: read_var("DATABASE_URL")?,
-            max_weeks_data: get_max_weeks_data(),
+            connection: self.read_var("DATABASE_URL")?,
+            max_weeks_data: self.get_max_weeks_data(),
         };
         Ok(info)
     }
 
+    /// Reads how long ago the last successful meal plan parsing may be for the server to still count as ready.
+    #[must_use]
+    pub fn read_max_parse_age(&self) -> Duration {
+        self.read_hours("MAX_PARSE_AGE_HOURS", DEFAULT_MAX_PARSE_AGE_HOURS)
+    }
+
     /// Reads the config for accessing the mail server from environment variables.
     /// # Errors
     /// when the environment variables are not set and no default is provided internally.  
     pub fn read_mail_info(&self) -> Result<MailInfo> {
         let info = MailInfo {
-            admin_email_address: read_var("ADMIN_EMAIL")?,
-            smtp_server: read_var("SMTP_SERVER")?,
-            smtp_port: read_var("SMTP_PORT")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(DEFAULT_SMTP_PORT),
-            username: read_var("SMTP_USERNAME")?,
-            password: read_var("SMTP_PASSWORD")?,
+            admin_email_address: self.read_var("ADMIN_EMAIL")?,
+            smtp_server: self.read_var("SMTP_SERVER")?,
+            smtp_port: self.read_parsed_or("SMTP_PORT", DEFAULT_SMTP_PORT),
+            username: self.read_var("SMTP_USERNAME")?,
+            password: self.read_var("SMTP_PASSWORD")?,
         };
         info!(
             "Sending mails to {} from {} using {}:{}",
@@ -107,20 +349,84 @@ impl ConfigReader {
         Ok(info)
     }
 
+    /// Reads which notifications get sent by mail and the webhooks to notify additionally from environment variables.
+    /// Webhooks are numbered, starting with `WEBHOOK_1_URL`.
+    /// # Errors
+    /// when the environment variables are not formatted correctly.
+    pub fn read_notification_info(&self) -> Result<NotificationInfo> {
+        let mut mail_filter = self.read_channel_filter("MAIL")?;
+        if self.read_var("DIGEST_SCHEDULE").is_ok() {
+            // everything else is part of the digest
+            if self.read_var("MAIL_EVENTS").is_err() {
+                mail_filter.kinds = vec![NotificationKind::Report];
+            }
+            if self.read_var("MAIL_REASONS").is_err() {
+                mail_filter.reasons = DIGEST_IMMEDIATE_REASONS.to_vec();
+            }
+        }
+        let mut webhooks = Vec::new();
+        for number in 1.. {
+            let prefix = format!("WEBHOOK_{number}");
+            let Ok(url) = self.read_var(&format!("{prefix}_URL")) else {
+                break;
+            };
+            let format = self.read_webhook_format(&format!("{prefix}_FORMAT"))?;
+            info!("Notifying administrators via {format:?} webhook {number}");
+            webhooks.push(WebhookInfo {
+                url,
+                format,
+                filter: self.read_channel_filter(&prefix)?,
+            });
+        }
+        Ok(NotificationInfo {
+            mail_filter,
+            webhooks,
+        })
+    }
+
     /// Reads the schedules for regular events from environment variables.
     /// # Errors
     /// when the environment variables are not set and no default is provided internally.
     pub fn read_schedule_info(&self) -> Result<ScheduleInfo> {
         let info = ScheduleInfo {
-            full_parse_schedule: env::var("FULL_PARSE_SCHEDULE")
-                .unwrap_or_else(|_| DEFAULT_NIGHTLY_SCHEDULE.into()),
-            update_parse_schedule: env::var("UPDATE_PARSE_SCHEDULE")
-                .unwrap_or_else(|_| DEFAULT_UPDATE_PARSE_SCHEDULE.into()),
+            full_parse_schedule: self
+                .read_schedule("FULL_PARSE_SCHEDULE", DEFAULT_NIGHTLY_SCHEDULE)?,
+            update_parse_schedule: self
+                .read_schedule("UPDATE_PARSE_SCHEDULE", DEFAULT_UPDATE_PARSE_SCHEDULE)?,
+            image_review_schedule: self
+                .read_schedule("IMAGE_REVIEW_SCHEDULE", DEFAULT_IMAGE_REVIEW_SCHEDULE)?,
+            image_reverification_schedule: self.read_schedule(
+                "IMAGE_REVERIFICATION_SCHEDULE",
+                DEFAULT_IMAGE_REVERIFICATION_SCHEDULE,
+            )?,
+            retention_schedule: self
+                .read_schedule("RETENTION_SCHEDULE", DEFAULT_RETENTION_SCHEDULE)?,
+            image_reconciliation_schedule: self.read_schedule(
+                "IMAGE_RECONCILIATION_SCHEDULE",
+                DEFAULT_IMAGE_RECONCILIATION_SCHEDULE,
+            )?,
+            digest_schedule: self
+                .read_var("DIGEST_SCHEDULE")
+                .ok()
+                .map(|schedule| check_schedule("DIGEST_SCHEDULE", &schedule).map(|()| schedule))
+                .transpose()?,
+            leader_lease: Duration::from_secs(
+                self.read_parsed_or("LEADER_LEASE_SECONDS", DEFAULT_LEADER_LEASE_SECONDS)
+                    .get(),
+            ),
         };
         info!(
-            "Running full parsing on `{}` and update parsing on `{}`",
-            info.full_parse_schedule, info.update_parse_schedule
+            "Running full parsing on `{}`, update parsing on `{}`, image review on `{}`, image reverification on `{}`, retention on `{}` and image reconciliation on `{}`",
+            info.full_parse_schedule,
+            info.update_parse_schedule,
+            info.image_review_schedule,
+            info.image_reverification_schedule,
+            info.retention_schedule,
+            info.image_reconciliation_schedule
         );
+        if let Some(digest_schedule) = &info.digest_schedule {
+            info!("Sending moderation digests on `{digest_schedule}`");
+        }
         Ok(info)
     }
 
@@ -128,13 +434,11 @@ impl ConfigReader {
     /// # Errors
     /// when the environment variables are not set and no default is provided internally.
     pub fn read_swka_info(&self) -> Result<SwKaInfo> {
-        let timeout = env::var("CLIENT_TIMEOUT")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(DEFAULT_CLIENT_TIMEOUT);
-        let timeout = Duration::from_millis(timeout);
+        let timeout =
+            Duration::from_millis(self.read_parsed_or("CLIENT_TIMEOUT", DEFAULT_CLIENT_TIMEOUT));
 
-        let canteens = read_var("CANTEENS")
+        let canteens = self
+            .read_var("CANTEENS")
             .unwrap_or_else(|_| DEFAULT_CANTEENS.into())
             .split(',')
             .map(str::trim)
@@ -142,12 +446,15 @@ impl ConfigReader {
             .collect();
 
         let info = SwKaInfo {
-            base_url: read_var("MENSA_BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.into()),
+            base_url: self
+                .read_var("MENSA_BASE_URL")
+                .unwrap_or_else(|_| DEFAULT_BASE_URL.into()),
             client_timeout: timeout,
-            client_user_agent: env::var("USER_AGENT")
+            client_user_agent: self
+                .read_var("USER_AGENT")
                 .unwrap_or_else(|_| String::from(DEFAULT_USER_AGENT)),
             valid_canteens: canteens,
-            number_of_weeks_to_poll: get_max_weeks_data(),
+            number_of_weeks_to_poll: self.get_max_weeks_data(),
         };
         info!(
             "getting canteen data from <{}> for canteens {}",
@@ -157,25 +464,40 @@ impl ConfigReader {
         Ok(info)
     }
 
+    /// Reads the key used for encrypting the secrets of api keys in the database.
+    /// # Errors
+    /// when the key is not set or no base64 encoded 32 byte key.
+    pub fn read_api_key_cipher(&self) -> Result<ApiKeyCipher> {
+        self.read_encryption_key(API_KEY_ENCRYPTION_KEY)
+            .map(|key| ApiKeyCipher::new(&key))
+    }
+
     /// Reads the config for the graphql web server from environment variables.
     /// # Errors
     /// when the environment variables are not set and no default is provided internally.
     pub fn read_api_info(&self) -> Result<ApiServerInfo> {
         let info = ApiServerInfo {
-            port: env::var("HTTP_PORT")
-                .ok()
-                .and_then(|p| p.parse().ok())
-                .unwrap_or(DEFAULT_HTTP_PORT),
-            image_dir: read_var("IMAGE_DIR").map(PathBuf::from)?,
-            rate_limit: read_var("RATE_LIMIT")
+            port: self.read_parsed_or("HTTP_PORT", DEFAULT_HTTP_PORT),
+            image_dir: self.read_var("IMAGE_DIR").map(PathBuf::from)?,
+            rate_limit: self
+                .read_var("RATE_LIMIT")
                 .ok()
                 .and_then(|r| r.parse().ok())
                 .and_then(NonZeroU64::new),
-            max_body_size: read_var("MAX_UPLOAD_SIZE")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(DEFAULT_UPLOAD_SIZE),
-            admin_key: read_var("ADMIN_KEY")?,
+            max_body_size: self.read_parsed_or("MAX_UPLOAD_SIZE", DEFAULT_UPLOAD_SIZE),
+            admin_key: self.read_var("ADMIN_KEY").unwrap_or_default(),
+            admin_session_duration: self
+                .read_hours("ADMIN_SESSION_HOURS", DEFAULT_ADMIN_SESSION_HOURS),
+            oidc: self.read_oidc_info()?,
+            api_key_refresh_interval: Duration::from_secs(
+                self.read_parsed_or("API_KEY_REFRESH_INTERVAL", DEFAULT_API_KEY_REFRESH_INTERVAL)
+                    .max(1),
+            ),
+            max_request_age: Duration::from_secs(
+                self.read_parsed_or("AUTH_MAX_REQUEST_AGE", DEFAULT_AUTH_MAX_REQUEST_AGE),
+            ),
+            rate_limits: self.read_rate_limit_info(),
+            mass_report_threshold: self.read_mass_report_threshold(),
         };
 
         info.rate_limit.map_or_else(
@@ -184,6 +506,21 @@ impl ConfigReader {
         );
 
         info!("Using max upload size of {} bytes.", info.max_body_size);
+        if info.admin_key.is_empty() {
+            info!("Shared admin key is disabled, only administrator accounts can log in.");
+        }
+        if let Some(oidc) = &info.oidc {
+            info!(
+                "Administrators can log in via openid connect at {}",
+                oidc.issuer_url
+            );
+        }
+        info!(
+            "Allowing {} graphql requests per client, {} per api key and {} per ip address and minute",
+            format_limit(info.rate_limits.client_limit),
+            format_limit(info.rate_limits.api_key_limit),
+            format_limit(info.rate_limits.ip_limit)
+        );
 
         Ok(info)
     }
@@ -192,14 +529,8 @@ impl ConfigReader {
     #[must_use]
     pub fn read_image_preprocessing_info(&self) -> ImagePreprocessingInfo {
         let info: ImagePreprocessingInfo = ImagePreprocessingInfo {
-            max_image_width: env::var("MAX_IMAGE_WIDTH")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(DEFAULT_MAX_IMAGE_WIDTH),
-            max_image_height: env::var("MAX_IMAGE_HEIGHT")
-                .ok()
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(DEFAULT_MAX_IMAGE_HEIGHT),
+            max_image_width: self.read_parsed_or("MAX_IMAGE_WIDTH", DEFAULT_MAX_IMAGE_WIDTH),
+            max_image_height: self.read_parsed_or("MAX_IMAGE_HEIGHT", DEFAULT_MAX_IMAGE_HEIGHT),
         };
         info!(
             "Scaling down images to {}x{}",
@@ -208,13 +539,131 @@ impl ConfigReader {
         info
     }
 
+    /// Reads the config deciding when reported images get hidden and how images get ranked.
+    /// # Errors
+    /// when a list of report reasons or weights could not be parsed
+    /// or a decimal setting is not a finite number, or not greater than 0 for the barrier growth divisor.
+    pub fn read_report_policy_info(&self) -> Result<ReportPolicyInfo> {
+        let default = ReportPolicyInfo::default();
+        let info = ReportPolicyInfo {
+            base_barrier: self.read_decimal("REPORT_BASE_BARRIER", default.base_barrier)?,
+            barrier_growth_divisor: self.read_positive_decimal(
+                "REPORT_BARRIER_GROWTH_DIVISOR",
+                default.barrier_growth_divisor,
+            )?,
+            report_window_days: self
+                .read_parsed_or("REPORT_WINDOW_DAYS", default.report_window_days),
+            reason_weights: self.read_reason_weights("REPORT_REASON_WEIGHTS")?,
+            immediate_hide_reasons: self.read_reasons("IMMEDIATE_HIDE_REASONS")?,
+            trusted_uploader_approved_images: self.read_parsed_or(
+                "TRUSTED_UPLOADER_APPROVED_IMAGES",
+                default.trusted_uploader_approved_images,
+            ),
+            trusted_uploader_factor: self
+                .read_decimal("TRUSTED_UPLOADER_FACTOR", default.trusted_uploader_factor)?,
+            suspicious_uploader_factor: self.read_decimal(
+                "SUSPICIOUS_UPLOADER_FACTOR",
+                default.suspicious_uploader_factor,
+            )?,
+            rank_report_penalty: self
+                .read_decimal("RANK_REPORT_PENALTY", default.rank_report_penalty)?,
+            rank_new_image_days: self
+                .read_parsed_or("RANK_NEW_IMAGE_DAYS", default.rank_new_image_days)
+                .max(1),
+        };
+        info!(
+            "Hiding images after {} + t²/{} weighted reports within {} days, with weights {:?} and immediately for {:?}",
+            info.base_barrier,
+            info.barrier_growth_divisor,
+            info.report_window_days,
+            info.reason_weights,
+            info.immediate_hide_reasons
+        );
+        Ok(info)
+    }
+
+    /// Reads the daily limits for actions of a single client.
+    #[must_use]
+    pub fn read_client_limit_info(&self) -> ClientLimitInfo {
+        let default = ClientLimitInfo::default();
+        let info = ClientLimitInfo {
+            report_limit: self.read_parsed_or("CLIENT_REPORT_LIMIT", default.report_limit),
+            upload_limit: self.read_parsed_or("CLIENT_UPLOAD_LIMIT", default.upload_limit),
+            rating_limit: self.read_parsed_or("CLIENT_RATING_LIMIT", default.rating_limit),
+            mass_report_threshold: self.read_mass_report_threshold(),
+        };
+        info!(
+            "Allowing {} reports, {} uploads and {} ratings per client and day",
+            info.report_limit, info.upload_limit, info.rating_limit
+        );
+        info
+    }
+
+    fn read_mass_report_threshold(&self) -> u32 {
+        self.read_parsed_or(
+            "MASS_REPORT_THRESHOLD",
+            ClientLimitInfo::default().mass_report_threshold,
+        )
+    }
+
+    /// Reads the config for reviewing uploaded images.
+    #[must_use]
+    pub fn read_image_review_info(&self) -> ImageReviewInfo {
+        let info = ImageReviewInfo {
+            max_attempts: self
+                .read_parsed_or("IMAGE_REVIEW_ATTEMPTS", DEFAULT_IMAGE_REVIEW_ATTEMPTS),
+            reverification_days: self
+                .read_parsed_or("REVERIFICATION_DAYS", DEFAULT_REVERIFICATION_DAYS),
+            reverification_limit: self
+                .read_parsed_or("REVERIFICATION_LIMIT", DEFAULT_REVERIFICATION_LIMIT),
+        };
+        info!(
+            "Trying to review each uploaded image up to {} times, verifying up to {} images again after {} days",
+            info.max_attempts, info.reverification_limit, info.reverification_days
+        );
+        info
+    }
+
+    /// Reads the config for removing data which is no longer needed.
+    #[must_use]
+    pub fn read_retention_info(&self) -> RetentionInfo {
+        let info = RetentionInfo {
+            deleted_image_grace_days: self
+                .read_parsed_or("DELETED_IMAGE_GRACE_DAYS", DEFAULT_DELETED_IMAGE_GRACE_DAYS),
+            hidden_image_days: self.read_parsed_or(
+                "HIDDEN_IMAGE_RETENTION_DAYS",
+                DEFAULT_HIDDEN_IMAGE_RETENTION_DAYS,
+            ),
+            report_days: self
+                .read_parsed_or("REPORT_RETENTION_DAYS", DEFAULT_REPORT_RETENTION_DAYS),
+            unserved_food_days: self.read_parsed_or(
+                "UNSERVED_FOOD_RETENTION_DAYS",
+                DEFAULT_UNSERVED_FOOD_RETENTION_DAYS,
+            ),
+            dry_run: self
+                .read_var_to_bool("RETENTION_DRY_RUN")
+                .unwrap_or(DEFAULT_RETENTION_DRY_RUN),
+        };
+        info!(
+            "Deleted images can be restored for {} days, hidden images get deleted after {} days, reports after {} days and foods not served for {} days",
+            info.deleted_image_grace_days,
+            info.hidden_image_days,
+            info.report_days,
+            info.unserved_food_days
+        );
+        if info.dry_run {
+            info!("Retention only reports what would be removed");
+        }
+        info
+    }
+
     /// Reads the config for the file handler.
     /// # Errors
     /// - when the environment variable is not set
     /// - when the image directory does not exist
     pub async fn read_file_handler_info(&self) -> Result<FileHandlerInfo> {
         let info: FileHandlerInfo = FileHandlerInfo {
-            image_dir: read_var("IMAGE_DIR")?.into(),
+            image_dir: self.read_var("IMAGE_DIR")?.into(),
         };
 
         if !tokio::fs::try_exists(&info.image_dir)
@@ -238,17 +687,18 @@ impl ConfigReader {
     /// - when usage could not be parsed
     pub async fn get_image_validation_info(&self) -> Result<ImageValidationInfo> {
         Ok(ImageValidationInfo {
-            safe_search_info: if read_var_to_bool("USE_SAFE_SEARCH")
+            safe_search_info: if self
+                .read_var_to_bool("USE_SAFE_SEARCH")
                 .unwrap_or(DEFAULT_USE_SAFE_SEARCH)
             {
-                let project_id = &read_var("GOOGLE_PROJECT_ID")?;
-                let acceptance = read_acceptance_var("IMAGE_ACCEPTANCE_VALUES")?;
+                let project_id = &self.read_var("GOOGLE_PROJECT_ID")?;
+                let acceptance = self.read_acceptance_var("IMAGE_ACCEPTANCE_VALUES")?;
                 info!("Using google safe search for image verification with cloud project '{project_id}' and category levels '{acceptance:?}'.");
                 Some(SafeSearchInfo {
                     acceptance,
-                    service_account_info: tokio::fs::read_to_string(read_var(
-                        "SERVICE_ACCOUNT_JSON",
-                    )?)
+                    service_account_info: tokio::fs::read_to_string(
+                        self.read_var("SERVICE_ACCOUNT_JSON")?,
+                    )
                     .await?,
                     project_id: project_id.clone(),
                 })
@@ -256,79 +706,888 @@ impl ConfigReader {
                 info!("Google safe search api is disabled.");
                 None
             },
-            gemini_info: if read_var_to_bool("USE_GEMINI_API").unwrap_or(DEFAULT_USE_GEMINI) {
-                let query = &read_var("GEMINI_TEXT_REQUEST")?;
+            gemini_info: if self
+                .read_var_to_bool("USE_GEMINI_API")
+                .unwrap_or(DEFAULT_USE_GEMINI)
+            {
+                let query = &self.read_var("GEMINI_TEXT_REQUEST")?;
                 info!("Using google gemini api for image verification with query: '{query}'");
                 Some(GeminiInfo {
-                    gemini_api_key: read_var("GEMINI_API_KEY")?,
+                    gemini_api_key: self.read_var("GEMINI_API_KEY")?,
                     gemini_text_request: query.clone(),
                 })
             } else {
                 info!("Google gemini api is disabled.");
                 None
             },
+            openai_info: if self
+                .read_var_to_bool("USE_OPENAI_API")
+                .unwrap_or(DEFAULT_USE_OPENAI)
+            {
+                let info = OpenAiInfo {
+                    base_url: self
+                        .read_var("OPENAI_BASE_URL")
+                        .unwrap_or_else(|_| DEFAULT_OPENAI_BASE_URL.into()),
+                    model: self
+                        .read_var("OPENAI_MODEL")
+                        .unwrap_or_else(|_| DEFAULT_OPENAI_MODEL.into()),
+                    api_key: self
+                        .read_var("OPENAI_API_KEY")
+                        .ok()
+                        .filter(|k| !k.is_empty()),
+                    text_request: self
+                        .read_var("OPENAI_TEXT_REQUEST")
+                        .unwrap_or_else(|_| DEFAULT_OPENAI_TEXT_REQUEST.into()),
+                    reject_meal_mismatch: self
+                        .read_var_to_bool("OPENAI_REJECT_MEAL_MISMATCH")
+                        .unwrap_or(DEFAULT_OPENAI_REJECT_MEAL_MISMATCH),
+                };
+                info!(
+                    "Using model '{}' at <{}> for image verification with query: '{}'",
+                    info.model, info.base_url, info.text_request
+                );
+                Some(info)
+            } else {
+                info!("OpenAI compatible api is disabled.");
+                None
+            },
         })
     }
 }
 
-fn read_var(var: &str) -> Result<String> {
-    env::var(var).map_err(|e| ServerError::MissingEnvVar(var.to_string(), e))
-}
+impl ConfigReader {
+    /// Reads a setting from the environment or else from the config file.
+    fn read_var(&self, var: &str) -> Result<String> {
+        env::var(var).or_else(|e| {
+            self.file_settings
+                .get(var)
+                .cloned()
+                .ok_or_else(|| ServerError::MissingEnvVar(var.to_string(), e))
+        })
+    }
 
-fn read_var_to_bool(key: &str) -> Result<bool> {
-    let value = read_var(key)?;
-    value
-        .to_lowercase()
-        .as_str()
-        .trim()
-        .parse()
-        .map_err(|_| ServerError::InvalidFormatError {
-            var: key.into(),
-            gotten: value,
-            expected_format: "`true` or `false`".into(),
+    fn read_var_to_bool(&self, key: &str) -> Result<bool> {
+        let value = self.read_var(key)?;
+        value
+            .to_lowercase()
+            .as_str()
+            .trim()
+            .parse()
+            .map_err(|_| ServerError::InvalidFormatError {
+                var: key.into(),
+                gotten: value,
+                expected_format: "`true` or `false`".into(),
+            })
+    }
+
+    fn read_parsed_or<T: FromStr>(&self, key: &str, default: T) -> T {
+        self.read_var(key)
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(default)
+    }
+
+    /// Reads a finite decimal number, failing instead of falling back to the default if the set value is invalid.
+    fn read_decimal(&self, key: &str, default: f64) -> Result<f64> {
+        self.read_checked_decimal(key, default, f64::is_finite, "a decimal number")
+    }
+
+    /// Reads a finite decimal number greater than 0, failing instead of falling back to the default if the set value is invalid.
+    fn read_positive_decimal(&self, key: &str, default: f64) -> Result<f64> {
+        self.read_checked_decimal(
+            key,
+            default,
+            |v| v.is_finite() && v > 0.0,
+            "a decimal number greater than 0",
+        )
+    }
+
+    fn read_checked_decimal(
+        &self,
+        key: &str,
+        default: f64,
+        valid: impl Fn(f64) -> bool,
+        expected_format: &str,
+    ) -> Result<f64> {
+        let Ok(value) = self.read_var(key) else {
+            return Ok(default);
+        };
+        value
+            .trim()
+            .parse()
+            .ok()
+            .filter(|&v| valid(v))
+            .ok_or_else(|| ServerError::InvalidFormatError {
+                var: key.into(),
+                gotten: value.clone(),
+                expected_format: expected_format.into(),
+            })
+    }
+
+    /// Reads a duration given in hours, which is at most [`MAX_HOURS`] long.
+    fn read_hours(&self, key: &str, default: u64) -> Duration {
+        Duration::from_hours(self.read_parsed_or(key, default).min(MAX_HOURS))
+    }
+
+    /// Reads a limit where 0 means disabled.
+    fn read_limit(&self, key: &str, default: Option<NonZeroU32>) -> Option<NonZeroU32> {
+        NonZeroU32::new(self.read_parsed_or(key, default.map_or(0, NonZeroU32::get)))
+    }
+
+    fn read_encryption_key(&self, var: &str) -> Result<[u8; API_KEY_CIPHER_KEY_LENGTH]> {
+        let value = self.read_var(var)?;
+        STANDARD
+            .decode(value.trim())
+            .ok()
+            .and_then(|key| key.try_into().ok())
+            .ok_or_else(|| ServerError::InvalidFormatError {
+                var: var.into(),
+                gotten: REDACTED.into(),
+                expected_format: format!(
+                    "{API_KEY_CIPHER_KEY_LENGTH} random bytes encoded in base64, e.g. from `openssl rand -base64 {API_KEY_CIPHER_KEY_LENGTH}`"
+                ),
+            })
+    }
+
+    fn read_acceptance_var(&self, key: &str) -> Result<[u8; 5]> {
+        let str_arr = self
+            .read_var(key)
+            .unwrap_or_else(|_| DEFAULT_IMAGE_ACCEPTANCE_VALUES.into());
+
+        str_arr
+            .split(',')
+            .map(str::trim)
+            .map(str::parse::<u8>)
+            .map(|r| r.ok().and_then(|i| (0..=5).contains(&i).then_some(i)))
+            .collect::<Option<Vec<_>>>()
+            .and_then(|v| v.try_into().ok())
+            .ok_or(ServerError::InvalidFormatError {
+                var: key.into(),
+                gotten: str_arr,
+                expected_format: "`x,x,x,x,x` where x is in `0..=5`".into(),
+            })
+    }
+
+    fn read_oidc_info(&self) -> Result<Option<OidcInfo>> {
+        let Ok(issuer_url) = self.read_var("OIDC_ISSUER_URL") else {
+            return Ok(None);
+        };
+        Ok(Some(OidcInfo {
+            issuer_url,
+            client_id: self.read_var("OIDC_CLIENT_ID")?,
+            client_secret: self.read_var("OIDC_CLIENT_SECRET")?,
+            redirect_url: self.read_var("OIDC_REDIRECT_URL")?,
+            roles_claim: self
+                .read_var("OIDC_ROLES_CLAIM")
+                .unwrap_or_else(|_| DEFAULT_OIDC_ROLES_CLAIM.into()),
+            moderator_group: self
+                .read_var("OIDC_MODERATOR_GROUP")
+                .unwrap_or_else(|_| DEFAULT_OIDC_MODERATOR_GROUP.into()),
+            operator_group: self
+                .read_var("OIDC_OPERATOR_GROUP")
+                .unwrap_or_else(|_| DEFAULT_OIDC_OPERATOR_GROUP.into()),
+        }))
+    }
+
+    /// Reads the filter of a notification channel from `<prefix>_EVENTS`, `<prefix>_REASONS` and `<prefix>_HIDDEN_ONLY`.
+    fn read_channel_filter(&self, prefix: &str) -> Result<ChannelFilter> {
+        Ok(ChannelFilter {
+            kinds: self.read_events(&format!("{prefix}_EVENTS"))?,
+            reasons: self.read_reasons(&format!("{prefix}_REASONS"))?,
+            hidden_only: self
+                .read_var_to_bool(&format!("{prefix}_HIDDEN_ONLY"))
+                .unwrap_or(false),
         })
-}
+    }
 
-fn read_acceptance_var(key: &str) -> Result<[u8; 5]> {
-    let str_arr = read_var(key).unwrap_or_else(|_| DEFAULT_IMAGE_ACCEPTANCE_VALUES.into());
-
-    str_arr
-        .split(',')
-        .map(str::trim)
-        .map(str::parse::<u8>)
-        .map(|r| r.ok().and_then(|i| (0..=5).contains(&i).then_some(i)))
-        .collect::<Option<Vec<_>>>()
-        .and_then(|v| v.try_into().ok())
-        .ok_or(ServerError::InvalidFormatError {
+    fn read_webhook_format(&self, key: &str) -> Result<WebhookFormat> {
+        let Ok(format) = self.read_var(key) else {
+            return Ok(WebhookFormat::default());
+        };
+        parse_variant(&format).ok_or_else(|| ServerError::InvalidFormatError {
             var: key.into(),
-            gotten: str_arr,
-            expected_format: "`x,x,x,x,x` where x is in `0..=5`".into(),
+            gotten: format,
+            expected_format: "`generic`, `slack` or `matrix`".into(),
         })
+    }
+
+    fn read_events(&self, key: &str) -> Result<Vec<NotificationKind>> {
+        let Ok(value) = self.read_var(key) else {
+            return Ok(Vec::new());
+        };
+        value
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(parse_variant::<NotificationKind>)
+            .collect::<Option<_>>()
+            .ok_or_else(|| ServerError::InvalidFormatError {
+                var: key.into(),
+                gotten: value.clone(),
+                expected_format:
+                    "comma separated events of `report`, `verified`, `deleted`, `withdrawn`, `review_failed` and `mass_reports`"
+                        .into(),
+            })
+    }
+
+    fn read_reasons(&self, key: &str) -> Result<Vec<ReportReason>> {
+        let Ok(value) = self.read_var(key) else {
+            return Ok(Vec::new());
+        };
+        value
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(parse_reason)
+            .collect::<Option<_>>()
+            .ok_or(ServerError::InvalidFormatError {
+                var: key.into(),
+                gotten: value.clone(),
+                expected_format: "comma separated report reasons, e.g. `Offensive,Advert`".into(),
+            })
+    }
+
+    fn read_reason_weights(&self, key: &str) -> Result<Vec<(ReportReason, f64)>> {
+        let Ok(value) = self.read_var(key) else {
+            return Ok(Vec::new());
+        };
+        value
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|entry| {
+                let (reason, weight) = entry.split_once('=')?;
+                let weight: f64 = weight.trim().parse().ok()?;
+                (weight >= 0.0).then_some((parse_reason(reason)?, weight))
+            })
+            .collect::<Option<_>>()
+            .ok_or(ServerError::InvalidFormatError {
+                var: key.into(),
+                gotten: value.clone(),
+                expected_format:
+                    "comma separated `reason=weight` pairs, e.g. `Offensive=2,WrongMeal=0.5`".into(),
+            })
+    }
+
+    /// Reads a schedule, making sure it is valid so the scheduler can be created with it.
+    fn read_schedule(&self, key: &str, default: &str) -> Result<String> {
+        let schedule = self.read_var(key).unwrap_or_else(|_| default.into());
+        check_schedule(key, &schedule)?;
+        Ok(schedule)
+    }
+
+    fn get_max_weeks_data(&self) -> u32 {
+        self.read_parsed_or("PARSE_WEEKS", DEFAULT_PARSE_WEEKS) + 1 // +1 for current week
+    }
+
+    /// Returns all settings, including those of the webhooks which are set.
+    fn settings(&self) -> Vec<Setting> {
+        [
+            service_settings(),
+            job_settings(),
+            moderation_settings(),
+            api_settings(),
+            self.webhook_settings(),
+        ]
+        .concat()
+    }
+
+    /// Settings of the numbered webhooks, as far as they are set.
+    fn webhook_settings(&self) -> Vec<Setting> {
+        use SettingKind::{Events, Flag, Reasons, Url};
+        let mut settings = Vec::new();
+        for number in 1.. {
+            let prefix = format!("WEBHOOK_{number}");
+            if self.read_var(&format!("{prefix}_URL")).is_err() {
+                break;
+            }
+            // urls of webhooks often contain a token
+            settings.extend([
+                Setting::new(format!("{prefix}_URL"), Url).secret(),
+                Setting::new(format!("{prefix}_FORMAT"), SettingKind::WebhookFormat)
+                    .with_default("generic"),
+                Setting::new(format!("{prefix}_EVENTS"), Events),
+                Setting::new(format!("{prefix}_REASONS"), Reasons),
+                Setting::new(format!("{prefix}_HIDDEN_ONLY"), Flag).with_default(false),
+            ]);
+        }
+        settings
+    }
+
+    /// Whether `name` is the name of a setting, e.g. to detect typos in the config file.
+    fn is_known_setting(&self, name: &str) -> bool {
+        WEBHOOK_SETTING.is_match(name) || self.settings().iter().any(|setting| setting.name == name)
+    }
 }
 
-fn get_max_weeks_data() -> u32 {
-    read_var("PARSE_WEEKS")
+fn format_limit(limit: Option<NonZeroU32>) -> String {
+    limit.map_or_else(|| "unlimited".into(), |l| l.to_string())
+}
+
+fn parse_variant<T: DeserializeOwned>(value: &str) -> Option<T> {
+    T::deserialize(IntoDeserializer::<serde::de::value::Error>::into_deserializer(value.trim()))
         .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(DEFAULT_PARSE_WEEKS)
-        + 1 // +1 for current week
+}
+
+fn parse_reason(reason: &str) -> Option<ReportReason> {
+    parse_variant(reason)
+}
+
+/// Format a setting has to be in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingKind {
+    Text,
+    /// `true` or `false`
+    Flag,
+    /// Non-negative integer fitting into 32 bits.
+    Number,
+    /// Non-negative integer fitting into 64 bits.
+    LargeNumber,
+    /// Positive integer fitting into 64 bits.
+    PositiveNumber,
+    /// Non-negative number of hours up to [`MAX_HOURS`].
+    Hours,
+    Port,
+    /// Finite decimal number.
+    Decimal,
+    /// Finite decimal number greater than zero.
+    PositiveDecimal,
+    /// Filter for log output, see [`EnvFilter`].
+    LogFilter,
+    /// Cron-like schedule including seconds.
+    Schedule,
+    Url,
+    /// Comma separated report reasons.
+    Reasons,
+    /// Comma separated `reason=weight` pairs.
+    ReasonWeights,
+    /// Comma separated notification events.
+    Events,
+    WebhookFormat,
+    /// Five comma separated levels for the google safe search categories.
+    AcceptanceValues,
+    /// Base64 encoded key for encryption.
+    EncryptionKey,
+}
+
+/// A setting read from an environment variable.
+#[derive(Debug, Clone)]
+struct Setting {
+    name: String,
+    kind: SettingKind,
+    /// Whether the value may not be shown, e.g. because it is a password.
+    secret: bool,
+    default: Option<String>,
+}
+
+impl Setting {
+    fn new(name: impl Into<String>, kind: SettingKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            secret: false,
+            default: None,
+        }
+    }
+
+    fn with_default(mut self, default: impl Display) -> Self {
+        self.default = Some(default.to_string());
+        self
+    }
+
+    const fn secret(mut self) -> Self {
+        self.secret = true;
+        self
+    }
+
+    /// Checks whether the value of this setting has the right format, if it is set.
+    fn check(&self, config: &ConfigReader) -> Result<()> {
+        let Ok(value) = config.read_var(&self.name) else {
+            return Ok(());
+        };
+        let invalid = |expected_format: &str| {
+            Err(ServerError::InvalidFormatError {
+                var: self.name.clone(),
+                gotten: if self.secret {
+                    REDACTED.into()
+                } else {
+                    value.clone()
+                },
+                expected_format: expected_format.into(),
+            })
+        };
+        let value = value.trim();
+        match self.kind {
+            SettingKind::Flag => config.read_var_to_bool(&self.name).map(drop),
+            SettingKind::Number if value.parse::<u32>().is_err() => {
+                invalid("a non-negative integer")
+            }
+            SettingKind::LargeNumber if value.parse::<u64>().is_err() => {
+                invalid("a non-negative integer")
+            }
+            SettingKind::PositiveNumber if value.parse::<NonZeroU64>().is_err() => {
+                invalid("a positive integer")
+            }
+            SettingKind::Port if value.parse::<u16>().is_err() => {
+                invalid("a port number between 0 and 65535")
+            }
+            SettingKind::Hours if !value.parse::<u64>().is_ok_and(|h| h <= MAX_HOURS) => {
+                invalid(&format!("a non-negative integer of at most {MAX_HOURS}"))
+            }
+            SettingKind::Decimal if !value.parse::<f64>().is_ok_and(f64::is_finite) => {
+                invalid("a decimal number")
+            }
+            SettingKind::PositiveDecimal
+                if !value.parse::<f64>().is_ok_and(|v| v.is_finite() && v > 0.0) =>
+            {
+                invalid("a decimal number greater than 0")
+            }
+            SettingKind::LogFilter if EnvFilter::builder().parse(value).is_err() => {
+                invalid("a log filter, e.g. `warn,mensa_app_backend=info`")
+            }
+            SettingKind::Schedule => check_schedule(&self.name, value),
+            SettingKind::Url if reqwest::Url::parse(value).is_err() => {
+                invalid("an absolute url, e.g. `https://example.org`")
+            }
+            SettingKind::Reasons => config.read_reasons(&self.name).map(drop),
+            SettingKind::ReasonWeights => config.read_reason_weights(&self.name).map(drop),
+            SettingKind::Events => config.read_events(&self.name).map(drop),
+            SettingKind::WebhookFormat => config.read_webhook_format(&self.name).map(drop),
+            SettingKind::AcceptanceValues => config.read_acceptance_var(&self.name).map(drop),
+            SettingKind::EncryptionKey => config.read_encryption_key(&self.name).map(drop),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Settings of logging, the database and mails.
+fn service_settings() -> Vec<Setting> {
+    use SettingKind::{EncryptionKey, Events, Flag, LogFilter, Port, Reasons, Text, Url};
+    vec![
+        Setting::new("LOG_CONFIG", LogFilter).with_default(DEFAULT_LOG_CONFIG),
+        // urls of logging services may contain credentials
+        Setting::new("LOKI_URL", Url).secret(),
+        Setting::new("OTLP_ENDPOINT", Url).secret(),
+        Setting::new("OTLP_FILTER", LogFilter).with_default(DEFAULT_OTLP_FILTER),
+        Setting::new("DATABASE_URL", Url).secret(),
+        Setting::new(API_KEY_ENCRYPTION_KEY, EncryptionKey).secret(),
+        Setting::new("ADMIN_EMAIL", Text),
+        Setting::new("SMTP_SERVER", Text),
+        Setting::new("SMTP_PORT", Port).with_default(DEFAULT_SMTP_PORT),
+        Setting::new("SMTP_USERNAME", Text),
+        Setting::new("SMTP_PASSWORD", Text).secret(),
+        Setting::new("MAIL_EVENTS", Events),
+        Setting::new("MAIL_REASONS", Reasons),
+        Setting::new("MAIL_HIDDEN_ONLY", Flag).with_default(false),
+    ]
+}
+
+/// Settings of the scheduled jobs, image validation and parsing.
+fn job_settings() -> Vec<Setting> {
+    use SettingKind::{
+        AcceptanceValues, Flag, Hours, LargeNumber, Number, Port, PositiveNumber, Schedule, Text,
+        Url,
+    };
+    vec![
+        Setting::new("FULL_PARSE_SCHEDULE", Schedule).with_default(DEFAULT_NIGHTLY_SCHEDULE),
+        Setting::new("UPDATE_PARSE_SCHEDULE", Schedule).with_default(DEFAULT_UPDATE_PARSE_SCHEDULE),
+        Setting::new("IMAGE_REVIEW_SCHEDULE", Schedule).with_default(DEFAULT_IMAGE_REVIEW_SCHEDULE),
+        Setting::new("IMAGE_REVERIFICATION_SCHEDULE", Schedule)
+            .with_default(DEFAULT_IMAGE_REVERIFICATION_SCHEDULE),
+        Setting::new("RETENTION_SCHEDULE", Schedule).with_default(DEFAULT_RETENTION_SCHEDULE),
+        Setting::new("IMAGE_RECONCILIATION_SCHEDULE", Schedule)
+            .with_default(DEFAULT_IMAGE_RECONCILIATION_SCHEDULE),
+        Setting::new("DIGEST_SCHEDULE", Schedule),
+        Setting::new("LEADER_LEASE_SECONDS", PositiveNumber)
+            .with_default(DEFAULT_LEADER_LEASE_SECONDS),
+        Setting::new("USE_SAFE_SEARCH", Flag).with_default(DEFAULT_USE_SAFE_SEARCH),
+        Setting::new("SERVICE_ACCOUNT_JSON", Text),
+        Setting::new("GOOGLE_PROJECT_ID", Text),
+        Setting::new("IMAGE_ACCEPTANCE_VALUES", AcceptanceValues)
+            .with_default(DEFAULT_IMAGE_ACCEPTANCE_VALUES),
+        Setting::new("USE_GEMINI_API", Flag).with_default(DEFAULT_USE_GEMINI),
+        Setting::new("GEMINI_API_KEY", Text).secret(),
+        Setting::new("GEMINI_TEXT_REQUEST", Text),
+        Setting::new("USE_OPENAI_API", Flag).with_default(DEFAULT_USE_OPENAI),
+        Setting::new("OPENAI_BASE_URL", Url).with_default(DEFAULT_OPENAI_BASE_URL),
+        Setting::new("OPENAI_MODEL", Text).with_default(DEFAULT_OPENAI_MODEL),
+        Setting::new("OPENAI_API_KEY", Text).secret(),
+        Setting::new("OPENAI_TEXT_REQUEST", Text).with_default(DEFAULT_OPENAI_TEXT_REQUEST),
+        Setting::new("OPENAI_REJECT_MEAL_MISMATCH", Flag)
+            .with_default(DEFAULT_OPENAI_REJECT_MEAL_MISMATCH),
+        Setting::new("CLIENT_TIMEOUT", LargeNumber).with_default(DEFAULT_CLIENT_TIMEOUT),
+        Setting::new("MENSA_BASE_URL", Url).with_default(DEFAULT_BASE_URL),
+        Setting::new("CANTEENS", Text).with_default(DEFAULT_CANTEENS),
+        Setting::new("USER_AGENT", Text).with_default(DEFAULT_USER_AGENT),
+        Setting::new("HTTP_PORT", Port).with_default(DEFAULT_HTTP_PORT),
+        Setting::new("PARSE_WEEKS", Number).with_default(DEFAULT_PARSE_WEEKS),
+        Setting::new("MAX_PARSE_AGE_HOURS", Hours).with_default(DEFAULT_MAX_PARSE_AGE_HOURS),
+        Setting::new("BASE_URL", Text).with_default(crate::util::DEFAULT_BASE_URL),
+    ]
+}
+
+/// Settings of image handling, moderation and retention.
+fn moderation_settings() -> Vec<Setting> {
+    use SettingKind::{Decimal, Flag, Number, PositiveDecimal, ReasonWeights, Reasons, Text};
+    let policy = ReportPolicyInfo::default();
+    let client_limits = ClientLimitInfo::default();
+    vec![
+        Setting::new("IMAGE_DIR", Text),
+        Setting::new("MAX_IMAGE_WIDTH", Number).with_default(DEFAULT_MAX_IMAGE_WIDTH),
+        Setting::new("MAX_IMAGE_HEIGHT", Number).with_default(DEFAULT_MAX_IMAGE_HEIGHT),
+        Setting::new("IMAGE_REVIEW_ATTEMPTS", Number).with_default(DEFAULT_IMAGE_REVIEW_ATTEMPTS),
+        Setting::new("REVERIFICATION_DAYS", Number).with_default(DEFAULT_REVERIFICATION_DAYS),
+        Setting::new("REVERIFICATION_LIMIT", Number).with_default(DEFAULT_REVERIFICATION_LIMIT),
+        Setting::new("DELETED_IMAGE_GRACE_DAYS", Number)
+            .with_default(DEFAULT_DELETED_IMAGE_GRACE_DAYS),
+        Setting::new("HIDDEN_IMAGE_RETENTION_DAYS", Number)
+            .with_default(DEFAULT_HIDDEN_IMAGE_RETENTION_DAYS),
+        Setting::new("REPORT_RETENTION_DAYS", Number).with_default(DEFAULT_REPORT_RETENTION_DAYS),
+        Setting::new("UNSERVED_FOOD_RETENTION_DAYS", Number)
+            .with_default(DEFAULT_UNSERVED_FOOD_RETENTION_DAYS),
+        Setting::new("RETENTION_DRY_RUN", Flag).with_default(DEFAULT_RETENTION_DRY_RUN),
+        Setting::new("REPORT_BASE_BARRIER", Decimal).with_default(policy.base_barrier),
+        Setting::new("REPORT_BARRIER_GROWTH_DIVISOR", PositiveDecimal)
+            .with_default(policy.barrier_growth_divisor),
+        Setting::new("REPORT_WINDOW_DAYS", Number).with_default(policy.report_window_days),
+        Setting::new("REPORT_REASON_WEIGHTS", ReasonWeights),
+        Setting::new("IMMEDIATE_HIDE_REASONS", Reasons),
+        Setting::new("TRUSTED_UPLOADER_APPROVED_IMAGES", Number)
+            .with_default(policy.trusted_uploader_approved_images),
+        Setting::new("TRUSTED_UPLOADER_FACTOR", Decimal)
+            .with_default(policy.trusted_uploader_factor),
+        Setting::new("SUSPICIOUS_UPLOADER_FACTOR", Decimal)
+            .with_default(policy.suspicious_uploader_factor),
+        Setting::new("RANK_REPORT_PENALTY", Decimal).with_default(policy.rank_report_penalty),
+        Setting::new("RANK_NEW_IMAGE_DAYS", Number).with_default(policy.rank_new_image_days),
+        Setting::new("CLIENT_REPORT_LIMIT", Number).with_default(client_limits.report_limit),
+        Setting::new("CLIENT_UPLOAD_LIMIT", Number).with_default(client_limits.upload_limit),
+        Setting::new("CLIENT_RATING_LIMIT", Number).with_default(client_limits.rating_limit),
+        Setting::new("MASS_REPORT_THRESHOLD", Number)
+            .with_default(client_limits.mass_report_threshold),
+    ]
+}
+
+/// Settings of the graphql and admin api.
+fn api_settings() -> Vec<Setting> {
+    use SettingKind::{Flag, Hours, LargeNumber, Number, Text, Url};
+    let rate_limits = RateLimitInfo::default();
+    let limit = |limit: Option<NonZeroU32>| limit.map_or(0, NonZeroU32::get);
+    vec![
+        Setting::new("RATE_LIMIT", LargeNumber),
+        Setting::new("MAX_UPLOAD_SIZE", LargeNumber).with_default(DEFAULT_UPLOAD_SIZE),
+        Setting::new("ADMIN_KEY", Text).secret(),
+        Setting::new("ADMIN_SESSION_HOURS", Hours).with_default(DEFAULT_ADMIN_SESSION_HOURS),
+        Setting::new("OIDC_ISSUER_URL", Url),
+        Setting::new("OIDC_CLIENT_ID", Text),
+        Setting::new("OIDC_CLIENT_SECRET", Text).secret(),
+        Setting::new("OIDC_REDIRECT_URL", Url),
+        Setting::new("OIDC_ROLES_CLAIM", Text).with_default(DEFAULT_OIDC_ROLES_CLAIM),
+        Setting::new("OIDC_MODERATOR_GROUP", Text).with_default(DEFAULT_OIDC_MODERATOR_GROUP),
+        Setting::new("OIDC_OPERATOR_GROUP", Text).with_default(DEFAULT_OIDC_OPERATOR_GROUP),
+        Setting::new("API_KEY_REFRESH_INTERVAL", LargeNumber)
+            .with_default(DEFAULT_API_KEY_REFRESH_INTERVAL),
+        Setting::new("AUTH_MAX_REQUEST_AGE", LargeNumber)
+            .with_default(DEFAULT_AUTH_MAX_REQUEST_AGE),
+        Setting::new("CLIENT_RATE_LIMIT", Number).with_default(limit(rate_limits.client_limit)),
+        Setting::new("API_KEY_RATE_LIMIT", Number).with_default(limit(rate_limits.api_key_limit)),
+        Setting::new("IP_RATE_LIMIT", Number).with_default(limit(rate_limits.ip_limit)),
+        Setting::new("MUTATION_RATE_LIMIT", Number).with_default(limit(rate_limits.mutation_limit)),
+        Setting::new("IMAGE_UPLOAD_COST", Number).with_default(rate_limits.image_upload_cost),
+        Setting::new("TRUST_FORWARDED_FOR", Flag).with_default(rate_limits.trust_forwarded_for),
+        Setting::new("GRAPHQL_MAX_DEPTH", Number).with_default(rate_limits.max_query_depth),
+        Setting::new("GRAPHQL_MAX_COMPLEXITY", Number)
+            .with_default(rate_limits.max_query_complexity),
+    ]
+}
+
+fn check_schedule(key: &str, schedule: &str) -> Result<()> {
+    // same parsing as when the scheduler adds a job
+    Job::new(schedule, |_, _| {})
+        .map(drop)
+        .map_err(|_| ServerError::InvalidFormatError {
+            var: key.into(),
+            gotten: schedule.into(),
+            expected_format: "a cron-like schedule including seconds, e.g. `0 0 2 * * *`".into(),
+        })
 }
 
 #[cfg(test)]
 mod tests {
+    use rusty_fork::rusty_fork_test;
+    use serial_test::serial;
     use tracing_test::traced_test;
 
-    use super::{read_acceptance_var, ConfigReader};
+    use std::io::Write;
+
+    use super::{ConfigReader, EffectiveSetting, SettingSource, REDACTED};
+    use crate::{
+        layer::data::notification::notification_info::{NotificationKind, WebhookFormat},
+        startup::server::ServerError,
+        util::ReportReason,
+    };
 
     #[test]
     fn test_read_acceptance_var() {
         let var = "TEST";
         std::env::set_var(var, "1,2, 3 ,4,05");
 
-        let res = read_acceptance_var(var).expect("should parse");
+        let res = ConfigReader::default()
+            .read_acceptance_var(var)
+            .expect("should parse");
         assert_eq!([1, 2, 3, 4, 5], res);
     }
 
+    #[test]
+    fn test_read_report_reasons() {
+        let config = ConfigReader::default();
+        let var = "TEST_REASONS";
+        std::env::set_var(var, "Offensive, ViolatesRights");
+        let res = config.read_reasons(var).expect("should parse");
+        assert_eq!(
+            vec![ReportReason::Offensive, ReportReason::ViolatesRights],
+            res
+        );
+
+        std::env::set_var(var, "Offensive,Unknown");
+        assert!(config.read_reasons(var).is_err());
+
+        let var = "TEST_REASON_WEIGHTS";
+        std::env::set_var(var, "Offensive=2, WrongMeal = 0.5");
+        let res = config.read_reason_weights(var).expect("should parse");
+        assert_eq!(
+            vec![
+                (ReportReason::Offensive, 2.0),
+                (ReportReason::WrongMeal, 0.5)
+            ],
+            res
+        );
+
+        std::env::set_var(var, "Offensive=-1");
+        assert!(config.read_reason_weights(var).is_err());
+        std::env::set_var(var, "Offensive");
+        assert!(config.read_reason_weights(var).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_read_notification_info() {
+        std::env::set_var("WEBHOOK_1_URL", "http://localhost/hook");
+        std::env::set_var("WEBHOOK_1_FORMAT", "matrix");
+        std::env::set_var("WEBHOOK_1_EVENTS", "report, deleted");
+        std::env::set_var("WEBHOOK_1_HIDDEN_ONLY", "true");
+        std::env::set_var("WEBHOOK_2_URL", "http://localhost/other");
+        std::env::remove_var("WEBHOOK_3_URL");
+
+        let info = ConfigReader::default()
+            .read_notification_info()
+            .expect("should parse");
+        assert_eq!(info.webhooks.len(), 2);
+        assert_eq!(info.webhooks[0].format, WebhookFormat::Matrix);
+        assert_eq!(
+            info.webhooks[0].filter.kinds,
+            vec![NotificationKind::Report, NotificationKind::Deleted]
+        );
+        assert!(info.webhooks[0].filter.hidden_only);
+        assert_eq!(info.webhooks[1].format, WebhookFormat::Generic);
+        assert!(info.webhooks[1].filter.kinds.is_empty());
+
+        std::env::set_var("WEBHOOK_2_FORMAT", "discord");
+        assert!(ConfigReader::default().read_notification_info().is_err());
+        std::env::remove_var("WEBHOOK_2_FORMAT");
+        std::env::set_var("WEBHOOK_2_EVENTS", "reported");
+        assert!(ConfigReader::default().read_notification_info().is_err());
+        std::env::remove_var("WEBHOOK_2_EVENTS");
+
+        // in digest mode, only severe reports get mailed immediately by default
+        std::env::remove_var("MAIL_EVENTS");
+        std::env::remove_var("MAIL_REASONS");
+        std::env::set_var("DIGEST_SCHEDULE", "0 0 7 * * *");
+        let mail_filter = ConfigReader::default()
+            .read_notification_info()
+            .expect("should parse")
+            .mail_filter;
+        assert_eq!(mail_filter.kinds, vec![NotificationKind::Report]);
+        assert_eq!(
+            mail_filter.reasons,
+            vec![ReportReason::Offensive, ReportReason::ViolatesRights]
+        );
+        std::env::set_var("MAIL_REASONS", "Advert");
+        let mail_filter = ConfigReader::default()
+            .read_notification_info()
+            .expect("should parse")
+            .mail_filter;
+        assert_eq!(mail_filter.reasons, vec![ReportReason::Advert]);
+        std::env::remove_var("MAIL_REASONS");
+        std::env::remove_var("DIGEST_SCHEDULE");
+    }
+
+    #[test]
+    #[serial]
+    fn test_validate() {
+        std::env::set_var("REVERIFICATION_LIMIT", "-1");
+        std::env::set_var("RETENTION_SCHEDULE", "daily");
+        std::env::set_var("OIDC_REDIRECT_URL", "/admin/oidc/callback");
+        std::env::set_var("OPENAI_REJECT_MEAL_MISMATCH", "yes");
+        std::env::set_var("LEADER_LEASE_SECONDS", "0");
+        std::env::set_var("REPORT_BARRIER_GROWTH_DIVISOR", "0");
+        std::env::set_var("RANK_REPORT_PENALTY", "NaN");
+        std::env::set_var("TRUSTED_UPLOADER_FACTOR", "inf");
+        std::env::set_var("MAX_PARSE_AGE_HOURS", u64::MAX.to_string());
+        std::env::set_var("ADMIN_SESSION_HOURS", "5124095576030432");
+        std::env::set_var("LOG_CONFIG", "mensa_app_backend=loud");
+
+        let Err(ServerError::InvalidConfig(errors)) = ConfigReader::default().validate() else {
+            panic!("invalid settings should be found");
+        };
+        for var in [
+            "REVERIFICATION_LIMIT",
+            "RETENTION_SCHEDULE",
+            "OIDC_REDIRECT_URL",
+            "OPENAI_REJECT_MEAL_MISMATCH",
+            "LEADER_LEASE_SECONDS",
+            "REPORT_BARRIER_GROWTH_DIVISOR",
+            "RANK_REPORT_PENALTY",
+            "TRUSTED_UPLOADER_FACTOR",
+            "MAX_PARSE_AGE_HOURS",
+            "ADMIN_SESSION_HOURS",
+            "LOG_CONFIG",
+        ] {
+            assert!(
+                errors.iter().any(|e| e.to_string().contains(var)),
+                "{var} should be invalid"
+            );
+        }
+        // invalid schedules get found before creating the scheduler
+        assert!(ConfigReader::default().read_schedule_info().is_err());
+        // invalid report policy values do not fall back to their defaults
+        assert!(ConfigReader::default().read_report_policy_info().is_err());
+        std::env::remove_var("RANK_REPORT_PENALTY");
+        std::env::remove_var("TRUSTED_UPLOADER_FACTOR");
+        let Err(ServerError::InvalidFormatError { var, .. }) =
+            ConfigReader::default().read_report_policy_info()
+        else {
+            panic!("a divisor of 0 should be rejected");
+        };
+        assert_eq!(var, "REPORT_BARRIER_GROWTH_DIVISOR");
+        // durations too long for the validation do not overflow when read anyway
+        assert!(ConfigReader::default().read_max_parse_age() > std::time::Duration::ZERO);
+
+        std::env::remove_var("REVERIFICATION_LIMIT");
+        std::env::remove_var("RETENTION_SCHEDULE");
+        std::env::remove_var("OIDC_REDIRECT_URL");
+        std::env::remove_var("OPENAI_REJECT_MEAL_MISMATCH");
+        std::env::remove_var("LEADER_LEASE_SECONDS");
+        std::env::remove_var("REPORT_BARRIER_GROWTH_DIVISOR");
+        std::env::remove_var("RANK_REPORT_PENALTY");
+        std::env::remove_var("TRUSTED_UPLOADER_FACTOR");
+        std::env::remove_var("MAX_PARSE_AGE_HOURS");
+        std::env::remove_var("ADMIN_SESSION_HOURS");
+        std::env::remove_var("LOG_CONFIG");
+    }
+
+    #[test]
+    #[serial]
+    fn test_config_file() {
+        let mut file = tempfile::NamedTempFile::new().expect("temp file available");
+        write!(
+            file,
+            r#"
+            oidc_roles_claim = "roles"
+            [oidc]
+            operator_group = "file-operators"
+            client_secret = "hunter2"
+            "#
+        )
+        .expect("file writable");
+        std::env::set_var("CONFIG_FILE", file.path());
+        std::env::set_var("OIDC_OPERATOR_GROUP", "env-operators");
+        std::env::remove_var("OIDC_ROLES_CLAIM");
+        std::env::remove_var("OIDC_CLIENT_SECRET");
+        std::env::remove_var("OIDC_MODERATOR_GROUP");
+
+        let config = ConfigReader::new(None).expect("config file readable");
+        // environment variables take precedence
+        assert_eq!(
+            config.read_var("OIDC_OPERATOR_GROUP").ok().as_deref(),
+            Some("env-operators")
+        );
+        assert_eq!(
+            config.read_var("OIDC_ROLES_CLAIM").ok().as_deref(),
+            Some("roles")
+        );
+        // settings of the file do not change the environment
+        assert!(std::env::var("OIDC_ROLES_CLAIM").is_err());
+        let effective = config.effective_config();
+        let setting = |name: &str| {
+            effective
+                .iter()
+                .find(|s| s.name == name)
+                .cloned()
+                .expect("setting exists")
+        };
+        assert_eq!(
+            setting("OIDC_ROLES_CLAIM"),
+            EffectiveSetting {
+                name: "OIDC_ROLES_CLAIM".into(),
+                value: Some("roles".into()),
+                source: SettingSource::ConfigFile,
+            }
+        );
+        assert_eq!(
+            setting("OIDC_OPERATOR_GROUP").source,
+            SettingSource::Environment
+        );
+        assert_eq!(
+            setting("OIDC_MODERATOR_GROUP").source,
+            SettingSource::Default
+        );
+        assert_eq!(
+            setting("OIDC_CLIENT_SECRET").value.as_deref(),
+            Some(REDACTED)
+        );
+
+        // typos do not get ignored
+        write!(file, "oidc_issuer = \"https://example.org\"").expect("file writable");
+        assert!(ConfigReader::new(None).is_err());
+
+        std::env::remove_var("CONFIG_FILE");
+        std::env::remove_var("OIDC_OPERATOR_GROUP");
+    }
+
+    // put in separate process, as other tests set invalid settings
+    rusty_fork_test! {
+        #[test]
+        fn test_reload() {
+            let mut file = tempfile::NamedTempFile::new().expect("temp file available");
+            write!(file, "canteens = \"mensa_adenauerring\"").expect("file writable");
+            let config = ConfigReader::default();
+            for (name, _) in std::env::vars().filter(|(name, _)| config.is_known_setting(name)) {
+                std::env::remove_var(name);
+            }
+            std::env::set_var("CONFIG_FILE", file.path());
+
+            let config = ConfigReader::new(None).expect("config file readable");
+            let var = |config: &ConfigReader, name: &str| config.read_var(name).ok();
+            assert_eq!(var(&config, "CANTEENS").as_deref(), Some("mensa_adenauerring"));
+
+            std::fs::write(file.path(), "canteens = \"mensa_moltke\"\nhttp_port = 8080")
+                .expect("file writable");
+            let config = config.reload().expect("valid settings");
+            assert_eq!(var(&config, "CANTEENS").as_deref(), Some("mensa_moltke"));
+            assert_eq!(var(&config, "HTTP_PORT").as_deref(), Some("8080"));
+
+            // invalid settings do not get read
+            std::fs::write(file.path(), "canteens = \"mensa_gottesaue\"\nhttp_port = \"http\"")
+                .expect("file writable");
+            assert!(config.reload().is_err());
+            assert_eq!(var(&config, "CANTEENS").as_deref(), Some("mensa_moltke"));
+
+            // removed settings are not set anymore
+            std::fs::write(file.path(), "").expect("file writable");
+            let config = config.reload().expect("valid settings");
+            assert!(var(&config, "CANTEENS").is_none());
+            assert!(std::env::var("CANTEENS").is_err());
+        }
+    }
+
     #[tokio::test]
     #[traced_test]
     async fn test_conf_reader() {
@@ -342,7 +1601,9 @@ mod tests {
         reader.read_file_handler_info().await.ok();
         reader.get_image_validation_info().await.ok();
         let _ = reader.read_image_preprocessing_info();
-        let _ = reader.should_migrate();
-        let _ = reader.should_print_help();
+        let _ = reader.read_image_review_info();
+        reader.read_report_policy_info().ok();
+        let _ = reader.read_client_limit_info();
+        let _ = reader.read_retention_info();
     }
 }
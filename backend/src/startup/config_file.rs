@@ -0,0 +1,144 @@
+//! Reading settings from a [TOML](https://toml.io) configuration file, see [`read_config_file`].
+use std::path::Path;
+
+use toml_edit::{Document, Item, Value};
+
+use super::server::{Result, ServerError};
+
+/// Reads the settings in the TOML file at `path` as pairs of environment variable name and value.
+///
+/// Keys are case insensitive and get prefixed with the name of their table, so `port` in `[smtp]` becomes `SMTP_PORT`.
+/// Arrays of values get joined by commas and arrays of tables get numbered starting at 1, so `url` in the first `[[webhook]]` becomes `WEBHOOK_1_URL`.
+pub(super) fn read_config_file(path: &Path) -> Result<Vec<(String, String)>> {
+    let error = |reason: String| ServerError::ConfigFileError {
+        path: path.display().to_string(),
+        reason,
+    };
+    let content = std::fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    let document = Document::parse(content).map_err(|e| error(e.to_string()))?;
+
+    let mut settings = Vec::new();
+    flatten_item("", document.as_item(), &mut settings).map_err(error)?;
+    Ok(settings)
+}
+
+fn flatten_item(
+    name: &str,
+    item: &Item,
+    settings: &mut Vec<(String, String)>,
+) -> std::result::Result<(), String> {
+    match item {
+        Item::None => {}
+        Item::Value(Value::InlineTable(table)) => {
+            for (key, value) in table {
+                flatten_item(&join_key(name, key), &Item::Value(value.clone()), settings)?;
+            }
+        }
+        Item::Value(value) => settings.push((name.into(), format_value(name, value)?)),
+        Item::Table(table) => {
+            for (key, item) in table {
+                flatten_item(&join_key(name, key), item, settings)?;
+            }
+        }
+        Item::ArrayOfTables(tables) => {
+            for (number, table) in (1..).zip(tables) {
+                for (key, item) in table {
+                    flatten_item(&join_key(&format!("{name}_{number}"), key), item, settings)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    let key = key.to_uppercase();
+    if prefix.is_empty() {
+        key
+    } else {
+        format!("{prefix}_{key}")
+    }
+}
+
+fn format_value(name: &str, value: &Value) -> std::result::Result<String, String> {
+    Ok(match value {
+        Value::String(s) => s.value().clone(),
+        Value::Integer(i) => i.value().to_string(),
+        Value::Float(f) => f.value().to_string(),
+        Value::Boolean(b) => b.value().to_string(),
+        Value::Datetime(d) => d.value().to_string(),
+        Value::Array(array) => array
+            .iter()
+            .map(|value| match value {
+                Value::Array(_) | Value::InlineTable(_) => {
+                    Err(format!("`{name}` may only contain plain values"))
+                }
+                value => format_value(name, value),
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?
+            .join(","),
+        Value::InlineTable(_) => return Err(format!("`{name}` may not be a table")),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::io::Write;
+
+    use super::read_config_file;
+
+    #[test]
+    fn test_read_config_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+            log_config = "info"
+            canteens = ["mensa_adenauerring", "mensa_moltke"]
+            retention_dry_run = true
+
+            [smtp]
+            port = 587
+            server = "mail.example.org"
+
+            [[webhook]]
+            url = "http://localhost/hook"
+            events = ["report"]
+
+            [[webhook]]
+            url = "http://localhost/other"
+            "#
+        )
+        .unwrap();
+
+        let settings = read_config_file(file.path()).unwrap();
+        let expected = [
+            ("LOG_CONFIG", "info"),
+            ("CANTEENS", "mensa_adenauerring,mensa_moltke"),
+            ("RETENTION_DRY_RUN", "true"),
+            ("SMTP_PORT", "587"),
+            ("SMTP_SERVER", "mail.example.org"),
+            ("WEBHOOK_1_URL", "http://localhost/hook"),
+            ("WEBHOOK_1_EVENTS", "report"),
+            ("WEBHOOK_2_URL", "http://localhost/other"),
+        ];
+        assert_eq!(
+            settings,
+            expected.map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_config_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "canteens = [[\"nested\"]]").unwrap();
+        assert!(read_config_file(file.path()).is_err());
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "http_port = ").unwrap();
+        assert!(read_config_file(file.path()).is_err());
+
+        assert!(read_config_file("does/not/exist.toml".as_ref()).is_err());
+    }
+}
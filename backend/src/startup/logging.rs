@@ -3,25 +3,60 @@
 use std::time::Duration;
 
 use chrono::Local;
+use opentelemetry::{global, trace::TracerProvider as _};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource};
 use time::{format_description::well_known::Rfc2822, UtcOffset};
-use tracing::info;
+use tracing::{info, warn, Subscriber};
 use tracing_loki::{url::Url, BackgroundTaskController};
 use tracing_subscriber::{
-    fmt::time::OffsetTime, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
-    Registry,
+    fmt::time::OffsetTime, layer::SubscriberExt, registry::LookupSpan, reload,
+    util::SubscriberInitExt, EnvFilter, Layer, Registry,
 };
 
+const SERVICE_NAME: &str = "mensa-ka";
+
 /// Struct containing all configurations available for the logging system.
 pub struct LogInfo {
     /// Logging specifier following the schema of <https://docs.rs/tracing-subscriber/latest/tracing_subscriber/filter/struct.EnvFilter.html>
     pub log_config: String,
     /// URL to the Grafana Loki instance to send logs to.
     pub loki_url: Option<String>,
+    /// Configuration for exporting traces over OTLP, `None` if disabled.
+    pub otlp: Option<OtlpInfo>,
+}
+
+/// Struct containing the configuration for exporting traces to e.g. Jaeger or Grafana Tempo.
+pub struct OtlpInfo {
+    /// URL traces get sent to over OTLP/HTTP, like `http://localhost:4318/v1/traces`.
+    pub endpoint: String,
+    /// Specifier which spans and events get exported, following the same schema as [`LogInfo::log_config`].
+    pub filter: String,
 }
 
 /// Class for initializing the logging.
 pub struct Logger {
     loki_shutdown: Option<BackgroundTaskController>,
+    tracer_provider: Option<SdkTracerProvider>,
+    filter: LogFilterHandle,
+}
+
+/// Handle for changing the filter of the local log output while the logger is in use.
+#[derive(Clone)]
+pub struct LogFilterHandle(reload::Handle<EnvFilter, Registry>);
+
+impl LogFilterHandle {
+    /// Replaces the filter with one following the schema of [`LogInfo::log_config`].
+    /// # Errors
+    /// if `log_config` is not a valid filter or the logger does not exist anymore.
+    pub fn set_filter(&self, log_config: &str) -> Result<(), String> {
+        let filter = EnvFilter::builder()
+            .parse(log_config)
+            .map_err(|e| e.to_string())?;
+        self.0.reload(filter).map_err(|e| e.to_string())?;
+        info!("Using local log config `{log_config}`.");
+        Ok(())
+    }
 }
 
 impl Logger {
@@ -32,17 +67,20 @@ impl Logger {
     #[allow(clippy::cognitive_complexity)] // somehow this has high cognitive complexity...
     pub fn init(info: LogInfo) -> Self {
         // env logger
-        let env_layer = Self::get_env_fmt_layer(&info.log_config);
+        let (env_layer, filter) = Self::get_env_fmt_layer(&info.log_config);
         // grafana loki
         let (loki, loki_shutdown) = info
             .loki_url
             .as_ref()
             .map(|url| Self::get_loki_layer(url))
             .unzip();
+        // opentelemetry traces
+        let (otlp, tracer_provider) = info.otlp.as_ref().map(Self::get_otlp_layer).unzip();
 
         tracing_subscriber::registry()
             .with(env_layer)
             .with(loki)
+            .with(otlp)
             .init();
 
         info!("Using local log config `{}`.", info.log_config);
@@ -54,37 +92,65 @@ impl Logger {
             info!("Logging to Grafana Loki is disabled.");
         }
 
-        Self { loki_shutdown }
+        if let Some(otlp) = info.otlp {
+            info!(
+                "Exporting traces to `{}` using filter `{}`.",
+                otlp.endpoint, otlp.filter
+            );
+        } else {
+            info!("Exporting traces is disabled.");
+        }
+
+        Self {
+            loki_shutdown,
+            tracer_provider,
+            filter,
+        }
+    }
+
+    /// Returns a handle for changing the filter of the local log output, e.g. when reloading the config.
+    #[must_use]
+    pub fn filter_handle(&self) -> LogFilterHandle {
+        self.filter.clone()
     }
 
-    /// Shuts down logger. Required when using external logging to Grafana loki, useless otherwise.
+    /// Shuts down logger. Required when using external logging to Grafana loki or exporting traces, useless otherwise.
     pub async fn shutdown(self) {
+        if let Some(provider) = self.tracer_provider {
+            // flushing uses a blocking http client
+            let result = tokio::task::spawn_blocking(move || provider.shutdown()).await;
+            if let Ok(Err(e)) = result {
+                warn!("Could not export remaining traces: {e}");
+            }
+        }
         if let Some(s) = self.loki_shutdown {
             tokio::time::sleep(Duration::from_millis(1)).await; // allow for last log messages to be send
             s.shutdown().await;
         }
     }
 
-    fn get_env_fmt_layer(log_config: &str) -> impl Layer<Registry> {
+    fn get_env_fmt_layer(log_config: &str) -> (impl Layer<Registry>, LogFilterHandle) {
         let env_filter = EnvFilter::builder()
             .parse(log_config)
             .expect("could not parse logging config");
+        let (env_filter, handle) = reload::Layer::new(env_filter);
 
         let sec_offset = Local::now().offset().local_minus_utc();
 
-        tracing_subscriber::fmt::layer()
+        let layer = tracing_subscriber::fmt::layer()
             .with_timer(OffsetTime::new(
                 UtcOffset::from_whole_seconds(sec_offset).expect("valid utc offset"),
                 Rfc2822,
             ))
             .pretty()
-            .with_filter(env_filter)
+            .with_filter(env_filter);
+        (layer, LogFilterHandle(handle))
     }
 
     fn get_loki_layer(loki_url: &str) -> (tracing_loki::Layer, BackgroundTaskController) {
         let loki_url_parsed = Url::parse(loki_url).expect("valid loki url");
         let (loki_layer, controller, task) = tracing_loki::builder()
-            .label("service_name", "mensa-ka")
+            .label("service_name", SERVICE_NAME)
             .expect("label `service_name` not yet set")
             .extra_field("pid", format!("{}", std::process::id()))
             .expect("field `pid` not yet set")
@@ -94,13 +160,39 @@ impl Logger {
         tokio::spawn(task); // todo graceful shutdown
         (loki_layer, controller)
     }
+
+    fn get_otlp_layer<S>(otlp: &OtlpInfo) -> (impl Layer<S>, SdkTracerProvider)
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let filter = EnvFilter::builder()
+            .parse(&otlp.filter)
+            .expect("could not parse trace export config");
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(&otlp.endpoint)
+            .build()
+            .expect("build otlp exporter");
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+            .build();
+
+        // continue traces of callers and pass them on in outgoing requests
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let layer = tracing_opentelemetry::layer()
+            .with_tracer(provider.tracer(SERVICE_NAME))
+            .with_filter(filter);
+        (layer, provider)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use rusty_fork::rusty_fork_test;
 
-    use super::{LogInfo, Logger};
+    use super::{LogInfo, Logger, OtlpInfo};
 
     // put in separate process to allow setting subscriber to avoid conflict with `traced_test`s
     rusty_fork_test! {
@@ -109,9 +201,26 @@ mod tests {
             let info = LogInfo {
                 log_config: "trace".into(),
                 loki_url: None,
+                otlp: Some(OtlpInfo {
+                    endpoint: "http://localhost:4318/v1/traces".into(),
+                    filter: "info".into(),
+                }),
             };
             let _ = Logger::init(info);
         }
 
+        #[test]
+        fn test_set_filter() {
+            let info = LogInfo {
+                log_config: "trace".into(),
+                loki_url: None,
+                otlp: None,
+            };
+            let logger = Logger::init(info);
+            let filter = logger.filter_handle();
+            assert!(filter.set_filter("warn,mensa_app_backend=debug").is_ok());
+            assert!(filter.set_filter("mensa_app_backend=loud").is_err());
+        }
+
     }
 }
@@ -2,5 +2,7 @@
 
 pub mod cli;
 pub mod config;
+mod config_file;
 pub mod logging;
+pub mod reload;
 pub mod server;
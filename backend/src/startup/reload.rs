@@ -0,0 +1,80 @@
+//! See [`ConfigReloader`].
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::{
+    interface::config_reload::{ConfigReload, ReloadError, Result},
+    layer::{
+        data::swka_parser::swka_parse_manager::CanteenHandle,
+        trigger::{api::server::ApiReloadHandle, scheduling::control::SchedulerControl},
+    },
+};
+
+use super::{config::ConfigReader, logging::LogFilterHandle, server::ServerError};
+
+/// Class applying changed settings to the running server, e.g. when it receives `SIGHUP`.
+///
+/// The log filter, parsed canteens, schedules of the jobs and rate limits can change this way.
+/// All other settings only change with a restart.
+pub struct ConfigReloader {
+    config: Mutex<ConfigReader>,
+    log_filter: LogFilterHandle,
+    canteens: CanteenHandle,
+    scheduler: SchedulerControl,
+    api: ApiReloadHandle,
+}
+
+impl ConfigReloader {
+    /// Creates a new instance applying the settings read by `config` to the given components.
+    #[must_use]
+    pub fn new(
+        config: ConfigReader,
+        log_filter: LogFilterHandle,
+        canteens: CanteenHandle,
+        scheduler: SchedulerControl,
+        api: ApiReloadHandle,
+    ) -> Self {
+        Self {
+            config: Mutex::new(config),
+            log_filter,
+            canteens,
+            scheduler,
+            api,
+        }
+    }
+}
+
+#[async_trait]
+impl ConfigReload for ConfigReloader {
+    async fn reload(&self) -> Result<()> {
+        let invalid = |e: ServerError| ReloadError::InvalidConfig(e.to_string());
+        // only one reload at a time
+        let mut config = self.config.lock().await;
+        // the new settings only get used once all of them could be read
+        let new_config = config.reload().map_err(invalid)?;
+        let log_info = new_config.read_log_info().map_err(invalid)?;
+        let swka_info = new_config.read_swka_info().map_err(invalid)?;
+        let schedule_info = new_config.read_schedule_info().map_err(invalid)?;
+        let rate_limits = new_config.read_rate_limit_info();
+        *config = new_config;
+
+        let mut not_applied = Vec::new();
+        if let Err(e) = self.log_filter.set_filter(&log_info.log_config) {
+            not_applied.push(format!("log config: {e}"));
+        }
+        self.canteens.set_canteens(swka_info.valid_canteens).await;
+        if let Err(e) = self.scheduler.reschedule(&schedule_info).await {
+            not_applied.push(format!("schedules: {e}"));
+        }
+        self.api.reload(&rate_limits).await;
+        drop(config);
+
+        if not_applied.is_empty() {
+            info!("Reloaded settings.");
+            Ok(())
+        } else {
+            Err(ReloadError::NotApplied(not_applied.join(", ")))
+        }
+    }
+}
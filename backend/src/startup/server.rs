@@ -1,27 +1,43 @@
 //! This module contains the server, the heart of the application.
-use std::fmt::{Debug, Display};
-use std::{env::VarError, num::ParseIntError};
+use clap::Parser;
+use std::fmt::{Debug, Display, Write};
+use std::{env::VarError, num::ParseIntError, sync::Arc, time::Duration};
 use thiserror::Error;
 use tokio::signal::ctrl_c;
 use tracing::info;
 
-use crate::interface::admin_notification::MailError;
+use crate::interface::admin_notification::NotificationError;
 use crate::interface::image_validation::ImageValidationError;
 use crate::layer::data::image_validation::google_api_handler::GoogleApiHandler;
 use crate::{
-    interface::{api_command::CommandError, mensa_parser::ParseError, persistent_data::DataError},
+    interface::{
+        api_command::CommandError,
+        config_reload::ConfigReload,
+        health::HealthCheck,
+        mensa_parser::ParseError,
+        persistent_data::{CommandDataAccess, DataError},
+    },
     layer::{
         data::{
             database::factory::DataAccessFactory, file_handler::FileHandler,
-            mail::mail_sender::MailSender, swka_parser::swka_parse_manager::SwKaParseManager,
+            mail::mail_sender::MailSender, notification::fan_out::NotificationFanOut,
+            swka_parser::swka_parse_manager::SwKaParseManager,
         },
         logic::{
-            api_command::command_handler::CommandHandler,
+            api_command::{command_handler::CommandHandler, report_policy::ReportPolicy},
+            digest::digest_manager::DigestManager,
+            image_review::image_review_manager::ImageReviewManager,
             mealplan_management::meal_plan_manager::MealPlanManager,
+            retention::retention_manager::RetentionManager,
         },
         trigger::{api::server::ApiServer, scheduling::scheduler::Scheduler},
     },
-    startup::{cli, config::ConfigReader, logging::Logger},
+    startup::{
+        cli::{self, Cli, Command, ServeArgs},
+        config::ConfigReader,
+        logging::Logger,
+        reload::ConfigReloader,
+    },
 };
 
 use super::cli::SubcommandError;
@@ -29,6 +45,9 @@ use super::cli::SubcommandError;
 /// Result returned from the server, potentially containing a [`ServerError`].
 pub type Result<T> = std::result::Result<T, ServerError>;
 
+/// Interval in which the database connection metrics get updated.
+const POOL_METRICS_INTERVAL: Duration = Duration::from_secs(15);
+
 /// Error indicating that there was an error while starting/stopping the server.
 #[derive(Error)]
 pub enum ServerError {
@@ -47,9 +66,20 @@ pub enum ServerError {
         /// expected format description
         expected_format: String,
     },
-    /// Error while creating the mail sender.
-    #[error("error while creating mail sender component: {0}")]
-    MailError(#[from] MailError),
+    /// The configuration file could not be read.
+    #[error("could not read config file {path}: {reason}")]
+    ConfigFileError {
+        /// path of the config file
+        path: String,
+        /// what went wrong
+        reason: String,
+    },
+    /// Some settings are invalid, each error describes one of them.
+    #[error("invalid configuration:{}", format_errors(.0))]
+    InvalidConfig(Vec<Self>),
+    /// Error while creating the mail sender or webhooks.
+    #[error("error while creating notification component: {0}")]
+    NotificationError(#[from] NotificationError),
     /// Error while creating command component.
     #[error("error cwhile reating command component: {0}")]
     CommandError(#[from] CommandError),
@@ -76,6 +106,14 @@ pub enum ServerError {
     SubcommandError(#[from] SubcommandError),
 }
 
+/// Lists the errors, each on its own line.
+fn format_errors(errors: &[ServerError]) -> String {
+    errors.iter().fold(String::new(), |mut list, error| {
+        let _ = write!(list, "\n  - {error}");
+        list
+    })
+}
+
 impl Debug for ServerError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         Display::fmt(&self, f)
@@ -86,76 +124,187 @@ impl Debug for ServerError {
 pub struct Server;
 
 impl Server {
-    /// Runs the server and everything that belongs to.
-    /// Therefore the configuration is read from environment variables.
-    /// Afterwards, the component structure is created.
+    /// Runs the server and everything that belongs to, or another command given in the program arguments.
+    /// Therefore the configuration is read from environment variables and the config file and checked for invalid values.
+    /// Afterwards, the async runtime is started and the component structure is created.
     /// # Errors
-    /// - when the the config could not read environment variables
+    /// - when the the config could not read environment variables or contains invalid values
     /// - when crating a component fails
-    #[allow(clippy::cognitive_complexity)]
-    pub async fn run() -> Result<()> {
-        let config = ConfigReader::default();
+    /// - when the command fails
+    pub fn run() -> Result<()> {
+        let args = Cli::parse();
+        // reading the config may change the environment, which is only safe before other threads run
+        let config = ConfigReader::new(args.config.clone())?;
+
+        tokio::runtime::Runtime::new()?.block_on(Self::run_with_config(config, args))
+    }
+
+    async fn run_with_config(config: ConfigReader, args: Cli) -> Result<()> {
+        if args.print_config {
+            cli::print_config(&config);
+            return config.validate();
+        }
+        // before logging, which cannot start with an invalid log config
+        config.validate()?;
 
         // logging
         let logger = Logger::init(config.read_log_info()?);
 
-        // help text
-        if config.should_print_help() {
-            cli::print_help();
-            return Ok(());
+        match args.command.unwrap_or_default() {
+            Command::Serve(serve_args) => serve(config, &logger, &serve_args).await?,
+            Command::Operation(command) => cli::run_command(&config, command).await?,
         }
+        logger.shutdown().await;
 
-        if config.should_migrate_images() {
-            cli::migrate_images(&config).await?;
-            return Ok(());
-        }
+        Ok(())
+    }
+}
 
-        info!("Starting server...");
+/// Creates the component structure and runs the server until it gets stopped.
+#[allow(clippy::cognitive_complexity)]
+async fn serve(config: ConfigReader, logger: &Logger, args: &ServeArgs) -> Result<()> {
+    info!("Starting server...");
 
-        // data layer
-        let factory =
-            DataAccessFactory::new(config.read_database_info()?, config.should_migrate()).await?;
-        let command_data = factory.get_command_data_access();
-        let mealplan_management_data = factory.get_mealplan_management_data_access();
-        let request_data = factory.get_request_data_access();
-        let auth_data = factory.get_auth_data_access();
+    // data layer
+    let factory = DataAccessFactory::new(config.read_database_info()?, args.migrate).await?;
+    factory.record_pool_metrics(POOL_METRICS_INTERVAL);
+    let command_data = factory.get_command_data_access();
+    let mealplan_management_data = factory.get_mealplan_management_data_access();
+    let request_data = factory.get_request_data_access();
+    let auth_data = factory.get_auth_data_access(config.read_api_key_cipher()?);
+    let encrypted = auth_data.encrypt_plain_keys().await?;
+    if encrypted > 0 {
+        info!("Encrypted {encrypted} api keys stored in plain text.");
+    }
+    let admin_data = factory.get_admin_data_access();
+    let image_review_data = factory.get_image_review_data_access();
+    let retention_data = factory.get_retention_data_access();
+    let digest_data = factory.get_digest_data_access();
 
-        let mail = MailSender::new(config.read_mail_info()?)?;
-        let parser = SwKaParseManager::new(config.read_swka_info()?)?;
-        let file_handler = FileHandler::new(config.read_file_handler_info().await?);
-        let google_vision = GoogleApiHandler::new(config.get_image_validation_info().await?)?;
+    let mail = MailSender::new(config.read_mail_info()?)?;
+    let notification_info = config.read_notification_info()?;
+    let notification = NotificationFanOut::new(notification_info.clone(), mail.clone())?;
+    let parser = SwKaParseManager::new(config.read_swka_info()?)?;
+    let canteens = parser.canteen_handle();
+    let file_handler_info = config.read_file_handler_info().await?;
+    let review_file_handler = FileHandler::new(file_handler_info.clone());
+    let retention_file_handler = FileHandler::new(file_handler_info.clone());
+    let file_handler = FileHandler::new(file_handler_info.clone());
+    let health_checks: Vec<Box<dyn HealthCheck>> = vec![
+        Box::new(factory.get_database_health()),
+        Box::new(factory.get_parse_health(config.read_max_parse_age())),
+        Box::new(FileHandler::new(file_handler_info)),
+        Box::new(mail.clone()),
+    ];
+    let google_vision = GoogleApiHandler::new(config.get_image_validation_info().await?)?;
 
-        // logic layer
-        let command = CommandHandler::new(
-            config.read_image_preprocessing_info(),
-            command_data,
-            mail,
-            file_handler,
-            google_vision,
-        )?;
-        let mealplan_management = MealPlanManager::new(mealplan_management_data, parser);
+    // logic layer
+    let report_policy = ReportPolicy::new(config.read_report_policy_info()?);
+    command_data
+        .set_rank_policy(&report_policy.rank_policy())
+        .await?;
+    let command = CommandHandler::new(
+        config.read_image_preprocessing_info(),
+        report_policy,
+        config.read_client_limit_info(),
+        command_data,
+        notification,
+        file_handler,
+    )?;
+    let mealplan_management = MealPlanManager::new(mealplan_management_data, parser);
+    let image_review = ImageReviewManager::new(
+        config.read_image_review_info(),
+        image_review_data,
+        review_file_handler,
+        google_vision,
+        NotificationFanOut::new(notification_info, mail.clone())?,
+    );
+    let retention = RetentionManager::new(
+        config.read_retention_info(),
+        retention_data,
+        retention_file_handler,
+    );
+    let digest = DigestManager::new(digest_data, mail);
 
-        // trigger layer
-        let mut api_server =
-            ApiServer::new(config.read_api_info()?, request_data, command, auth_data).await;
-        let mut scheduler = Scheduler::new(config.read_schedule_info()?, mealplan_management).await;
+    // trigger layer
+    let mut scheduler = Scheduler::new(
+        config.read_schedule_info()?,
+        mealplan_management,
+        image_review,
+        retention,
+        digest,
+        factory.get_scheduler_lease_data_access(),
+    )
+    .await;
+    let mut api_server = ApiServer::new(
+        config.read_api_info()?,
+        request_data,
+        command,
+        auth_data,
+        admin_data,
+        health_checks,
+        Some(scheduler.control()),
+    );
+    run_until_stopped(
+        ConfigReloader::new(
+            config,
+            logger.filter_handle(),
+            canteens,
+            scheduler.control(),
+            api_server.reload_handle(),
+        ),
+        &mut scheduler,
+        &mut api_server,
+    )
+    .await
+}
 
-        // run server
-        scheduler.start().await;
-        api_server.start().await;
+/// Runs the scheduler and api server until the server gets stopped, applying changed settings using `reloader` meanwhile.
+async fn run_until_stopped(
+    reloader: ConfigReloader,
+    scheduler: &mut Scheduler,
+    api_server: &mut ApiServer,
+) -> Result<()> {
+    let reloader = Arc::new(reloader);
+    api_server.set_config_reload(reloader.clone());
+    scheduler.start().await;
+    api_server.start().await;
 
-        info!("Server is running");
+    info!("Server is running");
 
-        ctrl_c().await?;
+    wait_for_shutdown(reloader.as_ref()).await?;
 
-        info!("Shutting down server...");
+    info!("Shutting down server...");
 
-        scheduler.shutdown().await;
-        api_server.shutdown().await;
+    scheduler.shutdown().await;
+    api_server.shutdown().await;
 
-        info!("Server stopped.");
-        logger.shutdown().await;
+    info!("Server stopped.");
+    Ok(())
+}
 
-        Ok(())
+/// Waits until the server should stop, reloading the settings whenever `SIGHUP` is received.
+#[cfg(unix)]
+async fn wait_for_shutdown(reloader: &impl ConfigReload) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+    use tracing::warn;
+
+    let mut hangup = signal(SignalKind::hangup())?;
+    loop {
+        tokio::select! {
+            result = ctrl_c() => return Ok(result?),
+            _ = hangup.recv() => {
+                info!("Received SIGHUP, reloading settings...");
+                if let Err(e) = reloader.reload().await {
+                    warn!("Could not reload settings: {e}");
+                }
+            }
+        }
     }
 }
+
+/// Waits until the server should stop. Settings can only be reloaded over the admin api, as there is no `SIGHUP`.
+#[cfg(not(unix))]
+async fn wait_for_shutdown(_reloader: &impl ConfigReload) -> Result<()> {
+    Ok(ctrl_c().await?)
+}
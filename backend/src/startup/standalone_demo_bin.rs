@@ -1,8 +1,10 @@
 //! This module contains the server, the heart of the application.
+use clap::Parser;
 use tokio::signal::ctrl_c;
 use tracing::info;
 
 use mensa_app_backend::{
+    interface::persistent_data::CommandDataAccess,
     layer::{
         data::{
             database::factory::DataAccessFactory, swka_parser::swka_parse_manager::SwKaParseManager,
@@ -10,16 +12,22 @@ use mensa_app_backend::{
         logic::{
             api_command::{
                 command_handler::CommandHandler,
-                mocks::{
-                    CommandAdminNotificationMock, CommandImageStorageMock,
-                    CommandImageValidationMock,
-                },
+                mocks::{CommandAdminNotificationMock, CommandImageStorageMock},
+                report_policy::ReportPolicy,
             },
+            digest::{digest_manager::DigestManager, mocks::DigestNotificationMock},
+            image_review::{image_review_manager::ImageReviewManager, mocks::ImageValidationMock},
             mealplan_management::meal_plan_manager::MealPlanManager,
+            retention::retention_manager::RetentionManager,
         },
         trigger::{api::server::ApiServer, scheduling::scheduler::Scheduler},
     },
-    startup::{cli, config::ConfigReader, logging::Logger, server::ServerError},
+    startup::{
+        cli::{self, Cli, Command},
+        config::ConfigReader,
+        logging::Logger,
+        server::ServerError,
+    },
 };
 
 /// Result returned from the server, potentially containing a [`ServerError`].
@@ -30,57 +38,95 @@ pub type Result<T> = std::result::Result<T, ServerError>;
 /// # Errors
 /// - when the the config could not read environment variables
 /// - when crating a component fails
-#[tokio::main]
-async fn main() {
-    run().await.unwrap();
+fn main() {
+    let args = Cli::parse();
+    // reading the config may change the environment, which is only safe before other threads run
+    let config = ConfigReader::new(args.config.clone()).unwrap();
+    tokio::runtime::Runtime::new()
+        .unwrap()
+        .block_on(run(config, args))
+        .unwrap();
 }
 
-async fn run() -> Result<()> {
-    let config = ConfigReader::default();
-
+async fn run(config: ConfigReader, args: Cli) -> Result<()> {
     // logging
     Logger::init(config.read_log_info()?);
+    config.validate()?;
 
-    info!("Starting server...");
-
-    // help text
-    if config.should_print_help() {
-        cli::print_help();
-        return Ok(());
-    }
+    let serve_args = match args.command.unwrap_or_default() {
+        Command::Serve(serve_args) => serve_args,
+        Command::Operation(command) => return Ok(cli::run_command(&config, command).await?),
+    };
 
-    if config.should_migrate_images() {
-        cli::migrate_images(&config).await?;
-        return Ok(());
-    }
+    info!("Starting server...");
 
     // data layer
-    let factory =
-        DataAccessFactory::new(config.read_database_info()?, config.should_migrate()).await?;
+    let factory = DataAccessFactory::new(config.read_database_info()?, serve_args.migrate).await?;
     let command_data = factory.get_command_data_access();
     let mealplan_management_data = factory.get_mealplan_management_data_access();
     let request_data = factory.get_request_data_access();
-    let auth_data = factory.get_auth_data_access();
+    let auth_data = factory.get_auth_data_access(config.read_api_key_cipher()?);
+    let encrypted = auth_data.encrypt_plain_keys().await?;
+    if encrypted > 0 {
+        info!("Encrypted {encrypted} api keys stored in plain text.");
+    }
+    let admin_data = factory.get_admin_data_access();
+    let image_review_data = factory.get_image_review_data_access();
+    let retention_data = factory.get_retention_data_access();
+    let digest_data = factory.get_digest_data_access();
 
-    let mail = CommandAdminNotificationMock;
+    let mail = CommandAdminNotificationMock::default();
     let parser = SwKaParseManager::new(config.read_swka_info()?)?;
     let file_handler = CommandImageStorageMock;
-    let google_vision = CommandImageValidationMock;
+    let google_vision = ImageValidationMock;
 
     // logic layer
+    let report_policy = ReportPolicy::new(config.read_report_policy_info()?);
+    command_data
+        .set_rank_policy(&report_policy.rank_policy())
+        .await?;
     let command = CommandHandler::new(
         config.read_image_preprocessing_info(),
+        report_policy,
+        config.read_client_limit_info(),
         command_data,
         mail,
         file_handler,
-        google_vision,
     )?;
     let mealplan_management = MealPlanManager::new(mealplan_management_data, parser);
+    let image_review = ImageReviewManager::new(
+        config.read_image_review_info(),
+        image_review_data,
+        CommandImageStorageMock,
+        google_vision,
+        CommandAdminNotificationMock::default(),
+    );
+    let retention = RetentionManager::new(
+        config.read_retention_info(),
+        retention_data,
+        CommandImageStorageMock,
+    );
+    let digest = DigestManager::new(digest_data, DigestNotificationMock::default());
 
     // trigger layer
-    let mut api_server =
-        ApiServer::new(config.read_api_info()?, request_data, command, auth_data).await;
-    let mut scheduler = Scheduler::new(config.read_schedule_info()?, mealplan_management).await;
+    let mut scheduler = Scheduler::new(
+        config.read_schedule_info()?,
+        mealplan_management,
+        image_review,
+        retention,
+        digest,
+        factory.get_scheduler_lease_data_access(),
+    )
+    .await;
+    let mut api_server = ApiServer::new(
+        config.read_api_info()?,
+        request_data,
+        command,
+        auth_data,
+        admin_data,
+        vec![Box::new(factory.get_database_health())],
+        Some(scheduler.control()),
+    );
 
     // run server
     scheduler.start().await;
@@ -2,11 +2,20 @@
 //!
 //! For a complete list and explanations you can see [here](https://www.sw-ka.de/media/?file=4458listeallergesetzlichausweisungspflichtigenzusatzstoffeundallergenefuerwebsite160218.pdf&download).
 
-use std::{fmt::Display, sync::LazyLock};
+use std::{fmt::Display, sync::OnceLock};
 
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, SaltString},
+    Argon2, PasswordHasher, PasswordVerifier,
+};
 use async_graphql::Enum;
 use image::DynamicImage;
-use serde::Serialize;
+use opentelemetry::global;
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 /// Date type used in multiple places.
 pub type Date = chrono::NaiveDate;
@@ -146,7 +155,7 @@ pub enum FoodType {
 }
 
 /// This enum lists all the predetermined reasons a image can be reported for.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Enum, sqlx::Type, Serialize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Enum, sqlx::Type, Serialize, Deserialize)]
 #[sqlx(type_name = "report_reason", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ReportReason {
     /// This picture shows offensive content.
@@ -169,6 +178,159 @@ impl Display for ReportReason {
     }
 }
 
+/// This enum lists the states of the automatic moderation an uploaded image goes through.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Enum, sqlx::Type, Serialize)]
+#[sqlx(type_name = "moderation_status", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ModerationStatus {
+    /// The image was uploaded but not yet validated. Only the uploader can see it.
+    #[default]
+    Pending,
+    /// The image passed the validation and is visible to everyone.
+    /// Images failing to be validated too often also get accepted, but stay hidden until an administrator makes them visible.
+    Accepted,
+    /// The image got rejected by the validation.
+    Rejected,
+}
+
+impl Display for ModerationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// This enum lists the actions of clients which are rate limited.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, sqlx::Type, Serialize)]
+#[sqlx(type_name = "client_action", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ClientAction {
+    /// Reporting an image.
+    Report,
+    /// Uploading an image.
+    Upload,
+    /// Voting for an image or rating a meal.
+    Rating,
+}
+
+impl Display for ClientAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Report => write!(f, "reports"),
+            Self::Upload => write!(f, "uploads"),
+            Self::Rating => write!(f, "ratings"),
+        }
+    }
+}
+
+/// This enum lists the scopes an api key can grant, each including the ones before.
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Enum, sqlx::Type, Serialize, Deserialize,
+)]
+#[sqlx(type_name = "api_key_scope", rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ApiKeyScope {
+    /// Only queries requiring authentication are allowed.
+    ReadOnly,
+    /// Additionally, all mutations except reporting images are allowed, including uploading images.
+    Upload,
+    /// Additionally, images may be reported.
+    Moderate,
+}
+
+impl Display for ApiKeyScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ReadOnly => write!(f, "READ_ONLY"),
+            Self::Upload => write!(f, "UPLOAD"),
+            Self::Moderate => write!(f, "MODERATE"),
+        }
+    }
+}
+
+/// This enum lists the roles of administrators, each including the permissions of the ones before.
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Enum, sqlx::Type, Serialize, Deserialize,
+)]
+#[sqlx(type_name = "admin_role", rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AdminRole {
+    /// Moderating images and clients is allowed.
+    Moderator,
+    /// Additionally, api keys, administrator accounts and client data may be managed.
+    Operator,
+}
+
+impl Display for AdminRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Moderator => write!(f, "MODERATOR"),
+            Self::Operator => write!(f, "OPERATOR"),
+        }
+    }
+}
+
+/// Actor recorded in the audit log for actions taken automatically, e.g. hiding reported images.
+pub const SYSTEM_ACTOR: &str = "system";
+
+/// This enum lists the state-changing actions recorded in the audit log.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "audit_action", rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AuditAction {
+    /// A client reported an image.
+    ReportImage,
+    /// An image got hidden automatically because of its reports.
+    HideImage,
+    /// A client voted an image up.
+    UpvoteImage,
+    /// A client voted an image down.
+    DownvoteImage,
+    /// A client removed its upvote of an image.
+    RemoveUpvote,
+    /// A client removed its downvote of an image.
+    RemoveDownvote,
+    /// A client uploaded an image.
+    UploadImage,
+    /// A client rated a meal.
+    RateMeal,
+    /// An administrator verified an image.
+    VerifyImage,
+    /// An administrator deleted an image.
+    DeleteImage,
+    /// A client deleted an image it uploaded.
+    DeleteOwnImage,
+    /// An administrator made a hidden image visible again.
+    UnhideImage,
+    /// An administrator restored an image.
+    RestoreImage,
+    /// An administrator banned a client.
+    BanClient,
+    /// An administrator lifted the ban of a client.
+    UnbanClient,
+    /// An administrator erased all data of a client.
+    EraseClientData,
+    /// An image got rejected by the automatic review.
+    RejectImage,
+    /// An administrator added an api key.
+    AddApiKey,
+    /// An administrator replaced the secret of an api key.
+    RotateApiKey,
+    /// An administrator revoked an api key.
+    RevokeApiKey,
+    /// An administrator enabled or disabled the compatibility mode of an api key.
+    SetCompatibilityMode,
+    /// An administrator account got created or changed.
+    SaveAdminAccount,
+    /// An administrator account got deleted.
+    DeleteAdminAccount,
+    /// The settings for ranking images changed.
+    SetRankPolicy,
+}
+
+impl Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
 /// This struct contains all price classes. All prices are listed in euro.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Price {
@@ -201,13 +363,25 @@ pub struct NutritionData {
     pub salt: u32,
 }
 
-static BASE_URL: LazyLock<String> =
-    LazyLock::new(|| std::env::var("BASE_URL").unwrap_or_else(|_| "localhost".into()));
+/// Domain of the server if `BASE_URL` is not set.
+pub(crate) const DEFAULT_BASE_URL: &str = "localhost";
+
+static BASE_URL: OnceLock<String> = OnceLock::new();
+
+/// Sets the domain of the server used by [`local_to_global_url`], e.g. from the config file.
+/// If not set before the first global url gets created, `BASE_URL` is read from the environment. Later calls have no effect.
+pub(crate) fn set_base_url(base_url: String) {
+    BASE_URL.set(base_url).ok();
+}
+
+fn base_url() -> &'static str {
+    BASE_URL.get_or_init(|| std::env::var("BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.into()))
+}
 
 /// Prepends the servers domain to get a global url. Slug should start with a `/`.
 #[must_use]
 pub fn local_to_global_url(slug: &str) -> String {
-    format!("{}{slug}", BASE_URL.as_str())
+    format!("{}{slug}", base_url())
 }
 
 /// Returns the url an image will be accessible given its id.
@@ -216,6 +390,67 @@ pub fn image_id_to_url(id: Uuid) -> String {
     local_to_global_url(&format!("{IMAGE_BASE_PATH}/{id}.{IMAGE_EXTENSION}"))
 }
 
+/// Number of leading characters of an api key identifying it in requests.
+pub const API_KEY_IDENT_LENGTH: usize = 10;
+
+/// Generates a new random api key.
+#[must_use]
+pub fn generate_api_key() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Returns the hex encoded SHA-512 hash of a random token, e.g. of an admin session, which is stored instead of the token itself.
+#[must_use]
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha512::digest(token.as_bytes()))
+}
+
+/// Returns the part of an api key identifying it in requests.
+#[must_use]
+pub fn api_key_ident(key: &str) -> &str {
+    key.get(..API_KEY_IDENT_LENGTH).unwrap_or(key)
+}
+
+/// Returns the argon2 hash of an administrator password including a random salt, in PHC string format.
+/// # Panics
+/// if the default argon2 parameters are invalid, which they are not
+#[must_use]
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("default argon2 parameters should be valid")
+        .to_string()
+}
+
+/// Checks whether a password matches a hash created by [`hash_password`].
+#[must_use]
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+/// Returns the headers passing the current trace on to the receiver of an outgoing http request.
+/// Empty if exporting traces is disabled.
+#[must_use]
+pub fn trace_context_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let context = tracing::Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(&mut headers));
+    });
+    headers
+}
+
+/// Returns the trace context a caller passed along in the headers of an incoming http request.
+#[must_use]
+pub fn trace_context_from_headers(headers: &HeaderMap) -> opentelemetry::Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -225,19 +460,62 @@ mod tests {
     #[test]
     fn test_global_url() {
         assert_eq!(
-            format!("{}/myslug", BASE_URL.as_str()),
+            format!("{}/myslug", base_url()),
             local_to_global_url("/myslug")
         );
     }
 
+    #[test]
+    fn test_api_key() {
+        let key = generate_api_key();
+        assert_eq!(key.len(), 64);
+        assert_ne!(key, generate_api_key());
+        assert_eq!(api_key_ident(&key), &key[..API_KEY_IDENT_LENGTH]);
+        assert_eq!(api_key_ident("abc"), "abc");
+
+        let hash = hash_token(&key);
+        assert_eq!(hash.len(), 128);
+        assert_eq!(hash, hash_token(&key));
+        assert_ne!(hash, hash_token("abc"));
+    }
+
+    #[test]
+    fn test_trace_context_headers() {
+        use opentelemetry::trace::TraceContextExt;
+        use opentelemetry_sdk::propagation::TraceContextPropagator;
+
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "traceparent",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"
+                .parse()
+                .expect("valid header"),
+        );
+        let context = trace_context_from_headers(&headers);
+        assert_eq!(
+            context.span().span_context().trace_id().to_string(),
+            "0af7651916cd43dd8448eb211c80319c"
+        );
+
+        // without exporting traces, nothing gets passed on
+        assert!(trace_context_headers().is_empty());
+    }
+
+    #[test]
+    fn test_password_hash() {
+        let hash = hash_password("secret");
+        assert_ne!(hash, hash_password("secret"));
+        assert!(verify_password("secret", &hash));
+        assert!(!verify_password("wrong", &hash));
+        assert!(!verify_password("secret", "no hash"));
+    }
+
     #[test]
     fn test_image_to_url() {
         let uuid = Uuid::from_str("3945b556-2f02-427e-83be-46c0aa1a5cc9").expect("valid uuid");
         assert_eq!(
-            format!(
-                "{}{IMAGE_BASE_PATH}/{uuid}.{IMAGE_EXTENSION}",
-                BASE_URL.as_str()
-            ),
+            format!("{}{IMAGE_BASE_PATH}/{uuid}.{IMAGE_EXTENSION}", base_url()),
             image_id_to_url(uuid)
         );
     }
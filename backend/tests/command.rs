@@ -7,7 +7,7 @@ use mensa_app_backend::{
             database::factory::DataAccessFactory, file_handler::FileHandler,
             mail::mail_sender::MailSender,
         },
-        logic::api_command::{command_handler::CommandHandler, mocks::CommandImageValidationMock},
+        logic::api_command::{command_handler::CommandHandler, report_policy::ReportPolicy},
     },
     startup::config::ConfigReader,
     util::{ReportReason, Uuid},
@@ -97,7 +97,6 @@ async fn setup_cmd() -> impl Command {
 
     let mail = MailSender::new(reader.read_mail_info().unwrap()).unwrap();
     let file_handler = FileHandler::new(reader.read_file_handler_info().await.unwrap());
-    let image_validation = CommandImageValidationMock; // todo
 
     let factory = DataAccessFactory::new(reader.read_database_info().unwrap(), true)
         .await
@@ -105,10 +104,11 @@ async fn setup_cmd() -> impl Command {
     let data = factory.get_command_data_access();
     CommandHandler::new(
         reader.read_image_preprocessing_info(),
+        ReportPolicy::new(reader.read_report_policy_info().unwrap()),
+        reader.read_client_limit_info(),
         data,
         mail,
         file_handler,
-        image_validation,
     )
     .unwrap()
 }
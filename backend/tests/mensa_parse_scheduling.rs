@@ -3,11 +3,29 @@ use mensa_app_backend::{
         data::{
             database::factory::DataAccessFactory, swka_parser::swka_parse_manager::SwKaParseManager,
         },
-        logic::mealplan_management::meal_plan_manager::MealPlanManager,
+        logic::{
+            digest::{
+                digest_manager::DigestManager,
+                mocks::{DigestDatabaseMock, DigestNotificationMock},
+            },
+            image_review::{
+                image_review_manager::{ImageReviewInfo, ImageReviewManager},
+                mocks::{
+                    ImageReviewDatabaseMock, ImageReviewStorageMock, ImageValidationMock,
+                    ReviewNotificationMock,
+                },
+            },
+            mealplan_management::meal_plan_manager::MealPlanManager,
+            retention::{
+                mocks::{RetentionDatabaseMock, RetentionStorageMock},
+                retention_manager::{RetentionInfo, RetentionManager},
+            },
+        },
         trigger::scheduling::scheduler::{ScheduleInfo, Scheduler},
     },
     startup::config::ConfigReader,
 };
+use std::time::Duration;
 use tokio::signal::ctrl_c;
 
 const NEVER: &str = "* * * 31 2 *";
@@ -18,6 +36,12 @@ async fn test_full_mensa_parse_scheduling() {
     let info = ScheduleInfo {
         full_parse_schedule: "0 */5 * * * *".to_string(),
         update_parse_schedule: NEVER.to_string(),
+        image_review_schedule: NEVER.to_string(),
+        image_reverification_schedule: NEVER.to_string(),
+        retention_schedule: NEVER.to_string(),
+        image_reconciliation_schedule: NEVER.to_string(),
+        digest_schedule: None,
+        leader_lease: Duration::from_secs(30),
     };
     let mut scheduler = setup(info).await;
     scheduler.start().await;
@@ -31,6 +55,12 @@ async fn test_update_mensa_parse_scheduling() {
     let info = ScheduleInfo {
         full_parse_schedule: NEVER.to_string(),
         update_parse_schedule: "0 */5 * * * *".to_string(),
+        image_review_schedule: NEVER.to_string(),
+        image_reverification_schedule: NEVER.to_string(),
+        retention_schedule: NEVER.to_string(),
+        image_reconciliation_schedule: NEVER.to_string(),
+        digest_schedule: None,
+        leader_lease: Duration::from_secs(30),
     };
     let mut scheduler = setup(info).await;
     scheduler.start().await;
@@ -47,5 +77,39 @@ async fn setup(info: ScheduleInfo) -> Scheduler {
     let parser = SwKaParseManager::new(reader.read_swka_info().unwrap()).unwrap();
 
     let mealplan_management = MealPlanManager::new(mealplan_management_data, parser);
-    Scheduler::new(info, mealplan_management).await
+    let image_review = ImageReviewManager::new(
+        ImageReviewInfo {
+            max_attempts: 1,
+            reverification_days: 180,
+            reverification_limit: 10,
+        },
+        ImageReviewDatabaseMock::default(),
+        ImageReviewStorageMock::default(),
+        ImageValidationMock,
+        ReviewNotificationMock::default(),
+    );
+    let retention = RetentionManager::new(
+        RetentionInfo {
+            deleted_image_grace_days: 0,
+            hidden_image_days: 0,
+            report_days: 0,
+            unserved_food_days: 0,
+            dry_run: true,
+        },
+        RetentionDatabaseMock::default(),
+        RetentionStorageMock::default(),
+    );
+    let digest = DigestManager::new(
+        DigestDatabaseMock::default(),
+        DigestNotificationMock::default(),
+    );
+    Scheduler::new(
+        info,
+        mealplan_management,
+        image_review,
+        retention,
+        digest,
+        database_factory.get_scheduler_lease_data_access(),
+    )
+    .await
 }